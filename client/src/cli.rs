@@ -1,9 +1,12 @@
 use clap::{AppSettings, Args, Parser, Subcommand};
+use clap_complete::Shell;
 use goxlr_types::{
     ButtonColourGroups, ButtonColourOffStyle, ButtonColourTargets, ChannelName,
-    CompressorAttackTime, CompressorRatio, CompressorReleaseTime, EqFrequencies, FaderDisplayStyle,
-    FaderName, GateTimes, InputDevice, MiniEqFrequencies, MuteFunction, OutputDevice,
+    CompressorAttackTime, CompressorRatio, CompressorReleaseTime, CoughButtonMode, EncoderName,
+    EqFrequencies, EqPreset, FaderDisplayStyle, FaderName, GateTimes, InputDevice, MicrophoneType,
+    MiniEqFrequencies, MuteFunction, OutputDevice, ProfileSection, SampleBank, SampleButtons,
 };
+use std::path::PathBuf;
 use std::str::FromStr;
 
 // TODO: Likely going to shuffle this to use subcommands rather than parameters..
@@ -25,6 +28,14 @@ pub struct Cli {
     #[clap(long)]
     pub status_json: bool,
 
+    /// Output results (and errors) as JSON, for use in scripts
+    #[clap(long, global = true)]
+    pub json: bool,
+
+    /// Check that the commands would be accepted by the device, without applying them
+    #[clap(long, global = true)]
+    pub dry_run: bool,
+
     #[clap(flatten, help_heading = "Microphone controls")]
     pub microphone_controls: MicrophoneControls,
 
@@ -54,6 +65,10 @@ pub struct MicrophoneControls {
 #[clap(setting = AppSettings::DeriveDisplayOrder)]
 #[clap(setting = AppSettings::ArgRequiredElseHelp)]
 pub enum SubCommands {
+    /// Display the device information
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Status {},
+
     /// Profile Settings
     Profiles {
         #[clap(subcommand)]
@@ -77,6 +92,152 @@ pub enum SubCommands {
         volume_percent: u8,
     },
 
+    /// Open an interactive mixer, showing faders and routing live
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Tui {},
+
+    /// Test the device's lights, displays and connection, and print a report
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Diagnostics {},
+
+    /// Compare the state the device reports with the daemon's, to find changes made by other
+    /// software
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    VerifyState {},
+
+    /// Show the state of every mute button and the cough button, and what each channel is muted to
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    MuteState {},
+
+    /// Read everything the device reports about itself, for support requests
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    HardwareInfo {},
+
+    /// Send a command to the device as it is, and print the response. This has to be allowed
+    /// with allow_raw_usb_commands in the daemon's settings
+    RawCommand {
+        /// The command's id in hex, for example 00800000
+        command_id: String,
+
+        /// The body to send, in hex
+        body: Option<String>,
+    },
+
+    /// Send the daemon's full state to the device again
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    SyncToHardware {},
+
+    /// Show the daemon's version, uptime, connected devices and USB errors
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Daemon {},
+
+    /// Restart the daemon, with the arguments it was started with
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    RestartDaemon {},
+
+    /// Print the address the web UI can be opened at
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    UiUrl {},
+
+    /// List the connected devices, with their profiles and whether the mic is muted
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Devices {},
+
+    /// Print notifications (devices connecting, profiles loading and so on) as they happen
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Notifications {},
+
+    /// Show the most recent commands sent to devices, and which client sent each of them
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    AuditLog {},
+
+    /// Print button presses, fader and encoder movements, and other events as they happen
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Events {},
+
+    /// Inspect the daemon's own settings
+    Settings {
+        #[clap(subcommand)]
+        command: SettingsCommands,
+    },
+
+    /// List the backups kept of a profile, or restore one
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    ProfileBackups {
+        /// The name of the profile
+        name: String,
+
+        /// Use the microphone profile with this name, rather than the profile
+        #[clap(long)]
+        mic: bool,
+
+        /// The id of a backup to replace the profile with
+        #[clap(long)]
+        restore: Option<String>,
+    },
+
+    /// Manage the files in the daemon's samples directory
+    Samples {
+        #[clap(subcommand)]
+        command: SampleCommands,
+    },
+
+    /// List, enable, disable or reload the scripts the daemon runs
+    Scripts {
+        #[clap(subcommand)]
+        command: ScriptCommands,
+    },
+
+    /// Print shell completions for the client
+    Completions {
+        #[clap(arg_enum)]
+        shell: Shell,
+    },
+
+    /// Get or Set Channel Volumes
+    Volumes {
+        #[clap(subcommand)]
+        command: VolumeCommands,
+    },
+
+    /// Mute or Unmute a Channel
+    Mute {
+        /// The Channel To Change
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        /// Should the channel be muted? [true | false]
+        #[clap(parse(try_from_str))]
+        muted: bool,
+    },
+
+    /// Cut everything from the stream at once, or restore it
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    StreamPanic {
+        /// Should the stream be cut? [true | false], toggles if not given
+        #[clap(parse(try_from_str))]
+        enabled: Option<bool>,
+    },
+
+    /// Hear only this channel (and any others soloed) in the headphones, or stop soloing it
+    Solo {
+        /// The Channel To Solo
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        /// Should the channel be soloed? [true | false], toggles if not given
+        #[clap(parse(try_from_str))]
+        soloed: Option<bool>,
+    },
+
+    /// Lock the device's buttons, faders and encoders so they're ignored, or unlock them
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Lock {
+        /// Should the controls be locked? [true | false], toggles if not given
+        #[clap(parse(try_from_str))]
+        locked: Option<bool>,
+    },
+
     /// Configure the Bleep Button
     BleepVolume {
         /// Set Bleep Button Volume
@@ -84,6 +245,12 @@ pub enum SubCommands {
         volume_percent: u8,
     },
 
+    /// Choose the outputs which hear the bleep, the others hear nothing from the mic meanwhile
+    BleepOutputs {
+        #[clap(arg_enum, required = true, min_values = 1)]
+        outputs: Vec<OutputDevice>,
+    },
+
     /// Commands to manipulate the individual GoXLR Faders
     Faders {
         #[clap(subcommand)]
@@ -96,6 +263,12 @@ pub enum SubCommands {
         command: CoughButtonBehaviours,
     },
 
+    /// Configure the samples assigned to the sampler buttons
+    Sampler {
+        #[clap(subcommand)]
+        command: SamplerCommands,
+    },
+
     /// Commands to manipulate the GoXLR Router
     Router {
         /// The input device
@@ -111,6 +284,18 @@ pub enum SubCommands {
         enabled: bool,
     },
 
+    /// Save and apply named copies of the routing
+    RoutingPreset {
+        #[clap(subcommand)]
+        command: RoutingPresetCommands,
+    },
+
+    /// Give channels and outputs names of your own, which can be used in place of theirs
+    DisplayName {
+        #[clap(subcommand)]
+        command: DisplayNameCommands,
+    },
+
     /// Commands to control the GoXLR lighting
     Lighting {
         #[clap(subcommand)]
@@ -118,6 +303,81 @@ pub enum SubCommands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum VolumeCommands {
+    /// Set the volume of a channel
+    Set {
+        /// The Channel To Change
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        /// The new volume [0 - 255]
+        volume: u8,
+    },
+
+    /// Set the volume of a channel in decibels
+    SetDb {
+        /// The Channel To Change
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        /// The new volume [-60 - 0]
+        #[clap(allow_hyphen_values = true)]
+        db: f32,
+    },
+
+    /// Limit the maximum volume of a channel, omit the limit to remove it
+    Limit {
+        /// The Channel To Change
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        /// The maximum volume [0 - 255]
+        limit: Option<u8>,
+    },
+
+    /// Link channels together, so changing the volume of one scales the others
+    Link {
+        #[clap(arg_enum, min_values = 2, required = true)]
+        channels: Vec<ChannelName>,
+    },
+
+    /// Remove a channel from its link
+    Unlink {
+        #[clap(arg_enum)]
+        channel: ChannelName,
+    },
+
+    /// Set the volume of a channel on the sub (monitor) mix
+    SubMix {
+        /// The Channel To Change
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        /// The new volume [0 - 255]
+        volume: u8,
+    },
+
+    /// Link a channel's sub mix volume to its main volume
+    SubMixLinked {
+        /// The Channel To Change
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        #[clap(parse(try_from_str))]
+        linked: bool,
+    },
+
+    /// Display the volume of a channel, or all channels if none is provided
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Get {
+        #[clap(arg_enum)]
+        channel: Option<ChannelName>,
+    },
+}
+
 fn percent_value(s: &str) -> Result<u8, String> {
     let value = u8::from_str(s);
     if value.is_err() {
@@ -140,17 +400,33 @@ pub enum CoughButtonBehaviours {
         is_hold: bool,
     },
 
+    /// Hold, Toggle, or Hybrid (a tap toggles, holding mutes until release)
+    ButtonMode {
+        #[clap(arg_enum)]
+        mode: CoughButtonMode,
+    },
+
     MuteBehaviour {
         /// Where a single press will mute (Hold will always Mute to All)
         #[clap(arg_enum)]
         mute_behaviour: MuteFunction,
     },
+
+    /// Mute or Unmute the Microphone, using the configured Mute Behaviour
+    Muted {
+        #[clap(parse(try_from_str))]
+        muted: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
 #[clap(setting = AppSettings::DeriveDisplayOrder)]
 #[clap(setting = AppSettings::ArgRequiredElseHelp)]
 pub enum ProfileType {
+    /// List all available profiles and mic profiles
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    List {},
+
     /// General Device Profile
     Device {
         #[clap(subcommand)]
@@ -162,12 +438,49 @@ pub enum ProfileType {
         #[clap(subcommand)]
         command: ProfileAction,
     },
+
+    /// Apply parts of a saved device profile, leaving everything else as it is
+    Apply {
+        /// The profile to take the settings from
+        profile_name: String,
+
+        /// The sections of the profile to apply
+        #[clap(arg_enum, required = true, min_values = 1)]
+        sections: Vec<ProfileSection>,
+    },
+
+    /// Switch back to the previously loaded profile, keeping the current volumes
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    QuickSwitch {},
+
+    /// Load one of the profiles built into the daemon
+    LoadEmbedded {
+        /// The name of the built in profile
+        profile_name: String,
+    },
+
+    /// Export the current microphone profile as JSON
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    ExportMicrophone {
+        /// The file to write to, the JSON is printed if this isn't given
+        path: Option<PathBuf>,
+    },
+
+    /// Replace the current microphone profile with one exported as JSON
+    ImportMicrophone {
+        /// The JSON file to read
+        path: PathBuf,
+    },
 }
 
 #[derive(Subcommand, Debug)]
 #[clap(setting = AppSettings::DeriveDisplayOrder)]
 #[clap(setting = AppSettings::ArgRequiredElseHelp)]
 pub enum ProfileAction {
+    /// List the available profiles
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    List {},
+
     /// Load a profile by name
     Load {
         /// The profile name to load
@@ -189,6 +502,25 @@ pub enum ProfileAction {
 #[clap(setting = AppSettings::DeriveDisplayOrder)]
 #[clap(setting = AppSettings::ArgRequiredElseHelp)]
 pub enum MicrophoneCommands {
+    /// Select the active Microphone Type
+    Type {
+        #[clap(arg_enum)]
+        mic_type: MicrophoneType,
+
+        /// Confirm turning on phantom power, needed with the phantom power interlock on
+        #[clap(long)]
+        confirm: bool,
+    },
+
+    /// Set the gain of a Microphone Type, and make it active
+    Gain {
+        #[clap(arg_enum)]
+        mic_type: MicrophoneType,
+
+        /// Value is in decibels and recommended to be lower than 72dB.
+        gain: u16,
+    },
+
     /// Configure the Equaliser for the Full GoXLR Device
     Equaliser {
         #[clap(subcommand)]
@@ -201,6 +533,15 @@ pub enum MicrophoneCommands {
         command: EqualiserMiniCommands,
     },
 
+    /// Apply a preset curve to both Equalisers
+    EqualiserPreset {
+        #[clap(arg_enum)]
+        preset: EqPreset,
+    },
+
+    /// Reset both Equalisers to their default frequencies with no gain
+    EqualiserReset {},
+
     /// Configure the microphone noise gate
     NoiseGate {
         #[clap(subcommand)]
@@ -212,6 +553,20 @@ pub enum MicrophoneCommands {
         #[clap(subcommand)]
         command: CompressorCommands,
     },
+
+    /// Set how much the De-esser reduces harsh 's' sounds, as a percentage [0 - 100]
+    DeEsser { amount: u8 },
+
+    /// Sample the microphone for a few seconds while you speak, and recommend a gain
+    Calibrate {
+        /// The Microphone to calibrate
+        #[clap(arg_enum)]
+        mic_type: MicrophoneType,
+
+        /// Apply the recommended gain once calibration is complete
+        #[clap(long)]
+        apply: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -337,6 +692,12 @@ pub enum NoiseGateCommands {
         #[clap(parse(try_from_str))]
         enabled: bool,
     },
+
+    /// Simple mode, sets the threshold, attenuation, attack and release from one percentage
+    Amount {
+        #[clap(parse(try_from_str=percent_value))]
+        value: u8,
+    },
 }
 
 fn parse_gate_threshold(s: &str) -> Result<i8, String> {
@@ -382,6 +743,12 @@ pub enum CompressorCommands {
         #[clap(parse(try_from_str=parse_compressor_makeup))]
         value: u8,
     },
+
+    /// Simple mode, sets all of the compressor settings from one percentage
+    Amount {
+        #[clap(parse(try_from_str=percent_value))]
+        value: u8,
+    },
 }
 
 fn parse_compressor_threshold(s: &str) -> Result<i8, String> {
@@ -419,6 +786,7 @@ fn parse_compressor_makeup(s: &str) -> Result<u8, String> {
 #[clap(setting = AppSettings::ArgRequiredElseHelp)]
 pub enum FaderCommands {
     /// Assign a new Channel to a Fader
+    #[clap(alias = "assign")]
     Channel {
         /// The Fader to Change
         #[clap(arg_enum)]
@@ -439,6 +807,31 @@ pub enum FaderCommands {
         #[clap(arg_enum)]
         mute_behaviour: MuteFunction,
     },
+
+    /// Mute or Unmute a Fader, using its configured Mute Behaviour
+    Muted {
+        /// The Fader to Change
+        #[clap(arg_enum)]
+        fader: FaderName,
+
+        /// Should the fader be muted? [true | false]
+        #[clap(parse(try_from_str))]
+        muted: bool,
+    },
+
+    /// Set the fader pages, each being four channels for faders A to D, or clear them
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Pages {
+        #[clap(arg_enum)]
+        channels: Vec<ChannelName>,
+    },
+
+    /// Move the faders to a page, starting at 0
+    Page { page: usize },
+
+    /// Move the faders to the next page
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    NextPage {},
 }
 
 #[derive(Subcommand, Debug)]
@@ -469,6 +862,16 @@ pub enum LightingCommands {
         command: FadersAllLightingCommands,
     },
 
+    /// Give a channel a colour which follows it to whichever fader it's on
+    Channel {
+        /// The Channel To Colour
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        /// The colour (RRGGBB, rgb() or a name) of the fader, scribble and mute button, omit to clear it
+        colour: Option<String>,
+    },
+
     /// Configure lighting for a specific button
     Button {
         #[clap(subcommand)]
@@ -480,6 +883,54 @@ pub enum LightingCommands {
         #[clap(subcommand)]
         command: ButtonGroupLightingCommands,
     },
+
+    /// Make every colour on the device brighter or darker
+    Brightness {
+        /// The change in brightness, as a percentage
+        #[clap(allow_hyphen_values = true)]
+        delta: i8,
+    },
+
+    /// Save, load and share lighting themes
+    Theme {
+        #[clap(subcommand)]
+        command: ThemeCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum ThemeCommands {
+    /// List the themes in the daemon's theme directory
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    List {},
+
+    /// Apply a theme from the daemon's theme directory
+    Load {
+        /// The theme name to load
+        theme_name: String,
+    },
+
+    /// Save the current lighting to the daemon's theme directory
+    Save {
+        /// The name to save the theme as
+        theme_name: String,
+    },
+
+    /// Write the current lighting to a theme file
+    Export {
+        /// The file to write the theme to
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+    },
+
+    /// Apply the lighting from a theme file
+    Import {
+        /// The theme file to apply
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -496,16 +947,31 @@ pub enum FaderLightingCommands {
         display: FaderDisplayStyle,
     },
 
+    /// Turns the gradient and level meter on or off individually
+    DisplayMode {
+        /// The Fader to Change
+        #[clap(arg_enum)]
+        fader: FaderName,
+
+        /// Blend between the top and bottom colours
+        #[clap(long)]
+        gradient: bool,
+
+        /// Show the channel's level on the fader
+        #[clap(long)]
+        meter: bool,
+    },
+
     /// Sets the Top and Bottom colours of a fader
     Colour {
         /// The Fader name to Change
         #[clap(arg_enum)]
         fader: FaderName,
 
-        /// Top colour in hex format [RRGGBB]
+        /// Top colour [RRGGBB, rgb(r, g, b) or a name]
         top: String,
 
-        /// Bottom colour in hex format [RRGGBB]
+        /// Bottom colour [RRGGBB, rgb(r, g, b) or a name]
         bottom: String,
     },
 }
@@ -522,10 +988,10 @@ pub enum FadersAllLightingCommands {
 
     /// Sets the Top and Bottom colours of a fader
     Colour {
-        /// Top colour in hex format [RRGGBB]
+        /// Top colour [RRGGBB, rgb(r, g, b) or a name]
         top: String,
 
-        /// Bottom colour in hex format [RRGGBB]
+        /// Bottom colour [RRGGBB, rgb(r, g, b) or a name]
         bottom: String,
     },
 }
@@ -539,10 +1005,10 @@ pub enum ButtonLightingCommands {
         #[clap(arg_enum)]
         button: ButtonColourTargets,
 
-        /// The primary button colour [RRGGBB]
+        /// The primary button colour [RRGGBB, rgb(r, g, b) or a name]
         colour_one: String,
 
-        /// The secondary button colour [RRGGBB]
+        /// The secondary button colour [RRGGBB, rgb(r, g, b) or a name]
         colour_two: Option<String>,
     },
 
@@ -555,6 +1021,33 @@ pub enum ButtonLightingCommands {
         #[clap(arg_enum)]
         off_style: ButtonColourOffStyle,
     },
+
+    /// Sets the primary button colour by hue, saturation and value
+    ColourHsv {
+        /// The Button to change
+        #[clap(arg_enum)]
+        button: ButtonColourTargets,
+
+        /// The hue, in degrees (0 - 360)
+        hue: f32,
+
+        /// The saturation (0 - 1)
+        saturation: f32,
+
+        /// The value (0 - 1)
+        value: f32,
+    },
+
+    /// Make a button's colours brighter or darker
+    Brightness {
+        /// The Button to change
+        #[clap(arg_enum)]
+        button: ButtonColourTargets,
+
+        /// The change in brightness, as a percentage
+        #[clap(allow_hyphen_values = true)]
+        delta: i8,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -566,10 +1059,10 @@ pub enum ButtonGroupLightingCommands {
         #[clap(arg_enum)]
         group: ButtonColourGroups,
 
-        /// The primary button colour [RRGGBB]
+        /// The primary button colour [RRGGBB, rgb(r, g, b) or a name]
         colour_one: String,
 
-        /// The secondary button colour [RRGGBB]
+        /// The secondary button colour [RRGGBB, rgb(r, g, b) or a name]
         colour_two: Option<String>,
     },
 
@@ -582,6 +1075,17 @@ pub enum ButtonGroupLightingCommands {
         #[clap(arg_enum)]
         off_style: ButtonColourOffStyle,
     },
+
+    /// Rotate the hue of every colour in the group
+    ShiftHue {
+        /// The group to change
+        #[clap(arg_enum)]
+        group: ButtonColourGroups,
+
+        /// How far to rotate the hue, in degrees
+        #[clap(allow_hyphen_values = true)]
+        degrees: f32,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -597,23 +1101,434 @@ pub enum AllFaderCommands {
 
     /// Set the colour of all GoXLR Faders
     Colour {
-        /// Top colour in hex format [RRGGBB]
+        /// Top colour [RRGGBB, rgb(r, g, b) or a name]
         top: String,
 
-        /// Bottom colour in hex format [RRGGBB]
+        /// Bottom colour [RRGGBB, rgb(r, g, b) or a name]
         bottom: String,
     },
 
     /// Set the colours of all the fader buttons
     ButtonColour {
-        /// The primary button colour [RRGGBB]
+        /// The primary button colour [RRGGBB, rgb(r, g, b) or a name]
         colour_one: String,
 
         /// How the button should be presented when 'off'
         #[clap(arg_enum)]
         off_style: ButtonColourOffStyle,
 
-        /// The secondary button colour [RRGGBB]
+        /// The secondary button colour [RRGGBB, rgb(r, g, b) or a name]
         colour_two: Option<String>,
     },
 }
+
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum SettingsCommands {
+    /// Show where the settings file is, and which version of the format it uses
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Info {},
+
+    /// Wait until any changes to the settings have been written to disk
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Flush {},
+
+    /// The daemon's settings for the selected device
+    Device {
+        #[clap(subcommand)]
+        command: DeviceSettingsCommands,
+    },
+
+    /// The settings given to devices the daemon hasn't seen before
+    Defaults {
+        #[clap(subcommand)]
+        command: DeviceSettingsCommands,
+    },
+
+    /// Show whether a newer version of the utility is available, or configure the update check
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Updates {
+        /// Check GitHub for new releases once a day [true | false]
+        #[clap(long, parse(try_from_str))]
+        enabled: Option<bool>,
+
+        /// Where to save the package for a new release (it won't be installed), leave empty to
+        /// stop downloading them
+        #[clap(long)]
+        download_directory: Option<PathBuf>,
+    },
+
+    /// Serve a directory from the HTTP server under /custom/ (for a custom UI), or stop serving it
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    CustomUiDirectory { directory: Option<PathBuf> },
+}
+
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum ScriptCommands {
+    /// List the scripts in the script directory, and any errors they've had
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    List {},
+
+    /// Run a script which was disabled
+    Enable {
+        /// The script's file name, without .rhai
+        name: String,
+    },
+
+    /// Stop running a script, and don't run it again until it's enabled
+    Disable {
+        /// The script's file name, without .rhai
+        name: String,
+    },
+
+    /// Read the script directory again, and restart every enabled script
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Reload {},
+}
+
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum SampleCommands {
+    /// List the samples, with their format and length
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    List {
+        /// Only list samples whose path contains this
+        #[clap(long)]
+        filter: Option<String>,
+
+        /// Only list samples with this extension
+        #[clap(long)]
+        extension: Option<String>,
+
+        /// How many matching samples to skip
+        #[clap(long, default_value = "0")]
+        offset: usize,
+
+        /// The most samples to list
+        #[clap(long)]
+        limit: Option<usize>,
+    },
+
+    /// Copy a sample into the samples directory
+    Add {
+        /// The sample file to copy
+        file: PathBuf,
+
+        /// Where to put it in the samples directory, defaults to the file's name
+        path: Option<String>,
+    },
+
+    /// Move or rename a sample or folder, new folders are created as needed
+    Move { from: String, to: String },
+
+    /// Delete a sample, or an empty folder
+    Delete { path: String },
+
+    /// Print the peak levels through a WAV sample, to help with choosing trim points
+    Waveform {
+        path: String,
+
+        /// How many sections to split the sample into
+        #[clap(long, default_value = "50")]
+        points: u16,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum RoutingPresetCommands {
+    /// Save the current routing as a preset, replacing any preset with the same name
+    Save { name: String },
+
+    /// Replace the current routing with a preset
+    Apply { name: String },
+
+    /// Delete a preset, unbinding any buttons which applied it
+    Delete { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum DisplayNameCommands {
+    /// Name a channel, omit the name to go back to the channel's own
+    Channel {
+        /// The Channel To Rename
+        #[clap(arg_enum)]
+        channel: ChannelName,
+
+        name: Option<String>,
+    },
+
+    /// Name an output, omit the name to go back to the output's own
+    Output {
+        /// The Output To Rename
+        #[clap(arg_enum)]
+        output: OutputDevice,
+
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum SamplerCommands {
+    /// Set where a sample starts and stops playing
+    Trim {
+        /// The sample bank
+        #[clap(arg_enum)]
+        bank: SampleBank,
+
+        /// The sampler button
+        #[clap(arg_enum)]
+        button: SampleButtons,
+
+        /// The position of the sample on the button, starting at 0
+        track: usize,
+
+        /// Where to start playing, as a percentage of the sample [0 - 100]
+        #[clap(parse(try_from_str=percent_value))]
+        start_percent: u8,
+
+        /// Where to stop playing, as a percentage of the sample [0 - 100]
+        #[clap(parse(try_from_str=percent_value))]
+        end_percent: u8,
+    },
+
+    /// Remove every sample from a bank
+    ClearBank {
+        #[clap(arg_enum)]
+        bank: SampleBank,
+    },
+
+    /// Replace a bank's samples with a copy of another bank's
+    CopyBank {
+        #[clap(arg_enum)]
+        from: SampleBank,
+
+        #[clap(arg_enum)]
+        to: SampleBank,
+    },
+
+    /// Swap the samples of two banks
+    SwapBanks {
+        #[clap(arg_enum)]
+        first: SampleBank,
+
+        #[clap(arg_enum)]
+        second: SampleBank,
+    },
+
+    /// Light every sampler button with these colours while the bank is selected
+    BankColours {
+        #[clap(arg_enum)]
+        bank: SampleBank,
+
+        /// The primary colour [RRGGBB, rgb(r, g, b) or a name]
+        colour_one: String,
+
+        /// The secondary colour [RRGGBB, rgb(r, g, b) or a name], contrasts the first if not set
+        colour_two: Option<String>,
+    },
+
+    /// Go back to each button's own colours for the bank
+    ClearBankColours {
+        #[clap(arg_enum)]
+        bank: SampleBank,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[clap(setting = AppSettings::DeriveDisplayOrder)]
+#[clap(setting = AppSettings::ArgRequiredElseHelp)]
+pub enum DeviceSettingsCommands {
+    /// Show the current settings
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    Show {},
+
+    /// The profile to load when the device is connected
+    Profile { profile_name: String },
+
+    /// The microphone profile to load when the device is connected
+    MicProfile { mic_profile_name: String },
+
+    /// How long a button must be held before it counts as a hold, in milliseconds
+    HoldTime { milliseconds: u16 },
+
+    /// Where to find samples, leave empty to use the daemon's samples directory
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    SamplesDirectory { path: Option<PathBuf> },
+
+    /// A sound to play through the sampler while bleeping, leave empty to use the hardware bleep
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    BleepSound { path: Option<PathBuf> },
+
+    /// How long the hardware bleep takes to rise to its volume, in milliseconds (0-1000)
+    BleepAttack { milliseconds: u16 },
+
+    /// How long a custom bleep sound fades out for after release, in milliseconds (0-2000). The
+    /// hardware bleep is stopped by the device itself, so it can't be faded out
+    BleepRelease { milliseconds: u16 },
+
+    /// Require confirmation before phantom power is turned on by a microphone type change
+    /// [true | false]
+    PhantomPowerInterlock {
+        #[clap(parse(try_from_str))]
+        enabled: bool,
+    },
+
+    /// Mute the microphone while phantom power turns on or off, in milliseconds (0-2000)
+    PhantomPowerMute { milliseconds: u16 },
+
+    /// What happens to samples still playing when the sample bank is changed
+    SampleBankSwitch {
+        #[clap(arg_enum)]
+        mode: SampleBankSwitchMode,
+
+        /// With 'fade-out', how long the fade takes in milliseconds (1-10000)
+        milliseconds: Option<u16>,
+    },
+
+    /// How far an encoder moves for each click
+    EncoderStep {
+        /// The encoder to change (pitch can't be changed)
+        #[clap(arg_enum)]
+        encoder: EncoderName,
+
+        /// The number of positions to move per click (1-12)
+        step: u8,
+    },
+
+    /// Move an encoder further for each click while it's turned quickly
+    EncoderAcceleration {
+        /// The encoder to change (pitch can't be accelerated)
+        #[clap(arg_enum)]
+        encoder: EncoderName,
+
+        /// What the step is multiplied by when turned quickly (1-10), 1 turns acceleration off
+        multiplier: u8,
+
+        /// How soon after the previous click a click counts as quick, in milliseconds (10-1000)
+        #[clap(default_value = "100")]
+        window: u16,
+    },
+
+    /// Move every encoder by one position per click, regardless of their steps and acceleration
+    /// [true | false]
+    EncoderFineMode {
+        #[clap(parse(try_from_str))]
+        enabled: bool,
+    },
+
+    /// Save the active profile and mic profile automatically when they change
+    Autosave {
+        #[clap(arg_enum)]
+        mode: AutosaveMode,
+
+        /// With 'every', the number of minutes between saves (1-1440)
+        minutes: Option<u16>,
+    },
+
+    /// Set a button which switches back to the previous profile when held, or clear it
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    QuickSwitchButton {
+        /// The button to hold
+        #[clap(arg_enum)]
+        button: Option<ButtonColourTargets>,
+    },
+
+    /// Set a button which toggles the stream panic mute when held, or clear it
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    StreamPanicButton {
+        /// The button to hold
+        #[clap(arg_enum)]
+        button: Option<ButtonColourTargets>,
+    },
+
+    /// Set a button which moves the faders to the next page when held, or clear it
+    #[clap(unset_setting = AppSettings::ArgRequiredElseHelp)]
+    FaderPageButton {
+        /// The button to hold
+        #[clap(arg_enum)]
+        button: Option<ButtonColourTargets>,
+    },
+
+    /// Set a button which applies a routing preset when held, or clear it
+    RoutingPresetButton {
+        /// The button to hold
+        #[clap(arg_enum)]
+        button: ButtonColourTargets,
+
+        /// The routing preset to apply, clears the button if not set
+        preset: Option<String>,
+    },
+
+    /// Change what holding a fader's mute button does
+    FaderHold {
+        /// The fader to change
+        #[clap(arg_enum)]
+        fader: FaderName,
+
+        /// What the channel is muted to when the button is held, or solo to solo it instead
+        #[clap(arg_enum)]
+        action: FaderHoldMode,
+
+        /// How long the button must be held, in milliseconds, instead of the hold time
+        #[clap(long)]
+        hold_time: Option<u16>,
+    },
+
+    /// Leave a button to other tools, the daemon will only report when it's pressed
+    Passthrough {
+        /// The button to change
+        #[clap(arg_enum)]
+        button: ButtonColourTargets,
+
+        /// Should the daemon ignore this button? [true | false]
+        #[clap(parse(try_from_str))]
+        enabled: bool,
+    },
+
+    /// Make a button act as a different button, such as an effect preset acting as the bleep
+    /// button
+    RemapButton {
+        /// The button being pressed
+        #[clap(arg_enum)]
+        button: ButtonColourTargets,
+
+        /// The button it should act as, leave this out to give the button its own function back
+        #[clap(arg_enum)]
+        function: Option<ButtonColourTargets>,
+    },
+}
+
+#[derive(clap::ArgEnum, Debug, Copy, Clone)]
+pub enum AutosaveMode {
+    Off,
+    OnChange,
+    Every,
+}
+
+#[derive(clap::ArgEnum, Debug, Copy, Clone)]
+pub enum SampleBankSwitchMode {
+    Continue,
+    Stop,
+    FadeOut,
+}
+
+#[derive(clap::ArgEnum, Debug, Copy, Clone)]
+pub enum FaderHoldMode {
+    All,
+    ToStream,
+    ToVoiceChat,
+    ToPhones,
+    ToLineOut,
+    Disabled,
+    Solo,
+}
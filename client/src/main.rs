@@ -1,26 +1,112 @@
 mod cli;
 mod microphone;
+mod tui;
 
 use crate::cli::{
-    ButtonGroupLightingCommands, ButtonLightingCommands, CompressorCommands, CoughButtonBehaviours,
-    EqualiserCommands, EqualiserMiniCommands, FaderCommands, FaderLightingCommands,
+    AutosaveMode, ButtonGroupLightingCommands, ButtonLightingCommands, CompressorCommands,
+    CoughButtonBehaviours, DeviceSettingsCommands, DisplayNameCommands, EqualiserCommands,
+    EqualiserMiniCommands, FaderCommands, FaderHoldMode, FaderLightingCommands,
     FadersAllLightingCommands, LightingCommands, MicrophoneCommands, NoiseGateCommands,
-    ProfileAction, ProfileType, SubCommands,
+    ProfileAction, ProfileType, RoutingPresetCommands, SampleBankSwitchMode, SampleCommands,
+    SamplerCommands, ScriptCommands, SettingsCommands, SubCommands, ThemeCommands, VolumeCommands,
 };
 use crate::microphone::apply_microphone_controls;
+use crate::tui::run_tui;
 use anyhow::{anyhow, Context, Result};
-use clap::Parser;
+use clap::{ArgEnum, ErrorKind, IntoApp, Parser};
+use clap_complete::generate;
 use cli::Cli;
 use goxlr_ipc::client::Client;
-use goxlr_ipc::{DaemonRequest, DaemonResponse, DeviceType, MixerStatus, UsbProductInformation};
-use goxlr_ipc::{GoXLRCommand, Socket};
-use goxlr_types::{ChannelName, FaderName, InputDevice, MicrophoneType, OutputDevice};
+use goxlr_ipc::{volume_to_percent, GoXLRCommand, Socket, PROTOCOL_VERSION};
+use goxlr_ipc::{
+    AuditEntry, Autosave, DaemonEvent, DaemonHealth, DaemonRequest, DaemonResponse, DaemonStatus,
+    DeviceFeatures, DeviceSettings, DeviceSummary, DeviceType, DiagnosticReport,
+    EncoderAcceleration, FaderHold, FaderHoldAction, HardwareInfo, HsvColour, InputEvent, Lighting,
+    MicCalibrationState, MixerStatus, MuteButtonState, MuteState, MuteStateReport,
+    SampleBankSwitch, SampleFile, SampleQuery, StateVerification, UpdateStatus,
+    UsbProductInformation,
+};
+use goxlr_types::{
+    ChannelName, FaderName, InputDevice, MicrophoneType, MuteFunction, OutputDevice, SampleBank,
+    SampleButtons,
+};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::Duration;
 use strum::IntoEnumIterator;
 use tokio::net::UnixStream;
+use tokio::time::sleep;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli: Cli = Cli::parse();
+    let cli = parse_cli().await;
+    let result = run(&cli).await;
+
+    if cli.json {
+        if let Err(error) = result {
+            println!("{}", serde_json::json!({ "error": format!("{:#}", error) }));
+            std::process::exit(1);
+        }
+    }
+
+    result
+}
+
+// Channels and outputs can be given by their display names, which only the daemon knows, so
+// arguments clap rejects are tried again with those swapped for the built in names.
+async fn parse_cli() -> Cli {
+    let error = match Cli::try_parse() {
+        Ok(cli) => return cli,
+        Err(error) => error,
+    };
+    if error.kind() != ErrorKind::InvalidValue {
+        error.exit();
+    }
+
+    let names = match get_display_names().await {
+        Ok(names) if !names.is_empty() => names,
+        _ => error.exit(),
+    };
+    let args = std::env::args_os().map(|arg| {
+        match arg.to_str().and_then(|arg| names.get(&arg.to_lowercase())) {
+            Some(name) => OsString::from(name),
+            None => arg,
+        }
+    });
+    Cli::try_parse_from(args).unwrap_or_else(|_| error.exit())
+}
+
+// The display names of every connected device, mapped to the names clap expects.
+async fn get_display_names() -> Result<HashMap<String, String>> {
+    let mut client = connect().await?;
+    client.poll_status().await?;
+
+    let mut names = HashMap::new();
+    for mixer in client.status().mixers.values() {
+        for channel in ChannelName::iter() {
+            if let Some(name) = &mixer.channel_names[channel as usize] {
+                names.insert(name.to_lowercase(), get_arg_name(channel));
+            }
+        }
+        for output in OutputDevice::iter() {
+            if let Some(name) = &mixer.output_names[output as usize] {
+                names.insert(name.to_lowercase(), get_arg_name(output));
+            }
+        }
+    }
+    Ok(names)
+}
+
+fn get_arg_name<T: ArgEnum>(value: T) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+async fn connect() -> Result<Client> {
     let stream = UnixStream::connect("/tmp/goxlr.socket")
         .await
         .context("Could not connect to the GoXLR daemon process")?;
@@ -28,7 +114,206 @@ async fn main() -> Result<()> {
         .peer_addr()
         .context("Could not get the address of the GoXLR daemon process")?;
     let socket: Socket<DaemonResponse, DaemonRequest> = Socket::new(address, stream);
-    let mut client = Client::new(socket);
+    Ok(Client::new(socket))
+}
+
+async fn run(cli: &Cli) -> Result<()> {
+    if let Some(SubCommands::Completions { shell }) = &cli.subcommands {
+        generate(
+            *shell,
+            &mut Cli::into_app(),
+            "goxlr-client",
+            &mut io::stdout(),
+        );
+        return Ok(());
+    }
+
+    let mut client = connect().await?;
+    client.set_dry_run(cli.dry_run);
+
+    let protocol_version = client.protocol_version().await;
+    if protocol_version == 0 {
+        // The request may still be waiting for an answer, or have closed the connection.
+        client = connect().await?;
+        client.set_dry_run(cli.dry_run);
+    }
+    if protocol_version != PROTOCOL_VERSION {
+        eprintln!(
+            "Warning: The daemon uses protocol version {}, this client uses version {}. \
+            Some commands may not work.",
+            protocol_version, PROTOCOL_VERSION
+        );
+    }
+
+    if let Some(SubCommands::Daemon {}) = &cli.subcommands {
+        let health = client.daemon_health().await?;
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&health)?);
+        } else {
+            print_daemon_health(&health);
+        }
+        return Ok(());
+    }
+
+    if let Some(SubCommands::RestartDaemon {}) = &cli.subcommands {
+        return client.restart_daemon().await;
+    }
+
+    if let Some(SubCommands::UiUrl {}) = &cli.subcommands {
+        println!("{}", client.ui_url().await?);
+        return Ok(());
+    }
+
+    if let Some(SubCommands::Devices {}) = &cli.subcommands {
+        let devices = client.device_summary().await?;
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&devices)?);
+        } else if devices.is_empty() {
+            println!("No devices connected");
+        } else {
+            for device in &devices {
+                print_device_summary(device);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(SubCommands::Notifications {}) = &cli.subcommands {
+        client.subscribe_notifications().await?;
+        loop {
+            let notification = client.wait_for_notification().await?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&notification)?);
+            } else {
+                match &notification.serial {
+                    Some(serial) => println!("[{}] {}", serial, notification.message),
+                    None => println!("{}", notification.message),
+                }
+            }
+        }
+    }
+
+    if let Some(SubCommands::AuditLog {}) = &cli.subcommands {
+        let entries = client.audit_log().await?;
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        } else {
+            for entry in &entries {
+                print_audit_entry(entry);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(SubCommands::Events {}) = &cli.subcommands {
+        return watch_events(&mut client, cli.device.as_deref(), cli.json).await;
+    }
+
+    if let Some(SubCommands::ProfileBackups { name, mic, restore }) = &cli.subcommands {
+        let profile_type = match mic {
+            true => goxlr_ipc::ProfileType::MicProfile,
+            false => goxlr_ipc::ProfileType::Profile,
+        };
+        if let Some(id) = restore {
+            return client.restore_profile_backup(profile_type, name, id).await;
+        }
+
+        let backups = client.profile_backups(profile_type, name).await?;
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&backups)?);
+        } else if backups.is_empty() {
+            println!("No backups of {}", name);
+        } else {
+            for backup in backups {
+                println!("{} ({} bytes)", backup.id, backup.size);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(SubCommands::Samples { command }) = &cli.subcommands {
+        return run_sample_command(&mut client, command, cli.json).await;
+    }
+
+    if let Some(SubCommands::Scripts { command }) = &cli.subcommands {
+        return run_script_command(&mut client, command, cli.json).await;
+    }
+
+    // Settings belong to the daemon rather than a device, so don't need one to be selected.
+    if let Some(SubCommands::Settings { command }) = &cli.subcommands {
+        match command {
+            SettingsCommands::Info {} => {
+                let info = client.settings_info().await?;
+                if cli.json {
+                    println!("{}", serde_json::to_string_pretty(&info)?);
+                } else {
+                    println!("Settings file: {}", info.path.to_string_lossy());
+                    println!("Settings version: {}", info.version);
+                }
+                return Ok(());
+            }
+            SettingsCommands::Flush {} => {
+                client
+                    .flush_settings()
+                    .await
+                    .context("Unable to save the settings")?;
+                return Ok(());
+            }
+            SettingsCommands::Defaults { command } => {
+                let mut settings = client.default_device_settings().await?;
+                if update_device_settings(&mut settings, command) {
+                    client
+                        .set_default_device_settings(settings)
+                        .await
+                        .context("Unable to update the default device settings")?;
+                } else {
+                    print_device_settings(&settings, cli.json)?;
+                }
+                return Ok(());
+            }
+            SettingsCommands::Updates {
+                enabled,
+                download_directory,
+            } => {
+                client.poll_status().await?;
+                let mut update_settings = client.status().updates.settings.clone();
+                if enabled.is_none() && download_directory.is_none() {
+                    print_update_status(&client.status().updates, cli.json)?;
+                    return Ok(());
+                }
+
+                if let Some(enabled) = enabled {
+                    update_settings.enabled = *enabled;
+                }
+                if let Some(directory) = download_directory {
+                    update_settings.download_directory =
+                        (!directory.as_os_str().is_empty()).then(|| directory.clone());
+                }
+                client
+                    .set_update_settings(update_settings)
+                    .await
+                    .context("Unable to update the update check settings")?;
+                return Ok(());
+            }
+            SettingsCommands::CustomUiDirectory { directory } => {
+                let directory = match directory {
+                    Some(directory) => Some(
+                        directory
+                            .canonicalize()
+                            .context("Unable to find the custom UI directory")?,
+                    ),
+                    None => None,
+                };
+                client
+                    .set_custom_ui_directory(directory)
+                    .await
+                    .context("Unable to set the custom UI directory")?;
+                return Ok(());
+            }
+            SettingsCommands::Device { .. } => {}
+        }
+    }
+
     client.poll_status().await?;
 
     let serial = if let Some(serial) = &cli.device {
@@ -58,12 +343,6 @@ async fn main() -> Result<()> {
         .await
         .context("Could not apply microphone controls")?;
 
-    // These will be moved around later :)
-    match &cli.subcommands {
-        None => {}
-        Some(_) => {}
-    }
-
     match &cli.subcommands {
         None => {}
         Some(command) => {
@@ -93,6 +372,16 @@ async fn main() -> Result<()> {
                                 .await?;
                         }
                     },
+                    MicrophoneCommands::EqualiserPreset { preset } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetEqPreset(*preset))
+                            .await?;
+                    }
+                    MicrophoneCommands::EqualiserReset {} => {
+                        client
+                            .command(&serial, GoXLRCommand::ResetEqualiser())
+                            .await?;
+                    }
                     MicrophoneCommands::NoiseGate { command } => match command {
                         NoiseGateCommands::Threshold { value } => {
                             client
@@ -119,6 +408,11 @@ async fn main() -> Result<()> {
                                 .command(&serial, GoXLRCommand::SetGateActive(*enabled))
                                 .await?;
                         }
+                        NoiseGateCommands::Amount { value } => {
+                            client
+                                .command(&serial, GoXLRCommand::SetGateAmount(*value))
+                                .await?;
+                        }
                     },
                     MicrophoneCommands::Compressor { command } => match command {
                         CompressorCommands::Threshold { value } => {
@@ -146,7 +440,33 @@ async fn main() -> Result<()> {
                                 .command(&serial, GoXLRCommand::SetCompressorMakeupGain(*value))
                                 .await?;
                         }
+                        CompressorCommands::Amount { value } => {
+                            client
+                                .command(&serial, GoXLRCommand::SetCompressorAmount(*value))
+                                .await?;
+                        }
                     },
+                    MicrophoneCommands::Type { mic_type, confirm } => {
+                        client
+                            .command(
+                                &serial,
+                                GoXLRCommand::SetMicrophoneType(*mic_type, *confirm),
+                            )
+                            .await?;
+                    }
+                    MicrophoneCommands::Gain { mic_type, gain } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetMicrophoneGain(*mic_type, *gain))
+                            .await?;
+                    }
+                    MicrophoneCommands::DeEsser { amount } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetDeEsser(*amount))
+                            .await?;
+                    }
+                    MicrophoneCommands::Calibrate { mic_type, apply } => {
+                        run_mic_calibration(&mut client, &serial, *mic_type, *apply).await?;
+                    }
                 },
                 SubCommands::Faders { fader } => match fader {
                     FaderCommands::Channel { fader, channel } => {
@@ -165,6 +485,33 @@ async fn main() -> Result<()> {
                             )
                             .await?;
                     }
+                    FaderCommands::Muted { fader, muted } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetFaderMuted(*fader, *muted))
+                            .await?;
+                    }
+                    FaderCommands::Pages { channels } => {
+                        if !channels.len().is_multiple_of(4) {
+                            return Err(anyhow!("Each page needs a channel for all four faders"));
+                        }
+                        let pages = channels
+                            .chunks(4)
+                            .map(|page| [page[0], page[1], page[2], page[3]])
+                            .collect();
+                        client
+                            .command(&serial, GoXLRCommand::SetFaderPages(pages))
+                            .await?;
+                    }
+                    FaderCommands::Page { page } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetFaderPage(*page))
+                            .await?;
+                    }
+                    FaderCommands::NextPage {} => {
+                        client
+                            .command(&serial, GoXLRCommand::NextFaderPage())
+                            .await?;
+                    }
                 },
                 SubCommands::Router {
                     input,
@@ -175,14 +522,168 @@ async fn main() -> Result<()> {
                         .command(&serial, GoXLRCommand::SetRouter(*input, *output, *enabled))
                         .await?;
                 }
+                SubCommands::RoutingPreset { command } => {
+                    let command = match command {
+                        RoutingPresetCommands::Save { name } => {
+                            GoXLRCommand::SaveRoutingPreset(name.clone())
+                        }
+                        RoutingPresetCommands::Apply { name } => {
+                            GoXLRCommand::ApplyRoutingPreset(name.clone())
+                        }
+                        RoutingPresetCommands::Delete { name } => {
+                            GoXLRCommand::DeleteRoutingPreset(name.clone())
+                        }
+                    };
+                    client.command(&serial, command).await?;
+                }
+                SubCommands::DisplayName { command } => {
+                    let command = match command {
+                        DisplayNameCommands::Channel { channel, name } => {
+                            GoXLRCommand::SetChannelDisplayName(*channel, name.clone())
+                        }
+                        DisplayNameCommands::Output { output, name } => {
+                            GoXLRCommand::SetOutputDisplayName(*output, name.clone())
+                        }
+                    };
+                    client.command(&serial, command).await?;
+                }
                 SubCommands::Volume {
                     channel,
                     volume_percent,
                 } => {
-                    let value = (255 * *volume_percent as u16) / 100;
+                    client
+                        .command(
+                            &serial,
+                            GoXLRCommand::SetVolumePercent(*channel, *volume_percent),
+                        )
+                        .await?;
+                }
+                SubCommands::Volumes { command } => match command {
+                    VolumeCommands::Set { channel, volume } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetVolume(*channel, *volume))
+                            .await?;
+                    }
+                    VolumeCommands::SetDb { channel, db } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetVolumeDb(*channel, *db))
+                            .await?;
+                    }
+                    VolumeCommands::Limit { channel, limit } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetVolumeLimit(*channel, *limit))
+                            .await?;
+                    }
+                    VolumeCommands::Link { channels } => {
+                        client
+                            .command(&serial, GoXLRCommand::LinkChannels(channels.clone()))
+                            .await?;
+                    }
+                    VolumeCommands::Unlink { channel } => {
+                        client
+                            .command(&serial, GoXLRCommand::UnlinkChannel(*channel))
+                            .await?;
+                    }
+                    VolumeCommands::SubMix { channel, volume } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetSubMixVolume(*channel, *volume))
+                            .await?;
+                    }
+                    VolumeCommands::SubMixLinked { channel, linked } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetSubMixLinked(*channel, *linked))
+                            .await?;
+                    }
+                    VolumeCommands::Get { channel } => {
+                        client.poll_status().await?;
+                        let mixer = client
+                            .status()
+                            .mixers
+                            .get(&serial)
+                            .context("Device not found")?;
+                        let channels: Vec<ChannelName> = match channel {
+                            Some(channel) => vec![*channel],
+                            None => ChannelName::iter().collect(),
+                        };
 
+                        if cli.json {
+                            let mut volumes = serde_json::Map::new();
+                            for channel in channels {
+                                volumes.insert(
+                                    channel.to_string(),
+                                    serde_json::json!({
+                                        "volume": mixer.get_channel_volume(channel),
+                                        "db": mixer.volumes_db[channel as usize],
+                                    }),
+                                );
+                            }
+                            println!("{}", serde_json::to_string_pretty(&volumes)?);
+                        } else {
+                            for channel in channels {
+                                println!(
+                                    "{}: {} ({:.1}dB)",
+                                    channel,
+                                    mixer.get_channel_volume(channel),
+                                    mixer.volumes_db[channel as usize]
+                                );
+                            }
+                        }
+                    }
+                },
+                SubCommands::Mute { channel, muted } => {
+                    client
+                        .command(&serial, GoXLRCommand::SetChannelMuted(*channel, *muted))
+                        .await?;
+                }
+                SubCommands::StreamPanic { enabled } => {
+                    let enabled = match enabled {
+                        Some(enabled) => *enabled,
+                        None => {
+                            client.poll_status().await?;
+                            let mixer = client
+                                .status()
+                                .mixers
+                                .get(&serial)
+                                .context("Device not found")?;
+                            !mixer.stream_panic
+                        }
+                    };
+                    client
+                        .command(&serial, GoXLRCommand::SetStreamPanic(enabled))
+                        .await?;
+                }
+                SubCommands::Solo { channel, soloed } => {
+                    let soloed = match soloed {
+                        Some(soloed) => *soloed,
+                        None => {
+                            client.poll_status().await?;
+                            let mixer = client
+                                .status()
+                                .mixers
+                                .get(&serial)
+                                .context("Device not found")?;
+                            !mixer.soloed_channels.contains(channel)
+                        }
+                    };
+                    client
+                        .command(&serial, GoXLRCommand::SetSolo(*channel, soloed))
+                        .await?;
+                }
+                SubCommands::Lock { locked } => {
+                    let locked = match locked {
+                        Some(locked) => *locked,
+                        None => {
+                            client.poll_status().await?;
+                            let mixer = client
+                                .status()
+                                .mixers
+                                .get(&serial)
+                                .context("Device not found")?;
+                            !mixer.locked
+                        }
+                    };
                     client
-                        .command(&serial, GoXLRCommand::SetVolume(*channel, value as u8))
+                        .command(&serial, GoXLRCommand::SetLocked(locked))
                         .await?;
                 }
                 SubCommands::CoughButton { command } => match command {
@@ -191,11 +692,78 @@ async fn main() -> Result<()> {
                             .command(&serial, GoXLRCommand::SetCoughIsHold(*is_hold))
                             .await?;
                     }
+                    CoughButtonBehaviours::ButtonMode { mode } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetCoughMode(*mode))
+                            .await?;
+                    }
                     CoughButtonBehaviours::MuteBehaviour { mute_behaviour } => {
                         client
                             .command(&serial, GoXLRCommand::SetCoughMuteFunction(*mute_behaviour))
                             .await?;
                     }
+                    CoughButtonBehaviours::Muted { muted } => {
+                        client
+                            .command(&serial, GoXLRCommand::SetCoughMuted(*muted))
+                            .await?;
+                    }
+                },
+                SubCommands::Sampler { command } => match command {
+                    SamplerCommands::Trim {
+                        bank,
+                        button,
+                        track,
+                        start_percent,
+                        end_percent,
+                    } => {
+                        let command = GoXLRCommand::SetSampleTrim(
+                            *bank,
+                            *button,
+                            *track,
+                            *start_percent,
+                            *end_percent,
+                        );
+                        client
+                            .command(&serial, command)
+                            .await
+                            .context("Unable to trim the sample")?;
+                    }
+                    SamplerCommands::ClearBank { bank } => {
+                        client
+                            .command(&serial, GoXLRCommand::ClearSampleBank(*bank))
+                            .await?;
+                    }
+                    SamplerCommands::CopyBank { from, to } => {
+                        client
+                            .command(&serial, GoXLRCommand::CopySampleBank(*from, *to))
+                            .await?;
+                    }
+                    SamplerCommands::SwapBanks { first, second } => {
+                        client
+                            .command(&serial, GoXLRCommand::SwapSampleBanks(*first, *second))
+                            .await?;
+                    }
+                    SamplerCommands::BankColours {
+                        bank,
+                        colour_one,
+                        colour_two,
+                    } => {
+                        client
+                            .command(
+                                &serial,
+                                GoXLRCommand::SetSampleBankColours(
+                                    *bank,
+                                    colour_one.to_string(),
+                                    colour_two.clone(),
+                                ),
+                            )
+                            .await?;
+                    }
+                    SamplerCommands::ClearBankColours { bank } => {
+                        client
+                            .command(&serial, GoXLRCommand::ClearSampleBankColours(*bank))
+                            .await?;
+                    }
                 },
                 SubCommands::BleepVolume { volume_percent } => {
                     // Ok, this is a value between -34 and 0, with 0 being loudest :D
@@ -207,6 +775,12 @@ async fn main() -> Result<()> {
                         )
                         .await?;
                 }
+                SubCommands::BleepOutputs { outputs } => {
+                    let outputs = outputs.iter().copied().collect();
+                    client
+                        .command(&serial, GoXLRCommand::SetSwearButtonOutputs(outputs))
+                        .await?;
+                }
 
                 SubCommands::Lighting { command } => match command {
                     LightingCommands::Fader { command } => match command {
@@ -218,6 +792,18 @@ async fn main() -> Result<()> {
                                 )
                                 .await?;
                         }
+                        FaderLightingCommands::DisplayMode {
+                            fader,
+                            gradient,
+                            meter,
+                        } => {
+                            client
+                                .command(
+                                    &serial,
+                                    GoXLRCommand::SetFaderDisplayMode(*fader, *gradient, *meter),
+                                )
+                                .await?;
+                        }
                         FaderLightingCommands::Colour { fader, top, bottom } => {
                             client
                                 .command(
@@ -279,6 +865,32 @@ async fn main() -> Result<()> {
                                 )
                                 .await?;
                         }
+                        ButtonLightingCommands::ColourHsv {
+                            button,
+                            hue,
+                            saturation,
+                            value,
+                        } => {
+                            let colour = HsvColour {
+                                hue: *hue,
+                                saturation: *saturation,
+                                value: *value,
+                            };
+                            client
+                                .command(
+                                    &serial,
+                                    GoXLRCommand::SetButtonColoursHsv(*button, colour, None),
+                                )
+                                .await?;
+                        }
+                        ButtonLightingCommands::Brightness { button, delta } => {
+                            client
+                                .command(
+                                    &serial,
+                                    GoXLRCommand::AdjustButtonBrightness(*button, *delta),
+                                )
+                                .await?;
+                        }
                     },
                     LightingCommands::ButtonGroup { command } => match command {
                         ButtonGroupLightingCommands::Colour {
@@ -310,21 +922,244 @@ async fn main() -> Result<()> {
                                 )
                                 .await?;
                         }
+                        ButtonGroupLightingCommands::ShiftHue { group, degrees } => {
+                            client
+                                .command(&serial, GoXLRCommand::ShiftHue(*group, *degrees))
+                                .await?;
+                        }
                     },
-                },
-
-                SubCommands::Profiles { command } => match command {
-                    ProfileType::Device { command } => match command {
-                        ProfileAction::Load { profile_name } => {
+                    LightingCommands::Channel { channel, colour } => {
+                        client
+                            .command(
+                                &serial,
+                                GoXLRCommand::SetChannelColour(*channel, colour.clone()),
+                            )
+                            .await?;
+                    }
+                    LightingCommands::Brightness { delta } => {
+                        client
+                            .command(&serial, GoXLRCommand::AdjustAllBrightness(*delta))
+                            .await?;
+                    }
+                    LightingCommands::Theme { command } => match command {
+                        ThemeCommands::List {} => {
+                            client.poll_status().await?;
+                            let themes = &client.status().files.themes;
+                            if cli.json {
+                                println!("{}", serde_json::to_string_pretty(themes)?);
+                            } else {
+                                print_list("Themes", themes);
+                            }
+                        }
+                        ThemeCommands::Load { theme_name } => {
                             client
                                 .command(
                                     &serial,
-                                    GoXLRCommand::LoadProfile(profile_name.to_string()),
+                                    GoXLRCommand::LoadLightingTheme(theme_name.to_string()),
                                 )
                                 .await
-                                .context("Unable to Load Profile")?;
+                                .context("Unable to Load Theme")?;
                         }
-                        ProfileAction::Save {} => {
+                        ThemeCommands::Save { theme_name } => {
+                            client
+                                .command(
+                                    &serial,
+                                    GoXLRCommand::SaveLightingTheme(theme_name.to_string()),
+                                )
+                                .await
+                                .context("Unable to Save Theme")?;
+                        }
+                        ThemeCommands::Export { path } => {
+                            client.poll_status().await?;
+                            let lighting = &client
+                                .status()
+                                .mixers
+                                .get(&serial)
+                                .context("Device not found")?
+                                .lighting;
+                            let writer =
+                                File::create(path).context("Unable to create theme file")?;
+                            serde_json::to_writer_pretty(writer, lighting)?;
+                        }
+                        ThemeCommands::Import { path } => {
+                            let reader = File::open(path).context("Unable to open theme file")?;
+                            let theme: Lighting =
+                                serde_json::from_reader(reader).context("Invalid theme file")?;
+                            client
+                                .command(&serial, GoXLRCommand::ApplyLightingTheme(theme))
+                                .await
+                                .context("Unable to Apply Theme")?;
+                        }
+                    },
+                },
+
+                SubCommands::Completions { .. }
+                | SubCommands::Daemon {}
+                | SubCommands::RestartDaemon {}
+                | SubCommands::UiUrl {}
+                | SubCommands::Devices {}
+                | SubCommands::Notifications {}
+                | SubCommands::AuditLog {}
+                | SubCommands::Events {}
+                | SubCommands::ProfileBackups { .. }
+                | SubCommands::Samples { .. }
+                | SubCommands::Scripts { .. } => {}
+                SubCommands::Settings { command } => {
+                    if let SettingsCommands::Device { command } = command {
+                        let mut settings = client.device_settings(&serial).await?;
+                        if update_device_settings(&mut settings, command) {
+                            client
+                                .set_device_settings(&serial, settings)
+                                .await
+                                .context("Unable to update the device settings")?;
+                        } else {
+                            print_device_settings(&settings, cli.json)?;
+                        }
+                    }
+                }
+                SubCommands::Tui {} => {
+                    run_tui(&mut client, &serial).await?;
+                }
+                SubCommands::SyncToHardware {} => {
+                    client
+                        .command(&serial, GoXLRCommand::SyncToHardware())
+                        .await
+                        .context("Unable to sync the device")?;
+                }
+                SubCommands::Diagnostics {} => {
+                    if !cli.json {
+                        println!("Running diagnostics, the device's lights will cycle..");
+                    }
+                    let report = client.run_diagnostics(&serial).await?;
+                    if cli.json {
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                    } else {
+                        print_diagnostics(&report);
+                    }
+                }
+                SubCommands::RawCommand { command_id, body } => {
+                    let command_id = u32::from_str_radix(command_id, 16)
+                        .context("The command id must be in hex")?;
+                    let body = match body {
+                        Some(body) => parse_hex(body).context("The body must be in hex")?,
+                        None => vec![],
+                    };
+                    let response = client.send_raw_command(&serial, command_id, body).await?;
+                    let hex: Vec<String> = response.iter().map(|b| format!("{:02x}", b)).collect();
+                    println!("{}", hex.concat());
+                }
+                SubCommands::MuteState {} => {
+                    let report = client.mute_state(&serial).await?;
+                    if cli.json {
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                    } else {
+                        print_mute_state(&report);
+                    }
+                }
+                SubCommands::HardwareInfo {} => {
+                    let info = client.hardware_info(&serial).await?;
+                    if cli.json {
+                        println!("{}", serde_json::to_string_pretty(&info)?);
+                    } else {
+                        print_hardware_info(&info);
+                    }
+                }
+                SubCommands::VerifyState {} => {
+                    let report = client.verify_state(&serial).await?;
+                    if cli.json {
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                    } else {
+                        print_state_verification(&report);
+                    }
+                }
+
+                SubCommands::Status {} => {
+                    client.poll_status().await?;
+                    if cli.json {
+                        println!("{}", serde_json::to_string_pretty(client.status())?);
+                    } else {
+                        print_status(client.status());
+                    }
+                }
+
+                SubCommands::Profiles { command } => match command {
+                    ProfileType::List {} => {
+                        client.poll_status().await?;
+                        let files = &client.status().files;
+                        if cli.json {
+                            println!("{}", serde_json::to_string_pretty(files)?);
+                        } else {
+                            print_list("Profiles", &files.profiles);
+                            print_list("Mic Profiles", &files.mic_profiles);
+                            print_list("Built in Profiles", &files.embedded_profiles);
+                        }
+                    }
+                    ProfileType::QuickSwitch {} => {
+                        client
+                            .command(&serial, GoXLRCommand::QuickSwitchProfile())
+                            .await
+                            .context("Unable to Switch Profile")?;
+                    }
+                    ProfileType::LoadEmbedded { profile_name } => {
+                        client
+                            .command(
+                                &serial,
+                                GoXLRCommand::LoadEmbeddedProfile(profile_name.to_string()),
+                            )
+                            .await
+                            .context("Unable to Load Profile")?;
+                    }
+                    ProfileType::Apply {
+                        profile_name,
+                        sections,
+                    } => {
+                        client
+                            .command(
+                                &serial,
+                                GoXLRCommand::ApplyProfileSections(
+                                    profile_name.to_string(),
+                                    sections.clone(),
+                                ),
+                            )
+                            .await
+                            .context("Unable to Apply Profile")?;
+                    }
+                    ProfileType::ExportMicrophone { path } => {
+                        let json = client.export_mic_profile(&serial).await?;
+                        match path {
+                            Some(path) => std::fs::write(path, json)
+                                .context("Unable to write the Microphone Profile")?,
+                            None => println!("{}", json),
+                        }
+                    }
+                    ProfileType::ImportMicrophone { path } => {
+                        let json = std::fs::read_to_string(path)
+                            .context("Unable to read the Microphone Profile")?;
+                        client
+                            .command(&serial, GoXLRCommand::ImportMicProfile(json))
+                            .await
+                            .context("Unable to Import Microphone Profile")?;
+                    }
+                    ProfileType::Device { command } => match command {
+                        ProfileAction::List {} => {
+                            client.poll_status().await?;
+                            let profiles = &client.status().files.profiles;
+                            if cli.json {
+                                println!("{}", serde_json::to_string_pretty(profiles)?);
+                            } else {
+                                print_list("Profiles", profiles);
+                            }
+                        }
+                        ProfileAction::Load { profile_name } => {
+                            client
+                                .command(
+                                    &serial,
+                                    GoXLRCommand::LoadProfile(profile_name.to_string()),
+                                )
+                                .await
+                                .context("Unable to Load Profile")?;
+                        }
+                        ProfileAction::Save {} => {
                             client
                                 .command(&serial, GoXLRCommand::SaveProfile())
                                 .await
@@ -341,6 +1176,15 @@ async fn main() -> Result<()> {
                         }
                     },
                     ProfileType::Microphone { command } => match command {
+                        ProfileAction::List {} => {
+                            client.poll_status().await?;
+                            let profiles = &client.status().files.mic_profiles;
+                            if cli.json {
+                                println!("{}", serde_json::to_string_pretty(profiles)?);
+                            } else {
+                                print_list("Mic Profiles", profiles);
+                            }
+                        }
                         ProfileAction::Load { profile_name } => {
                             client
                                 .command(
@@ -378,30 +1222,805 @@ async fn main() -> Result<()> {
 
     if cli.status {
         client.poll_status().await?;
+        print_status(client.status());
+    }
+
+    Ok(())
+}
+
+async fn run_mic_calibration(
+    client: &mut Client,
+    serial: &str,
+    mic_type: MicrophoneType,
+    apply: bool,
+) -> Result<()> {
+    client
+        .send(DaemonRequest::StartMicCalibration(
+            serial.to_string(),
+            mic_type,
+            apply,
+        ))
+        .await
+        .context("Unable to start Microphone Calibration")?;
+
+    println!(
+        "Calibrating {} microphone, please speak normally..",
+        mic_type
+    );
+    loop {
+        sleep(Duration::from_millis(250)).await;
+        client.poll_status().await?;
+
+        let calibration = client
+            .status()
+            .mixers
+            .get(serial)
+            .and_then(|mixer| mixer.mic_status.calibration.clone())
+            .context("Device is no longer reporting calibration status")?;
+
+        match calibration.state {
+            MicCalibrationState::Running => {
+                print!("\rProgress: {}%", calibration.progress);
+                io::stdout().flush()?;
+            }
+            MicCalibrationState::Failed => {
+                println!();
+                return Err(anyhow!("No microphone input was detected"));
+            }
+            MicCalibrationState::Complete => {
+                println!();
+                println!("Peak level: {:.1} dB", calibration.peak);
+                if let Some(gain) = calibration.recommended_gain {
+                    println!("Recommended gain: {} dB", gain);
+                }
+                if calibration.applied {
+                    println!("Recommended gain has been applied");
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn print_device_summary(device: &DeviceSummary) {
+    println!("{} ({:?})", device.serial, device.device_type);
+    println!("  Profile: {}", device.profile_name);
+    println!("  Mic Profile: {}", device.mic_profile_name);
+
+    let mut states = vec![];
+    if device.mic_muted {
+        states.push("Mic muted");
+    }
+    if device.stream_panic {
+        states.push("Stream panic");
+    }
+    if device.locked {
+        states.push("Locked");
+    }
+    if !states.is_empty() {
+        println!("  {}", states.join(", "));
+    }
+}
+
+fn print_status(status: &DaemonStatus) {
+    println!(
+        "Profile directory: {}",
+        status.paths.profile_directory.to_string_lossy()
+    );
+    println!(
+        "Mic Profile directory: {}",
+        status.paths.mic_profile_directory.to_string_lossy()
+    );
+    println!(
+        "Samples directory: {}",
+        status.paths.samples_directory.to_string_lossy()
+    );
+    for mixer in status.mixers.values() {
+        print_device(mixer);
+    }
+}
+
+// Runs until the daemon goes away, printing events for the selected device (or all of them).
+async fn watch_events(client: &mut Client, serial: Option<&str>, json: bool) -> Result<()> {
+    client.subscribe().await?;
+    loop {
+        let event = match client.wait_for_change().await? {
+            Some(event) => event,
+            None => continue,
+        };
+
+        let event_serial = match &event {
+            DaemonEvent::SampleRecorded { serial, .. } => serial,
+            DaemonEvent::Input { serial, .. } => serial,
+            DaemonEvent::Resumed { serial } => serial,
+            DaemonEvent::PhantomPower { serial, .. } => serial,
+            DaemonEvent::MicLevel { serial, .. } => serial,
+        };
+        if serial.is_some_and(|serial| serial != event_serial) {
+            continue;
+        }
+
+        if json {
+            println!("{}", serde_json::to_string(&event)?);
+        } else {
+            print_event(&event);
+        }
+    }
+}
+
+fn print_event(event: &DaemonEvent) {
+    match event {
+        DaemonEvent::SampleRecorded {
+            serial,
+            bank,
+            button,
+            file,
+        } => println!(
+            "[{}] Recorded {} to sample bank {:?} {:?}",
+            serial, file, bank, button
+        ),
+        DaemonEvent::Input {
+            serial,
+            time,
+            input,
+        } => {
+            let description = match input {
+                InputEvent::ButtonDown(button) => format!("{:?} pressed", button),
+                InputEvent::ButtonUp(button) => format!("{:?} released", button),
+                InputEvent::ButtonHeld(button) => format!("{:?} held", button),
+                InputEvent::FaderMoved(fader, value) => {
+                    format!("Fader {} moved to {}", fader, value)
+                }
+                InputEvent::EncoderTurned(encoder, value) => {
+                    format!("{} encoder turned to {}", encoder, value)
+                }
+            };
+            println!("[{}] {} {}", serial, time, description);
+        }
+        DaemonEvent::Resumed { serial } => {
+            println!("[{}] Settings re-applied after waking from sleep", serial)
+        }
+        DaemonEvent::PhantomPower { serial, enabled } => {
+            let state = if *enabled { "on" } else { "off" };
+            println!("[{}] Warning: phantom power turned {}", serial, state)
+        }
+        DaemonEvent::MicLevel { serial, level } => {
+            let clipping = if level.clipping { " (clipping)" } else { "" };
+            println!(
+                "[{}] Mic level {:.1} dB, peak {:.1} dB{}",
+                serial, level.level, level.peak, clipping
+            )
+        }
+    }
+}
+
+async fn run_script_command(
+    client: &mut Client,
+    command: &ScriptCommands,
+    json: bool,
+) -> Result<()> {
+    match command {
+        ScriptCommands::List {} => {
+            client.poll_status().await?;
+            let status = client.status();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&status.scripts)?);
+                return Ok(());
+            }
+
+            println!(
+                "Script directory: {}",
+                status.paths.script_directory.to_string_lossy()
+            );
+            for script in &status.scripts {
+                let state = match script.enabled {
+                    true => "Enabled",
+                    false => "Disabled",
+                };
+                match &script.error {
+                    Some(error) => println!("{}: {}, {}", script.name, state, error),
+                    None => println!("{}: {}", script.name, state),
+                }
+            }
+        }
+        ScriptCommands::Enable { name } => client.set_script_enabled(name, true).await?,
+        ScriptCommands::Disable { name } => client.set_script_enabled(name, false).await?,
+        ScriptCommands::Reload {} => client.reload_scripts().await?,
+    }
+    Ok(())
+}
+
+async fn run_sample_command(
+    client: &mut Client,
+    command: &SampleCommands,
+    json: bool,
+) -> Result<()> {
+    match command {
+        SampleCommands::List {
+            filter,
+            extension,
+            offset,
+            limit,
+        } => {
+            let query = SampleQuery {
+                filter: filter.clone(),
+                extension: extension.clone(),
+                offset: *offset,
+                limit: *limit,
+            };
+            let page = client.find_samples(query).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&page.samples)?);
+            } else {
+                print_samples(&page.samples);
+                if page.samples.len() < page.total {
+                    println!("Showing {} of {} samples", page.samples.len(), page.total);
+                }
+            }
+        }
+        SampleCommands::Add { file, path } => {
+            let path = match path {
+                Some(path) => path.to_owned(),
+                None => file
+                    .file_name()
+                    .context("The sample must be a file")?
+                    .to_string_lossy()
+                    .to_string(),
+            };
+            let data = std::fs::read(file).context("Unable to read the sample")?;
+            client
+                .add_sample(&path, data)
+                .await
+                .context("Unable to add the sample")?;
+        }
+        SampleCommands::Move { from, to } => {
+            client
+                .move_sample(from, to)
+                .await
+                .context("Unable to move the sample")?;
+        }
+        SampleCommands::Delete { path } => {
+            client
+                .delete_sample(path)
+                .await
+                .context("Unable to delete the sample")?;
+        }
+        SampleCommands::Waveform { path, points } => {
+            let peaks = client
+                .sample_waveform(path, *points)
+                .await
+                .context("Unable to read the sample")?;
+            if json {
+                println!("{}", serde_json::to_string(&peaks)?);
+            } else {
+                print_waveform(&peaks);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_samples(samples: &[SampleFile]) {
+    println!("Samples:");
+    for sample in samples {
+        let kilobytes = sample.size / 1024;
+        match (sample.duration_ms, sample.sample_rate, sample.channels) {
+            (Some(duration), Some(rate), Some(channels)) => println!(
+                "  {} ({}, {}KB, {:.1}s, {}Hz, {} channels)",
+                sample.path,
+                sample.format,
+                kilobytes,
+                duration as f64 / 1000.0,
+                rate,
+                channels
+            ),
+            _ => println!("  {} ({}, {}KB)", sample.path, sample.format, kilobytes),
+        }
+    }
+}
+
+fn describe_mute_state(state: MuteState) -> String {
+    match state {
+        MuteState::Unmuted => "Unmuted".to_string(),
+        MuteState::MutedToAll => "Muted".to_string(),
+        MuteState::MutedTo(outputs) => {
+            let outputs: Vec<String> = outputs.iter().map(|output| output.to_string()).collect();
+            format!("Muted to {}", outputs.join(", "))
+        }
+    }
+}
+
+fn print_waveform(peaks: &[f32]) {
+    let count = peaks.len();
+    for (index, peak) in peaks.iter().enumerate() {
+        let bar = "#".repeat((peak * 60.0).round() as usize);
+        println!("{:>3}% {}", index * 100 / count, bar);
+    }
+}
+
+fn print_list(title: &str, items: &[String]) {
+    println!("{}:", title);
+    for item in items {
+        println!("  {}", item);
+    }
+}
+
+fn print_audit_entry(entry: &AuditEntry) {
+    let client = &entry.client;
+    let mut who = vec![];
+    if let Some(name) = &client.name {
+        who.push(name.clone());
+    }
+    if let Some(pid) = client.pid {
+        who.push(format!("pid {}", pid));
+    }
+    if let Some(uid) = client.uid {
+        who.push(format!("uid {}", uid));
+    }
+    if let Some(address) = &client.address {
+        who.push(address.clone());
+    }
+    if who.is_empty() {
+        who.push("Unknown client".to_string());
+    }
+
+    print!(
+        "[{}] {} {}: {:?}",
+        entry.serial,
+        entry.time,
+        who.join(", "),
+        entry.command
+    );
+    match &entry.error {
+        Some(error) => println!(" (failed: {})", error),
+        None => println!(),
+    }
+}
+
+fn print_daemon_health(health: &DaemonHealth) {
+    println!("Daemon Version: {}", health.version);
+    println!("Uptime: {}s", health.uptime_secs);
+    println!("Settings: {}", health.settings_path.to_string_lossy());
+    let addresses = match health.http.addresses.is_empty() {
+        true => health.http.address.iter().cloned().collect(),
+        false => health.http.addresses.clone(),
+    };
+    match health.http.enabled && !addresses.is_empty() {
+        true => {
+            for address in addresses {
+                println!("HTTP Server: http://{}", address);
+            }
+        }
+        false => println!("HTTP Server: Disabled"),
+    }
+
+    if health.devices.is_empty() {
+        println!("No devices connected");
+    }
+    for device in &health.devices {
         println!(
-            "Profile directory: {}",
-            client.status().paths.profile_directory.to_string_lossy()
+            "{} ({:?}): {} USB errors",
+            device.serial, device.device_type, device.usb_errors
         );
+        if let Some(error) = &device.last_error {
+            println!("    Last error: {}", error);
+        }
+    }
+}
+
+// Applies a change from the command line, returning false if there was nothing to change.
+fn update_device_settings(settings: &mut DeviceSettings, command: &DeviceSettingsCommands) -> bool {
+    match command {
+        DeviceSettingsCommands::Show {} => return false,
+        DeviceSettingsCommands::Profile { profile_name } => {
+            settings.profile = profile_name.to_owned();
+        }
+        DeviceSettingsCommands::MicProfile { mic_profile_name } => {
+            settings.mic_profile = mic_profile_name.to_owned();
+        }
+        DeviceSettingsCommands::HoldTime { milliseconds } => settings.hold_time = *milliseconds,
+        DeviceSettingsCommands::SamplesDirectory { path } => {
+            settings.samples_directory = path.clone();
+        }
+        DeviceSettingsCommands::BleepSound { path } => settings.bleep_sound = path.clone(),
+        DeviceSettingsCommands::BleepAttack { milliseconds } => {
+            settings.bleep_attack = *milliseconds;
+        }
+        DeviceSettingsCommands::BleepRelease { milliseconds } => {
+            settings.bleep_release = *milliseconds;
+        }
+        DeviceSettingsCommands::PhantomPowerInterlock { enabled } => {
+            settings.phantom_power_interlock = *enabled;
+        }
+        DeviceSettingsCommands::PhantomPowerMute { milliseconds } => {
+            settings.phantom_power_mute = *milliseconds;
+        }
+        DeviceSettingsCommands::SampleBankSwitch { mode, milliseconds } => {
+            settings.sample_bank_switch = match mode {
+                SampleBankSwitchMode::Continue => SampleBankSwitch::Continue,
+                SampleBankSwitchMode::Stop => SampleBankSwitch::Stop,
+                SampleBankSwitchMode::FadeOut => {
+                    SampleBankSwitch::FadeOut(milliseconds.unwrap_or(500))
+                }
+            };
+        }
+        DeviceSettingsCommands::EncoderStep { encoder, step } => {
+            settings.encoder_steps.insert(*encoder, *step);
+        }
+        DeviceSettingsCommands::EncoderAcceleration {
+            encoder,
+            multiplier,
+            window,
+        } => {
+            if *multiplier == 1 {
+                settings.encoder_acceleration.remove(encoder);
+            } else {
+                let acceleration = EncoderAcceleration {
+                    window: *window,
+                    multiplier: *multiplier,
+                };
+                settings.encoder_acceleration.insert(*encoder, acceleration);
+            }
+        }
+        DeviceSettingsCommands::Autosave { mode, minutes } => {
+            settings.autosave = match mode {
+                AutosaveMode::Off => Autosave::Off,
+                AutosaveMode::OnChange => Autosave::OnChange,
+                AutosaveMode::Every => Autosave::Every(minutes.unwrap_or(5)),
+            };
+        }
+        DeviceSettingsCommands::EncoderFineMode { enabled } => {
+            settings.encoder_fine_mode = *enabled;
+        }
+        DeviceSettingsCommands::QuickSwitchButton { button } => {
+            settings.quick_switch_button = *button;
+        }
+        DeviceSettingsCommands::StreamPanicButton { button } => {
+            settings.stream_panic_button = *button;
+        }
+        DeviceSettingsCommands::FaderPageButton { button } => {
+            settings.fader_page_button = *button;
+        }
+        DeviceSettingsCommands::RoutingPresetButton { button, preset } => match preset {
+            Some(preset) => {
+                settings
+                    .routing_preset_buttons
+                    .insert(*button, preset.clone());
+            }
+            None => {
+                settings.routing_preset_buttons.remove(button);
+            }
+        },
+        DeviceSettingsCommands::FaderHold {
+            fader,
+            action,
+            hold_time,
+        } => {
+            let action = match action {
+                FaderHoldMode::All => FaderHoldAction::Mute(MuteFunction::All),
+                FaderHoldMode::ToStream => FaderHoldAction::Mute(MuteFunction::ToStream),
+                FaderHoldMode::ToVoiceChat => FaderHoldAction::Mute(MuteFunction::ToVoiceChat),
+                FaderHoldMode::ToPhones => FaderHoldAction::Mute(MuteFunction::ToPhones),
+                FaderHoldMode::ToLineOut => FaderHoldAction::Mute(MuteFunction::ToLineOut),
+                FaderHoldMode::Disabled => FaderHoldAction::Disabled,
+                FaderHoldMode::Solo => FaderHoldAction::Solo,
+            };
+            let hold = FaderHold {
+                hold_time: *hold_time,
+                action,
+            };
+            match hold == FaderHold::default() {
+                true => settings.fader_hold.remove(fader),
+                false => settings.fader_hold.insert(*fader, hold),
+            };
+        }
+        DeviceSettingsCommands::Passthrough { button, enabled } => {
+            if *enabled {
+                settings.passthrough_buttons.insert(*button);
+            } else {
+                settings.passthrough_buttons.remove(button);
+            }
+        }
+        DeviceSettingsCommands::RemapButton { button, function } => match function {
+            Some(function) if function != button => {
+                settings.button_remap.insert(*button, *function);
+            }
+            _ => {
+                settings.button_remap.remove(button);
+            }
+        },
+    }
+    true
+}
+
+fn print_device_settings(settings: &DeviceSettings, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(settings)?);
+        return Ok(());
+    }
+
+    println!("Profile: {}", settings.profile);
+    println!("Mic Profile: {}", settings.mic_profile);
+    println!("Bleep Volume: {}dB", settings.bleep_volume);
+    println!("Hold Time: {}ms", settings.hold_time);
+    match settings.autosave {
+        Autosave::Off => println!("Autosave: Off"),
+        Autosave::OnChange => println!("Autosave: On Change"),
+        Autosave::Every(minutes) => println!("Autosave: Every {} minutes", minutes),
+    }
+    match &settings.samples_directory {
+        Some(path) => println!("Samples Directory: {}", path.to_string_lossy()),
+        None => println!("Samples Directory: (daemon default)"),
+    }
+    match &settings.bleep_sound {
+        Some(path) => println!("Bleep Sound: {}", path.to_string_lossy()),
+        None => println!("Bleep Sound: (hardware bleep)"),
+    }
+    if settings.bleep_attack > 0 {
+        println!("Bleep Attack: {}ms", settings.bleep_attack);
+    }
+    if settings.bleep_release > 0 {
+        println!("Bleep Release: {}ms", settings.bleep_release);
+    }
+    println!(
+        "Phantom Power Interlock: {}",
+        if settings.phantom_power_interlock {
+            "On"
+        } else {
+            "Off"
+        }
+    );
+    if settings.phantom_power_mute > 0 {
+        println!("Phantom Power Mute: {}ms", settings.phantom_power_mute);
+    }
+    match settings.sample_bank_switch {
+        SampleBankSwitch::Continue => println!("Sample Bank Switch: Samples continue"),
+        SampleBankSwitch::Stop => println!("Sample Bank Switch: Samples stop"),
+        SampleBankSwitch::FadeOut(milliseconds) => {
+            println!(
+                "Sample Bank Switch: Samples fade out over {}ms",
+                milliseconds
+            )
+        }
+    }
+    if settings.bleep_outputs.len() < OutputDevice::iter().count() {
+        let names: Vec<String> = settings
+            .bleep_outputs
+            .iter()
+            .map(|output| output.to_string())
+            .collect();
+        println!("Bleep Outputs: {}", names.join(", "));
+    }
+    for (channel, limit) in &settings.volume_limits {
         println!(
-            "Mic Profile directory: {}",
-            client
-                .status()
-                .paths
-                .mic_profile_directory
-                .to_string_lossy()
+            "Volume Limit for {}: {}%",
+            channel,
+            volume_to_percent(*limit)
         );
+    }
+    for (channel, name) in &settings.channel_names {
+        println!("Display Name for {}: {}", channel, name);
+    }
+    for (output, name) in &settings.output_names {
+        println!("Display Name for {} output: {}", output, name);
+    }
+    for (channel, colour) in &settings.channel_colours {
+        println!("Colour for {}: {}", channel, colour);
+    }
+    for group in &settings.volume_links {
+        let names: Vec<String> = group.iter().map(|channel| channel.to_string()).collect();
+        println!("Linked Volumes: {}", names.join(", "));
+    }
+    for (encoder, step) in &settings.encoder_steps {
+        println!("Encoder Step for {}: {}", encoder, step);
+    }
+    for (encoder, acceleration) in &settings.encoder_acceleration {
         println!(
-            "Samples directory: {}",
-            client.status().paths.samples_directory.to_string_lossy()
+            "Encoder Acceleration for {}: x{} within {}ms",
+            encoder, acceleration.multiplier, acceleration.window
         );
-        for mixer in client.status().mixers.values() {
-            print_device(mixer);
+    }
+    println!(
+        "Encoder Fine Mode: {}",
+        if settings.encoder_fine_mode {
+            "On"
+        } else {
+            "Off"
+        }
+    );
+    if let Some(button) = settings.quick_switch_button {
+        println!("Quick Switch Button: {}", button);
+    }
+    if let Some(button) = settings.stream_panic_button {
+        println!("Stream Panic Button: {}", button);
+    }
+    for (index, page) in settings.fader_pages.iter().enumerate() {
+        let names: Vec<String> = page.iter().map(|channel| channel.to_string()).collect();
+        println!("Fader Page {}: {}", index, names.join(", "));
+    }
+    if let Some(button) = settings.fader_page_button {
+        println!("Fader Page Button: {}", button);
+    }
+    if !settings.routing_presets.is_empty() {
+        let mut presets: Vec<&String> = settings.routing_presets.keys().collect();
+        presets.sort();
+        println!("Routing Presets: {:?}", presets);
+    }
+    for (button, preset) in &settings.routing_preset_buttons {
+        println!("Routing Preset Button: {} applies {}", button, preset);
+    }
+    for fader in FaderName::iter() {
+        if let Some(hold) = settings.fader_hold.get(&fader) {
+            let action = match hold.action {
+                FaderHoldAction::Mute(function) => format!("Mute {}", function),
+                FaderHoldAction::Disabled => "Disabled".to_string(),
+                FaderHoldAction::Solo => "Solo".to_string(),
+            };
+            match hold.hold_time {
+                Some(time) => println!("Fader {} Hold: {} after {}ms", fader, action, time),
+                None => println!("Fader {} Hold: {}", fader, action),
+            }
         }
     }
+    if !settings.passthrough_buttons.is_empty() {
+        let names: Vec<String> = settings
+            .passthrough_buttons
+            .iter()
+            .map(|button| button.to_string())
+            .collect();
+        println!("Passthrough Buttons: {}", names.join(", "));
+    }
+    for (button, function) in &settings.button_remap {
+        println!("Button {} acts as: {}", button, function);
+    }
+    Ok(())
+}
+
+fn print_update_status(status: &UpdateStatus, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(status)?);
+        return Ok(());
+    }
 
+    println!(
+        "Update Check: {}",
+        if status.settings.enabled { "On" } else { "Off" }
+    );
+    if let Some(directory) = &status.settings.download_directory {
+        println!("Download Directory: {}", directory.to_string_lossy());
+    }
+    println!("Current Version: {}", status.current_version);
+    match &status.latest_version {
+        Some(version) if status.update_available => {
+            println!("Latest Version: {} (update available)", version);
+            if let Some(url) = &status.release_url {
+                println!("Release Notes: {}", url);
+            }
+        }
+        Some(version) => println!("Latest Version: {}", version),
+        None => println!("Latest Version: (not checked)"),
+    }
+    if let Some(path) = &status.downloaded_package {
+        println!("Downloaded Package: {}", path.to_string_lossy());
+    }
+    if let Some(error) = &status.error {
+        println!("Last Check Failed: {}", error);
+    }
     Ok(())
 }
 
+fn print_diagnostics(report: &DiagnosticReport) {
+    println!(
+        "Serial: {}, Firmware: {}, DICE: {}",
+        report.hardware.serial_number,
+        report.hardware.versions.firmware,
+        report.hardware.versions.dice
+    );
+    for check in &report.checks {
+        let result = if check.passed { "PASS" } else { "FAIL" };
+        println!("  [{}] {}: {}", result, check.name, check.message);
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+fn print_mute_state(report: &MuteStateReport) {
+    for (fader, button) in FaderName::iter().zip(&report.faders) {
+        print_mute_button(&format!("Fader {}", fader), button);
+    }
+    for button in &report.paged {
+        print_mute_button("Paged off", button);
+    }
+
+    let cough = &report.cough;
+    let mode = match (cough.is_hybrid, cough.is_toggle) {
+        (true, _) => "Hybrid",
+        (false, true) => "Toggle",
+        (false, false) => "Hold",
+    };
+    println!(
+        "Cough ({}, {}): to x {}, to all {}, press muted {}",
+        mode, cough.mute_function, cough.muted_to_x, cough.muted_to_all, cough.press_muted
+    );
+
+    for channel in ChannelName::iter() {
+        println!(
+            "{}: {}",
+            channel,
+            describe_mute_state(report.channels[channel as usize])
+        );
+    }
+}
+
+fn print_mute_button(name: &str, button: &MuteButtonState) {
+    println!(
+        "{} ({}, {}): to x {}, to all {}, active function {}, previous volume {}",
+        name,
+        button.channel,
+        button.mute_function,
+        button.muted_to_x,
+        button.muted_to_all,
+        button.active_function,
+        button.previous_volume
+    );
+}
+
+fn print_state_verification(report: &StateVerification) {
+    if report.drift.is_empty() {
+        println!("The device matches the daemon's state");
+    }
+    for drift in &report.drift {
+        println!(
+            "  {}: device has {}, daemon has {}",
+            drift.name, drift.device, drift.daemon
+        );
+    }
+    println!(
+        "Not readable from the device: {}",
+        report.unverified.join(", ")
+    );
+}
+
+fn print_hardware_info(info: &HardwareInfo) {
+    let hardware = &info.hardware;
+    println!("Device type: {:?}", hardware.device_type);
+    println!("Serial number: {}", hardware.serial_number);
+    println!("Manufacture date: {}", hardware.manufactured_date);
+    println!("Firmware: {}", hardware.versions.firmware);
+    println!("DICE: {}", hardware.versions.dice);
+    println!("FPGA count: {}", hardware.versions.fpga_count);
+    println!(
+        "Features: {}",
+        get_feature_names(&hardware.features).join(", ")
+    );
+    println!("DCP categories: {}", info.dcp_categories.join(", "));
+
+    print_usb_info(&hardware.usb_device);
+    let descriptor = &info.usb_descriptor;
+    println!(
+        "USB IDs: {:04x}:{:04x}",
+        descriptor.vendor_id, descriptor.product_id
+    );
+    println!(
+        "USB version: {}.{}.{}, speed: {}",
+        descriptor.usb_version.0,
+        descriptor.usb_version.1,
+        descriptor.usb_version.2,
+        descriptor.speed
+    );
+
+    println!("Firmware response: {}", info.firmware_version_response);
+    println!("System info response: {}", info.system_info_response);
+}
+
 fn print_device(device: &MixerStatus) {
     println!(
         "Device type: {}",
@@ -435,6 +2054,29 @@ fn print_usb_info(usb: &UsbProductInformation) {
     );
 }
 
+fn get_feature_names(features: &DeviceFeatures) -> Vec<&'static str> {
+    let mut names = vec![];
+    if features.effects {
+        names.push("Effects");
+    }
+    if features.encoders {
+        names.push("Encoders");
+    }
+    if features.sampler {
+        names.push("Sampler");
+    }
+    if features.full_eq {
+        names.push("Ten band EQ");
+    }
+    if features.fader_display_modes {
+        names.push("Fader gradients and meters");
+    }
+    if features.submixes {
+        names.push("Sub mixes");
+    }
+    names
+}
+
 fn print_mixer_info(mixer: &MixerStatus) {
     println!("Mixer firmware: {}", mixer.hardware.versions.firmware);
     println!("Mixer dice: {}", mixer.hardware.versions.dice);
@@ -444,20 +2086,54 @@ fn print_mixer_info(mixer: &MixerStatus) {
         "Mixer manufacture date: {}",
         mixer.hardware.manufactured_date
     );
+    println!(
+        "Mixer features: {}",
+        get_feature_names(&mixer.hardware.features).join(", ")
+    );
     println!("Mixer profile: {}", mixer.profile_name);
+    if let Some(previous) = &mixer.previous_profile_name {
+        println!("Previous profile: {}", previous);
+    }
+    if mixer.locked {
+        println!("Controls: locked");
+    }
+    if let Some(page) = mixer.fader_page {
+        println!("Fader page: {}", page);
+    }
+    if mixer.stream_panic {
+        println!("Stream panic: everything is cut from the stream");
+    }
+    if !mixer.soloed_channels.is_empty() {
+        let channels: Vec<String> = mixer
+            .soloed_channels
+            .iter()
+            .map(|channel| channel.to_string())
+            .collect();
+        println!("Soloed: {}", channels.join(", "));
+    }
 
     for fader in FaderName::iter() {
         println!(
-            "Fader {} assignment: {}, Mute Behaviour: {}",
+            "Fader {} assignment: {}, Mute Behaviour: {}, {}",
             fader,
             mixer.get_fader_status(fader).channel,
-            mixer.get_fader_status(fader).mute_type
+            mixer.get_fader_status(fader).mute_type,
+            describe_mute_state(mixer.get_fader_status(fader).mute_state)
         )
     }
 
     for channel in ChannelName::iter() {
-        let pct = (mixer.get_channel_volume(channel) as f32 / 255.0) * 100.0;
-        println!("{} volume: {:.0}%", channel, pct);
+        let name = match &mixer.channel_names[channel as usize] {
+            Some(name) => format!("{} ({})", channel, name),
+            None => channel.to_string(),
+        };
+        println!(
+            "{} volume: {}% ({:.1}dB), {}",
+            name,
+            volume_to_percent(mixer.get_channel_volume(channel)),
+            mixer.volumes_db[channel as usize],
+            describe_mute_state(mixer.get_channel_mute_state(channel))
+        );
     }
 
     for microphone in MicrophoneType::iter() {
@@ -474,6 +2150,78 @@ fn print_mixer_info(mixer: &MixerStatus) {
         }
     }
 
+    println!(
+        "Mic level: {:.1} dB (Peak: {:.1} dB){}",
+        mixer.mic_status.level.level,
+        mixer.mic_status.level.peak,
+        if mixer.mic_status.level.clipping {
+            " CLIPPING"
+        } else {
+            ""
+        }
+    );
+
+    let gate = &mixer.mic_status.noise_gate;
+    if mixer.mic_status.editor_modes.gate_advanced {
+        println!(
+            "Noise gate: {} (Advanced), threshold {} dB, attenuation {}%",
+            if gate.enabled { "On" } else { "Off" },
+            gate.threshold,
+            gate.attenuation
+        );
+    } else {
+        println!(
+            "Noise gate: {} (Simple), {}%",
+            if gate.enabled { "On" } else { "Off" },
+            gate.amount
+        );
+    }
+    println!("De-esser: {}%", mixer.mic_status.deesser);
+    println!("Bleep volume: {} dB", mixer.mic_status.bleep_volume);
+
+    if let Some(sampler) = &mixer.sampler {
+        println!("Sampler bank: {}", sampler.active_bank);
+        for bank in SampleBank::iter() {
+            for button in SampleButtons::iter() {
+                let tracks = sampler.banks.get(&bank).and_then(|bank| bank.get(&button));
+                for (index, track) in tracks.into_iter().flatten().enumerate() {
+                    println!(
+                        "Sample {} {} {}: {} ({}% - {}%)",
+                        bank, button, index, track.file, track.start_pct, track.end_pct
+                    );
+                }
+            }
+        }
+        for button in SampleButtons::iter() {
+            if let Some(playback) = sampler.playing.get(&button) {
+                let duration = match playback.duration_ms {
+                    Some(duration) => format!("{:.1}s", duration as f32 / 1000.0),
+                    None => "?".to_string(),
+                };
+                println!(
+                    "Playing {}: {} ({:.1}s / {}){}",
+                    button,
+                    playback.file,
+                    playback.elapsed_ms as f32 / 1000.0,
+                    duration,
+                    if playback.looping { " LOOPING" } else { "" }
+                );
+            }
+        }
+    }
+
+    for channel in ChannelName::iter() {
+        if let Some(colour) = &mixer.channel_colours[channel as usize] {
+            println!("{} colour: {}", channel, colour);
+        }
+    }
+
+    for output in OutputDevice::iter() {
+        if let Some(name) = &mixer.output_names[output as usize] {
+            println!("{} output name: {}", output, name);
+        }
+    }
+
     let max_col_len = OutputDevice::iter()
         .map(|s| s.to_string().len())
         .max()
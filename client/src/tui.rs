@@ -0,0 +1,304 @@
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use goxlr_ipc::client::Client;
+use goxlr_ipc::{is_valid_route, volume_to_percent, GoXLRCommand, MixerStatus};
+use goxlr_types::{FaderName, InputDevice, OutputDevice};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Gauge, Paragraph, Row, Table};
+use ratatui::{Frame, Terminal};
+use std::io;
+use strum::{EnumCount, IntoEnumIterator};
+use tokio::sync::mpsc;
+
+const VOLUME_STEP: u8 = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Focus {
+    Faders,
+    Router,
+}
+
+#[derive(Debug)]
+struct TuiState {
+    focus: Focus,
+    fader: usize,
+    input: usize,
+    output: usize,
+}
+
+enum TuiEvent {
+    StatusChanged,
+    Terminal(Option<Event>),
+}
+
+enum Action {
+    None,
+    Quit,
+    Command(GoXLRCommand),
+}
+
+pub async fn run_tui(client: &mut Client, serial: &str) -> Result<()> {
+    client
+        .subscribe()
+        .await
+        .context("Unable to subscribe to status updates")?;
+
+    // Terminal events are read on their own thread, as crossterm's reads are blocking.
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if sender.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let result = event_loop(&mut terminal, client, serial, &mut receiver).await;
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    result
+}
+
+async fn event_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    client: &mut Client,
+    serial: &str,
+    events: &mut mpsc::UnboundedReceiver<Event>,
+) -> Result<()> {
+    let mut state = TuiState {
+        focus: Focus::Faders,
+        fader: 0,
+        input: 0,
+        output: 0,
+    };
+
+    loop {
+        let mixer = client
+            .status()
+            .mixers
+            .get(serial)
+            .context("The device has been disconnected")?
+            .clone();
+        terminal.draw(|frame| draw(frame, &mixer, &state))?;
+
+        let event = tokio::select! {
+            result = client.wait_for_change() => {
+                result?;
+                TuiEvent::StatusChanged
+            }
+            event = events.recv() => TuiEvent::Terminal(event),
+        };
+
+        let key = match event {
+            TuiEvent::StatusChanged => continue,
+            TuiEvent::Terminal(None) => return Ok(()),
+            TuiEvent::Terminal(Some(Event::Key(key))) if key.kind == KeyEventKind::Press => key,
+
+            // Anything else (such as a resize) just needs a redraw.
+            TuiEvent::Terminal(Some(_)) => continue,
+        };
+
+        match state.handle_key(key, &mixer) {
+            Action::None => {}
+            Action::Quit => return Ok(()),
+            Action::Command(command) => client.command(serial, command).await?,
+        }
+    }
+}
+
+impl TuiState {
+    fn handle_key(&mut self, key: KeyEvent, mixer: &MixerStatus) -> Action {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Faders => Focus::Router,
+                    Focus::Router => Focus::Faders,
+                };
+                return Action::None;
+            }
+            _ => {}
+        }
+
+        match self.focus {
+            Focus::Faders => self.handle_fader_key(key, mixer),
+            Focus::Router => self.handle_router_key(key, mixer),
+        }
+    }
+
+    fn handle_fader_key(&mut self, key: KeyEvent, mixer: &MixerStatus) -> Action {
+        let fader = FaderName::iter().nth(self.fader).unwrap();
+        let channel = mixer.get_fader_status(fader).channel;
+        let volume = mixer.get_channel_volume(channel);
+
+        match key.code {
+            KeyCode::Left => self.fader = self.fader.saturating_sub(1),
+            KeyCode::Right => self.fader = (self.fader + 1).min(FaderName::COUNT - 1),
+            KeyCode::Up | KeyCode::Char('+') => {
+                let volume = volume.saturating_add(VOLUME_STEP);
+                return Action::Command(GoXLRCommand::SetVolume(channel, volume));
+            }
+            KeyCode::Down | KeyCode::Char('-') => {
+                let volume = volume.saturating_sub(VOLUME_STEP);
+                return Action::Command(GoXLRCommand::SetVolume(channel, volume));
+            }
+            KeyCode::Char('m') => {
+                return Action::Command(GoXLRCommand::SetFaderMuted(fader, true));
+            }
+            KeyCode::Char('u') => {
+                return Action::Command(GoXLRCommand::SetFaderMuted(fader, false));
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn handle_router_key(&mut self, key: KeyEvent, mixer: &MixerStatus) -> Action {
+        match key.code {
+            KeyCode::Up => self.input = self.input.saturating_sub(1),
+            KeyCode::Down => self.input = (self.input + 1).min(InputDevice::COUNT - 1),
+            KeyCode::Left => self.output = self.output.saturating_sub(1),
+            KeyCode::Right => self.output = (self.output + 1).min(OutputDevice::COUNT - 1),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                let input = InputDevice::iter().nth(self.input).unwrap();
+                let output = OutputDevice::iter().nth(self.output).unwrap();
+                if !is_valid_route(input, output) {
+                    return Action::None;
+                }
+                let enabled = !mixer.router_table[self.input][self.output];
+                return Action::Command(GoXLRCommand::SetRouter(input, output, enabled));
+            }
+            _ => {}
+        }
+        Action::None
+    }
+}
+
+fn draw<B: Backend>(frame: &mut Frame<B>, mixer: &MixerStatus, state: &TuiState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Min(InputDevice::COUNT as u16 + 3),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    let header = Paragraph::new(format!(
+        "Profile: {}    Mic Profile: {}",
+        mixer.profile_name, mixer.mic_profile_name
+    ))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" GoXLR {} ", mixer.hardware.serial_number)),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    draw_faders(frame, chunks[1], mixer, state);
+    draw_router(frame, chunks[2], mixer, state);
+
+    let help = match state.focus {
+        Focus::Faders => "←/→ select fader  ↑/↓ volume  m mute  u unmute  Tab routing  q quit",
+        Focus::Router => "arrows select route  Space toggle  Tab faders  q quit",
+    };
+    frame.render_widget(
+        Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+        chunks[3],
+    );
+}
+
+fn draw_faders<B: Backend>(
+    frame: &mut Frame<B>,
+    area: Rect,
+    mixer: &MixerStatus,
+    state: &TuiState,
+) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, FaderName::COUNT as u32); FaderName::COUNT])
+        .split(area);
+
+    for (index, fader) in FaderName::iter().enumerate() {
+        let status = mixer.get_fader_status(fader);
+        let volume = mixer.get_channel_volume(status.channel);
+
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {}: {} ", fader, status.channel));
+        if state.focus == Focus::Faders && state.fader == index {
+            block = block.border_style(Style::default().fg(Color::Yellow));
+        }
+
+        let gauge = Gauge::default()
+            .block(block)
+            .gauge_style(Style::default().fg(Color::Green))
+            .ratio(volume as f64 / 255.0)
+            .label(format!(
+                "{}% (mutes {})",
+                volume_to_percent(volume),
+                status.mute_type
+            ));
+        frame.render_widget(gauge, columns[index]);
+    }
+}
+
+fn draw_router<B: Backend>(
+    frame: &mut Frame<B>,
+    area: Rect,
+    mixer: &MixerStatus,
+    state: &TuiState,
+) {
+    let header = Row::new(
+        std::iter::once(Cell::from(""))
+            .chain(OutputDevice::iter().map(|output| Cell::from(output.to_string()))),
+    )
+    .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows = InputDevice::iter().enumerate().map(|(input_index, input)| {
+        let cells = OutputDevice::iter()
+            .enumerate()
+            .map(|(output_index, output)| {
+                if !is_valid_route(input, output) {
+                    return Cell::from("");
+                }
+
+                let enabled = mixer.router_table[input_index][output_index];
+                let mut style = Style::default();
+                if enabled {
+                    style = style.fg(Color::Green);
+                }
+                if state.focus == Focus::Router
+                    && state.input == input_index
+                    && state.output == output_index
+                {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Cell::from(if enabled { "  ■" } else { "  ·" }).style(style)
+            });
+
+        Row::new(std::iter::once(Cell::from(input.to_string())).chain(cells))
+    });
+
+    let widths = [Constraint::Length(14); OutputDevice::COUNT + 1];
+    let table = Table::new(rows)
+        .header(header)
+        .widths(&widths)
+        .block(Block::default().borders(Borders::ALL).title(" Routing "));
+    frame.render_widget(table, area);
+}
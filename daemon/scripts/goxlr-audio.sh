@@ -42,8 +42,19 @@ function pulse_get_input() {
 }
 
 function pulse_play_audio() {
-  # Playback the specified file through paplay..
-  paplay --volume=65536 -d "$DEVICE" "$FILE"
+  # Playback the specified file through paplay, replacing this script so the daemon knows its PID..
+  exec paplay --volume=65536 -d "$DEVICE" "$FILE"
+}
+
+function pulse_set_volume() {
+  # Find the stream being played by the given process, and set its volume as a percentage..
+  INPUT=$(pactl list sink-inputs | awk -v pid="application.process.id = \"$PID\"" '/^Sink Input #/ { input = substr($3, 2) } index($0, pid) { print input }')
+  if [ -z "$INPUT" ]; then
+    >&2 echo "Unable to find a stream played by $PID";
+    exit 1;
+  fi
+
+  pactl set-sink-input-volume "$INPUT" "$VOLUME%"
   exit 0;
 }
 
@@ -101,6 +112,18 @@ case $1 in
     >&2 echo "Unable to locate a compatible command to play audio";
     exit 1;
 
+    ;;
+  set-volume)
+    PID=$2
+    VOLUME=$3
+
+    if [ -x "$(command -v pactl)" ]; then
+      pulse_set_volume
+    fi
+
+    >&2 echo "Unable to locate a compatible command to set the volume";
+    exit 1;
+
     ;;
   record-file)
     DEVICE=$2
@@ -1,18 +1,48 @@
+use crate::wav;
 use anyhow::{anyhow, Context, Result};
 use directories::ProjectDirs;
 use goxlr_profile_loader::SampleButtons;
-use log::{debug, error};
+use log::{debug, error, warn};
 use std::collections::HashMap;
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often the volume of a sample is lowered while it fades out.
+const FADE_STEP: Duration = Duration::from_millis(50);
 
 #[derive(Debug)]
 pub struct AudioHandler {
     script_path: PathBuf,
     output_device: String,
-    _input_device: Option<String>,
+    input_device: Option<String>,
+
+    active_streams: HashMap<SampleButtons, ActiveSample>,
+    active_recordings: HashMap<SampleButtons, Child>,
+    active_bleep: Option<Child>,
+}
+
+#[derive(Debug)]
+struct ActiveSample {
+    process: Child,
 
-    active_streams: HashMap<SampleButtons, Child>,
+    // The sample as it was chosen, and the file given to the player, which may be a trimmed copy
+    source: PathBuf,
+    file: PathBuf,
+    started: Instant,
+    duration: Option<Duration>,
+    looping: bool,
+}
+
+// How far through a sample is, a looping sample starts again from zero each time round.
+#[derive(Debug)]
+pub struct SamplePlayback {
+    pub file: PathBuf,
+    pub elapsed: Duration,
+    pub duration: Option<Duration>,
+    pub looping: bool,
 }
 
 impl AudioHandler {
@@ -93,20 +123,36 @@ impl AudioHandler {
         Ok(Self {
             script_path,
             output_device,
-            _input_device: input_device,
+            input_device,
 
             active_streams: HashMap::new(),
+            active_recordings: HashMap::new(),
+            active_bleep: None,
         })
     }
 
     pub fn check_playing(&mut self) {
+        let script = &self.script_path;
+        let output_device = &self.output_device;
         let map = &mut self.active_streams;
         let mut to_remove = Vec::new();
 
         for (key, value) in &mut *map {
-            match value.try_wait() {
+            match value.process.try_wait() {
                 Ok(Some(status)) => {
-                    debug!("PID {} has terminated: {}", value.id(), status);
+                    debug!("PID {} has terminated: {}", value.process.id(), status);
+
+                    // Stopping a sample kills the player, so only one which finished is repeated.
+                    if value.looping && status.success() {
+                        match spawn_player(script, output_device, &value.file) {
+                            Ok(process) => {
+                                value.process = process;
+                                value.started = Instant::now();
+                                continue;
+                            }
+                            Err(e) => warn!("Unable to loop sample {:?}: {}", key, e),
+                        }
+                    }
                     to_remove.push(*key);
                 }
                 Ok(None) => {
@@ -127,19 +173,227 @@ impl AudioHandler {
         self.active_streams.contains_key(&button)
     }
 
-    pub fn play_for_button(&mut self, button: SampleButtons, file: String) -> Result<()> {
+    pub fn get_playback(&self, button: SampleButtons) -> Option<SamplePlayback> {
+        let sample = self.active_streams.get(&button)?;
+
+        // The player may take a moment to exit once it's reached the end.
+        let mut elapsed = sample.started.elapsed();
+        if let Some(duration) = sample.duration {
+            elapsed = elapsed.min(duration);
+        }
+        Some(SamplePlayback {
+            file: sample.source.clone(),
+            elapsed,
+            duration: sample.duration,
+            looping: sample.looping,
+        })
+    }
+
+    /**
+     Plays a sample, starting and stopping at the given percentages of the way through it. Only
+     WAV files can be trimmed, anything else is played in full. A looping sample is played again
+     each time it finishes, until it's stopped.
+    */
+    pub fn play_for_button(
+        &mut self,
+        button: SampleButtons,
+        source: &Path,
+        start_pct: u8,
+        end_pct: u8,
+        looping: bool,
+    ) -> Result<()> {
+        let mut file = source.to_path_buf();
+        let is_wav = file
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"));
+        let is_trimmed = start_pct < end_pct && (start_pct > 0 || end_pct < 100);
+
+        if is_wav && is_trimmed {
+            let trimmed = std::env::temp_dir().join(format!("goxlr-sample-{:?}.wav", button));
+
+            // If the last copy is still playing, it keeps its data while this one is written.
+            let _ = std::fs::remove_file(&trimmed);
+            match wav::write_trimmed(&file, &trimmed, start_pct, end_pct) {
+                Ok(()) => file = trimmed,
+                Err(e) => warn!("Unable to trim sample, playing it in full: {}", e),
+            }
+        }
+
+        // Only the length of WAV files is known, the header of anything else isn't read.
+        let header = if is_wav {
+            wav::read_header(&file).ok()
+        } else {
+            None
+        };
+        let duration = header.map(|header| Duration::from_millis(header.duration_ms()));
+
+        let process = spawn_player(&self.script_path, &self.output_device, &file)?;
+        let sample = ActiveSample {
+            process,
+            source: source.to_path_buf(),
+            file,
+            started: Instant::now(),
+            duration,
+            looping,
+        };
+        self.active_streams.insert(button, sample);
+        Ok(())
+    }
+
+    pub fn stop_sample(&mut self, button: SampleButtons) {
+        if let Some(sample) = self.active_streams.remove(&button) {
+            stop_player(button, sample.process);
+        }
+    }
+
+    pub fn stop_samples(&mut self) {
+        for (button, sample) in self.active_streams.drain() {
+            stop_player(button, sample.process);
+        }
+    }
+
+    /**
+     Fades out every sample that's playing, then stops them. The volume is lowered by the audio
+     script's set-volume, if it can't do that the samples play on until they're stopped.
+    */
+    pub fn fade_out_samples(&mut self, duration: Duration) {
+        for (button, sample) in self.active_streams.drain() {
+            debug!("Fading out sample {:?} over {:?}", button, duration);
+            let script = self.script_path.clone();
+            thread::spawn(move || fade_out(&script, sample.process, duration));
+        }
+    }
+
+    pub fn is_sample_recording(&self, button: SampleButtons) -> bool {
+        self.active_recordings.contains_key(&button)
+    }
+
+    pub fn record_for_button(&mut self, button: SampleButtons, file: &Path) -> Result<()> {
+        let input_device = self
+            .input_device
+            .as_ref()
+            .ok_or_else(|| anyhow!("No sample capture device found, unable to record"))?;
+
+        // The recorder is run in its own process group, so it can be stopped along with the script.
+        let command = Command::new(self.get_script())
+            .arg("record-file")
+            .arg(input_device)
+            .arg(file)
+            .process_group(0)
+            .spawn()
+            .context("Unable to run script")?;
+
+        self.active_recordings.insert(button, command);
+        Ok(())
+    }
+
+    // Stops a recording, returning once the recorder has finished writing the file.
+    pub fn stop_recording(&mut self, button: SampleButtons) -> Result<()> {
+        let mut process = self
+            .active_recordings
+            .remove(&button)
+            .ok_or_else(|| anyhow!("Not recording a sample for {:?}", button))?;
+
+        // Interrupting (rather than killing) the recorder lets it finish the file's header.
+        if !signal_group(&process, libc::SIGINT) {
+            debug!("Recorder for {:?} had already stopped", button);
+        }
+        let status = process.wait()?;
+        debug!("Recording for {:?} finished: {}", button, status);
+        Ok(())
+    }
+
+    pub fn play_bleep(&mut self, file: &Path) -> Result<()> {
+        self.stop_bleep();
+
         let command = Command::new(self.get_script())
             .arg("play-file")
             .arg(&self.output_device)
             .arg(file)
+            .process_group(0)
             .spawn()
-            .expect("Unable to run script");
+            .context("Unable to run script")?;
 
-        self.active_streams.insert(button, command);
+        self.active_bleep = Some(command);
         Ok(())
     }
 
+    // Lowers the bleep's volume after the button is released, then stops it.
+    pub fn fade_out_bleep(&mut self, duration: Duration) {
+        if let Some(process) = self.active_bleep.take() {
+            let script = self.script_path.clone();
+            thread::spawn(move || fade_out(&script, process, duration));
+        }
+    }
+
+    // The bleep only plays while the button is held, so it's cut off when released.
+    pub fn stop_bleep(&mut self) {
+        if let Some(mut process) = self.active_bleep.take() {
+            signal_group(&process, libc::SIGTERM);
+            if let Err(e) = process.wait() {
+                error!("Error waiting for bleep to stop: {}", e);
+            }
+        }
+    }
+
     fn get_script(&self) -> &str {
         self.script_path.to_str().unwrap()
     }
 }
+
+// The player is run in its own process group, so it can be stopped along with the script.
+fn spawn_player(script: &Path, output_device: &str, file: &Path) -> Result<Child> {
+    Command::new(script)
+        .arg("play-file")
+        .arg(output_device)
+        .arg(file)
+        .process_group(0)
+        .spawn()
+        .context("Unable to run script")
+}
+
+fn stop_player(button: SampleButtons, mut process: Child) {
+    signal_group(&process, libc::SIGTERM);
+    if let Err(e) = process.wait() {
+        error!("Error waiting for sample {:?} to stop: {}", button, e);
+    }
+}
+
+fn fade_out(script: &Path, mut process: Child, duration: Duration) {
+    let steps = (duration.as_millis() / FADE_STEP.as_millis()).max(1);
+    let mut can_set_volume = true;
+    for step in 1..steps {
+        if let Ok(Some(_)) = process.try_wait() {
+            return;
+        }
+
+        if can_set_volume {
+            let volume = 100 - step * 100 / steps;
+            let result = Command::new(script)
+                .arg("set-volume")
+                .arg(process.id().to_string())
+                .arg(volume.to_string())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            if !result.is_ok_and(|status| status.success()) {
+                debug!(
+                    "Unable to set the volume of PID {}, not fading",
+                    process.id()
+                );
+                can_set_volume = false;
+            }
+        }
+        thread::sleep(FADE_STEP);
+    }
+
+    signal_group(&process, libc::SIGTERM);
+    if let Err(e) = process.wait() {
+        error!("Error waiting for sample to stop: {}", e);
+    }
+}
+
+// Sends a signal to a process started in its own group, and anything it has started.
+fn signal_group(process: &Child, signal: i32) -> bool {
+    unsafe { libc::kill(-(process.id() as i32), signal) == 0 }
+}
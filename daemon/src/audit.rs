@@ -0,0 +1,51 @@
+/*
+Keeps the most recent commands sent to devices along with who sent them, so when several
+integrations (a Stream Deck plugin, the Web UI, scripts) are changing a device it can be seen which
+one changed what. Clients on the IPC socket are identified by their process and user, and can give
+themselves a name with SetClientName.
+ */
+
+use goxlr_ipc::{AuditEntry, ClientIdentity, GoXLRCommand};
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const AUDIT_LOG_LENGTH: usize = 500;
+
+#[derive(Default)]
+pub struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    // Commands in a batch share the batch's result.
+    pub fn record<T>(
+        &mut self,
+        client: &ClientIdentity,
+        serial: &str,
+        commands: &[GoXLRCommand],
+        result: &anyhow::Result<T>,
+    ) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_millis() as u64)
+            .unwrap_or_default();
+        let error = result.as_ref().err().map(|error| format!("{:#}", error));
+
+        for command in commands {
+            if self.entries.len() == AUDIT_LOG_LENGTH {
+                self.entries.pop_front();
+            }
+            self.entries.push_back(AuditEntry {
+                time,
+                client: client.clone(),
+                serial: serial.to_owned(),
+                command: command.clone(),
+                error: error.clone(),
+            });
+        }
+    }
+
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,136 @@
+/*
+Before a profile is overwritten, the old copy is kept in a 'backups' folder next to it, named
+after the profile and the time the backup was made. Only the newest few backups of each profile
+are kept, older ones are removed as new ones are made.
+ */
+
+use anyhow::{bail, Context, Result};
+use goxlr_ipc::ProfileBackup;
+use std::fs::{copy, create_dir_all, remove_file, rename};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BACKUP_DIRECTORY: &str = "backups";
+const BACKUP_COUNT: usize = 5;
+
+// Autosaves can happen every few seconds, so they only back a file up this often, otherwise the
+// backups would soon only hold the last few changes.
+const AUTOSAVE_BACKUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Copies the file at the path into the backups, if it exists.
+pub fn backup_file(path: &Path) -> Result<()> {
+    if !path.is_file() {
+        return Ok(());
+    }
+    let (directory, name, extension) = split_path(path)?;
+
+    let backup_directory = directory.join(BACKUP_DIRECTORY);
+    create_dir_all(&backup_directory).context(format!(
+        "Could not create backup directory at {}",
+        backup_directory.to_string_lossy()
+    ))?;
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let backup = backup_directory.join(format!("{}.{}.{}", name, time, extension));
+    copy(path, &backup).context(format!(
+        "Could not back up {} to {}",
+        path.to_string_lossy(),
+        backup.to_string_lossy()
+    ))?;
+
+    for old_backup in list_backups(path).into_iter().skip(BACKUP_COUNT) {
+        let _ = remove_file(backup_directory.join(old_backup.id));
+    }
+    Ok(())
+}
+
+// As backup_file, unless the newest backup is recent enough to still be worth keeping.
+pub fn backup_file_for_autosave(path: &Path) -> Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let interval = AUTOSAVE_BACKUP_INTERVAL.as_millis() as u64;
+    if let Some(newest) = list_backups(path).first() {
+        if now.saturating_sub(newest.created) < interval {
+            return Ok(());
+        }
+    }
+    backup_file(path)
+}
+
+// The backups of the file at the path, newest first.
+pub fn list_backups(path: &Path) -> Vec<ProfileBackup> {
+    let (directory, name, extension) = match split_path(path) {
+        Ok(parts) => parts,
+        Err(_) => return vec![],
+    };
+
+    let entries = match directory.join(BACKUP_DIRECTORY).read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut backups: Vec<ProfileBackup> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let id = entry.file_name().to_str()?.to_owned();
+            let created = parse_backup_time(&id, &name, &extension)?;
+            let size = entry.metadata().ok()?.len();
+            Some(ProfileBackup { id, created, size })
+        })
+        .collect();
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+    backups
+}
+
+// Replaces the file at the path with one of its backups, backing up the current file first.
+pub fn restore_backup(path: &Path, id: &str) -> Result<()> {
+    let (directory, name, extension) = split_path(path)?;
+    if parse_backup_time(id, &name, &extension).is_none() || id.contains(['/', '\\']) {
+        bail!("{} is not a backup of {}", id, name);
+    }
+
+    let backup = directory.join(BACKUP_DIRECTORY).join(id);
+    if !backup.is_file() {
+        bail!("Backup {} does not exist", id);
+    }
+
+    backup_file(path)?;
+
+    // Copy next to the file then rename it into place, so a failed copy can't break the file.
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    copy(&backup, &temp).context(format!("Could not restore backup {}", id))?;
+    rename(&temp, path).context(format!("Could not restore backup {}", id))?;
+    Ok(())
+}
+
+fn split_path(path: &Path) -> Result<(PathBuf, String, String)> {
+    let directory = path.parent().context("Invalid profile path")?;
+    let name = path.file_stem().and_then(|name| name.to_str());
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    match (name, extension) {
+        (Some(name), Some(extension)) => Ok((
+            directory.to_path_buf(),
+            name.to_owned(),
+            extension.to_owned(),
+        )),
+        _ => bail!("Invalid profile path: {}", path.to_string_lossy()),
+    }
+}
+
+// Backups are named <name>.<time>.<extension>, returns the time if the id is a backup of the name.
+fn parse_backup_time(id: &str, name: &str, extension: &str) -> Option<u64> {
+    let time = id
+        .strip_prefix(name)?
+        .strip_prefix('.')?
+        .strip_suffix(extension)?
+        .strip_suffix('.')?;
+    time.parse().ok()
+}
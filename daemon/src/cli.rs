@@ -1,5 +1,6 @@
 use clap::{Parser, ValueEnum};
 use directories::ProjectDirs;
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -16,6 +17,65 @@ pub struct Cli {
     /// Disable the HTTP Server and Client Web UI
     #[clap(long)]
     pub disable_http: bool,
+
+    /// An address the HTTP Server listens on, in place of the http_addresses in the settings
+    /// (and can be given more than once). Anything other than loopback makes the Web UI and API
+    /// available to other machines on the network
+    #[clap(long)]
+    pub http_address: Vec<IpAddr>,
+
+    /// How many HTTP API requests each client on the network can make a second, 0 for no limit.
+    /// Clients on this machine are never limited.
+    #[clap(long, default_value = "50")]
+    pub http_rate_limit: u32,
+
+    /// Don't advertise the HTTP Server over mDNS when it's available on the network
+    #[clap(long)]
+    pub disable_mdns: bool,
+
+    /// Serve the Web UI from this directory instead of the copy built into the daemon, so
+    /// changes to the UI can be tried without rebuilding
+    #[clap(long, conflicts_with = "disable-http")]
+    pub ui_path: Option<PathBuf>,
+
+    /// How long to wait for the GoXLR to respond to a USB request, in milliseconds
+    #[clap(long, default_value = "1000")]
+    pub usb_timeout: u64,
+
+    /// How many times to retry a USB request which failed with a transient error
+    #[clap(long, default_value = "2")]
+    pub usb_retries: u8,
+
+    /// How often to poll the GoXLR for changes, in milliseconds. Devices which support
+    /// interrupts will also report changes as soon as they happen.
+    #[clap(long, default_value = "100", value_parser = clap::value_parser!(u64).range(10..))]
+    pub poll_interval: u64,
+
+    /// Start with the buttons, faders and encoders of every GoXLR locked, so they're ignored until
+    /// unlocked with SetLocked
+    #[clap(long)]
+    pub locked: bool,
+
+    /// Run with a simulated GoXLR instead of looking for real ones
+    #[clap(long, value_enum)]
+    pub mock_device: Option<MockDeviceType>,
+
+    /// A script of button, fader and encoder events for the simulated GoXLR
+    #[clap(long, requires = "mock-device")]
+    pub mock_script: Option<PathBuf>,
+
+    /// Write every request sent to the GoXLR and its response to this file, one JSON object a
+    /// line, for reporting protocol problems
+    #[clap(long, conflicts_with = "mock-device")]
+    pub capture_usb: Option<PathBuf>,
+
+    /// Save every write sent to the simulated GoXLR to this file when the daemon stops
+    #[clap(long, requires = "mock-device")]
+    pub record_trace: Option<PathBuf>,
+
+    /// Fail if the writes sent to the simulated GoXLR differ from those in this trace file
+    #[clap(long, requires = "mock-device")]
+    pub expect_trace: Option<PathBuf>,
 }
 
 fn default_config_location() -> PathBuf {
@@ -25,6 +85,12 @@ fn default_config_location() -> PathBuf {
     proj_dirs.config_dir().join("settings.json")
 }
 
+#[derive(ValueEnum, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MockDeviceType {
+    Full,
+    Mini,
+}
+
 #[repr(usize)]
 #[derive(ValueEnum, Copy, Clone, Eq, PartialEq, Debug)]
 pub enum LevelFilter {
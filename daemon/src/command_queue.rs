@@ -0,0 +1,107 @@
+/*
+Collapses bursts of commands which set a value outright, so dragging a slider in a UI (which can
+send dozens of SetVolume commands a second) doesn't queue up far more USB writes than the device
+can keep up with. Only the newest value matters for these, so while a command is waiting behind
+another for the same target, the older one is answered straight away without being run.
+
+Nothing is delayed to do this, commands are only collapsed while they're waiting behind slower
+ones. Any other command waiting between the two stops the older one being dropped, so commands
+are never reordered around something which might depend on them.
+ */
+
+use crate::primary_worker::{DeviceCommand, DeviceReceiver};
+use goxlr_ipc::GoXLRCommand;
+use goxlr_types::{ButtonColourGroups, ButtonColourTargets, ChannelName, FaderName};
+use log::trace;
+use std::collections::VecDeque;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Target {
+    Volume(ChannelName),
+    SubMixVolume(ChannelName),
+    FaderColours(FaderName),
+    AllFaderColours,
+    ButtonColours(ButtonColourTargets),
+    ButtonGroupColours(ButtonColourGroups),
+}
+
+pub struct CommandQueue {
+    receiver: DeviceReceiver,
+    pending: VecDeque<DeviceCommand>,
+}
+
+impl CommandQueue {
+    pub fn new(receiver: DeviceReceiver) -> Self {
+        Self {
+            receiver,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub async fn recv(&mut self) -> Option<DeviceCommand> {
+        loop {
+            let command = match self.pending.pop_front() {
+                Some(command) => command,
+                None => self.receiver.recv().await?,
+            };
+            while let Ok(waiting) = self.receiver.try_recv() {
+                self.pending.push_back(waiting);
+            }
+
+            match command {
+                DeviceCommand::RunDeviceCommand(serial, command, _, sender)
+                    if self.is_superseded(&serial, &command) =>
+                {
+                    trace!(
+                        "Skipping {:?} on {}, a newer value is waiting",
+                        command,
+                        serial
+                    );
+                    let _ = sender.send(Ok(()));
+                }
+                command => return Some(command),
+            }
+        }
+    }
+
+    // Whether a command for the same target is waiting, with only other such commands before it.
+    fn is_superseded(&self, serial: &str, command: &GoXLRCommand) -> bool {
+        let target = match get_target(command) {
+            Some(target) => target,
+            None => return false,
+        };
+        for waiting in &self.pending {
+            let (waiting_serial, waiting_target) = match waiting {
+                DeviceCommand::RunDeviceCommand(serial, command, _, _) => {
+                    match get_target(command) {
+                        Some(target) => (serial, target),
+                        None => return false,
+                    }
+                }
+                _ => return false,
+            };
+            if waiting_serial == serial && waiting_target == target {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn get_target(command: &GoXLRCommand) -> Option<Target> {
+    match command {
+        GoXLRCommand::SetVolume(channel, _)
+        | GoXLRCommand::SetVolumePercent(channel, _)
+        | GoXLRCommand::SetVolumeDb(channel, _) => Some(Target::Volume(*channel)),
+        GoXLRCommand::SetSubMixVolume(channel, _) => Some(Target::SubMixVolume(*channel)),
+        GoXLRCommand::SetFaderColours(fader, _, _)
+        | GoXLRCommand::SetFaderColoursHsv(fader, _, _) => Some(Target::FaderColours(*fader)),
+        GoXLRCommand::SetAllFaderColours(_, _) => Some(Target::AllFaderColours),
+        GoXLRCommand::SetButtonColours(button, _, _)
+        | GoXLRCommand::SetButtonColoursHsv(button, _, _) => Some(Target::ButtonColours(*button)),
+        GoXLRCommand::SetButtonGroupColours(group, _, _) => {
+            Some(Target::ButtonGroupColours(*group))
+        }
+        _ => None,
+    }
+}
@@ -1,14 +1,24 @@
-use crate::primary_worker::{DeviceCommand, DeviceSender};
+use crate::primary_worker::{is_usb_error, DeviceCommand, DeviceSender};
+use crate::status::{Notifications, Subscription};
 use crate::Shutdown;
 use anyhow::{anyhow, Context, Result};
+use bytes::{Bytes, BytesMut};
 use goxlr_ipc::Socket;
-use goxlr_ipc::{DaemonRequest, DaemonResponse};
+use goxlr_ipc::{
+    ClientIdentity, DaemonError, DaemonRequest, DaemonResponse, DeviceSettings, StatusPatch,
+    PROTOCOL_VERSION,
+};
 use log::{debug, info, warn};
-use tokio::net::UnixListener;
-use tokio::sync::oneshot;
+use std::future::pending;
+use std::io;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, oneshot};
+use tokio_util::codec::{Decoder, Encoder};
 
 pub async fn listen_for_connections(
     listener: UnixListener,
+    json_listener: UnixListener,
     usb_tx: DeviceSender,
     mut shutdown_signal: Shutdown,
 ) {
@@ -16,9 +26,18 @@ pub async fn listen_for_connections(
         tokio::select! {
             Ok((stream, addr)) = listener.accept() => {
                 let usb_tx = usb_tx.clone();
+                let client = get_peer_identity(&stream);
                 tokio::spawn(async move {
                     let socket = Socket::new(addr, stream);
-                    handle_connection(socket, usb_tx).await
+                    handle_connection(socket, usb_tx, client).await
+                });
+            }
+            Ok((stream, addr)) = json_listener.accept() => {
+                let usb_tx = usb_tx.clone();
+                let client = get_peer_identity(&stream);
+                tokio::spawn(async move {
+                    let socket = Socket::new_line_delimited(addr, stream);
+                    handle_connection(socket, usb_tx, client).await
                 });
             }
             () = shutdown_signal.recv() => {
@@ -29,38 +48,185 @@ pub async fn listen_for_connections(
     }
 }
 
-async fn handle_connection(
-    mut socket: Socket<DaemonRequest, DaemonResponse>,
+fn get_peer_identity(stream: &UnixStream) -> ClientIdentity {
+    match stream.peer_cred() {
+        Ok(credentials) => ClientIdentity {
+            pid: credentials.pid(),
+            uid: Some(credentials.uid()),
+            ..Default::default()
+        },
+        Err(error) => {
+            debug!("Unable to get the credentials of a client: {}", error);
+            Default::default()
+        }
+    }
+}
+
+async fn handle_connection<Codec>(
+    mut socket: Socket<DaemonRequest, DaemonResponse, Codec>,
     mut usb_tx: DeviceSender,
-) {
-    while let Some(msg) = socket.read().await {
-        match msg {
-            Ok(msg) => match handle_packet(msg, &mut usb_tx).await {
-                Ok(response) => {
-                    if let Err(e) = socket.send(response).await {
-                        warn!("Couldn't reply to {:?}: {}", socket.address(), e);
-                        return;
+    mut client: ClientIdentity,
+) where
+    Codec: Decoder<Item = BytesMut, Error = io::Error> + Encoder<Bytes, Error = io::Error> + Unpin,
+{
+    let mut subscription: Option<Subscription> = None;
+    let mut notifications: Option<Notifications> = None;
+
+    loop {
+        tokio::select! {
+            msg = socket.read() => {
+                let msg = match msg {
+                    Some(msg) => msg,
+                    None => break,
+                };
+
+                let response = match msg {
+                    Ok(DaemonRequest::Subscribe) => match subscribe(&mut usb_tx).await {
+                        Ok((status, receiver)) => {
+                            subscription = Some(receiver);
+                            DaemonResponse::StatusPatch(status)
+                        }
+                        Err(e) => DaemonResponse::Error(to_daemon_error(e)),
+                    },
+                    Ok(DaemonRequest::SubscribeNotifications) => {
+                        match subscribe_notifications(&mut usb_tx).await {
+                            Ok(receiver) => {
+                                notifications = Some(receiver);
+                                DaemonResponse::Ok
+                            }
+                            Err(e) => DaemonResponse::Error(to_daemon_error(e)),
+                        }
                     }
-                }
-                Err(e) => {
-                    if let Err(e) = socket.send(DaemonResponse::Error(e.to_string())).await {
-                        warn!("Couldn't reply to {:?}: {}", socket.address(), e);
-                        return;
+                    Ok(msg) => match handle_packet(msg, &mut usb_tx, &mut client).await {
+                        Ok(response) => response,
+                        Err(e) => DaemonResponse::Error(to_daemon_error(e)),
+                    },
+                    Err(e) => {
+                        // Most likely a newer client, let it know rather than leaving it waiting.
+                        warn!("Invalid message from {:?}: {}", socket.address(), e);
+                        DaemonResponse::Error(DaemonError::Unsupported(e.to_string()))
                     }
+                };
+
+                if let Err(e) = socket.send(response).await {
+                    warn!("Couldn't reply to {:?}: {}", socket.address(), e);
+                    return;
+                }
+            }
+            Some(update) = next_update(&mut subscription) => {
+                if let Err(e) = socket.send(update).await {
+                    warn!("Couldn't send status to {:?}: {}", socket.address(), e);
+                    return;
                 }
-            },
-            Err(e) => warn!("Invalid message from {:?}: {}", socket.address(), e),
+            }
+            Some(notification) = next_notification(&mut notifications) => {
+                if let Err(e) = socket.send(notification).await {
+                    warn!("Couldn't send a notification to {:?}: {}", socket.address(), e);
+                    return;
+                }
+            }
         }
     }
     debug!("Disconnected {:?}", socket.address());
 }
 
+// Errors raised with a DaemonError are passed on as they are, anything else is described as well
+// as possible.
+pub fn to_daemon_error(error: anyhow::Error) -> DaemonError {
+    if let Some(error) = error.downcast_ref::<DaemonError>() {
+        return error.clone();
+    }
+    if is_usb_error(&error) {
+        return DaemonError::UsbFailure(format!("{:#}", error));
+    }
+    DaemonError::Other(error.to_string())
+}
+
+pub async fn subscribe(usb_tx: &mut DeviceSender) -> Result<(StatusPatch, Subscription)> {
+    let (tx, rx) = oneshot::channel();
+    usb_tx
+        .send(DeviceCommand::Subscribe(tx))
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("Could not communicate with the device task")?;
+    rx.await.context("Could not subscribe to the device task")
+}
+
+pub async fn subscribe_notifications(usb_tx: &mut DeviceSender) -> Result<Notifications> {
+    let (tx, rx) = oneshot::channel();
+    usb_tx
+        .send(DeviceCommand::SubscribeNotifications(tx))
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("Could not communicate with the device task")?;
+    rx.await.context("Could not subscribe to notifications")
+}
+
+pub async fn next_notification(
+    notifications: &mut Option<Notifications>,
+) -> Option<DaemonResponse> {
+    let receiver = match notifications {
+        Some(receiver) => receiver,
+        None => return pending().await,
+    };
+
+    let notification = next_message(receiver).await;
+    if notification.is_none() {
+        *notifications = None;
+    }
+    notification.map(DaemonResponse::Notification)
+}
+
+// Waits for the next patch on a subscription. If the receiver falls behind, patches are dropped
+// and the client will notice the gap in sequence numbers and resubscribe.
+pub async fn next_patch(subscription: &mut Option<Subscription>) -> Option<StatusPatch> {
+    let receiver = match subscription {
+        Some(subscription) => &mut subscription.patches,
+        None => return pending().await,
+    };
+
+    let patch = next_message(receiver).await;
+    if patch.is_none() {
+        *subscription = None;
+    }
+    patch
+}
+
+// Waits for the next patch or event on a subscription, as a response for the client.
+pub async fn next_update(subscription: &mut Option<Subscription>) -> Option<DaemonResponse> {
+    let (patches, events) = match subscription {
+        Some(subscription) => (&mut subscription.patches, &mut subscription.events),
+        None => return pending().await,
+    };
+
+    let update = tokio::select! {
+        patch = next_message(patches) => patch.map(DaemonResponse::StatusPatch),
+        event = next_message(events) => event.map(DaemonResponse::Event),
+    };
+    if update.is_none() {
+        *subscription = None;
+    }
+    update
+}
+
+async fn next_message<T: Clone>(receiver: &mut broadcast::Receiver<T>) -> Option<T> {
+    loop {
+        match receiver.recv().await {
+            Ok(message) => return Some(message),
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
 pub async fn handle_packet(
     request: DaemonRequest,
     usb_tx: &mut DeviceSender,
+    client: &mut ClientIdentity,
 ) -> Result<DaemonResponse> {
     match request {
         DaemonRequest::Ping => Ok(DaemonResponse::Ok),
+        DaemonRequest::GetProtocolVersion => Ok(DaemonResponse::ProtocolVersion(PROTOCOL_VERSION)),
         DaemonRequest::GetStatus => {
             let (tx, rx) = oneshot::channel();
             usb_tx
@@ -75,7 +241,12 @@ pub async fn handle_packet(
         DaemonRequest::Command(serial, command) => {
             let (tx, rx) = oneshot::channel();
             usb_tx
-                .send(DeviceCommand::RunDeviceCommand(serial, command, tx))
+                .send(DeviceCommand::RunDeviceCommand(
+                    serial,
+                    command,
+                    client.clone(),
+                    tx,
+                ))
                 .await
                 .map_err(|e| anyhow!(e.to_string()))
                 .context("Could not communicate with the GoXLR device")?;
@@ -83,5 +254,385 @@ pub async fn handle_packet(
                 .context("Could not execute the command on the GoXLR device")??;
             Ok(DaemonResponse::Ok)
         }
+        DaemonRequest::CommandBatch(serial, commands, rollback) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::RunDeviceCommandBatch(
+                    serial,
+                    commands,
+                    rollback,
+                    client.clone(),
+                    tx,
+                ))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            rx.await
+                .context("Could not execute the commands on the GoXLR device")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::Validate(serial, command) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::ValidateDeviceCommand(serial, command, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            rx.await
+                .context("Could not validate the command on the GoXLR device")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::Subscribe | DaemonRequest::SubscribeNotifications => {
+            Err(anyhow!("Subscriptions are not supported here"))
+        }
+        DaemonRequest::StartMicCalibration(serial, mic_type, apply) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::StartMicCalibration(
+                    serial, mic_type, apply, tx,
+                ))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            rx.await
+                .context("Could not start calibration on the GoXLR device")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::RunDiagnostics(serial) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::RunDiagnostics(serial, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            let report = rx
+                .await
+                .context("Could not run diagnostics on the GoXLR device")??;
+            Ok(DaemonResponse::Diagnostics(report))
+        }
+        DaemonRequest::VerifyState(serial) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::VerifyState(serial, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            let report = rx
+                .await
+                .context("Could not verify the state of the GoXLR device")??;
+            Ok(DaemonResponse::StateVerification(report))
+        }
+        DaemonRequest::GetMuteState(serial) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetMuteState(serial, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            let report = rx
+                .await
+                .context("Could not get the mute state of the GoXLR device")??;
+            Ok(DaemonResponse::MuteState(report))
+        }
+        DaemonRequest::GetHardwareInfo(serial) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetHardwareInfo(serial, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            let info = rx
+                .await
+                .context("Could not read the hardware info of the GoXLR device")??;
+            Ok(DaemonResponse::HardwareInfo(info))
+        }
+        DaemonRequest::SendRawCommand(serial, command_id, body) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::SendRawCommand(serial, command_id, body, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            let response = rx
+                .await
+                .context("Could not send the command to the GoXLR device")??;
+            Ok(DaemonResponse::RawResponse(response))
+        }
+        DaemonRequest::ExportMicProfile(serial) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::ExportMicProfile(serial, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the GoXLR device")?;
+            let json = rx
+                .await
+                .context("Could not export the mic profile from the GoXLR device")??;
+            Ok(DaemonResponse::MicProfileJson(json))
+        }
+        DaemonRequest::GetSettingsInfo => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetSettingsInfo(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            Ok(DaemonResponse::SettingsInfo(
+                rx.await
+                    .context("Could not read the settings information")?,
+            ))
+        }
+        DaemonRequest::FlushSettings => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::FlushSettings(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not save the settings")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::GetDaemonStatus => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetDaemonHealth(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            Ok(DaemonResponse::DaemonHealth(
+                rx.await.context("Could not read the daemon status")?,
+            ))
+        }
+        DaemonRequest::GetDeviceSettings(serial) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetDeviceSettings(Some(serial), tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            Ok(DaemonResponse::DeviceSettings(
+                rx.await.context("Could not read the device settings")?,
+            ))
+        }
+        DaemonRequest::GetDefaultDeviceSettings => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetDeviceSettings(None, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            Ok(DaemonResponse::DeviceSettings(
+                rx.await.context("Could not read the device settings")?,
+            ))
+        }
+        DaemonRequest::SetDeviceSettings(serial, settings) => {
+            set_device_settings(usb_tx, Some(serial), settings).await
+        }
+        DaemonRequest::SetDefaultDeviceSettings(settings) => {
+            set_device_settings(usb_tx, None, settings).await
+        }
+        DaemonRequest::GetSamples => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetSamples(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            let samples = rx.await.context("Could not read the samples")??;
+            Ok(DaemonResponse::Samples(samples))
+        }
+        DaemonRequest::FindSamples(query) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::FindSamples(query, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            let page = rx.await.context("Could not read the samples")??;
+            Ok(DaemonResponse::SamplePage(page))
+        }
+        DaemonRequest::AddSample(path, data) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::AddSample(path, data, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not add the sample")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::MoveSample(from, to) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::MoveSample(from, to, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not move the sample")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::DeleteSample(path) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::DeleteSample(path, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not delete the sample")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::GetSampleWaveform(path, points) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetSampleWaveform(path, points, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            let waveform = rx.await.context("Could not read the sample")??;
+            Ok(DaemonResponse::SampleWaveform(waveform))
+        }
+        DaemonRequest::GetProfileBackups(profile_type, name) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetProfileBackups(profile_type, name, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            let backups = rx.await.context("Could not list the profile backups")??;
+            Ok(DaemonResponse::ProfileBackups(backups))
+        }
+        DaemonRequest::RestoreProfileBackup(profile_type, name, id) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::RestoreProfileBackup(
+                    profile_type,
+                    name,
+                    id,
+                    tx,
+                ))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not restore the profile backup")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::SetObsSettings(settings) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::SetObsSettings(settings, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not update the OBS settings")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::SetUpdateSettings(settings) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::SetUpdateSettings(settings, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not update the update settings")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::SetCustomUiDirectory(directory) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::SetCustomUiDirectory(directory, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await
+                .context("Could not set the custom UI directory")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::SetClientName(name) => {
+            debug!("Client {:?} is {}", client, name);
+            client.name = Some(name);
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::GetAuditLog => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetAuditLog(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            Ok(DaemonResponse::AuditLog(
+                rx.await.context("Could not get the audit log")?,
+            ))
+        }
+        DaemonRequest::SetScriptEnabled(name, enabled) => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::SetScriptEnabled(name, enabled, tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not update the script")??;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::GetUiUrl => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetUiUrl(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            Ok(DaemonResponse::UiUrl(
+                rx.await.context("Could not get the UI's URL")??,
+            ))
+        }
+        DaemonRequest::RestartDaemon => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::RestartDaemon(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not restart the daemon")?;
+            Ok(DaemonResponse::Ok)
+        }
+        DaemonRequest::GetDeviceSummary => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::GetDeviceSummary(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            Ok(DaemonResponse::DeviceSummary(
+                rx.await.context("Could not get the device summary")?,
+            ))
+        }
+        DaemonRequest::ReloadScripts => {
+            let (tx, rx) = oneshot::channel();
+            usb_tx
+                .send(DeviceCommand::ReloadScripts(tx))
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("Could not communicate with the device task")?;
+            rx.await.context("Could not reload the scripts")?;
+            Ok(DaemonResponse::Ok)
+        }
     }
 }
+
+async fn set_device_settings(
+    usb_tx: &mut DeviceSender,
+    serial: Option<String>,
+    settings: DeviceSettings,
+) -> Result<DaemonResponse> {
+    let (tx, rx) = oneshot::channel();
+    usb_tx
+        .send(DeviceCommand::SetDeviceSettings(
+            serial,
+            Box::new(settings),
+            tx,
+        ))
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("Could not communicate with the device task")?;
+    rx.await.context("Could not update the device settings")??;
+    Ok(DaemonResponse::Ok)
+}
@@ -1,40 +1,134 @@
+mod handlers;
+mod lighting;
+mod mic;
+mod profiles;
+mod routing;
+mod sampler;
+mod volumes;
+
 use crate::audio::AudioHandler;
-use crate::mic_profile::MicProfileAdapter;
-use crate::profile::{version_newer_or_equal_to, ProfileAdapter};
-use crate::SettingsHandle;
+use crate::communication::to_daemon_error;
+use crate::device::handlers::CommandHandlers;
+use crate::hooks::{run_hooks, HookEvent};
+use crate::mic_profile::{get_eq_freq_range, MicProfileAdapter};
+use crate::mute::{CoughMute, FaderMute, MuteAction};
+use crate::profile::{
+    map_button_to_standard_colour_target, profile_to_standard_mute_function,
+    profile_to_standard_sample_button, standard_to_profile_mute_function,
+    version_newer_or_equal_to, ProfileAdapter,
+};
+use crate::settings::SettingsHandle;
+use crate::themes::theme_exists;
 use anyhow::{anyhow, Result};
 use enum_map::EnumMap;
 use enumset::EnumSet;
-use futures::executor::block_on;
-use goxlr_ipc::{DeviceType, FaderStatus, GoXLRCommand, HardwareStatus, MicSettings, MixerStatus};
+use goxlr_ipc::{
+    is_valid_route, volume_to_db, Autosave, ButtonLightState, CommandLatency, CoughMuteState,
+    DaemonError, DaemonEvent, DeviceFeatures, DeviceSettings, DeviceType, DiagnosticCheck,
+    DiagnosticReport, FaderHold, FaderHoldAction, FaderStatus, GoXLRCommand, HardwareInfo,
+    HardwareStatus, HsvColour, InputEvent, InvalidRequest, Lighting, MicCalibration,
+    MicCalibrationState, MicLevel, MicSettings, MixerStatus, MuteButtonState, MuteState,
+    MuteStateReport, SampleBankSwitch, SamplePlayback, StateDrift, StateVerification,
+    UsbDescriptorInfo,
+};
 use goxlr_profile_loader::components::mute::MuteFunction;
 use goxlr_profile_loader::SampleButtons;
 use goxlr_types::{
-    ChannelName, EffectBankPresets, EffectKey, EncoderName, FaderName,
-    InputDevice as BasicInputDevice, MicrophoneParamKey, OutputDevice as BasicOutputDevice,
-    SampleBank, VersionNumber,
+    ButtonColourGroups, ButtonColourTargets, ChannelName, EffectBankPresets, EffectKey,
+    EncoderName, FaderDisplayStyle, FaderName, InputDevice as BasicInputDevice, MicrophoneParamKey,
+    MicrophoneType, OutputDevice as BasicOutputDevice, ProfileSection, RgbColour, SampleBank,
+    VersionNumber,
 };
 use goxlr_usb::buttonstate::{ButtonStates, Buttons};
 use goxlr_usb::channelstate::ChannelState::{Muted, Unmuted};
-use goxlr_usb::goxlr::GoXLR;
+use goxlr_usb::commands::{Command, HardwareInfoCommand};
+use goxlr_usb::dcp::DCPCategory;
+use goxlr_usb::device::{GoXlrDevice, InterruptWait};
 use goxlr_usb::routing::{InputDevice, OutputDevice};
-use goxlr_usb::rusb::UsbContext;
-use log::{debug, error, info};
-use std::collections::HashSet;
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
-use strum::IntoEnumIterator;
-
-#[derive(Debug)]
-pub struct Device<'a, T: UsbContext> {
-    goxlr: GoXLR<T>,
+use goxlr_usb::trace::to_hex;
+use log::{debug, error, info, warn};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use strum::{EnumCount, IntoEnumIterator};
+use tokio::time::sleep;
+
+pub struct Device<'a> {
+    goxlr: Box<dyn GoXlrDevice>,
     hardware: HardwareStatus,
     last_buttons: EnumSet<Buttons>,
+    last_inputs: Option<([u8; 4], [i8; 4])>,
+
+    // When each encoder last moved, to tell how quickly it's being turned
+    encoder_turned: [Option<Instant>; EncoderName::COUNT],
     button_states: EnumMap<Buttons, ButtonState>,
     profile: ProfileAdapter,
     mic_profile: MicProfileAdapter,
+
+    // The profile which was loaded before the current one, as it was left
+    previous_profile: Option<ProfileAdapter>,
+
+    // While set, nothing is routed to the Broadcast Mix, regardless of the profile.
+    stream_panic: bool,
+
+    // While any are soloed, only these channels are routed to the Headphones.
+    soloed_channels: Vec<ChannelName>,
+
+    // While set, the buttons, faders and encoders are reported but otherwise ignored. The
+    // encoders are held where they were when first seen locked.
+    locked: bool,
+    locked_encoders: Option<[i8; 4]>,
+
+    // The mute function used when a fader's mute button was held, until it's unmuted
+    held_mute_functions: [Option<MuteFunction>; 4],
+
+    // The fader page last moved to, and the mute buttons of muted channels paged off the faders
+    fader_page: Option<usize>,
+    paged_mutes: [Option<PagedMute>; ChannelName::COUNT],
+
+    // Whether the last press of a hybrid cough button muted the mic
+    cough_press_muted: bool,
+
+    // Whether the bleep button is being held, and how far the bleep has risen with an attack
+    bleeping: bool,
+    bleep_ramp: Option<BleepRamp>,
     audio_handler: Option<AudioHandler>,
+    mic_level: MicLevelMeter,
+
+    // When the mic level was last sent to subscribers, and what it was
+    mic_level_sent: Option<(Instant, MicLevel)>,
+    mic_calibration: Option<MicCalibrationRun>,
+    hook_state: HookState,
+    mute_changes: Vec<(ChannelName, bool)>,
+    recordings: HashMap<SampleButtons, SampleRecording>,
+    events: Vec<DaemonEvent>,
     settings: &'a SettingsHandle,
+
+    // A copy of this device's daemon settings, so they can be read without waiting on the lock.
+    device_settings: DeviceSettings,
+
+    // The profiles as they were last loaded or saved, so autosave can tell when they've changed.
+    saved_profile: Option<Vec<u8>>,
+    saved_mic_profile: Option<Vec<u8>>,
+    last_autosave: Instant,
+
+    // What was last sent to the device, so unchanged lighting isn't sent again.
+    last_button_states: Option<[ButtonStates; 24]>,
+    last_colour_map: Option<[u8; 520]>,
+
+    command_handlers: CommandHandlers,
+}
+
+// The state of a mute button whose channel was paged off the faders. The channel is left as it
+// was, and the state goes back on whichever mute button the channel returns to.
+#[derive(Debug, Copy, Clone)]
+struct PagedMute {
+    muted_to_x: bool,
+    muted_to_all: bool,
+    mute_function: MuteFunction,
+    previous_volume: u8,
 }
 
 // Experimental code:
@@ -44,32 +138,87 @@ struct ButtonState {
     hold_handled: bool,
 }
 
-impl<'a, T: UsbContext> Device<'a, T> {
+// The lowest level reported by the meter, anything quieter is treated as silence.
+const MIC_LEVEL_FLOOR: f32 = -72.0;
+
+// Levels at or above this are considered to be clipping.
+const MIC_CLIP_THRESHOLD: f32 = -0.5;
+
+// How long each step of the diagnostic light sweep is shown for.
+const DIAGNOSTIC_STEP_DURATION: Duration = Duration::from_millis(60);
+const DIAGNOSTIC_LATENCY_SAMPLES: usize = 20;
+
+// The quietest the hardware bleep can be set to.
+const MIN_BLEEP_VOLUME: i8 = -34;
+
+const MIC_PEAK_HOLD: Duration = Duration::from_millis(1500);
+const MIC_CLIP_HOLD: Duration = Duration::from_millis(2000);
+
+// Subscribers are sent the mic level no more often than this.
+const MIC_LEVEL_EVENT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Copy, Clone)]
+struct MicLevelMeter {
+    level: f32,
+    peak: f32,
+    peak_time: Instant,
+    clip_time: Option<Instant>,
+}
+
+// How long to sample the microphone for when calibrating, and the peak level we aim for.
+const MIC_CALIBRATION_TIME: Duration = Duration::from_millis(5000);
+const MIC_CALIBRATION_TARGET: f32 = -12.0;
+const MIC_MAX_GAIN: f32 = 72.0;
+
+#[derive(Debug, Clone)]
+struct MicCalibrationRun {
+    started: Instant,
+    gain: u16,
+    apply: bool,
+    status: MicCalibration,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct BleepRamp {
+    started: Instant,
+    volume: i8,
+}
+
+// A sample being recorded, which will be added to the bank that was active when it started.
+#[derive(Debug, Clone)]
+struct SampleRecording {
+    bank: SampleBank,
+    file: String,
+}
+
+// The last seen state of things which trigger hooks (or OBS changes) when they change.
+#[derive(Debug, Default, Copy, Clone)]
+struct HookState {
+    mic_muted: bool,
+    fx_enabled: bool,
+    stream_muted: [bool; ChannelName::COUNT],
+}
+
+impl Default for MicLevelMeter {
+    fn default() -> Self {
+        Self {
+            level: MIC_LEVEL_FLOOR,
+            peak: MIC_LEVEL_FLOOR,
+            peak_time: Instant::now(),
+            clip_time: None,
+        }
+    }
+}
+
+impl<'a> Device<'a> {
     pub fn new(
-        goxlr: GoXLR<T>,
+        goxlr: Box<dyn GoXlrDevice>,
         hardware: HardwareStatus,
-        profile_name: Option<String>,
-        mic_profile_name: Option<String>,
-        profile_directory: &Path,
-        mic_profile_directory: &Path,
+        profile: ProfileAdapter,
+        mic_profile: MicProfileAdapter,
         settings_handle: &'a SettingsHandle,
+        device_settings: DeviceSettings,
     ) -> Result<Self> {
-        info!(
-            "Loading Profile: {}",
-            profile_name
-                .clone()
-                .unwrap_or_else(|| "Not Defined".to_string())
-        );
-        info!(
-            "Loading Mic Profile: {}",
-            mic_profile_name
-                .clone()
-                .unwrap_or_else(|| "Not Defined".to_string())
-        );
-        let profile = ProfileAdapter::from_named_or_default(profile_name, vec![profile_directory]);
-        let mic_profile =
-            MicProfileAdapter::from_named_or_default(mic_profile_name, vec![mic_profile_directory]);
-
         let mut audio_handler = None;
         if let Ok(audio) = AudioHandler::new() {
             audio_handler = Some(audio);
@@ -78,16 +227,46 @@ impl<'a, T: UsbContext> Device<'a, T> {
         let mut device = Self {
             profile,
             mic_profile,
+            previous_profile: None,
+            stream_panic: false,
+            soloed_channels: Vec::new(),
+            locked: false,
+            locked_encoders: None,
+            held_mute_functions: [None; 4],
+            fader_page: None,
+            paged_mutes: [None; ChannelName::COUNT],
+            cough_press_muted: false,
+            bleeping: false,
+            bleep_ramp: None,
             goxlr,
             hardware,
             last_buttons: EnumSet::empty(),
+            last_inputs: None,
+            encoder_turned: [None; EncoderName::COUNT],
             button_states: EnumMap::default(),
             audio_handler,
+            mic_level: MicLevelMeter::default(),
+            mic_level_sent: None,
+            mic_calibration: None,
+            hook_state: HookState::default(),
+            mute_changes: Vec::new(),
+            recordings: HashMap::new(),
+            events: Vec::new(),
             settings: settings_handle,
+            device_settings,
+            saved_profile: None,
+            saved_mic_profile: None,
+            last_autosave: Instant::now(),
+            last_button_states: None,
+            last_colour_map: None,
+            command_handlers: CommandHandlers::default(),
         };
 
         device.apply_profile()?;
         device.apply_mic_profile()?;
+        device.hook_state = device.get_hook_state();
+        device.saved_profile = device.profile.get_contents().ok();
+        device.saved_mic_profile = device.mic_profile.get_contents().ok();
 
         Ok(device)
     }
@@ -96,6 +275,10 @@ impl<'a, T: UsbContext> Device<'a, T> {
         &self.hardware.serial_number
     }
 
+    pub fn hardware(&self) -> &HardwareStatus {
+        &self.hardware
+    }
+
     pub fn status(&self) -> MixerStatus {
         let mut fader_map = [Default::default(); 4];
         fader_map[FaderName::A as usize] = self.get_fader_state(FaderName::A);
@@ -103,12 +286,24 @@ impl<'a, T: UsbContext> Device<'a, T> {
         fader_map[FaderName::C as usize] = self.get_fader_state(FaderName::C);
         fader_map[FaderName::D as usize] = self.get_fader_state(FaderName::D);
 
+        let mut mute_states = [MuteState::Unmuted; ChannelName::COUNT];
+        for channel in ChannelName::iter() {
+            mute_states[channel as usize] = self.get_channel_mute_state(channel);
+        }
+
         MixerStatus {
             hardware: self.hardware.clone(),
             fader_status: fader_map,
             cough_button: self.profile.get_cough_status(),
             bleep_volume: self.get_bleep_volume(),
             volumes: self.profile.get_volumes(),
+            volumes_db: self.profile.get_volumes().map(volume_to_db),
+            volume_limits: self.get_volume_limits(),
+            channel_names: self.get_channel_names(),
+            channel_colours: self.get_channel_colours(),
+            output_names: self.get_output_names(),
+            volume_links: self.device_settings.volume_links.clone(),
+            mute_states,
             router: self.profile.create_router(),
             router_table: self.profile.create_router_table(),
             mic_status: MicSettings {
@@ -118,12 +313,39 @@ impl<'a, T: UsbContext> Device<'a, T> {
                 equaliser: self.mic_profile.equalizer_ipc(),
                 equaliser_mini: self.mic_profile.equalizer_mini_ipc(),
                 compressor: self.mic_profile.compressor_ipc(),
+                level: self.get_mic_level(),
+                calibration: self
+                    .mic_calibration
+                    .as_ref()
+                    .map(|calibration| calibration.status.clone()),
+                deesser: self.mic_profile.get_deesser() as u8,
+                bleep_volume: self.get_bleep_volume(),
+                editor_modes: self.mic_profile.editor_modes_ipc(),
             },
             lighting: self
                 .profile
                 .get_lighting_ipc(self.hardware.device_type == DeviceType::Mini),
+            button_states: self.get_button_light_states(),
             profile_name: self.profile.name().to_owned(),
             mic_profile_name: self.mic_profile.name().to_owned(),
+            previous_profile_name: self
+                .previous_profile
+                .as_ref()
+                .map(|profile| profile.name().to_owned()),
+            stream_panic: self.stream_panic,
+            soloed_channels: self.soloed_channels.clone(),
+            locked: self.locked,
+            fader_page: self.fader_page,
+            submixes: self
+                .hardware
+                .features
+                .submixes
+                .then(|| self.profile.get_submixes_ipc()),
+            sampler: self.hardware.features.sampler.then(|| {
+                let mut sampler = self.profile.get_sampler_ipc();
+                sampler.playing = self.get_sample_playback();
+                sampler
+            }),
         }
     }
 
@@ -145,8 +367,21 @@ impl<'a, T: UsbContext> Device<'a, T> {
             self.sync_sample_lighting().await?;
         }
 
+        if let Ok(level) = self.goxlr.get_microphone_level() {
+            self.update_mic_level(level);
+            self.send_mic_level();
+            self.update_mic_calibration()?;
+        }
+
         if let Ok(state) = self.goxlr.get_button_states() {
-            self.update_volumes_to(state.volumes);
+            self.report_input_changes(state.volumes, state.encoders);
+            if self.locked {
+                self.report_locked_buttons(state.pressed);
+                self.hold_locked_inputs(state.volumes, state.encoders)?;
+                self.check_hook_state().await;
+                return Ok(());
+            }
+            self.update_volumes_to(state.volumes)?;
             self.update_encoders_to(state.encoders)?;
 
             let pressed_buttons = state.pressed.difference(self.last_buttons);
@@ -157,6 +392,12 @@ impl<'a, T: UsbContext> Device<'a, T> {
                     hold_handled: false,
                 };
 
+                self.push_input_event(InputEvent::ButtonDown(
+                    map_button_to_standard_colour_target(button),
+                ));
+                if self.is_passthrough_button(button) {
+                    continue;
+                }
                 if let Err(error) = self.on_button_down(button).await {
                     error!("{}", error);
                 }
@@ -165,10 +406,15 @@ impl<'a, T: UsbContext> Device<'a, T> {
             let released_buttons = self.last_buttons.difference(state.pressed);
             for button in released_buttons {
                 let button_state = self.button_states[button];
+                self.push_input_event(InputEvent::ButtonUp(map_button_to_standard_colour_target(
+                    button,
+                )));
 
                 // Output errors, but don't throw them up the stack!
-                if let Err(error) = self.on_button_up(button, &button_state).await {
-                    error!("{}", error);
+                if !self.is_passthrough_button(button) {
+                    if let Err(error) = self.on_button_up(button, &button_state).await {
+                        error!("{}", error);
+                    }
                 }
 
                 self.button_states[button] = ButtonState {
@@ -178,13 +424,19 @@ impl<'a, T: UsbContext> Device<'a, T> {
             }
 
             // Finally, iterate over our existing button states, and see if any have been
-            // pressed for more than half a second and not handled.
+            // pressed for longer than the hold time and not handled.
             for button in state.pressed {
                 if !self.button_states[button].hold_handled {
+                    let hold_time = self.button_hold_time(button);
                     let now = self.get_epoch_ms();
-                    if (now - self.button_states[button].press_time) > 500 {
-                        if let Err(error) = self.on_button_hold(button).await {
-                            error!("{}", error);
+                    if (now - self.button_states[button].press_time) > hold_time {
+                        self.push_input_event(InputEvent::ButtonHeld(
+                            map_button_to_standard_colour_target(button),
+                        ));
+                        if !self.is_passthrough_button(button) {
+                            if let Err(error) = self.on_button_hold(button).await {
+                                error!("{}", error);
+                            }
                         }
                         self.button_states[button].hold_handled = true;
                     }
@@ -194,10 +446,134 @@ impl<'a, T: UsbContext> Device<'a, T> {
             self.last_buttons = state.pressed;
         }
 
+        self.check_hook_state().await;
+        Ok(())
+    }
+
+    // Reports faders and encoders which have physically moved since they were last read.
+    fn report_input_changes(&mut self, volumes: [u8; 4], encoders: [i8; 4]) {
+        if let Some((last_volumes, last_encoders)) = self.last_inputs {
+            for fader in FaderName::iter() {
+                let volume = volumes[fader as usize];
+                if volume != last_volumes[fader as usize] {
+                    self.push_input_event(InputEvent::FaderMoved(fader, volume));
+                }
+            }
+
+            for encoder in EncoderName::iter() {
+                let value = encoders[encoder as usize];
+                if value != last_encoders[encoder as usize] {
+                    self.push_input_event(InputEvent::EncoderTurned(encoder, value));
+                }
+            }
+        }
+        self.last_inputs = Some((volumes, encoders));
+    }
+
+    fn is_quick_switch_button(&self, button: Buttons) -> bool {
+        self.device_settings.quick_switch_button
+            == Some(map_button_to_standard_colour_target(button))
+    }
+
+    fn is_fader_page_button(&self, button: Buttons) -> bool {
+        self.device_settings.fader_page_button == Some(map_button_to_standard_colour_target(button))
+    }
+
+    fn is_stream_panic_button(&self, button: Buttons) -> bool {
+        self.device_settings.stream_panic_button
+            == Some(map_button_to_standard_colour_target(button))
+    }
+
+    fn get_routing_preset_button(&self, button: Buttons) -> Option<String> {
+        self.device_settings
+            .routing_preset_buttons
+            .get(&map_button_to_standard_colour_target(button))
+            .cloned()
+    }
+
+    // Passthrough buttons belong to other tools, so only have their input events sent.
+    fn is_passthrough_button(&self, button: Buttons) -> bool {
+        self.device_settings
+            .passthrough_buttons
+            .contains(&map_button_to_standard_colour_target(button))
+    }
+
+    // The button whose function a press of this one performs.
+    fn remap_button(&self, button: Buttons) -> Buttons {
+        let target = map_button_to_standard_colour_target(button);
+        match self.device_settings.button_remap.get(&target) {
+            Some(function) => Buttons::iter()
+                .find(|button| map_button_to_standard_colour_target(*button) == *function)
+                .unwrap_or(button),
+            None => button,
+        }
+    }
+
+    fn push_input_event(&mut self, input: InputEvent) {
+        self.events.push(DaemonEvent::Input {
+            serial: self.serial().to_owned(),
+            time: self.get_epoch_ms() as u64,
+            input,
+        });
+    }
+
+    fn get_hook_state(&self) -> HookState {
+        let mut stream_muted = [false; ChannelName::COUNT];
+        for channel in ChannelName::iter() {
+            stream_muted[channel as usize] = self.is_channel_muted_to_stream(channel);
+        }
+
+        HookState {
+            mic_muted: self.mic_muted_by_fader() || self.mic_muted_by_cough(),
+            fx_enabled: self.profile.is_fx_enabled(),
+            stream_muted,
+        }
+    }
+
+    /// Returns the channels whose stream mute state has changed since this was last called.
+    pub fn take_mute_changes(&mut self) -> Vec<(ChannelName, bool)> {
+        std::mem::take(&mut self.mute_changes)
+    }
+
+    // The device may have lost its settings while the computer was asleep.
+    pub fn resume(&mut self) -> Result<()> {
+        self.sync_to_hardware()?;
+        self.events.push(DaemonEvent::Resumed {
+            serial: self.serial().to_owned(),
+        });
         Ok(())
     }
 
+    pub fn take_events(&mut self) -> Vec<DaemonEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // Runs the hooks for any state which has changed since this was last called.
+    pub async fn check_hook_state(&mut self) {
+        let state = self.get_hook_state();
+
+        if state.mic_muted != self.hook_state.mic_muted {
+            let data = json!({ "muted": state.mic_muted });
+            run_hooks(self.settings, HookEvent::MicMuted, self.serial(), data).await;
+        }
+
+        if state.fx_enabled != self.hook_state.fx_enabled {
+            let data = json!({ "enabled": state.fx_enabled });
+            run_hooks(self.settings, HookEvent::FxEnabled, self.serial(), data).await;
+        }
+
+        for channel in ChannelName::iter() {
+            let muted = state.stream_muted[channel as usize];
+            if muted != self.hook_state.stream_muted[channel as usize] {
+                self.mute_changes.push((channel, muted));
+            }
+        }
+
+        self.hook_state = state;
+    }
+
     async fn on_button_down(&mut self, button: Buttons) -> Result<()> {
+        let button = self.remap_button(button);
         debug!("Handling Button Down: {:?}", button);
 
         match button {
@@ -214,55 +590,76 @@ impl<'a, T: UsbContext> Device<'a, T> {
     }
 
     async fn on_button_hold(&mut self, button: Buttons) -> Result<()> {
+        let button = self.remap_button(button);
         debug!("Handling Button Hold: {:?}", button);
-        match button {
-            Buttons::Fader1Mute => {
-                self.handle_fader_mute(FaderName::A, true).await?;
-            }
-            Buttons::Fader2Mute => {
-                self.handle_fader_mute(FaderName::B, true).await?;
-            }
-            Buttons::Fader3Mute => {
-                self.handle_fader_mute(FaderName::C, true).await?;
-            }
-            Buttons::Fader4Mute => {
-                self.handle_fader_mute(FaderName::D, true).await?;
+        if self.is_quick_switch_button(button) {
+            return self.quick_switch_profile().await;
+        }
+        if self.is_stream_panic_button(button) {
+            return self.set_stream_panic(!self.stream_panic);
+        }
+        if self.is_fader_page_button(button) && !self.device_settings.fader_pages.is_empty() {
+            return self.next_fader_page().await;
+        }
+        if let Some(preset) = self.get_routing_preset_button(button) {
+            return self.apply_routing_preset(&preset);
+        }
+
+        if let Some(fader) = get_mute_button_fader(button) {
+            match self.fader_hold(fader).action {
+                FaderHoldAction::Mute(_) => self.handle_fader_mute(fader, true).await?,
+                FaderHoldAction::Solo => {
+                    let channel = self.profile.get_fader_assignment(fader);
+                    if get_channel_input(channel).is_some() {
+                        self.set_solo(channel, !self.soloed_channels.contains(&channel))?;
+                    }
+                }
+                FaderHoldAction::Disabled => {}
             }
-            Buttons::MicrophoneMute => {
-                self.handle_cough_mute(false, false, true, false).await?;
+        }
+
+        // Holding an empty pad records until it's released, where handle_sample_button stops it.
+        if let Some(sample_button) = get_sample_button(button) {
+            if self.audio_handler.is_some()
+                && !self.recordings.contains_key(&sample_button)
+                && !self.profile.current_sample_bank_has_samples(sample_button)
+            {
+                self.start_recording(sample_button).await?;
             }
-            _ => {}
+        }
+
+        if button == Buttons::MicrophoneMute {
+            self.handle_cough_mute(false, false, true, false).await?;
         }
         self.update_button_states()?;
         Ok(())
     }
 
     async fn on_button_up(&mut self, button: Buttons, state: &ButtonState) -> Result<()> {
+        let button = self.remap_button(button);
         debug!(
             "Handling Button Release: {:?}, Has Long Press Handled: {:?}",
             button, state.hold_handled
         );
-        match button {
-            Buttons::Fader1Mute => {
-                if !state.hold_handled {
-                    self.handle_fader_mute(FaderName::A, false).await?;
-                }
-            }
-            Buttons::Fader2Mute => {
-                if !state.hold_handled {
-                    self.handle_fader_mute(FaderName::B, false).await?;
-                }
-            }
-            Buttons::Fader3Mute => {
-                if !state.hold_handled {
-                    self.handle_fader_mute(FaderName::C, false).await?;
-                }
-            }
-            Buttons::Fader4Mute => {
-                if !state.hold_handled {
-                    self.handle_fader_mute(FaderName::D, false).await?;
-                }
+
+        // The hold has already done the button's bound action, so its normal action is skipped.
+        if state.hold_handled
+            && (self.is_quick_switch_button(button)
+                || self.is_stream_panic_button(button)
+                || self.is_fader_page_button(button)
+                || self.get_routing_preset_button(button).is_some())
+        {
+            return Ok(());
+        }
+
+        if let Some(fader) = get_mute_button_fader(button) {
+            // With holding disabled, the button acts on release however long it was held.
+            if !state.hold_handled || self.fader_hold(fader).action == FaderHoldAction::Disabled {
+                self.handle_fader_mute(fader, false).await?;
             }
+        }
+
+        match button {
             Buttons::MicrophoneMute => {
                 self.handle_cough_mute(false, true, false, state.hold_handled)
                     .await?;
@@ -337,97 +734,169 @@ impl<'a, T: UsbContext> Device<'a, T> {
     }
 
     async fn handle_fader_mute(&mut self, fader: FaderName, held: bool) -> Result<()> {
-        // OK, so a fader button has been pressed, we need to determine behaviour, based on the colour map..
         let channel = self.profile.get_fader_assignment(fader);
-        let current_volume = self.profile.get_channel_volume(channel);
-
-        let (muted_to_x, muted_to_all, mute_function) = self.profile.get_mute_button_state(fader);
+        let volume = self.profile.get_channel_volume(channel);
+        let mut mute = self.get_fader_mute(fader);
 
-        // Map the channel to BasicInputDevice in case we need it later..
-        let basic_input = match channel {
-            ChannelName::Mic => Some(BasicInputDevice::Microphone),
-            ChannelName::LineIn => Some(BasicInputDevice::LineIn),
-            ChannelName::Console => Some(BasicInputDevice::Console),
-            ChannelName::System => Some(BasicInputDevice::System),
-            ChannelName::Game => Some(BasicInputDevice::Game),
-            ChannelName::Chat => Some(BasicInputDevice::Chat),
-            ChannelName::Sample => Some(BasicInputDevice::Samples),
-            ChannelName::Music => Some(BasicInputDevice::Music),
-            _ => None,
+        let actions = match self.fader_hold(fader).action {
+            FaderHoldAction::Mute(function) if held => {
+                mute.hold(volume, standard_to_profile_mute_function(function))
+            }
+            _ => {
+                let muted_by_cough = channel == ChannelName::Mic && self.mic_muted_by_cough();
+                mute.tap(volume, muted_by_cough)
+            }
         };
 
-        // Should we be muting this fader to all channels?
-        if held || (!muted_to_x && mute_function == MuteFunction::All) {
-            if held && muted_to_all {
-                // Holding the button when it's already muted to all does nothing.
-                return Ok(());
-            }
+        self.set_fader_mute(fader, mute);
+        self.apply_mute_actions(channel, actions)
+    }
 
-            self.profile
-                .set_mute_button_previous_volume(fader, current_volume);
+    fn get_fader_mute(&self, fader: FaderName) -> FaderMute {
+        let (muted_to_x, muted_to_all, mute_function) = self.profile.get_mute_button_state(fader);
+        FaderMute {
+            muted_to_x,
+            muted_to_all,
+            mute_function,
+            held_function: self.held_mute_functions[fader as usize],
+            previous_volume: self.profile.get_mute_button_previous_volume(fader),
+        }
+    }
 
-            self.goxlr.set_volume(channel, 0)?;
-            self.goxlr.set_channel_state(channel, Muted)?;
+    fn set_fader_mute(&mut self, fader: FaderName, mute: FaderMute) {
+        self.profile.set_mute_button_on(fader, mute.muted_to_x);
+        self.profile.set_mute_button_blink(fader, mute.muted_to_all);
+        self.profile
+            .set_mute_button_previous_volume(fader, mute.previous_volume);
+        self.held_mute_functions[fader as usize] = mute.held_function;
+    }
+
+    fn get_cough_mute(&self) -> CoughMute {
+        let (is_toggle, muted_to_x, muted_to_all, mute_function) =
+            self.profile.get_mute_chat_button_state();
+        CoughMute {
+            is_toggle,
+            is_hybrid: self.profile.is_chat_mute_button_hybrid(),
+            muted_to_x,
+            muted_to_all,
+            mute_function,
+            press_muted: self.cough_press_muted,
+        }
+    }
 
-            self.profile.set_mute_button_on(fader, true);
+    fn set_cough_mute(&mut self, mute: CoughMute) {
+        self.profile.set_mute_chat_button_on(mute.muted_to_x);
+        self.profile.set_mute_chat_button_blink(mute.muted_to_all);
+        self.cough_press_muted = mute.press_muted;
+    }
 
-            if held {
-                self.profile.set_mute_button_blink(fader, true);
+    fn apply_mute_actions(&mut self, channel: ChannelName, actions: Vec<MuteAction>) -> Result<()> {
+        for action in actions {
+            match action {
+                MuteAction::SetVolume(volume) => {
+                    self.goxlr.set_volume(channel, volume)?;
+                    self.profile.set_channel_volume(channel, volume);
+                }
+                MuteAction::SetChannelMuted(true) => {
+                    self.goxlr.set_channel_state(channel, Muted)?
+                }
+                MuteAction::SetChannelMuted(false) => {
+                    self.goxlr.set_channel_state(channel, Unmuted)?
+                }
+                MuteAction::ApplyRouting => {
+                    if let Some(input) = get_channel_input(channel) {
+                        self.apply_routing(input)?;
+                    }
+                }
             }
+        }
+        Ok(())
+    }
 
-            self.profile.set_channel_volume(channel, 0);
+    // The mute button's state, with the function used by a hold in place of the button's own.
+    fn get_fader_mute_state(&self, fader: FaderName) -> (bool, bool, MuteFunction) {
+        let mute = self.get_fader_mute(fader);
+        (mute.muted_to_x, mute.muted_to_all, mute.active_function())
+    }
 
-            return Ok(());
-        }
+    fn fader_hold(&self, fader: FaderName) -> FaderHold {
+        self.device_settings
+            .fader_hold
+            .get(&fader)
+            .copied()
+            .unwrap_or_default()
+    }
 
-        // Button has been pressed, and we're already in some kind of muted state..
-        if !held && muted_to_x {
-            // Disable the lighting regardless of action
-            self.profile.set_mute_button_on(fader, false);
-            self.profile.set_mute_button_blink(fader, false);
+    fn button_hold_time(&self, button: Buttons) -> u128 {
+        let hold_time = get_mute_button_fader(self.remap_button(button))
+            .and_then(|fader| self.fader_hold(fader).hold_time)
+            .unwrap_or(self.device_settings.hold_time);
+        hold_time as u128
+    }
 
-            if muted_to_all || mute_function == MuteFunction::All {
-                let previous_volume = self.profile.get_mute_button_previous_volume(fader);
+    async fn unmute_if_muted(&mut self, fader: FaderName) -> Result<()> {
+        let (muted_to_x, muted_to_all, _mute_function) = self.get_fader_mute_state(fader);
 
-                self.goxlr.set_volume(channel, previous_volume)?;
-                self.profile.set_channel_volume(channel, previous_volume);
+        if muted_to_x || muted_to_all {
+            self.handle_fader_mute(fader, false).await?;
+        }
 
-                if channel != ChannelName::Mic
-                    || (channel == ChannelName::Mic && !self.mic_muted_by_cough())
-                {
-                    self.goxlr.set_channel_state(channel, Unmuted)?;
-                }
-            } else if basic_input.is_some() {
-                self.apply_routing(basic_input.unwrap())?;
-            }
+        Ok(())
+    }
 
+    async fn set_fader_muted(&mut self, fader: FaderName, muted: bool) -> Result<()> {
+        let (muted_to_x, muted_to_all, _mute_function) = self.get_fader_mute_state(fader);
+        if muted == (muted_to_x || muted_to_all) {
+            // Already in the requested state, nothing to do.
             return Ok(());
         }
 
-        if !held && !muted_to_x && mute_function != MuteFunction::All {
-            // Mute channel to X via transient routing table update
-            self.profile.set_mute_button_on(fader, true);
-            if basic_input.is_some() {
-                self.apply_routing(basic_input.unwrap())?;
-            }
+        // A tap of the mute button toggles between unmuted and the configured mute function.
+        self.handle_fader_mute(fader, false).await
+    }
+
+    async fn set_cough_muted(&mut self, muted: bool) -> Result<()> {
+        let (mute_toggle, muted_to_x, muted_to_all, _mute_function) =
+            self.profile.get_mute_chat_button_state();
+        if muted == (muted_to_x || muted_to_all) {
+            return Ok(());
+        }
+
+        // A toggle button flips on release, a hold button mutes on press and unmutes on release,
+        // and a hybrid button needs the whole tap.
+        if self.profile.is_chat_mute_button_hybrid() {
+            self.handle_cough_mute(true, false, false, false).await?;
+            self.handle_cough_mute(false, true, false, false).await
+        } else if !mute_toggle && muted {
+            self.handle_cough_mute(true, false, false, false).await
+        } else {
+            self.handle_cough_mute(false, true, false, false).await
         }
-        Ok(())
     }
 
-    async fn unmute_if_muted(&mut self, fader: FaderName) -> Result<()> {
-        let (muted_to_x, muted_to_all, _mute_function) = self.profile.get_mute_button_state(fader);
+    async fn set_channel_muted(&mut self, channel: ChannelName, muted: bool) -> Result<()> {
+        // Mute state is tracked against the mute buttons, so find the one controlling this channel.
+        for fader in FaderName::iter() {
+            if self.profile.get_fader_assignment(fader) == channel {
+                return self.set_fader_muted(fader, muted).await;
+            }
+        }
 
-        if muted_to_x || muted_to_all {
-            self.handle_fader_mute(fader, false).await?;
+        // The Mic can still be muted with the cough button when it's not on a fader.
+        if channel == ChannelName::Mic {
+            return self.set_cough_muted(muted).await;
         }
 
-        Ok(())
+        Err(invalid(InvalidRequest::ChannelNotOnFader(channel)))
     }
 
     async fn unmute_chat_if_muted(&mut self) -> Result<()> {
         let (_mute_toggle, muted_to_x, muted_to_all, _mute_function) =
             self.profile.get_mute_chat_button_state();
 
+        if self.profile.is_chat_mute_button_hybrid() {
+            return self.set_cough_muted(false).await;
+        }
         if muted_to_x || muted_to_all {
             self.handle_cough_mute(true, false, false, false).await?;
         }
@@ -435,8 +904,6 @@ impl<'a, T: UsbContext> Device<'a, T> {
         Ok(())
     }
 
-    // This one's a little obnoxious because it's heavily settings dependent, so will contain a
-    // large volume of comments working through states, feel free to remove them later :)
     async fn handle_cough_mute(
         &mut self,
         press: bool,
@@ -444,107 +911,70 @@ impl<'a, T: UsbContext> Device<'a, T> {
         held: bool,
         held_called: bool,
     ) -> Result<()> {
-        // This *GENERALLY* works in the same way as other mute buttons, however we need to
-        // accommodate the hold and toggle behaviours, so lets grab the config.
-        let (mute_toggle, muted_to_x, muted_to_all, mute_function) =
-            self.profile.get_mute_chat_button_state();
-
-        // Ok, lets handle things in order, was this button just pressed?
-        if press {
-            if mute_toggle {
-                // Mute toggles are only handled on release.
-                return Ok(());
-            }
-
-            // Enable the cough button in all cases..
-            self.profile.set_mute_chat_button_on(true);
-
-            if mute_function == MuteFunction::All {
-                // In this scenario, we should just set cough_button_on and mute the channel.
-                self.goxlr.set_channel_state(ChannelName::Mic, Muted)?;
-                return Ok(());
-            }
-
-            self.apply_routing(BasicInputDevice::Microphone)?;
+        let mut mute = self.get_cough_mute();
+        let actions = if press {
+            mute.press()
+        } else if held {
+            mute.hold()
+        } else if release {
+            mute.release(held_called, self.mic_muted_by_fader())
+        } else {
             return Ok(());
-        }
-
-        if held {
-            if !mute_toggle {
-                // Holding in this scenario just keeps the channel muted, so no change here.
-                return Ok(());
-            }
+        };
 
-            // We're togglable, so enable blink, set cough_button_on, mute the channel fully and
-            // remove any transient routing which may be set.
-            self.profile.set_mute_chat_button_on(true);
-            self.profile.set_mute_chat_button_blink(true);
+        self.set_cough_mute(mute);
+        self.apply_mute_actions(ChannelName::Mic, actions)
+    }
 
-            self.goxlr.set_channel_state(ChannelName::Mic, Muted)?;
-            self.apply_routing(BasicInputDevice::Microphone)?;
-            return Ok(());
+    async fn handle_swear_button(&mut self, press: bool) -> Result<()> {
+        // Pretty simple, turn the light on when pressed, off when released..
+        self.profile.set_swear_button_on(press);
+        self.bleeping = press;
+        if self.device_settings.bleep_outputs != EnumSet::all() {
+            self.apply_bleep_routing()?;
         }
 
-        if release {
-            if mute_toggle {
-                if held_called {
-                    // We don't need to do anything here, a long press has already been handled.
-                    return Ok(());
-                }
-
-                if muted_to_x || muted_to_all {
-                    self.profile.set_mute_chat_button_on(false);
-                    self.profile.set_mute_chat_button_blink(false);
-
-                    if (muted_to_all || (muted_to_x && mute_function == MuteFunction::All))
-                        && !self.mic_muted_by_fader()
-                    {
-                        self.goxlr.set_channel_state(ChannelName::Mic, Unmuted)?;
-                    }
-
-                    if muted_to_x && mute_function != MuteFunction::All {
-                        self.apply_routing(BasicInputDevice::Microphone)?;
-                    }
-
-                    return Ok(());
-                }
-
-                // In all cases, enable the button
-                self.profile.set_mute_chat_button_on(true);
-
-                if mute_function == MuteFunction::All {
-                    self.goxlr.set_channel_state(ChannelName::Mic, Muted)?;
-                    return Ok(());
-                }
-
-                // Update the transient routing..
-                self.apply_routing(BasicInputDevice::Microphone)?;
-                return Ok(());
-            }
+        // The hardware bleeps the moment the button goes down, and stops when it's released, so
+        // there's no gap for the daemon to fill. With an attack the bleep rests at its quietest,
+        // and is raised by update_bleep_ramp while the button is held.
+        let has_attack =
+            self.device_settings.bleep_sound.is_none() && self.device_settings.bleep_attack > 0;
+        self.bleep_ramp = (press && has_attack).then(|| BleepRamp {
+            started: Instant::now(),
+            volume: MIN_BLEEP_VOLUME,
+        });
+        if !press && has_attack {
+            self.apply_bleep_volume()?;
+        }
 
-            self.profile.set_mute_chat_button_on(false);
-            if mute_function == MuteFunction::All {
-                if !self.mic_muted_by_fader() {
-                    self.goxlr.set_channel_state(ChannelName::Chat, Unmuted)?;
-                }
-                return Ok(());
+        if let (Some(sound), Some(audio_handler)) =
+            (&self.device_settings.bleep_sound, &mut self.audio_handler)
+        {
+            // Unlike the hardware bleep, which the device stops itself, this can trail off.
+            let release = self.device_settings.bleep_release;
+            if press {
+                audio_handler.play_bleep(sound)?;
+            } else if release > 0 {
+                audio_handler.fade_out_bleep(Duration::from_millis(release.into()));
+            } else {
+                audio_handler.stop_bleep();
             }
-
-            // Disable button and refresh transient routing
-            self.apply_routing(BasicInputDevice::Microphone)?;
-            return Ok(());
         }
-
-        Ok(())
-    }
-
-    async fn handle_swear_button(&mut self, press: bool) -> Result<()> {
-        // Pretty simple, turn the light on when pressed, off when released..
-        self.profile.set_swear_button_on(press);
         Ok(())
     }
 
     async fn load_sample_bank(&mut self, bank: SampleBank) -> Result<()> {
+        if bank != self.profile.get_active_sample_bank() {
+            if let Some(audio_handler) = &mut self.audio_handler {
+                match self.device_settings.sample_bank_switch {
+                    SampleBankSwitch::Continue => {}
+                    SampleBankSwitch::Stop => audio_handler.stop_samples(),
+                    SampleBankSwitch::FadeOut(milliseconds) => {
+                        audio_handler.fade_out_samples(Duration::from_millis(milliseconds.into()))
+                    }
+                }
+            }
+        }
         self.profile.load_sample_bank(bank);
 
         Ok(())
@@ -558,32 +988,140 @@ impl<'a, T: UsbContext> Device<'a, T> {
             ));
         }
 
+        if self.recordings.contains_key(&button) {
+            return self.finish_recording(button).await;
+        }
+
         if !self.profile.current_sample_bank_has_samples(button) {
-            // On release, so do nothing really..
-            return Ok(());
+            // As with the official app, pressing an empty button records a sample for it.
+            return self.start_recording(button).await;
         }
 
-        let sample = self.profile.get_sample_file(button);
-        let mut sample_path = self.settings.get_samples_directory().await;
+        let sample = self.profile.get_sample_track(button);
+        let mut sample_path = self.get_samples_directory().await;
 
-        if sample.starts_with("Recording_") {
+        if sample.file.starts_with("Recording_") {
             sample_path = sample_path.join("Recorded");
         }
 
-        sample_path = sample_path.join(sample);
+        sample_path = sample_path.join(&sample.file);
 
         if !sample_path.exists() {
             return Err(anyhow!("Sample File does not exist!"));
         }
 
-        debug!("Attempting to play: {}", sample_path.to_string_lossy());
+        // A looping sample plays until its button is pressed again.
+        let looping = self.profile.is_sample_looping(button);
         let audio_handler = self.audio_handler.as_mut().unwrap();
-        audio_handler.play_for_button(button, sample_path.to_str().unwrap().to_string())?;
-        self.profile.set_sample_button_state(button, true);
+        if looping && audio_handler.is_sample_playing(button) {
+            audio_handler.stop_sample(button);
+            self.profile.set_sample_button_state(button, false);
+            return Ok(());
+        }
 
+        debug!("Attempting to play: {}", sample_path.to_string_lossy());
+        audio_handler.play_for_button(
+            button,
+            &sample_path,
+            sample.start_pct,
+            sample.end_pct,
+            looping,
+        )?;
+        self.profile.set_sample_button_state(button, true);
+
+        let data = json!({
+            "button": format!("{:?}", button),
+            "file": sample_path,
+        });
+        run_hooks(self.settings, HookEvent::SamplePlayed, self.serial(), data).await;
+
+        Ok(())
+    }
+
+    async fn start_recording(&mut self, button: SampleButtons) -> Result<()> {
+        let directory = self.get_samples_directory().await.join("Recorded");
+        create_dir_all(&directory)?;
+
+        // Recordings are named the same way as the official app, which expects them in 'Recorded'.
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let file = format!("Recording_{}.wav", timestamp);
+
+        debug!("Recording {:?} to {}", button, file);
+        let audio_handler = self.audio_handler.as_mut().unwrap();
+        audio_handler.record_for_button(button, &directory.join(&file))?;
+
+        let bank = self.profile.get_active_sample_bank();
+        self.recordings
+            .insert(button, SampleRecording { bank, file });
+        self.profile.set_sample_button_state(button, true);
         Ok(())
     }
 
+    async fn finish_recording(&mut self, button: SampleButtons) -> Result<()> {
+        let recording = self.recordings.remove(&button).unwrap();
+        let audio_handler = self.audio_handler.as_mut().unwrap();
+        audio_handler.stop_recording(button)?;
+        self.profile.set_sample_button_state(button, false);
+
+        let path = self.get_samples_directory().await.join("Recorded");
+        if !path.join(&recording.file).is_file() {
+            self.update_button_states()?;
+            return Err(anyhow!("The recording for {:?} was not saved", button));
+        }
+
+        // The button was dark while it was empty, so its colours need to be sent again.
+        self.profile
+            .add_sample_file(recording.bank, button, recording.file.clone());
+        self.load_colour_map()?;
+        self.update_button_states()?;
+
+        if let Some(button) = profile_to_standard_sample_button(button) {
+            self.events.push(DaemonEvent::SampleRecorded {
+                serial: self.serial().to_owned(),
+                bank: recording.bank,
+                button,
+                file: recording.file,
+            });
+        }
+        Ok(())
+    }
+
+    async fn get_samples_directory(&self) -> PathBuf {
+        match &self.device_settings.samples_directory {
+            Some(directory) => directory.clone(),
+            None => self.settings.get_samples_directory().await,
+        }
+    }
+
+    fn get_sample_playback(&self) -> HashMap<goxlr_types::SampleButtons, SamplePlayback> {
+        let mut playing = HashMap::new();
+        let audio_handler = match &self.audio_handler {
+            Some(audio_handler) => audio_handler,
+            None => return playing,
+        };
+
+        for button in SampleButtons::iter() {
+            if let (Some(standard), Some(playback)) = (
+                profile_to_standard_sample_button(button),
+                audio_handler.get_playback(button),
+            ) {
+                let file = playback.file.file_name().unwrap_or_default();
+                playing.insert(
+                    standard,
+                    SamplePlayback {
+                        file: file.to_string_lossy().into_owned(),
+                        elapsed_ms: playback.elapsed.as_millis() as u64,
+                        duration_ms: playback
+                            .duration
+                            .map(|duration| duration.as_millis() as u64),
+                        looping: playback.looping,
+                    },
+                );
+            }
+        }
+        playing
+    }
+
     async fn sync_sample_lighting(&mut self) -> Result<()> {
         if self.audio_handler.is_none() {
             // No audio handler, no point.
@@ -593,11 +1131,9 @@ impl<'a, T: UsbContext> Device<'a, T> {
         let mut changed = false;
 
         for button in SampleButtons::iter() {
-            let playing = self
-                .audio_handler
-                .as_ref()
-                .unwrap()
-                .is_sample_playing(button);
+            let audio_handler = self.audio_handler.as_ref().unwrap();
+            let playing = audio_handler.is_sample_playing(button)
+                || audio_handler.is_sample_recording(button);
 
             if self.profile.is_sample_active(button) && !playing {
                 self.profile.set_sample_button_state(button, false);
@@ -678,11 +1214,118 @@ impl<'a, T: UsbContext> Device<'a, T> {
         }
 
         let fader = self.profile.fader_from_id(mic_fader_id);
-        let (muted_to_x, muted_to_all, mute_function) = self.profile.get_mute_button_state(fader);
+        let (muted_to_x, muted_to_all, mute_function) = self.get_fader_mute_state(fader);
 
         muted_to_all || (muted_to_x && mute_function == MuteFunction::All)
     }
 
+    // Whether the stream can hear this channel, from either its fader's mute button or the
+    // cough button for the mic.
+    fn is_channel_muted_to_stream(&self, channel: ChannelName) -> bool {
+        if self.stream_panic {
+            return true;
+        }
+        match self.get_channel_mute_state(channel) {
+            MuteState::Unmuted => false,
+            MuteState::MutedToAll => true,
+            MuteState::MutedTo(outputs) => outputs.contains(BasicOutputDevice::BroadcastMix),
+        }
+    }
+
+    // A channel can be muted by any fader it's assigned to, and the Mic by the cough button too.
+    fn get_channel_mute_state(&self, channel: ChannelName) -> MuteState {
+        let mut states = vec![];
+        for fader in FaderName::iter() {
+            if self.profile.get_fader_assignment(fader) == channel {
+                let (muted_to_x, muted_to_all, function) = self.get_fader_mute_state(fader);
+                states.push(get_mute_state(muted_to_x, muted_to_all, function));
+            }
+        }
+
+        if let Some(mute) = self.paged_mutes[channel as usize] {
+            states.push(get_mute_state(
+                mute.muted_to_x,
+                mute.muted_to_all,
+                mute.mute_function,
+            ));
+        }
+
+        if channel == ChannelName::Mic {
+            let (_, muted_to_x, muted_to_all, function) = self.profile.get_mute_chat_button_state();
+            states.push(get_mute_state(muted_to_x, muted_to_all, function));
+        }
+
+        let mut outputs = EnumSet::empty();
+        for state in states {
+            match state {
+                MuteState::Unmuted => {}
+                MuteState::MutedToAll => return MuteState::MutedToAll,
+                MuteState::MutedTo(muted) => outputs |= muted,
+            }
+        }
+
+        if outputs.is_empty() {
+            MuteState::Unmuted
+        } else {
+            MuteState::MutedTo(outputs)
+        }
+    }
+
+    pub fn get_mute_state_report(&self) -> MuteStateReport {
+        let faders = FaderName::iter()
+            .map(|fader| {
+                let channel = self.profile.get_fader_assignment(fader);
+                let (muted_to_x, muted_to_all, active_function) = self.get_fader_mute_state(fader);
+                MuteButtonState {
+                    channel,
+                    mute_function: self.profile.get_mute_button_behaviour(fader),
+                    active_function: profile_to_standard_mute_function(active_function),
+                    muted_to_x,
+                    muted_to_all,
+                    previous_volume: self.profile.get_mute_button_previous_volume(fader),
+                }
+            })
+            .collect();
+
+        let paged = ChannelName::iter()
+            .filter_map(|channel| {
+                let mute = self.paged_mutes[channel as usize]?;
+                let mute_function = profile_to_standard_mute_function(mute.mute_function);
+                Some(MuteButtonState {
+                    channel,
+                    mute_function,
+                    active_function: mute_function,
+                    muted_to_x: mute.muted_to_x,
+                    muted_to_all: mute.muted_to_all,
+                    previous_volume: mute.previous_volume,
+                })
+            })
+            .collect();
+
+        let (is_toggle, muted_to_x, muted_to_all, mute_function) =
+            self.profile.get_mute_chat_button_state();
+        let cough = CoughMuteState {
+            is_toggle,
+            is_hybrid: self.profile.is_chat_mute_button_hybrid(),
+            mute_function: profile_to_standard_mute_function(mute_function),
+            muted_to_x,
+            muted_to_all,
+            press_muted: self.cough_press_muted,
+        };
+
+        let mut channels = [MuteState::Unmuted; ChannelName::COUNT];
+        for channel in ChannelName::iter() {
+            channels[channel as usize] = self.get_channel_mute_state(channel);
+        }
+
+        MuteStateReport {
+            faders,
+            paged,
+            cough,
+            channels,
+        }
+    }
+
     fn mic_muted_by_cough(&self) -> bool {
         let (_mute_toggle, muted_to_x, muted_to_all, mute_function) =
             self.profile.get_mute_chat_button_state();
@@ -690,32 +1333,444 @@ impl<'a, T: UsbContext> Device<'a, T> {
         muted_to_all || (muted_to_x && mute_function == MuteFunction::All)
     }
 
-    fn update_volumes_to(&mut self, volumes: [u8; 4]) {
+    pub fn set_locked(&mut self, locked: bool) -> Result<()> {
+        self.locked = locked;
+        self.locked_encoders = None;
+
+        // Anything held when the lock changed is treated as released, so it can't act later.
+        for button in self.last_buttons {
+            self.button_states[button].hold_handled = true;
+        }
+        self.update_button_states()
+    }
+
+    // Buttons only send their input events while locked.
+    fn report_locked_buttons(&mut self, pressed: EnumSet<Buttons>) {
+        for button in pressed.difference(self.last_buttons) {
+            self.push_input_event(InputEvent::ButtonDown(
+                map_button_to_standard_colour_target(button),
+            ));
+        }
+        for button in self.last_buttons.difference(pressed) {
+            self.push_input_event(InputEvent::ButtonUp(map_button_to_standard_colour_target(
+                button,
+            )));
+        }
+        for button in pressed {
+            self.button_states[button].hold_handled = true;
+        }
+        self.last_buttons = pressed;
+    }
+
+    // Puts back any fader or encoder which was moved while locked.
+    fn hold_locked_inputs(&mut self, volumes: [u8; 4], encoders: [i8; 4]) -> Result<()> {
+        for fader in FaderName::iter() {
+            let channel = self.profile.get_fader_assignment(fader);
+            let volume = self.profile.get_channel_volume(channel);
+            if volumes[fader as usize] != volume {
+                self.goxlr.set_volume(channel, volume)?;
+                if let Some((last_volumes, _)) = &mut self.last_inputs {
+                    last_volumes[fader as usize] = volume;
+                }
+            }
+        }
+
+        if !self.hardware.features.encoders {
+            return Ok(());
+        }
+        let held = *self.locked_encoders.get_or_insert(encoders);
+        for encoder in EncoderName::iter() {
+            let value = held[encoder as usize];
+            if encoders[encoder as usize] != value {
+                self.goxlr.set_encoder_value(encoder, value as u8)?;
+                if let Some((_, last_encoders)) = &mut self.last_inputs {
+                    last_encoders[encoder as usize] = value;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn update_volumes_to(&mut self, volumes: [u8; 4]) -> Result<()> {
         for fader in FaderName::iter() {
             let channel = self.profile.get_fader_assignment(fader);
             let old_volume = self.profile.get_channel_volume(channel);
 
-            let new_volume = volumes[fader as usize];
+            let fader_volume = volumes[fader as usize];
+            let new_volume = fader_volume.min(self.get_volume_limit(channel));
             if new_volume != old_volume {
                 debug!(
                     "Updating {} volume from {} to {} as a human moved the fader",
                     channel, old_volume, new_volume
                 );
+                if new_volume != fader_volume {
+                    // The fader has gone past the limit, hold the volume at the limit.
+                    self.goxlr.set_volume(channel, new_volume)?;
+                }
                 self.profile.set_channel_volume(channel, new_volume);
+                self.apply_submix_volume(channel)?;
+                self.update_linked_volumes(channel, old_volume, new_volume)?;
             }
         }
+        Ok(())
     }
 
-    fn update_encoders_to(&mut self, encoders: [i8; 4]) -> Result<()> {
-        // Ok, this is funky, due to the way pitch works, the encoder 'value' doesn't match
-        // the profile value if hardtune is enabled, so we'll pre-emptively calculate pitch here..
-        let mut pitch_value = encoders[0];
-        if self.profile.is_hardtune_pitch_enabled() {
-            pitch_value *= 12;
-        } else if self.profile.is_pitch_narrow() {
-            pitch_value /= 2;
+    fn update_mic_level(&mut self, raw_level: u16) {
+        // The level is reported as a linear value, with u16::MAX being full scale.
+        let mut level = MIC_LEVEL_FLOOR;
+        if raw_level > 0 {
+            level = (20.0 * (raw_level as f32 / u16::MAX as f32).log10()).max(MIC_LEVEL_FLOOR);
+        }
+
+        // An Instant, as the system clock can go backwards.
+        let now = Instant::now();
+        let meter = &mut self.mic_level;
+        meter.level = level;
+
+        if level >= meter.peak || now.duration_since(meter.peak_time) > MIC_PEAK_HOLD {
+            meter.peak = level;
+            meter.peak_time = now;
         }
 
+        if level >= MIC_CLIP_THRESHOLD {
+            meter.clip_time = Some(now);
+        }
+    }
+
+    /**
+     Exercises the device and reports on how it responded. The lights and fader displays are put
+     through a visible sweep, so a user can confirm they're working, and are restored afterwards.
+    */
+    pub async fn run_diagnostics(&mut self) -> DiagnosticReport {
+        let mut checks = Vec::new();
+
+        let result = self.goxlr.get_firmware_version();
+        checks.push(match result {
+            Ok(versions) if versions == self.hardware.versions => {
+                DiagnosticCheck::passed("Firmware Versions", format!("{:?}", versions))
+            }
+            Ok(versions) => DiagnosticCheck::failed(
+                "Firmware Versions",
+                format!("Expected {:?}, read {:?}", self.hardware.versions, versions),
+            ),
+            Err(error) => DiagnosticCheck::failed("Firmware Versions", error.to_string()),
+        });
+
+        let result = self.goxlr.get_serial_number();
+        checks.push(match result {
+            Ok((serial, _)) if serial == self.hardware.serial_number => {
+                DiagnosticCheck::passed("Serial Number", serial)
+            }
+            Ok((serial, _)) => DiagnosticCheck::failed(
+                "Serial Number",
+                format!("Expected {}, read {}", self.hardware.serial_number, serial),
+            ),
+            Err(error) => DiagnosticCheck::failed("Serial Number", error.to_string()),
+        });
+
+        let mut latency = None;
+        let result = self.measure_command_latency();
+        checks.push(match result {
+            Ok(measured) => {
+                let message = format!(
+                    "{} samples, {}µs average ({}µs - {}µs)",
+                    measured.samples, measured.average_us, measured.min_us, measured.max_us
+                );
+                latency = Some(measured);
+                DiagnosticCheck::passed("Command Latency", message)
+            }
+            Err(error) => DiagnosticCheck::failed("Command Latency", error.to_string()),
+        });
+
+        let result = self.sweep_button_lights().await;
+        checks.push(DiagnosticCheck::from_result("Button Lights", result));
+
+        let result = self.cycle_fader_displays().await;
+        checks.push(DiagnosticCheck::from_result("Fader Displays", result));
+
+        DiagnosticReport {
+            hardware: self.hardware.clone(),
+            checks,
+            latency,
+        }
+    }
+
+    pub fn hardware_info(&mut self) -> Result<HardwareInfo> {
+        let usb_info = self.goxlr.get_usb_info()?;
+        let mut dcp_categories = Vec::new();
+        for category in DCPCategory::iter() {
+            if self.goxlr.supports_dcp_category(category)? {
+                dcp_categories.push(format!("{:?}", category));
+            }
+        }
+
+        let firmware_version = self.goxlr.request_data(
+            Command::GetHardwareInfo(HardwareInfoCommand::FirmwareVersion),
+            &[],
+        )?;
+        let system_info = self.goxlr.get_system_info()?;
+
+        Ok(HardwareInfo {
+            hardware: self.hardware.clone(),
+            usb_descriptor: UsbDescriptorInfo {
+                vendor_id: usb_info.vendor_id,
+                product_id: usb_info.product_id,
+                usb_version: usb_info.usb_version,
+                speed: usb_info.speed,
+            },
+            dcp_categories,
+            firmware_version_response: to_hex(&firmware_version),
+            system_info_response: to_hex(&system_info),
+        })
+    }
+
+    /**
+     The device can only report its fader positions and encoder values, so those are compared
+     with what the daemon expects. Mic parameters, effects, routing and lighting are write only,
+     so can't be checked.
+    */
+    pub fn verify_state(&mut self) -> Result<StateVerification> {
+        let state = self.goxlr.get_button_states()?;
+        let mut drift = vec![];
+
+        for fader in FaderName::iter() {
+            let channel = self.profile.get_fader_assignment(fader);
+            let device = state.volumes[fader as usize].min(self.get_volume_limit(channel));
+            let daemon = self.profile.get_channel_volume(channel);
+            if device != daemon {
+                drift.push(StateDrift {
+                    name: format!("Fader {} ({}) Volume", fader, channel),
+                    device: device.into(),
+                    daemon: daemon.into(),
+                });
+            }
+        }
+
+        if self.hardware.device_type != DeviceType::Mini {
+            let encoders = [
+                (
+                    EncoderName::Pitch,
+                    self.encoder_to_pitch(state.encoders[0]),
+                    self.profile.get_pitch_value(),
+                ),
+                (
+                    EncoderName::Gender,
+                    state.encoders[1],
+                    self.profile.get_gender_value(),
+                ),
+                (
+                    EncoderName::Reverb,
+                    state.encoders[2],
+                    self.profile.get_reverb_value(),
+                ),
+                (
+                    EncoderName::Echo,
+                    state.encoders[3],
+                    self.profile.get_echo_value(),
+                ),
+            ];
+            for (encoder, device, daemon) in encoders {
+                if device != daemon {
+                    drift.push(StateDrift {
+                        name: format!("{} Encoder", encoder),
+                        device: device.into(),
+                        daemon: daemon.into(),
+                    });
+                }
+            }
+        }
+
+        let unverified = [
+            "Mic Parameters",
+            "Effect Parameters",
+            "Routing",
+            "Mute States",
+            "Lighting",
+            "Scribbles",
+        ];
+        Ok(StateVerification {
+            drift,
+            unverified: unverified.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+
+    pub fn send_raw_command(&mut self, command_id: u32, body: &[u8]) -> Result<Vec<u8>> {
+        warn!(
+            "Sending raw command {:08x} to {} with body {}",
+            command_id,
+            self.serial(),
+            to_hex(body)
+        );
+        Ok(self.goxlr.request_data(Command::Raw(command_id), body)?)
+    }
+
+    fn measure_command_latency(&mut self) -> Result<CommandLatency> {
+        let mut timings = Vec::with_capacity(DIAGNOSTIC_LATENCY_SAMPLES);
+        for _ in 0..DIAGNOSTIC_LATENCY_SAMPLES {
+            let start = Instant::now();
+            self.goxlr.get_button_states()?;
+            timings.push(start.elapsed().as_micros() as u64);
+        }
+
+        Ok(CommandLatency {
+            samples: timings.len() as u32,
+            min_us: *timings.iter().min().unwrap(),
+            average_us: timings.iter().sum::<u64>() / timings.len() as u64,
+            max_us: *timings.iter().max().unwrap(),
+        })
+    }
+
+    async fn sweep_button_lights(&mut self) -> Result<String> {
+        let buttons = self.get_device_buttons();
+        let result = self.light_buttons_in_turn(&buttons).await;
+
+        self.update_button_states()?;
+        result?;
+        Ok(format!("Lit {} buttons in turn", buttons.len()))
+    }
+
+    async fn light_buttons_in_turn(&mut self, buttons: &[Buttons]) -> Result<()> {
+        for button in buttons {
+            let mut states = [ButtonStates::DimmedColour1; 24];
+            states[*button as usize] = ButtonStates::Colour1;
+            self.send_button_states(states)?;
+            sleep(DIAGNOSTIC_STEP_DURATION).await;
+        }
+        Ok(())
+    }
+
+    async fn cycle_fader_displays(&mut self) -> Result<String> {
+        let mut result = Ok(());
+        for style in FaderDisplayStyle::iter() {
+            result = self.set_all_fader_displays(style);
+            if result.is_err() {
+                break;
+            }
+            sleep(DIAGNOSTIC_STEP_DURATION * 4).await;
+        }
+
+        for fader in FaderName::iter() {
+            self.set_fader_display_from_profile(fader)?;
+        }
+        result?;
+        Ok(format!("Displayed {} styles", FaderDisplayStyle::COUNT))
+    }
+
+    fn set_all_fader_displays(&mut self, style: FaderDisplayStyle) -> Result<()> {
+        for fader in FaderName::iter() {
+            self.goxlr
+                .set_fader_display_mode(fader, style.is_gradient(), style.is_meter())?;
+        }
+        Ok(())
+    }
+
+    pub fn start_mic_calibration(&mut self, mic_type: MicrophoneType, apply: bool) -> Result<()> {
+        if let Some(calibration) = &self.mic_calibration {
+            if calibration.status.state == MicCalibrationState::Running {
+                return Err(invalid(InvalidRequest::MicCalibrationRunning));
+            }
+        }
+        if self.needs_phantom_power_confirmation(mic_type) {
+            return Err(invalid(InvalidRequest::PhantomPowerNotConfirmed));
+        }
+
+        // Switch to the microphone being calibrated so we're sampling the right input.
+        self.mic_profile.set_mic_type(mic_type);
+        self.apply_mic_gain()?;
+
+        self.mic_level = MicLevelMeter::default();
+        self.mic_calibration = Some(MicCalibrationRun {
+            started: Instant::now(),
+            gain: self.mic_profile.mic_gains()[mic_type as usize],
+            apply,
+            status: MicCalibration {
+                mic_type,
+                state: MicCalibrationState::Running,
+                progress: 0,
+                peak: MIC_LEVEL_FLOOR,
+                recommended_gain: None,
+                applied: false,
+            },
+        });
+
+        Ok(())
+    }
+
+    fn update_mic_calibration(&mut self) -> Result<()> {
+        let level = self.mic_level.level;
+
+        let calibration = match &mut self.mic_calibration {
+            Some(calibration) if calibration.status.state == MicCalibrationState::Running => {
+                calibration
+            }
+            _ => return Ok(()),
+        };
+
+        calibration.status.peak = calibration.status.peak.max(level);
+
+        let elapsed = calibration.started.elapsed();
+        if elapsed < MIC_CALIBRATION_TIME {
+            calibration.status.progress =
+                ((elapsed.as_millis() * 100) / MIC_CALIBRATION_TIME.as_millis()) as u8;
+            return Ok(());
+        }
+        calibration.status.progress = 100;
+
+        if calibration.status.peak <= MIC_LEVEL_FLOOR {
+            // Nothing was heard, we can't make a recommendation.
+            calibration.status.state = MicCalibrationState::Failed;
+            return Ok(());
+        }
+
+        // The level is measured after gain, so adjust the gain by the distance from the target.
+        let gain = calibration.gain as f32 + (MIC_CALIBRATION_TARGET - calibration.status.peak);
+        let gain = gain.round().clamp(0.0, MIC_MAX_GAIN) as u16;
+
+        calibration.status.recommended_gain = Some(gain);
+        calibration.status.state = MicCalibrationState::Complete;
+
+        if calibration.apply {
+            let mic_type = calibration.status.mic_type;
+            calibration.status.applied = true;
+
+            info!("Applying calibrated gain of {}dB to {}", gain, mic_type);
+            self.mic_profile.set_mic_gain(mic_type, gain);
+            self.apply_mic_gain()?;
+        }
+
+        Ok(())
+    }
+
+    fn send_mic_level(&mut self) {
+        let level = self.get_mic_level();
+        if let Some((sent, last_level)) = self.mic_level_sent {
+            if sent.elapsed() < MIC_LEVEL_EVENT_INTERVAL || last_level == level {
+                return;
+            }
+        }
+
+        self.mic_level_sent = Some((Instant::now(), level));
+        self.events.push(DaemonEvent::MicLevel {
+            serial: self.serial().to_owned(),
+            level,
+        });
+    }
+
+    fn get_mic_level(&self) -> MicLevel {
+        let clipping = self
+            .mic_level
+            .clip_time
+            .is_some_and(|clip_time| clip_time.elapsed() <= MIC_CLIP_HOLD);
+
+        MicLevel {
+            level: self.mic_level.level,
+            peak: self.mic_level.peak,
+            clipping,
+        }
+    }
+
+    fn update_encoders_to(&mut self, mut encoders: [i8; 4]) -> Result<()> {
+        let pitch_value = self.encoder_to_pitch(encoders[0]);
         if pitch_value != self.profile.get_pitch_value() {
             debug!(
                 "Updating PITCH value from {} to {} as human moved the dial",
@@ -728,6 +1783,22 @@ impl<'a, T: UsbContext> Device<'a, T> {
             self.apply_effects(HashSet::from([EffectKey::PitchAmount]))?;
         }
 
+        encoders[1] = self.step_encoder(
+            EncoderName::Gender,
+            self.profile.get_gender_value(),
+            encoders[1],
+        )?;
+        encoders[2] = self.step_encoder(
+            EncoderName::Reverb,
+            self.profile.get_reverb_value(),
+            encoders[2],
+        )?;
+        encoders[3] = self.step_encoder(
+            EncoderName::Echo,
+            self.profile.get_echo_value(),
+            encoders[3],
+        )?;
+
         if encoders[1] != self.profile.get_gender_value() {
             debug!(
                 "Updating GENDER value from {} to {} as human moved the dial",
@@ -761,297 +1832,589 @@ impl<'a, T: UsbContext> Device<'a, T> {
         Ok(())
     }
 
-    pub async fn perform_command(&mut self, command: GoXLRCommand) -> Result<()> {
-        match command {
-            GoXLRCommand::SetFader(fader, channel) => {
-                self.set_fader(fader, channel).await?;
-            }
-            GoXLRCommand::SetFaderMuteFunction(fader, behaviour) => {
-                if self.profile.get_mute_button_behaviour(fader) == behaviour {
-                    // Settings are the same..
-                    return Ok(());
-                }
+    // The hardware moves encoders by one per click, this applies the configured step to a move
+    // and puts the hardware at the resulting position.
+    // Ok, this is funky, due to the way pitch works, the encoder 'value' doesn't match the
+    // profile value if hardtune is enabled, so we'll pre-emptively calculate pitch here..
+    fn encoder_to_pitch(&self, mut value: i8) -> i8 {
+        if self.profile.is_hardtune_pitch_enabled() {
+            value *= 12;
+        } else if self.profile.is_pitch_narrow() {
+            value /= 2;
+        }
+        value
+    }
 
-                // Unmute the channel to prevent weirdness, then set new behaviour
-                self.unmute_if_muted(fader).await?;
-                self.profile.set_mute_button_behaviour(fader, behaviour);
+    fn step_encoder(&mut self, encoder: EncoderName, current: i8, reported: i8) -> Result<i8> {
+        if reported == current {
+            return Ok(reported);
+        }
+
+        let step = if self.device_settings.encoder_fine_mode {
+            1
+        } else {
+            let steps = &self.device_settings.encoder_steps;
+            let step = steps.get(&encoder).copied().unwrap_or(1);
+            step * self.encoder_acceleration(encoder)
+        };
+        if step == 1 {
+            return Ok(reported);
+        }
+
+        let (min, max) = get_encoder_range(encoder);
+        let value = (i32::from(current)
+            + (i32::from(reported) - i32::from(current)) * i32::from(step))
+        .clamp(i32::from(min), i32::from(max)) as i8;
+        if value != reported {
+            self.goxlr.set_encoder_value(encoder, value as u8)?;
+
+            // Don't report our own correction as the encoder being turned.
+            if let Some((_, last_encoders)) = &mut self.last_inputs {
+                last_encoders[encoder as usize] = value;
             }
+        }
+        Ok(value)
+    }
 
-            GoXLRCommand::SetVolume(channel, volume) => {
-                self.goxlr.set_volume(channel, volume)?;
-                self.profile.set_channel_volume(channel, volume);
+    // Clicks which follow quickly after the encoder's previous one are multiplied, so turning it
+    // fast covers the range while turning it slowly still moves by the step.
+    fn encoder_acceleration(&mut self, encoder: EncoderName) -> u8 {
+        let now = Instant::now();
+        let previous = self.encoder_turned[encoder as usize].replace(now);
+
+        let acceleration = self.device_settings.encoder_acceleration.get(&encoder);
+        match (acceleration, previous) {
+            (Some(acceleration), Some(previous))
+                if now.duration_since(previous)
+                    < Duration::from_millis(acceleration.window.into()) =>
+            {
+                acceleration.multiplier
+            }
+            _ => 1,
+        }
+    }
+
+    pub async fn perform_command(&mut self, command: GoXLRCommand) -> Result<()> {
+        self.validate_command(&command).await?;
+
+        self.dispatch_command(command).await
+    }
+
+    /*
+    The device can't report most of its state (only the faders, encoders and buttons can be
+    read), and there's no known way to have it keep settings itself. So the daemon is always the
+    source of truth, and this simply sends everything again.
+     */
+    fn sync_to_hardware(&mut self) -> Result<()> {
+        info!("Sending the full device state to {}", self.serial());
+        self.clear_lighting_cache();
+        self.apply_profile()?;
+        self.apply_mic_profile()?;
+        self.update_button_states()
+    }
+
+    fn set_stream_panic(&mut self, enabled: bool) -> Result<()> {
+        if self.stream_panic == enabled {
+            return Ok(());
+        }
+        info!(
+            "Stream panic mute {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+
+        self.stream_panic = enabled;
+        for input in BasicInputDevice::iter() {
+            self.apply_routing(input)?;
+        }
+        self.update_button_states()
+    }
+
+    fn set_solo(&mut self, channel: ChannelName, soloed: bool) -> Result<()> {
+        if self.soloed_channels.contains(&channel) == soloed {
+            return Ok(());
+        }
+        info!(
+            "Solo {} for {}",
+            if soloed { "enabled" } else { "disabled" },
+            channel
+        );
+
+        if soloed {
+            self.soloed_channels.push(channel);
+        } else {
+            self.soloed_channels.retain(|other| *other != channel);
+        }
+        for input in BasicInputDevice::iter() {
+            self.apply_routing(input)?;
+        }
+        Ok(())
+    }
+
+    // Swaps to the previous profile as it was left, with the volumes of the current one.
+    async fn quick_switch_profile(&mut self) -> Result<()> {
+        let mut profile = self
+            .previous_profile
+            .take()
+            .ok_or_else(|| DaemonError::Unsupported("There's no profile to switch to".into()))?;
+
+        for channel in ChannelName::iter() {
+            profile.set_channel_volume(channel, self.profile.get_channel_volume(channel));
+        }
+        self.load_profile(profile).await
+    }
+
+    // Saves the profile over the one it was loaded from.
+    async fn save_profile(&mut self, autosave: bool) -> Result<()> {
+        let profile_directory = self.settings.get_profile_directory().await;
+        let profile_name = self.settings.get_device_profile_name(self.serial()).await;
+
+        if let Some(profile_name) = profile_name {
+            self.profile
+                .write_profile(profile_name, &profile_directory, true, autosave)?;
+            self.saved_profile = self.profile.get_contents().ok();
+        }
+        Ok(())
+    }
+
+    async fn save_mic_profile(&mut self, autosave: bool) -> Result<()> {
+        let mic_profile_directory = self.settings.get_mic_profile_directory().await;
+        let mic_profile_name = self
+            .settings
+            .get_device_mic_profile_name(self.serial())
+            .await;
+
+        if let Some(profile_name) = mic_profile_name {
+            self.mic_profile
+                .write_profile(profile_name, &mic_profile_directory, true, autosave)?;
+            self.saved_mic_profile = self.mic_profile.get_contents().ok();
+        }
+        Ok(())
+    }
+
+    /// Saves the profile and mic profile if autosave is due, and they've changed since they were
+    /// loaded or last saved.
+    pub async fn autosave(&mut self) -> Result<()> {
+        let interval = match self.device_settings.autosave {
+            Autosave::Off => return Ok(()),
+            Autosave::OnChange => Duration::ZERO,
+            Autosave::Every(minutes) => Duration::from_secs(u64::from(minutes) * 60),
+        };
+        if self.last_autosave.elapsed() < interval {
+            return Ok(());
+        }
+        self.last_autosave = Instant::now();
+
+        if self.profile.get_contents().ok() != self.saved_profile {
+            debug!("Autosaving profile {}", self.profile.name());
+            self.save_profile(true).await?;
+        }
+        if self.mic_profile.get_contents().ok() != self.saved_mic_profile {
+            debug!("Autosaving mic profile {}", self.mic_profile.name());
+            self.save_mic_profile(true).await?;
+        }
+        Ok(())
+    }
+
+    /**
+     Runs commands in order, stopping at the first which fails. All of the commands are validated
+     before any are run, and with `rollback` set a failure puts the profile and mic profile back to
+     how they were before the batch. Changes to the daemon's settings aren't rolled back.
+    */
+    pub async fn perform_batch(
+        &mut self,
+        commands: Vec<GoXLRCommand>,
+        rollback: bool,
+    ) -> Result<()> {
+        for (index, command) in commands.iter().enumerate() {
+            if let Err(error) = self.validate_command(command).await {
+                return Err(batch_error(index, error));
             }
+        }
 
-            GoXLRCommand::SetCoughMuteFunction(mute_function) => {
-                if self.profile.get_chat_mute_button_behaviour() == mute_function {
-                    // Settings are the same..
-                    return Ok(());
+        let snapshot = if rollback {
+            Some((self.profile.try_clone()?, self.mic_profile.try_clone()?))
+        } else {
+            None
+        };
+
+        for (index, command) in commands.into_iter().enumerate() {
+            if let Err(error) = self.perform_command(command).await {
+                if let Some((profile, mic_profile)) = snapshot {
+                    warn!(
+                        "Command {} of the batch failed, restoring the profiles",
+                        index + 1
+                    );
+                    self.profile = profile;
+                    self.mic_profile = mic_profile;
+                    self.apply_profile()?;
+                    self.apply_mic_profile()?;
                 }
+                return Err(batch_error(index, error));
+            }
+        }
+        Ok(())
+    }
 
-                // Unmute the channel to prevent weirdness, then set new behaviour
-                self.unmute_chat_if_muted().await?;
-                self.profile.set_chat_mute_button_behaviour(mute_function);
+    /**
+     Checks a command against the values it accepts and what this device supports, without
+     changing anything. Every command is checked before it's performed, and clients can ask for
+     this on its own to find out whether a command would succeed.
+    */
+    pub async fn validate_command(&self, command: &GoXLRCommand) -> Result<()> {
+        match command {
+            GoXLRCommand::SetVolumePercent(_, percent) => {
+                check_range("Volume percentage", *percent, 0, 100)?;
             }
-            GoXLRCommand::SetCoughIsHold(is_hold) => {
-                self.unmute_chat_if_muted().await?;
-                self.profile.set_chat_mute_button_is_held(is_hold);
+            GoXLRCommand::SetVolumeDb(_, db) if !db.is_finite() || *db > 0.0 => {
+                return Err(invalid(InvalidRequest::VolumeAboveZeroDb));
             }
-            GoXLRCommand::SetSwearButtonVolume(volume) => {
-                if volume < -34 || volume > 0 {
-                    return Err(anyhow!("Mute volume must be between -34 and 0"));
+            GoXLRCommand::SetFaderPages(pages) => {
+                for page in pages {
+                    for (index, channel) in page.iter().enumerate() {
+                        if page[..index].contains(channel) {
+                            return Err(invalid(InvalidRequest::FaderPageRepeatsChannel(*channel)));
+                        }
+                    }
+                }
+            }
+            GoXLRCommand::SetSampleBankSwitch(SampleBankSwitch::FadeOut(milliseconds)) => {
+                check_range("Sample fade time", *milliseconds, 1, 10000)?;
+            }
+            GoXLRCommand::SetSolo(channel, _) if get_channel_input(*channel).is_none() => {
+                return Err(invalid(InvalidRequest::CannotSolo(*channel)));
+            }
+            GoXLRCommand::SetMicrophoneType(mic_type, false)
+            | GoXLRCommand::SetMicrophoneGain(mic_type, _)
+                if self.needs_phantom_power_confirmation(*mic_type) =>
+            {
+                return Err(invalid(InvalidRequest::PhantomPowerNotConfirmed));
+            }
+            GoXLRCommand::SetFaderPage(page) if *page >= self.device_settings.fader_pages.len() => {
+                return Err(invalid(InvalidRequest::FaderPageNotFound(*page)));
+            }
+            GoXLRCommand::NextFaderPage() if self.device_settings.fader_pages.is_empty() => {
+                return Err(invalid(InvalidRequest::NoFaderPages));
+            }
+            GoXLRCommand::LinkChannels(channels) if get_link_group(channels).len() < 2 => {
+                return Err(invalid(InvalidRequest::TooFewChannelsToLink));
+            }
+            GoXLRCommand::SetChannelDisplayName(channel, Some(name)) => {
+                // Names have to stay unique, or the client couldn't tell which channel was meant.
+                let taken = ChannelName::iter()
+                    .filter(|other| other != channel)
+                    .any(|other| {
+                        is_same_name(&other.to_string(), name)
+                            || self
+                                .device_settings
+                                .channel_names
+                                .get(&other)
+                                .is_some_and(|other| is_same_name(other, name))
+                    });
+                if taken {
+                    return Err(invalid(InvalidRequest::DisplayNameInUse(name.clone())));
                 }
-                self.settings
-                    .set_device_bleep_volume(self.serial(), volume)
-                    .await;
-                self.settings.save().await;
-
-                self.goxlr
-                    .set_effect_values(&[(EffectKey::BleepLevel, volume as i32)])?;
             }
-            GoXLRCommand::SetMicrophoneType(mic_type) => {
-                self.mic_profile.set_mic_type(mic_type);
-                self.apply_mic_gain()?;
+            GoXLRCommand::SetOutputDisplayName(output, Some(name)) => {
+                let taken = BasicOutputDevice::iter()
+                    .filter(|other| other != output)
+                    .any(|other| {
+                        is_same_name(&other.to_string(), name)
+                            || self
+                                .device_settings
+                                .output_names
+                                .get(&other)
+                                .is_some_and(|other| is_same_name(other, name))
+                    });
+                if taken {
+                    return Err(invalid(InvalidRequest::DisplayNameInUse(name.clone())));
+                }
+            }
+            GoXLRCommand::SetSubMixVolume(..) | GoXLRCommand::SetSubMixLinked(..) => {
+                self.require_feature(self.hardware.features.submixes, "Sub mixes")?;
             }
-            GoXLRCommand::SetMicrophoneGain(mic_type, gain) => {
-                self.mic_profile.set_mic_type(mic_type);
-                self.mic_profile.set_mic_gain(mic_type, gain);
-                self.apply_mic_gain()?;
+            GoXLRCommand::SetSwearButtonVolume(volume) => {
+                check_range("Mute volume", *volume, -34, 0)?;
             }
             GoXLRCommand::SetRouter(input, output, enabled) => {
-                debug!("Setting Routing: {:?} {:?} {}", input, output, enabled);
-                self.profile.set_routing(input, output, enabled);
-
-                // Apply the change..
-                self.apply_routing(input)?;
+                validate_route(*input, *output, *enabled)?;
             }
-
-            // Equaliser
-            GoXLRCommand::SetEqMiniGain(gain, value) => {
-                if value < -9 || value > 9 {
-                    return Err(anyhow!("Gain volume should be between -9 and 9 dB"));
+            GoXLRCommand::SetRouterRow(input, outputs) => {
+                for output in BasicOutputDevice::iter() {
+                    validate_route(*input, output, outputs[output as usize])?;
                 }
-
-                let param = self.mic_profile.set_mini_eq_gain(gain, value);
-                self.apply_mic_params(HashSet::from([param]))?;
             }
-            GoXLRCommand::SetEqMiniFreq(freq, value) => {
-                // TODO: Verify?
-                if !(300.0..=18000.0).contains(&value) {
-                    return Err(anyhow!("EQ Frequency should be between 300hz and 18khz"));
+            GoXLRCommand::SetRouterColumn(output, inputs) => {
+                for input in BasicInputDevice::iter() {
+                    validate_route(input, *output, inputs[input as usize])?;
                 }
-
-                let param = self.mic_profile.set_mini_eq_freq(freq, value);
-                self.apply_mic_params(HashSet::from([param]))?;
             }
-            GoXLRCommand::SetEqGain(gain, value) => {
-                if value < -9 || value > 9 {
-                    return Err(anyhow!("Gain volume should be between -9 and 9 dB"));
-                }
+            GoXLRCommand::ResetRouterToProfile() => {
+                self.check_profile_exists(self.profile.name()).await?;
+            }
+            GoXLRCommand::SaveRoutingPreset(name) if name.trim().is_empty() => {
+                return Err(invalid(InvalidRequest::RoutingPresetNameEmpty));
+            }
+            GoXLRCommand::ApplyRoutingPreset(name) | GoXLRCommand::DeleteRoutingPreset(name)
+                if !self.device_settings.routing_presets.contains_key(name) =>
+            {
+                return Err(invalid(InvalidRequest::RoutingPresetNotFound(name.clone())));
+            }
 
-                let param = self.mic_profile.set_eq_gain(gain, value);
-                self.apply_effects(HashSet::from([param]))?;
+            GoXLRCommand::SetEqMiniGain(_, value) => check_range("EQ gain", *value, -9, 9)?,
+            GoXLRCommand::SetEqMiniFreq(_, value) => {
+                check_range("EQ frequency", *value, 300.0, 18000.0)?;
+            }
+            GoXLRCommand::SetEqGain(_, value) => {
+                self.require_feature(self.hardware.features.full_eq, "The ten band EQ")?;
+                check_range("EQ gain", *value, -9, 9)?;
             }
             GoXLRCommand::SetEqFreq(freq, value) => {
-                let param = self.mic_profile.set_eq_freq(freq, value)?;
-                self.apply_effects(HashSet::from([param]))?;
+                self.require_feature(self.hardware.features.full_eq, "The ten band EQ")?;
+                let (min, max) = get_eq_freq_range(*freq);
+                check_range("EQ frequency", *value, min, max)?;
             }
             GoXLRCommand::SetGateThreshold(value) => {
-                if value > 0 || value < -59 {
-                    return Err(anyhow!("Threshold should be between 0 and -59dB"));
-                }
-                self.mic_profile.set_gate_threshold(value);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::GateThreshold]))?;
-                self.apply_effects(HashSet::from([EffectKey::GateThreshold]))?;
+                check_range("Gate threshold", *value, -59, 0)?;
             }
-
-            // Noise Gate
             GoXLRCommand::SetGateAttenuation(percentage) => {
-                if percentage > 100 {
-                    return Err(anyhow!("Attentuation should be a percentage"));
-                }
-                self.mic_profile.set_gate_attenuation(percentage);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::GateAttenuation]))?;
-                self.apply_effects(HashSet::from([EffectKey::GateAttenuation]))?;
+                check_range("Gate attenuation", *percentage, 0, 100)?;
             }
-            GoXLRCommand::SetGateAttack(attack_time) => {
-                self.mic_profile.set_gate_attack(attack_time);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::GateAttack]))?;
-                self.apply_effects(HashSet::from([EffectKey::GateAttack]))?;
+            GoXLRCommand::SetGateAmount(amount)
+            | GoXLRCommand::SetCompressorAmount(amount)
+            | GoXLRCommand::SetDeEsser(amount) => {
+                check_range("Amount", *amount, 0, 100)?;
             }
-            GoXLRCommand::SetGateRelease(release_time) => {
-                self.mic_profile.set_gate_release(release_time);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::GateRelease]))?;
-                self.apply_effects(HashSet::from([EffectKey::GateRelease]))?;
+            GoXLRCommand::SetCompressorThreshold(value) => {
+                check_range("Compressor threshold", *value, -24, 0)?;
             }
-            GoXLRCommand::SetGateActive(active) => {
-                self.mic_profile.set_gate_active(active);
-
-                // GateEnabled appears to only be an effect key.
-                self.apply_effects(HashSet::from([EffectKey::GateEnabled]))?;
+            GoXLRCommand::SetCompressorMakeupGain(value) => {
+                check_range("Compressor makeup gain", *value, 0, 24)?;
             }
 
-            // Compressor
-            GoXLRCommand::SetCompressorThreshold(value) => {
-                if value > 0 || value < -24 {
-                    return Err(anyhow!("Compressor Threshold must be between 0 and -24 dB"));
-                }
-                self.mic_profile.set_compressor_threshold(value);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorMakeUpGain]))?;
-                self.apply_effects(HashSet::from([EffectKey::CompressorMakeUpGain]))?;
-            }
-            GoXLRCommand::SetCompressorRatio(ratio) => {
-                self.mic_profile.set_compressor_ratio(ratio);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorRatio]))?;
-                self.apply_effects(HashSet::from([EffectKey::CompressorRatio]))?;
+            GoXLRCommand::SetChannelColour(_, Some(colour)) => validate_colour(colour)?,
+            GoXLRCommand::SetFaderDisplayStyle(_, style)
+            | GoXLRCommand::SetAllFaderDisplayStyle(style) => self.check_fader_display(*style)?,
+            GoXLRCommand::SetFaderDisplayMode(_, gradient, meter) => {
+                self.check_fader_display(FaderDisplayStyle::from_flags(*gradient, *meter))?;
             }
-            GoXLRCommand::SetCompressorAttack(value) => {
-                self.mic_profile.set_compressor_attack(value);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorAttack]))?;
-                self.apply_effects(HashSet::from([EffectKey::CompressorAttack]))?;
+            GoXLRCommand::SetFaderColours(_, top, bottom)
+            | GoXLRCommand::SetAllFaderColours(top, bottom) => {
+                validate_colour(top)?;
+                validate_colour(bottom)?;
             }
-            GoXLRCommand::SetCompressorReleaseTime(value) => {
-                self.mic_profile.set_compressor_release(value);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorRelease]))?;
-                self.apply_effects(HashSet::from([EffectKey::CompressorRelease]))?;
+            GoXLRCommand::SetFaderColoursHsv(_, top, bottom) => {
+                validate_hsv(top)?;
+                validate_hsv(bottom)?;
             }
-            GoXLRCommand::SetCompressorMakeupGain(value) => {
-                if value > 24 {
-                    return Err(anyhow!("Makeup Gain should be between 0 and 24dB"));
+            GoXLRCommand::SetButtonColours(target, colour, colour_two) => {
+                self.check_button_target(*target)?;
+                validate_colour(colour)?;
+                if let Some(colour_two) = colour_two {
+                    validate_colour(colour_two)?;
                 }
-                self.mic_profile.set_compressor_makeup(value);
-                self.apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorMakeUpGain]))?;
-                self.apply_effects(HashSet::from([EffectKey::CompressorMakeUpGain]))?;
             }
-
-            // Colouring..
-            GoXLRCommand::SetFaderDisplayStyle(fader, display) => {
-                self.profile.set_fader_display(fader, display);
-                self.set_fader_display_from_profile(fader)?;
-            }
-            GoXLRCommand::SetFaderColours(fader, top, bottom) => {
-                // Need to get the fader colour map, and set values..
-                self.profile.set_fader_colours(fader, top, bottom)?;
-                self.load_colour_map()?;
+            GoXLRCommand::SetButtonColoursHsv(target, colour, colour_two) => {
+                self.check_button_target(*target)?;
+                validate_hsv(colour)?;
+                if let Some(colour_two) = colour_two {
+                    validate_hsv(colour_two)?;
+                }
             }
-            GoXLRCommand::SetAllFaderColours(top, bottom) => {
-                // I considered this as part of SetFaderColours, but spamming a new colour map
-                // for every fader change seemed excessive, this allows us to set them all before
-                // reloading.
-                for fader in FaderName::iter() {
-                    self.profile
-                        .set_fader_colours(fader, top.to_owned(), bottom.to_owned())?;
+            GoXLRCommand::SetButtonOffStyle(target, _) => self.check_button_target(*target)?,
+            GoXLRCommand::AdjustButtonBrightness(target, delta) => {
+                self.check_button_target(*target)?;
+                validate_brightness_delta(*delta)?;
+            }
+            GoXLRCommand::AdjustAllBrightness(delta) => validate_brightness_delta(*delta)?,
+            GoXLRCommand::SetButtonGroupColours(group, colour, colour_two) => {
+                self.check_button_group(*group)?;
+                validate_colour(colour)?;
+                if let Some(colour_two) = colour_two {
+                    validate_colour(colour_two)?;
                 }
-                self.load_colour_map()?;
             }
-            GoXLRCommand::SetAllFaderDisplayStyle(display_style) => {
-                for fader in FaderName::iter() {
-                    self.profile.set_fader_display(fader, display_style);
+            GoXLRCommand::SetButtonGroupOffStyle(group, _) | GoXLRCommand::ShiftHue(group, _) => {
+                self.check_button_group(*group)?;
+            }
+            GoXLRCommand::LoadLightingTheme(theme_name) => {
+                let theme_directory = self.settings.get_theme_directory().await;
+                if !theme_exists(&theme_directory, theme_name) {
+                    return Err(invalid(InvalidRequest::LightingThemeNotFound(
+                        theme_name.clone(),
+                    )));
                 }
-                self.load_colour_map()?;
             }
-            GoXLRCommand::SetButtonColours(target, colour, colour2) => {
-                self.profile
-                    .set_button_colours(target, colour, colour2.as_ref())?;
 
-                // Reload the colour map and button states..
-                self.load_colour_map()?;
-                self.update_button_states()?;
+            GoXLRCommand::SetSampleTrim(bank, button, track, start_pct, end_pct) => {
+                self.require_feature(self.hardware.features.sampler, "The sampler")?;
+                check_range("Sample end", *end_pct, 1, 100)?;
+                if start_pct >= end_pct {
+                    return Err(invalid(InvalidRequest::SampleStartsAfterEnd));
+                }
+                if !self.profile.has_sample_track(*bank, *button, *track) {
+                    return Err(invalid(InvalidRequest::NoSampleAtPosition));
+                }
             }
-            GoXLRCommand::SetButtonOffStyle(target, off_style) => {
-                self.profile.set_button_off_style(target, off_style);
-
-                self.load_colour_map()?;
-                self.update_button_states()?;
+            GoXLRCommand::ClearSampleBank(..) | GoXLRCommand::ClearSampleBankColours(..) => {
+                self.require_feature(self.hardware.features.sampler, "The sampler")?;
             }
-            GoXLRCommand::SetButtonGroupColours(target, colour, colour_2) => {
-                self.profile
-                    .set_group_button_colours(target, colour, colour_2)?;
-
-                self.load_colour_map()?;
-                self.update_button_states()?;
+            GoXLRCommand::SetSampleBankColours(_, colour, colour_two) => {
+                self.require_feature(self.hardware.features.sampler, "The sampler")?;
+                validate_colour(colour)?;
+                if let Some(colour_two) = colour_two {
+                    validate_colour(colour_two)?;
+                }
             }
-            GoXLRCommand::SetButtonGroupOffStyle(target, off_style) => {
-                self.profile.set_group_button_off_style(target, off_style);
-                self.load_colour_map()?;
-                self.update_button_states()?;
+            GoXLRCommand::CopySampleBank(first, second)
+            | GoXLRCommand::SwapSampleBanks(first, second) => {
+                self.require_feature(self.hardware.features.sampler, "The sampler")?;
+                if first == second {
+                    return Err(invalid(InvalidRequest::SameSampleBanks));
+                }
             }
 
-            // Profiles
             GoXLRCommand::LoadProfile(profile_name) => {
-                let profile_directory = self.settings.get_profile_directory().await;
-                self.profile = ProfileAdapter::from_named(profile_name, vec![&profile_directory])?;
-                self.apply_profile()?;
-                self.settings
-                    .set_device_profile_name(self.serial(), self.profile.name())
-                    .await;
-                self.settings.save().await;
-            }
-            GoXLRCommand::SaveProfile() => {
-                let profile_directory = self.settings.get_profile_directory().await;
-                let profile_name = self.settings.get_device_profile_name(self.serial()).await;
-
-                if let Some(profile_name) = profile_name {
-                    self.profile
-                        .write_profile(profile_name, &profile_directory, true)?;
+                self.check_profile_exists(profile_name).await?;
+            }
+            GoXLRCommand::LoadEmbeddedProfile(profile_name)
+                if !ProfileAdapter::is_embedded(profile_name) =>
+            {
+                return Err(DaemonError::ProfileNotFound(profile_name.to_owned()).into());
+            }
+            GoXLRCommand::ApplyProfileSections(profile_name, sections) => {
+                if sections.contains(&ProfileSection::Sampler) {
+                    self.require_feature(self.hardware.features.sampler, "The sampler")?;
                 }
+                self.check_profile_exists(profile_name).await?;
             }
-            GoXLRCommand::SaveProfileAs(profile_name) => {
-                let profile_directory = self.settings.get_profile_directory().await;
-                self.profile
-                    .write_profile(profile_name.clone(), &profile_directory, false)?;
-
-                // Save the new name in the settings
-                self.settings
-                    .set_device_profile_name(self.serial(), profile_name.as_str())
-                    .await;
-
-                self.settings.save().await;
+            GoXLRCommand::QuickSwitchProfile() if self.previous_profile.is_none() => {
+                let message = "There's no profile to switch to".to_string();
+                return Err(DaemonError::Unsupported(message).into());
+            }
+            GoXLRCommand::ImportMicProfile(json) => {
+                MicProfileAdapter::from_json(self.mic_profile.name().to_owned(), json)?;
             }
             GoXLRCommand::LoadMicProfile(mic_profile_name) => {
                 let mic_profile_directory = self.settings.get_mic_profile_directory().await;
-                self.mic_profile =
-                    MicProfileAdapter::from_named(mic_profile_name, vec![&mic_profile_directory])?;
-                self.apply_mic_profile()?;
-                self.settings
-                    .set_device_mic_profile_name(self.serial(), self.mic_profile.name())
-                    .await;
-                self.settings.save().await;
-            }
-            GoXLRCommand::SaveMicProfile() => {
-                let mic_profile_directory = self.settings.get_mic_profile_directory().await;
-                let mic_profile_name = self
-                    .settings
-                    .get_device_mic_profile_name(self.serial())
-                    .await;
-
-                if let Some(profile_name) = mic_profile_name {
-                    self.mic_profile
-                        .write_profile(profile_name, &mic_profile_directory, true)?;
+                if !MicProfileAdapter::exists(mic_profile_name, vec![&mic_profile_directory]) {
+                    let name = mic_profile_name.to_owned();
+                    return Err(DaemonError::MicProfileNotFound(name).into());
                 }
             }
-            GoXLRCommand::SaveMicProfileAs(profile_name) => {
-                let profile_directory = self.settings.get_mic_profile_directory().await;
-                self.mic_profile
-                    .write_profile(profile_name.clone(), &profile_directory, false)?;
+            _ => {}
+        }
 
-                // Save the new name in the settings
-                self.settings
-                    .set_device_mic_profile_name(self.serial(), profile_name.as_str())
-                    .await;
+        Ok(())
+    }
 
-                self.settings.save().await;
-            }
+    async fn check_profile_exists(&self, profile_name: &str) -> Result<()> {
+        let profile_directory = self.settings.get_profile_directory().await;
+        if !ProfileAdapter::exists(profile_name, vec![&profile_directory]) {
+            return Err(DaemonError::ProfileNotFound(profile_name.to_owned()).into());
         }
+        Ok(())
+    }
 
+    fn require_feature(&self, supported: bool, feature: &str) -> Result<()> {
+        if !supported {
+            let message = format!("{} is not available on this device", feature);
+            return Err(DaemonError::Unsupported(message).into());
+        }
         Ok(())
     }
 
+    // Every device can go back to two colours, so a Mini can always be reset.
+    fn check_fader_display(&self, style: FaderDisplayStyle) -> Result<()> {
+        let supported =
+            style == FaderDisplayStyle::TwoColour || self.hardware.features.fader_display_modes;
+        self.require_feature(supported, "A gradient or meter on the faders")
+    }
+
+    fn check_button_target(&self, target: ButtonColourTargets) -> Result<()> {
+        let supported = self.has_button_target(target);
+        self.require_feature(supported, &format!("The {} button", target))
+    }
+
+    fn check_button_group(&self, group: ButtonColourGroups) -> Result<()> {
+        let supported = match group {
+            ButtonColourGroups::FaderMute => true,
+            ButtonColourGroups::EffectSelector => self.hardware.features.effects,
+            ButtonColourGroups::SampleBankSelector | ButtonColourGroups::SamplerButtons => {
+                self.hardware.features.sampler
+            }
+        };
+        self.require_feature(supported, &format!("The {} buttons", group))
+    }
+
+    fn has_button_target(&self, target: ButtonColourTargets) -> bool {
+        let features = &self.hardware.features;
+        match target {
+            ButtonColourTargets::EffectSelect1
+            | ButtonColourTargets::EffectSelect2
+            | ButtonColourTargets::EffectSelect3
+            | ButtonColourTargets::EffectSelect4
+            | ButtonColourTargets::EffectSelect5
+            | ButtonColourTargets::EffectSelect6
+            | ButtonColourTargets::EffectFx
+            | ButtonColourTargets::EffectMegaphone
+            | ButtonColourTargets::EffectRobot
+            | ButtonColourTargets::EffectHardTune => features.effects,
+            ButtonColourTargets::SamplerSelectA
+            | ButtonColourTargets::SamplerSelectB
+            | ButtonColourTargets::SamplerSelectC
+            | ButtonColourTargets::SamplerTopLeft
+            | ButtonColourTargets::SamplerTopRight
+            | ButtonColourTargets::SamplerBottomLeft
+            | ButtonColourTargets::SamplerBottomRight
+            | ButtonColourTargets::SamplerClear => features.sampler,
+            _ => true,
+        }
+    }
+
     fn update_button_states(&mut self) -> Result<()> {
         let button_states = self.create_button_states();
+        self.send_button_states(button_states)
+    }
+
+    fn send_button_states(&mut self, button_states: [ButtonStates; 24]) -> Result<()> {
+        if self.last_button_states == Some(button_states) {
+            return Ok(());
+        }
+
+        self.last_button_states = None;
         self.goxlr.set_button_states(button_states)?;
+        self.last_button_states = Some(button_states);
         Ok(())
     }
 
+    // Forces the next lighting update to be sent, for when the device's state isn't known.
+    fn clear_lighting_cache(&mut self) {
+        self.last_button_states = None;
+        self.last_colour_map = None;
+    }
+
+    fn get_button_light_states(&self) -> HashMap<ButtonColourTargets, ButtonLightState> {
+        let button_states = self.create_button_states();
+        self.get_device_buttons()
+            .into_iter()
+            .map(|button| {
+                let state = match button_states[button as usize] {
+                    ButtonStates::Colour1 => ButtonLightState::On,
+                    ButtonStates::Flashing => ButtonLightState::Flashing,
+                    _ => ButtonLightState::Off,
+                };
+                (map_button_to_standard_colour_target(button), state)
+            })
+            .collect()
+    }
+
+    // The buttons physically present on this device.
+    fn get_device_buttons(&self) -> Vec<Buttons> {
+        Buttons::iter()
+            .filter(|button| self.has_button_target(map_button_to_standard_colour_target(*button)))
+            .collect()
+    }
+
     fn create_button_states(&self) -> [ButtonStates; 24] {
         let mut result = [ButtonStates::DimmedColour1; 24];
 
@@ -1061,6 +2424,47 @@ impl<'a, T: UsbContext> Device<'a, T> {
 
         // Replace the Cough Button button data with correct data.
         result[Buttons::MicrophoneMute as usize] = self.profile.get_mute_chat_button_colour_state();
+
+        // Make it obvious the stream is cut by flashing every mute button together.
+        if self.stream_panic {
+            let mut buttons = vec![
+                Buttons::Fader1Mute,
+                Buttons::Fader2Mute,
+                Buttons::Fader3Mute,
+                Buttons::Fader4Mute,
+                Buttons::MicrophoneMute,
+            ];
+            buttons.extend(Buttons::iter().filter(|button| self.is_stream_panic_button(*button)));
+            for button in buttons {
+                result[button as usize] = ButtonStates::Flashing;
+            }
+        }
+
+        // Pads flash while they're recording.
+        for button in Buttons::iter() {
+            if get_sample_button(button).is_some_and(|pad| self.recordings.contains_key(&pad)) {
+                result[button as usize] = ButtonStates::Flashing;
+            }
+        }
+
+        // Remapped buttons show the state of the button they act as.
+        if !self.device_settings.button_remap.is_empty() {
+            let states = result;
+            for button in Buttons::iter() {
+                result[button as usize] = states[self.remap_button(button) as usize];
+            }
+        }
+
+        // Everything is dimmed while the controls are locked, to show they won't do anything.
+        if self.locked {
+            for state in result.iter_mut() {
+                *state = match state {
+                    ButtonStates::Colour1 => ButtonStates::DimmedColour1,
+                    ButtonStates::Colour2 => ButtonStates::DimmedColour2,
+                    _ => *state,
+                };
+            }
+        }
         result
     }
 
@@ -1132,7 +2536,45 @@ impl<'a, T: UsbContext> Device<'a, T> {
                 self.apply_transient_fader_routing(fader, router);
             }
         }
+        if let Some(mute) = self.paged_mutes[channel_name as usize] {
+            self.apply_transient_channel_routing(
+                mute.muted_to_x,
+                mute.muted_to_all,
+                mute.mute_function,
+                router,
+            );
+        }
         self.apply_transient_cough_routing(router);
+
+        if self.stream_panic {
+            router[BasicOutputDevice::BroadcastMix] = false;
+        }
+
+        if !self.soloed_channels.is_empty() && !self.soloed_channels.contains(&channel_name) {
+            router[BasicOutputDevice::Headphones] = false;
+        }
+
+        // The hardware censors the mic before it's routed, so outputs which shouldn't hear the
+        // bleep can only be given silence. A custom bleep sound plays through the sampler.
+        let bleep_input = match self.device_settings.bleep_sound {
+            Some(_) => input == BasicInputDevice::Microphone || input == BasicInputDevice::Samples,
+            None => input == BasicInputDevice::Microphone,
+        };
+        if self.bleeping && bleep_input {
+            for output in BasicOutputDevice::iter() {
+                if !self.device_settings.bleep_outputs.contains(output) {
+                    router[output] = false;
+                }
+            }
+        }
+    }
+
+    fn apply_bleep_routing(&mut self) -> Result<()> {
+        self.apply_routing(BasicInputDevice::Microphone)?;
+        if self.device_settings.bleep_sound.is_some() {
+            self.apply_routing(BasicInputDevice::Samples)?;
+        }
+        Ok(())
     }
 
     fn apply_transient_fader_routing(
@@ -1140,7 +2582,7 @@ impl<'a, T: UsbContext> Device<'a, T> {
         fader: FaderName,
         router: &mut EnumMap<BasicOutputDevice, bool>,
     ) {
-        let (muted_to_x, muted_to_all, mute_function) = self.profile.get_mute_button_state(fader);
+        let (muted_to_x, muted_to_all, mute_function) = self.get_fader_mute_state(fader);
         self.apply_transient_channel_routing(muted_to_x, muted_to_all, mute_function, router);
     }
 
@@ -1172,6 +2614,25 @@ impl<'a, T: UsbContext> Device<'a, T> {
         }
     }
 
+    fn apply_routing_preset(&mut self, name: &str) -> Result<()> {
+        let preset = self
+            .device_settings
+            .routing_presets
+            .get(name)
+            .cloned()
+            .ok_or_else(|| invalid(InvalidRequest::RoutingPresetNotFound(name.to_string())))?;
+
+        for input in BasicInputDevice::iter() {
+            let outputs = preset[input as usize];
+            for output in BasicOutputDevice::iter() {
+                let enabled = outputs.contains(output) && is_valid_route(input, output);
+                self.profile.set_routing(input, output, enabled);
+            }
+            self.apply_routing(input)?;
+        }
+        Ok(())
+    }
+
     fn apply_routing(&mut self, input: BasicInputDevice) -> Result<()> {
         // Load the routing for this channel from the profile..
         let mut router = self.profile.get_router(input);
@@ -1188,10 +2649,11 @@ impl<'a, T: UsbContext> Device<'a, T> {
         // Basically stripped down behaviour from handle_fader_mute which simply applies stuff.
         let channel = self.profile.get_fader_assignment(fader);
 
-        let (muted_to_x, muted_to_all, mute_function) = self.profile.get_mute_button_state(fader);
+        let (muted_to_x, muted_to_all, mute_function) = self.get_fader_mute_state(fader);
         if muted_to_all || (muted_to_x && mute_function == MuteFunction::All) {
             // This channel should be fully muted
             self.goxlr.set_channel_state(channel, Muted)?;
+            return Ok(());
         }
 
         // This channel isn't supposed to be muted (The Router will handle anything else).
@@ -1218,6 +2680,18 @@ impl<'a, T: UsbContext> Device<'a, T> {
     }
 
     async fn set_fader(&mut self, fader: FaderName, new_channel: ChannelName) -> Result<()> {
+        self.assign_fader(fader, new_channel).await?;
+
+        // Channels with their own colour take it with them to the new fader.
+        if self.device_settings.channel_colours.is_empty() {
+            return Ok(());
+        }
+        self.apply_channel_colours();
+        self.load_colour_map()?;
+        self.update_button_states()
+    }
+
+    async fn assign_fader(&mut self, fader: FaderName, new_channel: ChannelName) -> Result<()> {
         // A couple of things need to happen when a fader change occurs depending on scenario..
         if new_channel == self.profile.get_fader_assignment(fader) {
             // We don't need to do anything at all in theory, set the fader anyway..
@@ -1243,8 +2717,7 @@ impl<'a, T: UsbContext> Device<'a, T> {
         if fader_to_switch.is_none() {
             // Whatever is on the fader already is going away, per windows behaviour we need to
             // ensure any mute behaviour is restored as it can no longer be tracked.
-            let (muted_to_x, _muted_to_all, _mute_function) =
-                self.profile.get_mute_button_state(fader);
+            let (muted_to_x, _muted_to_all, _mute_function) = self.get_fader_mute_state(fader);
 
             if muted_to_x {
                 // Simulate a mute button tap, this should restore everything..
@@ -1290,10 +2763,121 @@ impl<'a, T: UsbContext> Device<'a, T> {
         Ok(())
     }
 
+    async fn next_fader_page(&mut self) -> Result<()> {
+        let pages = self.device_settings.fader_pages.len();
+        if pages == 0 {
+            return Err(invalid(InvalidRequest::NoFaderPages));
+        }
+        let page = self.fader_page.map_or(0, |page| (page + 1) % pages);
+        self.set_fader_page(page).await
+    }
+
+    // Moves the faders to a page. Channels already on a fader are swapped into place with their
+    // mute buttons, as SetFader does, while muted channels leaving the faders stay muted and take
+    // their mute button's state with them.
+    async fn set_fader_page(&mut self, page: usize) -> Result<()> {
+        let channels = self
+            .device_settings
+            .fader_pages
+            .get(page)
+            .copied()
+            .ok_or_else(|| invalid(InvalidRequest::FaderPageNotFound(page)))?;
+
+        for fader in FaderName::iter() {
+            let channel = channels[fader as usize];
+            let existing = self.profile.get_fader_assignment(fader);
+            if channel == existing {
+                continue;
+            }
+
+            let current = FaderName::iter()
+                .find(|other| self.profile.get_fader_assignment(*other) == channel);
+            if let Some(other) = current {
+                self.profile.switch_fader_assignment(fader, other);
+                self.held_mute_functions
+                    .swap(fader as usize, other as usize);
+                self.goxlr.set_fader(other, existing)?;
+            } else {
+                self.page_out_mute(fader, existing);
+                self.profile.set_fader_assignment(fader, channel);
+                self.page_in_mute(fader, channel);
+            }
+            self.goxlr.set_fader(fader, channel)?;
+        }
+
+        // The cough button needs to know which fader the mic is on, if any.
+        let mic_fader = FaderName::iter()
+            .find(|fader| self.profile.get_fader_assignment(*fader) == ChannelName::Mic);
+        self.profile
+            .set_mic_fader_id(mic_fader.map_or(4, |fader| fader as u8));
+
+        self.fader_page = Some(page);
+        self.apply_channel_colours();
+        self.load_colour_map()?;
+        for fader in FaderName::iter() {
+            self.set_fader_display_from_profile(fader)?;
+        }
+        self.update_button_states()
+    }
+
+    fn page_out_mute(&mut self, fader: FaderName, channel: ChannelName) {
+        let (muted_to_x, muted_to_all, mute_function) = self.get_fader_mute_state(fader);
+        if muted_to_x || muted_to_all {
+            self.paged_mutes[channel as usize] = Some(PagedMute {
+                muted_to_x,
+                muted_to_all,
+                mute_function,
+                previous_volume: self.profile.get_mute_button_previous_volume(fader),
+            });
+        }
+        self.profile.set_mute_button_on(fader, false);
+        self.profile.set_mute_button_blink(fader, false);
+        self.held_mute_functions[fader as usize] = None;
+    }
+
+    fn page_in_mute(&mut self, fader: FaderName, channel: ChannelName) {
+        if let Some(mute) = self.paged_mutes[channel as usize].take() {
+            self.profile.set_mute_button_on(fader, mute.muted_to_x);
+            self.profile.set_mute_button_blink(fader, mute.muted_to_all);
+            self.profile
+                .set_mute_button_previous_volume(fader, mute.previous_volume);
+            if mute.muted_to_x {
+                self.held_mute_functions[fader as usize] = Some(mute.mute_function);
+            }
+        }
+    }
+
+    // A new profile decides the faders, so channels muted while paged off are let go.
+    fn clear_paged_mutes(&mut self) -> Result<()> {
+        for channel in ChannelName::iter() {
+            if let Some(mute) = self.paged_mutes[channel as usize].take() {
+                if mute.muted_to_all || mute.mute_function == MuteFunction::All {
+                    self.goxlr.set_channel_state(channel, Unmuted)?;
+                }
+            }
+        }
+        self.fader_page = None;
+        Ok(())
+    }
+
+    fn apply_channel_colours(&mut self) {
+        for fader in FaderName::iter() {
+            let channel = self.profile.get_fader_assignment(fader);
+            if let Some(colour) = self.device_settings.channel_colours.get(&channel) {
+                // The settings can be edited by hand, a bad colour shouldn't stop a profile loading.
+                if let Err(error) = self.profile.set_fader_channel_colour(fader, colour) {
+                    warn!("Ignoring the colour for {}: {}", channel, error);
+                }
+            }
+        }
+    }
+
     fn get_fader_state(&self, fader: FaderName) -> FaderStatus {
+        let (muted_to_x, muted_to_all, function) = self.get_fader_mute_state(fader);
         FaderStatus {
             channel: self.profile().get_fader_assignment(fader),
             mute_type: self.profile().get_mute_button_behaviour(fader),
+            mute_state: get_mute_state(muted_to_x, muted_to_all, function),
         }
     }
 
@@ -1306,34 +2890,208 @@ impl<'a, T: UsbContext> Device<'a, T> {
         Ok(())
     }
 
+    fn get_channel_names(&self) -> [Option<String>; ChannelName::COUNT] {
+        let mut names: [Option<String>; ChannelName::COUNT] = Default::default();
+        for (channel, name) in &self.device_settings.channel_names {
+            names[*channel as usize] = Some(name.clone());
+        }
+        names
+    }
+
+    fn get_channel_colours(&self) -> [Option<String>; ChannelName::COUNT] {
+        let mut colours: [Option<String>; ChannelName::COUNT] = Default::default();
+        for (channel, colour) in &self.device_settings.channel_colours {
+            colours[*channel as usize] = Some(colour.clone());
+        }
+        colours
+    }
+
+    fn get_output_names(&self) -> [Option<String>; BasicOutputDevice::COUNT] {
+        let mut names: [Option<String>; BasicOutputDevice::COUNT] = Default::default();
+        for (output, name) in &self.device_settings.output_names {
+            names[*output as usize] = Some(name.clone());
+        }
+        names
+    }
+
+    fn get_volume_limit(&self, channel: ChannelName) -> u8 {
+        self.get_volume_limits()[channel as usize].unwrap_or(255)
+    }
+
+    fn get_volume_limits(&self) -> [Option<u8>; ChannelName::COUNT] {
+        let mut limits = [None; ChannelName::COUNT];
+        for (channel, limit) in &self.device_settings.volume_limits {
+            limits[*channel as usize] = Some(*limit);
+        }
+        limits
+    }
+
     fn get_bleep_volume(&self) -> i8 {
-        // This should be fast, block on the request..
-        let value = block_on(self.settings.get_device_bleep_volume(self.serial()));
+        self.device_settings.bleep_volume
+    }
 
-        if let Some(bleep) = value {
-            return bleep;
+    // With a custom bleep sound, the hardware bleep is turned down as far as it will go. With an
+    // attack it's kept there until the button is pressed, then rises to the bleep volume.
+    fn get_hardware_bleep_volume(&self) -> i8 {
+        if self.device_settings.bleep_sound.is_some() {
+            return MIN_BLEEP_VOLUME;
+        }
+        if self.device_settings.bleep_attack == 0 {
+            return self.get_bleep_volume();
+        }
+        match self.bleep_ramp {
+            Some(ramp) => ramp.volume,
+            None => MIN_BLEEP_VOLUME,
         }
-        -14
     }
 
-    fn load_colour_map(&mut self) -> Result<()> {
-        // The new colour format occurred on different firmware versions depending on device,
-        // so do the check here.
-
-        let use_1_3_40_format: bool = match self.hardware.device_type {
-            DeviceType::Unknown => true,
-            DeviceType::Full => version_newer_or_equal_to(
-                &self.hardware.versions.firmware,
-                VersionNumber(1, 3, 40, 0),
-            ),
-            DeviceType::Mini => version_newer_or_equal_to(
-                &self.hardware.versions.firmware,
-                VersionNumber(1, 1, 8, 0),
-            ),
+    fn apply_bleep_volume(&mut self) -> Result<()> {
+        let volume = self.get_hardware_bleep_volume();
+        self.goxlr
+            .set_effect_values(&[(EffectKey::BleepLevel, volume as i32)])?;
+        Ok(())
+    }
+
+    pub fn is_bleep_ramping(&self) -> bool {
+        self.bleep_ramp
+            .is_some_and(|ramp| ramp.volume < self.get_bleep_volume())
+    }
+
+    // Raises the bleep towards its volume, the device worker calls this every few milliseconds
+    // while it's rising. The volume is in dB, so even steps sound like a steady fade in.
+    pub fn update_bleep_ramp(&mut self) -> Result<()> {
+        let attack = self.device_settings.bleep_attack as f32;
+        let target = self.get_bleep_volume();
+        let ramp = match &mut self.bleep_ramp {
+            Some(ramp) => ramp,
+            None => return Ok(()),
         };
 
+        let progress = match attack {
+            attack if attack > 0.0 => (ramp.started.elapsed().as_millis() as f32 / attack).min(1.0),
+            _ => 1.0,
+        };
+        let volume =
+            MIN_BLEEP_VOLUME + ((target - MIN_BLEEP_VOLUME) as f32 * progress).round() as i8;
+        if volume != ramp.volume {
+            ramp.volume = volume;
+            self.apply_bleep_volume()?;
+        }
+        Ok(())
+    }
+
+    fn set_channel_volume(&mut self, channel: ChannelName, volume: u8) -> Result<()> {
+        let old_volume = self.profile.get_channel_volume(channel);
+        let volume = self.apply_channel_volume(channel, volume)?;
+        self.update_linked_volumes(channel, old_volume, volume)
+    }
+
+    // Sets the volume of only this channel, returning the volume after any limit is applied.
+    fn apply_channel_volume(&mut self, channel: ChannelName, volume: u8) -> Result<u8> {
+        let volume = volume.min(self.get_volume_limit(channel));
+        self.goxlr.set_volume(channel, volume)?;
+        self.profile.set_channel_volume(channel, volume);
+        self.apply_submix_volume(channel)?;
+        Ok(volume)
+    }
+
+    // Scales the volume of any channels linked to this one by the same ratio it just changed by.
+    fn update_linked_volumes(&mut self, channel: ChannelName, old: u8, new: u8) -> Result<()> {
+        if old == new {
+            return Ok(());
+        }
+
+        for linked in self.get_linked_channels(channel) {
+            let current = self.profile.get_channel_volume(linked) as u32;
+            let volume = match old {
+                0 => new,
+                _ => ((current * new as u32 + old as u32 / 2) / old as u32).min(255) as u8,
+            };
+            self.apply_channel_volume(linked, volume)?;
+        }
+        Ok(())
+    }
+
+    fn get_linked_channels(&self, channel: ChannelName) -> Vec<ChannelName> {
+        self.device_settings
+            .volume_links
+            .iter()
+            .find(|group| group.contains(&channel))
+            .map(|group| group.iter().copied().filter(|c| *c != channel).collect())
+            .unwrap_or_default()
+    }
+
+    // Removes the channels from any existing link, and links them together if requested.
+    async fn set_channels_linked(&mut self, channels: &[ChannelName], linked: bool) {
+        let mut links = self.device_settings.volume_links.clone();
+        for group in links.iter_mut() {
+            group.retain(|channel| !channels.contains(channel));
+        }
+        if linked {
+            links.push(channels.to_vec());
+        }
+        links.retain(|group| group.len() > 1);
+
+        self.settings
+            .set_device_volume_links(self.serial(), links)
+            .await;
+        self.settings.save().await;
+        self.refresh_device_settings().await;
+    }
+
+    async fn refresh_device_settings(&mut self) {
+        self.device_settings = self.settings.get_device_settings(self.serial()).await;
+    }
+
+    // Applies any changes made to this device's settings from outside of the device.
+    pub async fn reload_device_settings(&mut self) -> Result<()> {
+        self.refresh_device_settings().await;
+
+        self.apply_bleep_volume()?;
+
+        for channel in ChannelName::iter() {
+            let limit = self.get_volume_limit(channel);
+            if self.profile.get_channel_volume(channel) > limit {
+                self.apply_channel_volume(channel, limit)?;
+            }
+        }
+
+        let pages = self.device_settings.fader_pages.len();
+        if self.fader_page.is_some_and(|page| page >= pages) {
+            self.fader_page = None;
+        }
+
+        self.apply_channel_colours();
+        self.load_colour_map()?;
+        self.update_button_states()
+    }
+
+    // Sends a channel's sub mix volume to the device, linked channels follow the main volume.
+    fn apply_submix_volume(&mut self, channel: ChannelName) -> Result<()> {
+        if !self.hardware.features.submixes {
+            return Ok(());
+        }
+
+        if self.profile.is_submix_linked(channel) {
+            let volume = self.profile.get_channel_volume(channel);
+            self.profile.set_submix_volume(channel, volume)?;
+        }
+
+        if let Some(volume) = self.profile.get_submix_volume(channel) {
+            self.goxlr.set_sub_volume(channel, volume)?;
+        }
+        Ok(())
+    }
+
+    fn load_colour_map(&mut self) -> Result<()> {
+        let use_1_3_40_format = self.hardware.features.extended_colour_map;
+
         let colour_map = self.profile.get_colour_map(use_1_3_40_format);
+        if self.last_colour_map == Some(colour_map) {
+            return Ok(());
+        }
 
+        self.last_colour_map = None;
         if use_1_3_40_format {
             self.goxlr.set_button_colours_1_3_40(colour_map)?;
         } else {
@@ -1342,10 +3100,41 @@ impl<'a, T: UsbContext> Device<'a, T> {
             self.goxlr.set_button_colours(map)?;
         }
 
+        self.last_colour_map = Some(colour_map);
+        Ok(())
+    }
+
+    async fn load_profile(&mut self, profile: ProfileAdapter) -> Result<()> {
+        let previous = std::mem::replace(&mut self.profile, profile);
+        if previous.name() != self.profile.name() {
+            self.previous_profile = Some(previous);
+        }
+        self.held_mute_functions = [None; 4];
+        self.clear_paged_mutes()?;
+        self.apply_profile()?;
+        self.saved_profile = self.profile.get_contents().ok();
+        self.settings
+            .set_device_profile_name(self.serial(), self.profile.name())
+            .await;
+        self.settings.save().await;
+
+        let data = json!({ "name": self.profile.name() });
+        run_hooks(self.settings, HookEvent::ProfileLoaded, self.serial(), data).await;
         Ok(())
     }
 
     fn apply_profile(&mut self) -> Result<()> {
+        self.goxlr.begin_batch();
+        let result = self.apply_profile_settings();
+        if let Err(e) = self.goxlr.end_batch() {
+            // Some of the lighting may not have made it to the device.
+            self.clear_lighting_cache();
+            return Err(e.into());
+        }
+        result
+    }
+
+    fn apply_profile_settings(&mut self) -> Result<()> {
         // Set volumes first, applying mute may modify stuff..
         debug!("Applying Profile..");
 
@@ -1368,6 +3157,7 @@ impl<'a, T: UsbContext> Device<'a, T> {
         self.apply_cough_from_profile()?;
 
         debug!("Loading Colour Map..");
+        self.apply_channel_colours();
         self.load_colour_map()?;
 
         debug!("Setting Fader display modes..");
@@ -1378,11 +3168,22 @@ impl<'a, T: UsbContext> Device<'a, T> {
 
         debug!("Setting Channel Volumes..");
         for channel in ChannelName::iter() {
-            let channel_volume = self.profile.get_channel_volume(channel);
+            let channel_volume = self
+                .profile
+                .get_channel_volume(channel)
+                .min(self.get_volume_limit(channel));
+            self.profile.set_channel_volume(channel, channel_volume);
             debug!("Setting volume for {} to {}", channel, channel_volume);
             self.goxlr.set_volume(channel, channel_volume)?;
         }
 
+        if self.hardware.features.submixes {
+            debug!("Applying Sub Mix volumes..");
+            for channel in ChannelName::iter() {
+                self.apply_submix_volume(channel)?;
+            }
+        }
+
         debug!("Updating button states..");
         self.update_button_states()?;
 
@@ -1396,6 +3197,80 @@ impl<'a, T: UsbContext> Device<'a, T> {
         Ok(())
     }
 
+    fn apply_lighting_theme(&mut self, theme: &Lighting) -> Result<()> {
+        self.profile.set_lighting_ipc(theme)?;
+
+        self.load_colour_map()?;
+        for fader in FaderName::iter() {
+            self.set_fader_display_from_profile(fader)?;
+        }
+        self.update_button_states()
+    }
+
+    // Brings one section of the live profile in line with another profile, only touching the
+    // parts which actually differ.
+    async fn apply_profile_section(
+        &mut self,
+        saved: &ProfileAdapter,
+        section: ProfileSection,
+    ) -> Result<()> {
+        match section {
+            ProfileSection::Faders => {
+                for fader in FaderName::iter() {
+                    let channel = saved.get_fader_assignment(fader);
+                    if self.profile.get_fader_assignment(fader) != channel {
+                        self.set_fader(fader, channel).await?;
+                    }
+
+                    let behaviour = saved.get_mute_button_behaviour(fader);
+                    if self.profile.get_mute_button_behaviour(fader) != behaviour {
+                        self.unmute_if_muted(fader).await?;
+                        self.profile.set_mute_button_behaviour(fader, behaviour);
+                    }
+                }
+                self.update_button_states()?;
+            }
+            ProfileSection::Volumes => {
+                for channel in ChannelName::iter() {
+                    let volume = saved.get_channel_volume(channel);
+                    if self.profile.get_channel_volume(channel) != volume {
+                        self.apply_channel_volume(channel, volume)?;
+                    }
+                }
+            }
+            ProfileSection::Routing => {
+                for input in BasicInputDevice::iter() {
+                    let router = saved.get_router(input);
+                    if self.profile.get_router(input) == router {
+                        continue;
+                    }
+
+                    for output in BasicOutputDevice::iter() {
+                        self.profile.set_routing(input, output, router[output]);
+                    }
+                    self.apply_routing(input)?;
+                }
+            }
+            ProfileSection::Lighting => {
+                if self.profile.copy_lighting_from(saved) {
+                    self.load_colour_map()?;
+                    for fader in FaderName::iter() {
+                        self.set_fader_display_from_profile(fader)?;
+                    }
+                    self.update_button_states()?;
+                }
+            }
+            ProfileSection::Sampler => {
+                // Sample buttons go dark when they have nothing assigned, so refresh the colours.
+                if self.profile.copy_samples_from(saved) {
+                    self.load_colour_map()?;
+                    self.update_button_states()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Applies a Set of Microphone Parameters based on input, designed this way
     /// so that commands and other abstract entities can apply a subset of params
     fn apply_mic_params(&mut self, params: HashSet<MicrophoneParamKey>) -> Result<()> {
@@ -1404,9 +3279,12 @@ impl<'a, T: UsbContext> Device<'a, T> {
             vec.push((
                 param,
                 self.mic_profile
-                    .get_param_value(param, self.serial(), self.settings),
+                    .get_param_value(param, self.get_hardware_bleep_volume()),
             ));
         }
+
+        // Sets have no order, sort so the same change always sends the same data.
+        vec.sort_by_key(|(key, _)| *key as u32);
         self.goxlr.set_mic_param(vec.as_slice())?;
         Ok(())
     }
@@ -1418,13 +3296,14 @@ impl<'a, T: UsbContext> Device<'a, T> {
                 effect,
                 self.mic_profile.get_effect_value(
                     effect,
-                    self.serial(),
-                    self.settings,
+                    self.get_hardware_bleep_volume(),
                     self.profile(),
                 ),
             ));
         }
 
+        vec.sort_by_key(|(key, _)| *key as u32);
+
         for effect in &vec {
             let (key, value) = effect;
             debug!("Setting {:?} to {}", key, value);
@@ -1433,6 +3312,92 @@ impl<'a, T: UsbContext> Device<'a, T> {
         Ok(())
     }
 
+    fn apply_equalisers(&mut self) -> Result<()> {
+        self.goxlr.begin_batch();
+        let result = self.apply_equaliser_settings();
+        self.goxlr.end_batch()?;
+        result
+    }
+
+    fn apply_equaliser_settings(&mut self) -> Result<()> {
+        self.apply_mic_params(self.mic_profile.get_mini_eq_keyset())?;
+        if self.hardware.features.full_eq {
+            self.apply_effects(self.mic_profile.get_eq_keyset())?;
+        }
+        Ok(())
+    }
+
+    // With the interlock on, phantom power is only turned on when it's been asked for directly.
+    /*
+    Anything which switches to a new mic type goes through this: the commands which set it, mic
+    calibration, and loading or importing a mic profile. Applying the profiles at startup (from
+    the settings or a state snapshot), SyncToHardware and a batch rollback are exempt, as they only
+    put back the type the daemon was already using, and at startup there's nothing to compare to.
+     */
+    fn needs_phantom_power_confirmation(&self, mic_type: MicrophoneType) -> bool {
+        self.device_settings.phantom_power_interlock
+            && mic_type.has_phantom_power()
+            && !self.mic_profile.mic_type().has_phantom_power()
+    }
+
+    // Phantom power can pop as it turns on or off, so the mic can be muted while it settles.
+    async fn set_mic_type(&mut self, mic_type: MicrophoneType) -> Result<()> {
+        let phantom_power = mic_type.has_phantom_power();
+        if phantom_power == self.mic_profile.mic_type().has_phantom_power() {
+            self.mic_profile.set_mic_type(mic_type);
+            return self.apply_mic_gain();
+        }
+
+        let mute_time = self.device_settings.phantom_power_mute;
+        if mute_time > 0 {
+            self.goxlr.set_channel_state(ChannelName::Mic, Muted)?;
+        }
+        self.mic_profile.set_mic_type(mic_type);
+        self.apply_mic_gain()?;
+        if mute_time > 0 {
+            sleep(Duration::from_millis(mute_time.into())).await;
+            if self.get_channel_mute_state(ChannelName::Mic) != MuteState::MutedToAll {
+                self.goxlr.set_channel_state(ChannelName::Mic, Unmuted)?;
+            }
+        }
+
+        warn!(
+            "Phantom power turned {} for {}",
+            if phantom_power { "on" } else { "off" },
+            self.serial()
+        );
+        self.events.push(DaemonEvent::PhantomPower {
+            serial: self.serial().to_owned(),
+            enabled: phantom_power,
+        });
+        Ok(())
+    }
+
+    // A new mic profile keeps the current mic type until the interlock allows its own, so loading
+    // one can't turn phantom power on without a confirmed SetMicrophoneType.
+    async fn apply_new_mic_profile(&mut self, mic_profile: MicProfileAdapter) -> Result<()> {
+        let mic_type = mic_profile.mic_type();
+        let current_type = self.mic_profile.mic_type();
+
+        self.mic_profile = mic_profile;
+        self.mic_profile.set_mic_type(current_type);
+        self.apply_mic_profile()?;
+        if mic_type == current_type {
+            return Ok(());
+        }
+
+        if self.needs_phantom_power_confirmation(mic_type) {
+            warn!(
+                "Mic profile {} uses a {:?} mic, keeping {:?} until phantom power is confirmed",
+                self.mic_profile.name(),
+                mic_type,
+                current_type
+            );
+            return Ok(());
+        }
+        self.set_mic_type(mic_type).await
+    }
+
     fn apply_mic_gain(&mut self) -> Result<()> {
         let mic_type = self.mic_profile.mic_type();
         let gain = self.mic_profile.mic_gains()[mic_type as usize];
@@ -1442,6 +3407,13 @@ impl<'a, T: UsbContext> Device<'a, T> {
     }
 
     fn apply_mic_profile(&mut self) -> Result<()> {
+        self.goxlr.begin_batch();
+        let result = self.apply_mic_profile_settings();
+        self.goxlr.end_batch()?;
+        result
+    }
+
+    fn apply_mic_profile_settings(&mut self) -> Result<()> {
         // Configure the microphone..
         self.apply_mic_gain()?;
 
@@ -1461,13 +3433,13 @@ impl<'a, T: UsbContext> Device<'a, T> {
         let mut keys = HashSet::new();
         keys.extend(self.mic_profile.get_common_keys());
 
-        if self.hardware.device_type == DeviceType::Full {
+        if self.hardware.features.effects {
             keys.extend(self.mic_profile.get_full_keys());
         }
 
         self.apply_effects(keys)?;
 
-        if self.hardware.device_type == DeviceType::Full {
+        if self.hardware.features.encoders {
             self.load_effects()?;
             self.set_pitch_mode()?;
         }
@@ -1496,8 +3468,7 @@ impl<'a, T: UsbContext> Device<'a, T> {
     }
 
     fn set_pitch_mode(&mut self) -> Result<()> {
-        if self.hardware.device_type != DeviceType::Full {
-            // Not a Full GoXLR, nothing to do.
+        if !self.hardware.features.encoders {
             return Ok(());
         }
 
@@ -1525,4 +3496,173 @@ impl<'a, T: UsbContext> Device<'a, T> {
     pub fn is_connected(&self) -> bool {
         self.goxlr.is_connected()
     }
+
+    pub fn interrupt_reader(&self) -> Result<Box<dyn InterruptWait>> {
+        Ok(self.goxlr.interrupt_reader()?)
+    }
+}
+
+fn validate_route(input: BasicInputDevice, output: BasicOutputDevice, enabled: bool) -> Result<()> {
+    if enabled && !is_valid_route(input, output) {
+        return Err(invalid(InvalidRequest::InvalidRoute { input, output }));
+    }
+    Ok(())
+}
+
+pub fn get_device_features(device_type: &DeviceType, firmware: &VersionNumber) -> DeviceFeatures {
+    let is_full = *device_type == DeviceType::Full;
+
+    // Sub mixes and the new colour format arrived in different firmware versions on each device.
+    let (submixes, extended_colour_map) = match device_type {
+        DeviceType::Unknown => (false, true),
+        DeviceType::Full => (
+            version_newer_or_equal_to(firmware, VersionNumber(1, 4, 2, 107)),
+            version_newer_or_equal_to(firmware, VersionNumber(1, 3, 40, 0)),
+        ),
+        DeviceType::Mini => (
+            version_newer_or_equal_to(firmware, VersionNumber(1, 2, 0, 46)),
+            version_newer_or_equal_to(firmware, VersionNumber(1, 1, 8, 0)),
+        ),
+    };
+
+    DeviceFeatures {
+        effects: is_full,
+        encoders: is_full,
+        sampler: is_full,
+        full_eq: is_full,
+        fader_display_modes: is_full,
+        submixes,
+        extended_colour_map,
+    }
+}
+
+fn batch_error(index: usize, error: anyhow::Error) -> anyhow::Error {
+    DaemonError::BatchFailed {
+        index,
+        error: Box::new(to_daemon_error(error)),
+    }
+    .into()
+}
+
+// Whether two names would be the same on the command line, where LineIn is line-in.
+fn is_same_name(a: &str, b: &str) -> bool {
+    let key = |name: &str| -> String {
+        name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    key(a) == key(b)
+}
+
+// Channels to link together, in order and without duplicates.
+fn get_link_group(channels: &[ChannelName]) -> Vec<ChannelName> {
+    let mut group: Vec<ChannelName> = vec![];
+    for channel in channels {
+        if !group.contains(channel) {
+            group.push(*channel);
+        }
+    }
+    group
+}
+
+fn validate_colour(colour: &str) -> Result<()> {
+    match colour.parse::<RgbColour>() {
+        Ok(_) => Ok(()),
+        Err(error) => Err(invalid(InvalidRequest::InvalidColour(
+            colour.to_string(),
+            error,
+        ))),
+    }
+}
+
+fn validate_hsv(colour: &HsvColour) -> Result<()> {
+    check_range("Saturation", colour.saturation, 0.0, 1.0)?;
+    check_range("Value", colour.value, 0.0, 1.0)
+}
+
+fn validate_brightness_delta(delta: i8) -> Result<()> {
+    check_range("Brightness adjustment", delta, -100, 100)
+}
+
+fn get_mute_state(muted_to_x: bool, muted_to_all: bool, function: MuteFunction) -> MuteState {
+    if muted_to_all || (muted_to_x && function == MuteFunction::All) {
+        return MuteState::MutedToAll;
+    }
+    if !muted_to_x {
+        return MuteState::Unmuted;
+    }
+
+    let output = match function {
+        MuteFunction::ToStream => BasicOutputDevice::BroadcastMix,
+        MuteFunction::ToVoiceChat => BasicOutputDevice::ChatMic,
+        MuteFunction::ToPhones => BasicOutputDevice::Headphones,
+        MuteFunction::ToLineOut => BasicOutputDevice::LineOut,
+        MuteFunction::All => unreachable!(),
+    };
+    MuteState::MutedTo(EnumSet::only(output))
+}
+
+// The knob positions an encoder can be turned between.
+fn get_mute_button_fader(button: Buttons) -> Option<FaderName> {
+    match button {
+        Buttons::Fader1Mute => Some(FaderName::A),
+        Buttons::Fader2Mute => Some(FaderName::B),
+        Buttons::Fader3Mute => Some(FaderName::C),
+        Buttons::Fader4Mute => Some(FaderName::D),
+        _ => None,
+    }
+}
+
+// Only the channels which are inputs can be routed.
+fn get_channel_input(channel: ChannelName) -> Option<BasicInputDevice> {
+    match channel {
+        ChannelName::Mic => Some(BasicInputDevice::Microphone),
+        ChannelName::LineIn => Some(BasicInputDevice::LineIn),
+        ChannelName::Console => Some(BasicInputDevice::Console),
+        ChannelName::System => Some(BasicInputDevice::System),
+        ChannelName::Game => Some(BasicInputDevice::Game),
+        ChannelName::Chat => Some(BasicInputDevice::Chat),
+        ChannelName::Sample => Some(BasicInputDevice::Samples),
+        ChannelName::Music => Some(BasicInputDevice::Music),
+        _ => None,
+    }
+}
+
+fn get_sample_button(button: Buttons) -> Option<SampleButtons> {
+    match button {
+        Buttons::SamplerTopLeft => Some(SampleButtons::TopLeft),
+        Buttons::SamplerTopRight => Some(SampleButtons::TopRight),
+        Buttons::SamplerBottomLeft => Some(SampleButtons::BottomLeft),
+        Buttons::SamplerBottomRight => Some(SampleButtons::BottomRight),
+        _ => None,
+    }
+}
+
+fn get_encoder_range(encoder: EncoderName) -> (i8, i8) {
+    match encoder {
+        EncoderName::Pitch | EncoderName::Gender => (-24, 24),
+        EncoderName::Reverb | EncoderName::Echo => (0, 24),
+    }
+}
+
+fn invalid(reason: InvalidRequest) -> anyhow::Error {
+    DaemonError::InvalidRequest(reason).into()
+}
+
+pub fn check_range<T: PartialOrd + Into<f64>>(
+    setting: &str,
+    value: T,
+    min: T,
+    max: T,
+) -> Result<()> {
+    if value < min || value > max {
+        return Err(DaemonError::InvalidValueRange {
+            setting: setting.to_string(),
+            min: min.into(),
+            max: max.into(),
+        }
+        .into());
+    }
+    Ok(())
 }
@@ -0,0 +1,69 @@
+/*
+Commands are performed by handlers, each of which looks after one family of commands (volumes,
+routing, lighting and so on). A handler is given every command which the handlers registered
+before it didn't take, and either performs it or hands it back, so a new family of commands only
+needs its own handler registering here rather than changes to the device itself.
+ */
+
+use crate::device::Device;
+use anyhow::{anyhow, Result};
+use futures::future::BoxFuture;
+use goxlr_ipc::GoXLRCommand;
+use log::debug;
+
+// Resolves to the command if the handler didn't perform it.
+pub type CommandFuture<'a> = BoxFuture<'a, Result<Option<GoXLRCommand>>>;
+pub type CommandHandler = for<'a, 'b> fn(&'a mut Device<'b>, GoXLRCommand) -> CommandFuture<'a>;
+
+#[derive(Clone)]
+pub struct CommandHandlers {
+    handlers: Vec<(&'static str, CommandHandler)>,
+}
+
+impl CommandHandlers {
+    pub fn new() -> Self {
+        Self {
+            handlers: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: &'static str, handler: CommandHandler) {
+        self.handlers.push((name, handler));
+    }
+
+    pub fn get(&self, index: usize) -> Option<(&'static str, CommandHandler)> {
+        self.handlers.get(index).copied()
+    }
+}
+
+impl Default for CommandHandlers {
+    fn default() -> Self {
+        let mut handlers = Self::new();
+        handlers.register("volumes", super::volumes::perform);
+        handlers.register("routing", super::routing::perform);
+        handlers.register("lighting", super::lighting::perform);
+        handlers.register("mic", super::mic::perform);
+        handlers.register("profiles", super::profiles::perform);
+        handlers.register("sampler", super::sampler::perform);
+        handlers
+    }
+}
+
+impl Device<'_> {
+    // The handlers are copied out one at a time, as each needs the device to itself.
+    pub(super) async fn dispatch_command(&mut self, command: GoXLRCommand) -> Result<()> {
+        let mut command = command;
+        let mut index = 0;
+        while let Some((name, handler)) = self.command_handlers.get(index) {
+            command = match handler(self, command).await? {
+                Some(command) => command,
+                None => {
+                    debug!("Command performed by the {} handler", name);
+                    return Ok(());
+                }
+            };
+            index += 1;
+        }
+        Err(anyhow!("No handler is registered for {:?}", command))
+    }
+}
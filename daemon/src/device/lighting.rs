@@ -0,0 +1,139 @@
+// Colours and display styles for the faders and buttons.
+
+use crate::device::handlers::CommandFuture;
+use crate::device::Device;
+use crate::profile::hsv_to_rgb;
+use crate::themes::{load_theme, save_theme};
+use goxlr_ipc::GoXLRCommand;
+use goxlr_types::{FaderDisplayStyle, FaderName, RgbColour};
+use strum::IntoEnumIterator;
+
+pub fn perform<'a>(device: &'a mut Device<'_>, command: GoXLRCommand) -> CommandFuture<'a> {
+    Box::pin(async move {
+        match command {
+            GoXLRCommand::SetChannelColour(channel, colour) => {
+                // Stored as RRGGBB, whichever format it was given in
+                let colour = colour
+                    .map(|colour| colour.parse::<RgbColour>().map(|c| c.to_string()))
+                    .transpose()?;
+                device
+                    .settings
+                    .set_device_channel_colour(device.serial(), channel, colour)
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+
+                device.apply_channel_colours();
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            // Colouring..
+            GoXLRCommand::SetFaderDisplayStyle(fader, display) => {
+                device.profile.set_fader_display(fader, display);
+                device.set_fader_display_from_profile(fader)?;
+            }
+            GoXLRCommand::SetFaderDisplayMode(fader, gradient, meter) => {
+                let display = FaderDisplayStyle::from_flags(gradient, meter);
+                device.profile.set_fader_display(fader, display);
+                device.set_fader_display_from_profile(fader)?;
+            }
+            GoXLRCommand::SetFaderColours(fader, top, bottom) => {
+                // Need to get the fader colour map, and set values..
+                device.profile.set_fader_colours(fader, top, bottom)?;
+                device.load_colour_map()?;
+            }
+            GoXLRCommand::SetAllFaderColours(top, bottom) => {
+                // I considered this as part of SetFaderColours, but spamming a new colour map
+                // for every fader change seemed excessive, this allows us to set them all before
+                // reloading.
+                for fader in FaderName::iter() {
+                    device
+                        .profile
+                        .set_fader_colours(fader, top.to_owned(), bottom.to_owned())?;
+                }
+                device.load_colour_map()?;
+            }
+            GoXLRCommand::SetAllFaderDisplayStyle(display_style) => {
+                for fader in FaderName::iter() {
+                    device.profile.set_fader_display(fader, display_style);
+                    device.set_fader_display_from_profile(fader)?;
+                }
+                device.load_colour_map()?;
+            }
+            GoXLRCommand::SetButtonColours(target, colour, colour2) => {
+                device
+                    .profile
+                    .set_button_colours(target, colour, colour2.as_ref())?;
+
+                // Reload the colour map and button states..
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::SetButtonOffStyle(target, off_style) => {
+                device.profile.set_button_off_style(target, off_style);
+
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::SetFaderColoursHsv(fader, top, bottom) => {
+                device
+                    .profile
+                    .set_fader_colours(fader, hsv_to_rgb(&top), hsv_to_rgb(&bottom))?;
+                device.load_colour_map()?;
+            }
+            GoXLRCommand::SetButtonColoursHsv(target, colour, colour_two) => {
+                let colour_two = colour_two.as_ref().map(hsv_to_rgb);
+                device.profile.set_button_colours(
+                    target,
+                    hsv_to_rgb(&colour),
+                    colour_two.as_ref(),
+                )?;
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::AdjustButtonBrightness(target, delta) => {
+                device.profile.adjust_button_brightness(target, delta);
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::AdjustAllBrightness(delta) => {
+                device.profile.adjust_all_brightness(delta);
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::ShiftHue(group, degrees) => {
+                device.profile.shift_group_hue(group, degrees);
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::ApplyLightingTheme(theme) => {
+                device.apply_lighting_theme(&theme)?;
+            }
+            GoXLRCommand::LoadLightingTheme(theme_name) => {
+                let theme_directory = device.settings.get_theme_directory().await;
+                let theme = load_theme(&theme_directory, &theme_name)?;
+                device.apply_lighting_theme(&theme)?;
+            }
+            GoXLRCommand::SaveLightingTheme(theme_name) => {
+                let theme_directory = device.settings.get_theme_directory().await;
+                let theme = device.profile.get_lighting_ipc(false);
+                save_theme(&theme_directory, &theme_name, &theme)?;
+            }
+            GoXLRCommand::SetButtonGroupColours(target, colour, colour_2) => {
+                device
+                    .profile
+                    .set_group_button_colours(target, colour, colour_2)?;
+
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::SetButtonGroupOffStyle(target, off_style) => {
+                device.profile.set_group_button_off_style(target, off_style);
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            command => return Ok(Some(command)),
+        }
+        Ok(None)
+    })
+}
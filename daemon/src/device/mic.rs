@@ -0,0 +1,153 @@
+// The microphone, its equaliser, noise gate, compressor and de-esser, and the bleep's volume.
+
+use crate::device::handlers::CommandFuture;
+use crate::device::Device;
+use goxlr_ipc::GoXLRCommand;
+use goxlr_types::{EffectKey, MicrophoneParamKey};
+use std::collections::HashSet;
+
+pub fn perform<'a>(device: &'a mut Device<'_>, command: GoXLRCommand) -> CommandFuture<'a> {
+    Box::pin(async move {
+        match command {
+            GoXLRCommand::SetSwearButtonVolume(volume) => {
+                device
+                    .settings
+                    .set_device_bleep_volume(device.serial(), volume)
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+                device.apply_bleep_volume()?;
+            }
+            GoXLRCommand::SetMicrophoneType(mic_type, _) => {
+                device.set_mic_type(mic_type).await?;
+            }
+            GoXLRCommand::SetMicrophoneGain(mic_type, gain) => {
+                device.mic_profile.set_mic_gain(mic_type, gain);
+                device.set_mic_type(mic_type).await?;
+            }
+            // Equaliser
+            GoXLRCommand::SetEqMiniGain(gain, value) => {
+                let param = device.mic_profile.set_mini_eq_gain(gain, value);
+                device.apply_mic_params(HashSet::from([param]))?;
+            }
+            GoXLRCommand::SetEqMiniFreq(freq, value) => {
+                let param = device.mic_profile.set_mini_eq_freq(freq, value);
+                device.apply_mic_params(HashSet::from([param]))?;
+            }
+            GoXLRCommand::SetEqGain(gain, value) => {
+                let param = device.mic_profile.set_eq_gain(gain, value);
+                device.apply_effects(HashSet::from([param]))?;
+            }
+            GoXLRCommand::SetEqFreq(freq, value) => {
+                let param = device.mic_profile.set_eq_freq(freq, value);
+                device.apply_effects(HashSet::from([param]))?;
+            }
+            GoXLRCommand::SetEqPreset(preset) => {
+                device.mic_profile.set_eq_preset(preset);
+                device.apply_equalisers()?;
+            }
+            GoXLRCommand::ResetEqualiser() => {
+                device.mic_profile.reset_equalisers();
+                device.apply_equalisers()?;
+            }
+            GoXLRCommand::SetGateThreshold(value) => {
+                device.mic_profile.set_gate_threshold(value);
+                device.apply_mic_params(HashSet::from([MicrophoneParamKey::GateThreshold]))?;
+                device.apply_effects(HashSet::from([EffectKey::GateThreshold]))?;
+            }
+
+            // Noise Gate
+            GoXLRCommand::SetGateAttenuation(percentage) => {
+                device.mic_profile.set_gate_attenuation(percentage);
+                device.apply_mic_params(HashSet::from([MicrophoneParamKey::GateAttenuation]))?;
+                device.apply_effects(HashSet::from([EffectKey::GateAttenuation]))?;
+            }
+            GoXLRCommand::SetGateAttack(attack_time) => {
+                device.mic_profile.set_gate_attack(attack_time);
+                device.apply_mic_params(HashSet::from([MicrophoneParamKey::GateAttack]))?;
+                device.apply_effects(HashSet::from([EffectKey::GateAttack]))?;
+            }
+            GoXLRCommand::SetGateRelease(release_time) => {
+                device.mic_profile.set_gate_release(release_time);
+                device.apply_mic_params(HashSet::from([MicrophoneParamKey::GateRelease]))?;
+                device.apply_effects(HashSet::from([EffectKey::GateRelease]))?;
+            }
+            GoXLRCommand::SetGateActive(active) => {
+                device.mic_profile.set_gate_active(active);
+
+                // GateEnabled appears to only be an effect key.
+                device.apply_effects(HashSet::from([EffectKey::GateEnabled]))?;
+            }
+
+            GoXLRCommand::SetGateAmount(amount) => {
+                device.mic_profile.set_gate_amount(amount);
+                device.apply_mic_params(HashSet::from([
+                    MicrophoneParamKey::GateThreshold,
+                    MicrophoneParamKey::GateAttenuation,
+                    MicrophoneParamKey::GateAttack,
+                    MicrophoneParamKey::GateRelease,
+                ]))?;
+                device.apply_effects(HashSet::from([
+                    EffectKey::GateThreshold,
+                    EffectKey::GateAttenuation,
+                    EffectKey::GateAttack,
+                    EffectKey::GateRelease,
+                ]))?;
+            }
+
+            // Compressor
+            GoXLRCommand::SetCompressorThreshold(value) => {
+                device.mic_profile.set_compressor_threshold(value);
+                device
+                    .apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorMakeUpGain]))?;
+                device.apply_effects(HashSet::from([EffectKey::CompressorMakeUpGain]))?;
+            }
+            GoXLRCommand::SetCompressorRatio(ratio) => {
+                device.mic_profile.set_compressor_ratio(ratio);
+                device.apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorRatio]))?;
+                device.apply_effects(HashSet::from([EffectKey::CompressorRatio]))?;
+            }
+            GoXLRCommand::SetCompressorAttack(value) => {
+                device.mic_profile.set_compressor_attack(value);
+                device.apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorAttack]))?;
+                device.apply_effects(HashSet::from([EffectKey::CompressorAttack]))?;
+            }
+            GoXLRCommand::SetCompressorReleaseTime(value) => {
+                device.mic_profile.set_compressor_release(value);
+                device.apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorRelease]))?;
+                device.apply_effects(HashSet::from([EffectKey::CompressorRelease]))?;
+            }
+            GoXLRCommand::SetCompressorMakeupGain(value) => {
+                device.mic_profile.set_compressor_makeup(value);
+                device
+                    .apply_mic_params(HashSet::from([MicrophoneParamKey::CompressorMakeUpGain]))?;
+                device.apply_effects(HashSet::from([EffectKey::CompressorMakeUpGain]))?;
+            }
+
+            GoXLRCommand::SetCompressorAmount(amount) => {
+                device.mic_profile.set_compressor_amount(amount);
+                device.apply_mic_params(HashSet::from([
+                    MicrophoneParamKey::CompressorThreshold,
+                    MicrophoneParamKey::CompressorRatio,
+                    MicrophoneParamKey::CompressorAttack,
+                    MicrophoneParamKey::CompressorRelease,
+                    MicrophoneParamKey::CompressorMakeUpGain,
+                ]))?;
+                device.apply_effects(HashSet::from([
+                    EffectKey::CompressorThreshold,
+                    EffectKey::CompressorRatio,
+                    EffectKey::CompressorAttack,
+                    EffectKey::CompressorRelease,
+                    EffectKey::CompressorMakeUpGain,
+                ]))?;
+            }
+
+            GoXLRCommand::SetDeEsser(amount) => {
+                device.mic_profile.set_deesser(amount);
+                device.apply_effects(HashSet::from([EffectKey::DeEsser]))?;
+            }
+            command => return Ok(Some(command)),
+        }
+        Ok(None)
+    })
+}
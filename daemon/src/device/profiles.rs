@@ -0,0 +1,93 @@
+// Loading and saving the profile and the mic profile.
+
+use crate::device::handlers::CommandFuture;
+use crate::device::Device;
+use crate::mic_profile::MicProfileAdapter;
+use crate::profile::ProfileAdapter;
+use goxlr_ipc::GoXLRCommand;
+
+pub fn perform<'a>(device: &'a mut Device<'_>, command: GoXLRCommand) -> CommandFuture<'a> {
+    Box::pin(async move {
+        match command {
+            // Profiles
+            GoXLRCommand::LoadProfile(profile_name) => {
+                let profile_directory = device.settings.get_profile_directory().await;
+                let profile = ProfileAdapter::from_named(profile_name, vec![&profile_directory])?;
+                device.load_profile(profile).await?;
+            }
+            GoXLRCommand::LoadEmbeddedProfile(profile_name) => {
+                device
+                    .load_profile(ProfileAdapter::from_embedded(&profile_name)?)
+                    .await?;
+            }
+            GoXLRCommand::ApplyProfileSections(profile_name, sections) => {
+                let profile_directory = device.settings.get_profile_directory().await;
+                let saved = ProfileAdapter::from_named(profile_name, vec![&profile_directory])?;
+                for section in sections {
+                    device.apply_profile_section(&saved, section).await?;
+                }
+            }
+            GoXLRCommand::SaveProfile() => device.save_profile(false).await?,
+            GoXLRCommand::QuickSwitchProfile() => device.quick_switch_profile().await?,
+            GoXLRCommand::SyncToHardware() => device.sync_to_hardware()?,
+            GoXLRCommand::SaveProfileAs(profile_name) => {
+                let profile_directory = device.settings.get_profile_directory().await;
+                device.profile.write_profile(
+                    profile_name.clone(),
+                    &profile_directory,
+                    false,
+                    false,
+                )?;
+                device.saved_profile = device.profile.get_contents().ok();
+
+                // Save the new name in the settings
+                device
+                    .settings
+                    .set_device_profile_name(device.serial(), profile_name.as_str())
+                    .await;
+
+                device.settings.save().await;
+            }
+            GoXLRCommand::LoadMicProfile(mic_profile_name) => {
+                let mic_profile_directory = device.settings.get_mic_profile_directory().await;
+                let mic_profile =
+                    MicProfileAdapter::from_named(mic_profile_name, vec![&mic_profile_directory])?;
+                device.apply_new_mic_profile(mic_profile).await?;
+                device.saved_mic_profile = device.mic_profile.get_contents().ok();
+                device
+                    .settings
+                    .set_device_mic_profile_name(device.serial(), device.mic_profile.name())
+                    .await;
+                device.settings.save().await;
+            }
+            GoXLRCommand::ImportMicProfile(json) => {
+                // This edits the current mic profile like any other command, so it's left unsaved
+                // and an autosave will write it over the named profile's file.
+                let name = device.mic_profile.name().to_owned();
+                let mic_profile = MicProfileAdapter::from_json(name, &json)?;
+                device.apply_new_mic_profile(mic_profile).await?;
+            }
+            GoXLRCommand::SaveMicProfile() => device.save_mic_profile(false).await?,
+            GoXLRCommand::SaveMicProfileAs(profile_name) => {
+                let profile_directory = device.settings.get_mic_profile_directory().await;
+                device.mic_profile.write_profile(
+                    profile_name.clone(),
+                    &profile_directory,
+                    false,
+                    false,
+                )?;
+                device.saved_mic_profile = device.mic_profile.get_contents().ok();
+
+                // Save the new name in the settings
+                device
+                    .settings
+                    .set_device_mic_profile_name(device.serial(), profile_name.as_str())
+                    .await;
+
+                device.settings.save().await;
+            }
+            command => return Ok(Some(command)),
+        }
+        Ok(None)
+    })
+}
@@ -0,0 +1,100 @@
+// The router, its presets, and where the outputs (and the bleep) are sent.
+
+use crate::device::handlers::CommandFuture;
+use crate::device::Device;
+use crate::profile::ProfileAdapter;
+use goxlr_ipc::{GoXLRCommand, RoutingPreset};
+use goxlr_types::{InputDevice as BasicInputDevice, OutputDevice as BasicOutputDevice};
+use log::debug;
+use strum::IntoEnumIterator;
+
+pub fn perform<'a>(device: &'a mut Device<'_>, command: GoXLRCommand) -> CommandFuture<'a> {
+    Box::pin(async move {
+        match command {
+            GoXLRCommand::SetOutputDisplayName(output, name) => {
+                let name = name.map(|name| name.trim().to_string());
+                device
+                    .settings
+                    .set_device_output_name(device.serial(), output, name.filter(|n| !n.is_empty()))
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+            }
+            GoXLRCommand::SetSwearButtonOutputs(outputs) => {
+                device
+                    .settings
+                    .set_device_bleep_outputs(device.serial(), outputs)
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+                device.apply_bleep_routing()?;
+            }
+            GoXLRCommand::SetSolo(channel, soloed) => device.set_solo(channel, soloed)?,
+            GoXLRCommand::SetRouter(input, output, enabled) => {
+                debug!("Setting Routing: {:?} {:?} {}", input, output, enabled);
+                device.profile.set_routing(input, output, enabled);
+
+                // Apply the change..
+                device.apply_routing(input)?;
+            }
+            GoXLRCommand::SetRouterRow(input, outputs) => {
+                for output in BasicOutputDevice::iter() {
+                    device
+                        .profile
+                        .set_routing(input, output, outputs[output as usize]);
+                }
+                device.apply_routing(input)?;
+            }
+            GoXLRCommand::SetRouterColumn(output, inputs) => {
+                for input in BasicInputDevice::iter() {
+                    device
+                        .profile
+                        .set_routing(input, output, inputs[input as usize]);
+                    device.apply_routing(input)?;
+                }
+            }
+            GoXLRCommand::ResetRouterToProfile() => {
+                let profile_directory = device.settings.get_profile_directory().await;
+                let saved = ProfileAdapter::from_named(
+                    device.profile.name().to_owned(),
+                    vec![&profile_directory],
+                )?;
+
+                for input in BasicInputDevice::iter() {
+                    let router = saved.get_router(input);
+                    for output in BasicOutputDevice::iter() {
+                        device.profile.set_routing(input, output, router[output]);
+                    }
+                    device.apply_routing(input)?;
+                }
+            }
+            GoXLRCommand::SaveRoutingPreset(name) => {
+                let mut preset = RoutingPreset::default();
+                for input in BasicInputDevice::iter() {
+                    let router = device.profile.get_router(input);
+                    let outputs = BasicOutputDevice::iter().filter(|output| router[*output]);
+                    preset[input as usize] = outputs.collect();
+                }
+                device
+                    .settings
+                    .set_device_routing_preset(device.serial(), &name, Some(preset))
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+            }
+            GoXLRCommand::ApplyRoutingPreset(name) => {
+                device.apply_routing_preset(&name)?;
+            }
+            GoXLRCommand::DeleteRoutingPreset(name) => {
+                device
+                    .settings
+                    .set_device_routing_preset(device.serial(), &name, None)
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+            }
+            command => return Ok(Some(command)),
+        }
+        Ok(None)
+    })
+}
@@ -0,0 +1,56 @@
+// Sample banks, their colours and the trimming of samples.
+
+use crate::device::handlers::CommandFuture;
+use crate::device::Device;
+use goxlr_ipc::GoXLRCommand;
+
+pub fn perform<'a>(device: &'a mut Device<'_>, command: GoXLRCommand) -> CommandFuture<'a> {
+    Box::pin(async move {
+        match command {
+            // Sampler
+            GoXLRCommand::SetSampleTrim(bank, button, track, start_pct, end_pct) => {
+                device
+                    .profile
+                    .set_sample_trim(bank, button, track, start_pct, end_pct)?;
+            }
+            // Sample buttons go dark when they have nothing assigned, so refresh the colours.
+            GoXLRCommand::ClearSampleBank(bank) => {
+                device.profile.clear_sample_bank(bank);
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::SetSampleBankColours(bank, colour, colour_two) => {
+                device
+                    .profile
+                    .set_sample_bank_colours(bank, &colour, colour_two.as_ref())?;
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::ClearSampleBankColours(bank) => {
+                device.profile.clear_sample_bank_colours(bank);
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::CopySampleBank(from, to) => {
+                device.profile.copy_sample_bank(from, to);
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::SwapSampleBanks(first, second) => {
+                device.profile.swap_sample_banks(first, second);
+                device.load_colour_map()?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::SetSampleBankSwitch(behaviour) => {
+                device
+                    .settings
+                    .set_device_sample_bank_switch(device.serial(), behaviour)
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+            }
+            command => return Ok(Some(command)),
+        }
+        Ok(None)
+    })
+}
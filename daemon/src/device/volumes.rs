@@ -0,0 +1,141 @@
+// Faders, channel volumes and the mute buttons (including the cough button).
+
+use crate::device::handlers::CommandFuture;
+use crate::device::{get_link_group, Device};
+use goxlr_ipc::{db_to_volume, percent_to_volume, GoXLRCommand};
+
+pub fn perform<'a>(device: &'a mut Device<'_>, command: GoXLRCommand) -> CommandFuture<'a> {
+    Box::pin(async move {
+        match command {
+            GoXLRCommand::SetFader(fader, channel) => {
+                device.set_fader(fader, channel).await?;
+            }
+            GoXLRCommand::SetFaderPages(pages) => {
+                if device.fader_page.is_some_and(|page| page >= pages.len()) {
+                    device.fader_page = None;
+                }
+                device
+                    .settings
+                    .set_device_fader_pages(device.serial(), pages)
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+            }
+            GoXLRCommand::SetFaderPage(page) => {
+                device.set_fader_page(page).await?;
+            }
+            GoXLRCommand::NextFaderPage() => {
+                device.next_fader_page().await?;
+            }
+            GoXLRCommand::SetLocked(locked) => {
+                device.set_locked(locked)?;
+            }
+            GoXLRCommand::SetFaderMuteFunction(fader, behaviour) => {
+                if device.profile.get_mute_button_behaviour(fader) == behaviour {
+                    // Settings are the same..
+                    return Ok(None);
+                }
+
+                // Unmute the channel to prevent weirdness, then set new behaviour
+                device.unmute_if_muted(fader).await?;
+                device.profile.set_mute_button_behaviour(fader, behaviour);
+            }
+            GoXLRCommand::SetFaderMuted(fader, muted) => {
+                device.set_fader_muted(fader, muted).await?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::SetChannelMuted(channel, muted) => {
+                device.set_channel_muted(channel, muted).await?;
+                device.update_button_states()?;
+            }
+
+            GoXLRCommand::SetVolume(channel, volume) => {
+                device.set_channel_volume(channel, volume)?;
+            }
+            GoXLRCommand::SetVolumePercent(channel, percent) => {
+                device.set_channel_volume(channel, percent_to_volume(percent))?;
+            }
+            GoXLRCommand::SetVolumeDb(channel, db) => {
+                device.set_channel_volume(channel, db_to_volume(db))?;
+            }
+            GoXLRCommand::SetVolumeLimit(channel, limit) => {
+                device
+                    .settings
+                    .set_device_volume_limit(device.serial(), channel, limit)
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+
+                // Bring the current volume down if it's now above the limit
+                let volume = device.profile.get_channel_volume(channel);
+                if let Some(limit) = limit {
+                    if volume > limit {
+                        device.set_channel_volume(channel, limit)?;
+                    }
+                }
+            }
+            GoXLRCommand::LinkChannels(channels) => {
+                device
+                    .set_channels_linked(&get_link_group(&channels), true)
+                    .await;
+            }
+            GoXLRCommand::UnlinkChannel(channel) => {
+                device.set_channels_linked(&[channel], false).await;
+            }
+            GoXLRCommand::SetChannelDisplayName(channel, name) => {
+                let name = name.map(|name| name.trim().to_string());
+                device
+                    .settings
+                    .set_device_channel_name(
+                        device.serial(),
+                        channel,
+                        name.filter(|n| !n.is_empty()),
+                    )
+                    .await;
+                device.settings.save().await;
+                device.refresh_device_settings().await;
+            }
+            GoXLRCommand::SetSubMixVolume(channel, volume) => {
+                let volume = volume.min(device.get_volume_limit(channel));
+                device.profile.set_submix_volume(channel, volume)?;
+
+                // Linked channels share a volume, so move the main mix along with it.
+                if device.profile.is_submix_linked(channel) {
+                    device.goxlr.set_volume(channel, volume)?;
+                    device.profile.set_channel_volume(channel, volume);
+                }
+                device.apply_submix_volume(channel)?;
+            }
+            GoXLRCommand::SetSubMixLinked(channel, linked) => {
+                device.profile.set_submix_linked(channel, linked)?;
+                device.apply_submix_volume(channel)?;
+            }
+
+            GoXLRCommand::SetCoughMuteFunction(mute_function) => {
+                if device.profile.get_chat_mute_button_behaviour() == mute_function {
+                    // Settings are the same..
+                    return Ok(None);
+                }
+
+                // Unmute the channel to prevent weirdness, then set new behaviour
+                device.unmute_chat_if_muted().await?;
+                device.profile.set_chat_mute_button_behaviour(mute_function);
+            }
+            GoXLRCommand::SetCoughIsHold(is_hold) => {
+                device.unmute_chat_if_muted().await?;
+                device.profile.set_chat_mute_button_is_held(is_hold);
+            }
+            GoXLRCommand::SetCoughMode(mode) => {
+                device.unmute_chat_if_muted().await?;
+                device.profile.set_chat_mute_button_mode(mode);
+            }
+            GoXLRCommand::SetCoughMuted(muted) => {
+                device.set_cough_muted(muted).await?;
+                device.update_button_states()?;
+            }
+            GoXLRCommand::SetStreamPanic(enabled) => device.set_stream_panic(enabled)?,
+            command => return Ok(Some(command)),
+        }
+        Ok(None)
+    })
+}
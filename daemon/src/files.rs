@@ -8,16 +8,26 @@ This has been created as a separate mod primarily because profile.rs is big enou
 secondly because it's managing different types of files
  */
 
+use crate::backups;
+use crate::themes::THEME_EXTENSION;
+use crate::wav;
 use crate::SettingsHandle;
-use futures::executor::block_on;
+use anyhow::{anyhow, bail, Context, Result};
+use goxlr_ipc::{ProfileBackup, ProfileType, SampleFile, SamplePage, SampleQuery, MAX_SAMPLE_SIZE};
 use log::debug;
-use std::path::PathBuf;
+use std::fs::create_dir_all;
+use std::path::{Component, Path, PathBuf};
 use std::time::{Duration, Instant};
 
+// The formats the official app will play from the sampler.
+const SAMPLE_EXTENSIONS: [&str; 2] = ["wav", "mp3"];
+
 #[derive(Debug)]
 pub struct FileManager {
     profiles: FileList,
     mic_profiles: FileList,
+    themes: FileList,
+    samples: SampleIndex,
 }
 
 #[derive(Debug, Clone)]
@@ -35,40 +45,68 @@ impl Default for FileList {
     }
 }
 
+// Reading every sample's header is slow in large libraries, so the results are kept for a while.
+#[derive(Debug, Clone)]
+struct SampleIndex {
+    samples: Vec<SampleFile>,
+    timeout: Instant,
+}
+
+impl Default for SampleIndex {
+    fn default() -> Self {
+        Self {
+            timeout: Instant::now(),
+            samples: vec![],
+        }
+    }
+}
+
 impl FileManager {
     pub fn new() -> Self {
         Self {
             profiles: Default::default(),
             mic_profiles: Default::default(),
+            themes: Default::default(),
+            samples: Default::default(),
         }
     }
 
-    pub fn get_profiles(&mut self, settings: &SettingsHandle) -> Vec<String> {
+    pub async fn get_profiles(&mut self, settings: &SettingsHandle) -> Vec<String> {
         // There might be a nicer way to do this, which doesn't result in duplicating
         // code with different members..
         if self.profiles.timeout > Instant::now() {
             return self.profiles.names.clone();
         }
 
-        let path = block_on(settings.get_profile_directory());
+        let path = settings.get_profile_directory().await;
         let extension = "goxlr";
 
         self.profiles = self.get_file_list(path, extension);
         self.profiles.names.clone()
     }
 
-    pub fn get_mic_profiles(&mut self, settings: &SettingsHandle) -> Vec<String> {
+    pub async fn get_mic_profiles(&mut self, settings: &SettingsHandle) -> Vec<String> {
         if self.mic_profiles.timeout > Instant::now() {
             return self.mic_profiles.names.clone();
         }
 
-        let path = block_on(settings.get_mic_profile_directory());
+        let path = settings.get_mic_profile_directory().await;
         let extension = "goxlrMicProfile";
 
         self.mic_profiles = self.get_file_list(path, extension);
         self.mic_profiles.names.clone()
     }
 
+    pub async fn get_themes(&mut self, settings: &SettingsHandle) -> Vec<String> {
+        if self.themes.timeout > Instant::now() {
+            return self.themes.names.clone();
+        }
+
+        let path = settings.get_theme_directory().await;
+        self.themes = self.get_file_list(path, THEME_EXTENSION);
+        self.themes.names.clone()
+    }
+
     fn get_file_list(&self, path: PathBuf, extension: &str) -> FileList {
         // We need to refresh..
         FileList {
@@ -105,4 +143,266 @@ impl FileManager {
         );
         return vec![];
     }
+
+    /*
+    Samples aren't part of the status (as there could be a lot of them, with folders), so these
+    are only read when requested. They're cached like the other lists, but changes made through
+    the daemon are seen straight away.
+     */
+    pub async fn get_samples(&mut self, settings: &SettingsHandle) -> Result<Vec<SampleFile>> {
+        if self.samples.timeout > Instant::now() {
+            return Ok(self.samples.samples.clone());
+        }
+
+        let directory = settings.get_samples_directory().await;
+        let mut samples = vec![];
+        if directory.is_dir() {
+            find_samples(&directory, &directory, &mut samples)?;
+        }
+        samples.sort_by(|a, b| a.path.cmp(&b.path));
+
+        self.samples = SampleIndex {
+            samples: samples.clone(),
+            timeout: Instant::now() + Duration::from_secs(5),
+        };
+        Ok(samples)
+    }
+
+    pub async fn find_samples(
+        &mut self,
+        settings: &SettingsHandle,
+        query: &SampleQuery,
+    ) -> Result<SamplePage> {
+        let filter = query.filter.as_ref().map(|filter| filter.to_lowercase());
+        let extension = query
+            .extension
+            .as_ref()
+            .map(|extension| extension.trim_start_matches('.').to_lowercase());
+
+        let samples: Vec<SampleFile> = self
+            .get_samples(settings)
+            .await?
+            .into_iter()
+            .filter(|sample| {
+                filter
+                    .as_ref()
+                    .is_none_or(|filter| sample.path.to_lowercase().contains(filter))
+            })
+            .filter(|sample| extension.as_ref().is_none_or(|e| sample.format == *e))
+            .collect();
+
+        Ok(SamplePage {
+            total: samples.len(),
+            samples: samples
+                .into_iter()
+                .skip(query.offset)
+                .take(query.limit.unwrap_or(usize::MAX))
+                .collect(),
+        })
+    }
+
+    pub async fn add_sample(
+        &mut self,
+        settings: &SettingsHandle,
+        path: &str,
+        data: &[u8],
+    ) -> Result<()> {
+        if data.len() > MAX_SAMPLE_SIZE {
+            bail!("Samples can be at most {} bytes", MAX_SAMPLE_SIZE);
+        }
+
+        let path = get_sample_path(&settings.get_samples_directory().await, path)?;
+        check_sample_extension(&path)?;
+        if path.exists() {
+            bail!("Sample exists, will not overwrite");
+        }
+
+        create_parent(&path)?;
+        std::fs::write(&path, data).context("Could not write the sample")?;
+        self.samples.timeout = Instant::now();
+        Ok(())
+    }
+
+    pub async fn move_sample(
+        &mut self,
+        settings: &SettingsHandle,
+        from: &str,
+        to: &str,
+    ) -> Result<()> {
+        let directory = settings.get_samples_directory().await;
+        let from = get_sample_path(&directory, from)?;
+        let to = get_sample_path(&directory, to)?;
+
+        if !from.exists() {
+            bail!("Sample {} does not exist", from.to_string_lossy());
+        }
+        if from.is_file() {
+            check_sample_extension(&to)?;
+        }
+        if to.exists() {
+            bail!("{} exists, will not overwrite", to.to_string_lossy());
+        }
+
+        create_parent(&to)?;
+        std::fs::rename(&from, &to).context("Could not move the sample")?;
+        self.samples.timeout = Instant::now();
+        Ok(())
+    }
+
+    pub async fn get_sample_waveform(
+        &self,
+        settings: &SettingsHandle,
+        path: &str,
+        points: u16,
+    ) -> Result<Vec<f32>> {
+        let path = get_sample_path(&settings.get_samples_directory().await, path)?;
+        if get_sample_format(&path).as_deref() != Some("wav") {
+            bail!("Waveforms can only be read from WAV samples");
+        }
+        if !path.is_file() {
+            bail!("Sample {} does not exist", path.to_string_lossy());
+        }
+        wav::get_peaks(&path, points.into())
+    }
+
+    pub async fn delete_sample(&mut self, settings: &SettingsHandle, path: &str) -> Result<()> {
+        let path = get_sample_path(&settings.get_samples_directory().await, path)?;
+        if path.is_dir() {
+            // This fails if there's anything in it, so samples are never deleted by accident.
+            std::fs::remove_dir(&path).context("Could not delete the folder, is it empty?")?;
+        } else {
+            std::fs::remove_file(&path).context("Could not delete the sample")?;
+        }
+        self.samples.timeout = Instant::now();
+        Ok(())
+    }
+
+    pub async fn get_profile_backups(
+        &self,
+        settings: &SettingsHandle,
+        profile_type: ProfileType,
+        name: &str,
+    ) -> Result<Vec<ProfileBackup>> {
+        let path = get_profile_path(settings, profile_type, name).await?;
+        Ok(backups::list_backups(&path))
+    }
+
+    pub async fn restore_profile_backup(
+        &mut self,
+        settings: &SettingsHandle,
+        profile_type: ProfileType,
+        name: &str,
+        id: &str,
+    ) -> Result<()> {
+        let path = get_profile_path(settings, profile_type, name).await?;
+        backups::restore_backup(&path, id)?;
+
+        // The profile may have been deleted, so make sure it's listed again.
+        self.profiles.timeout = Instant::now();
+        self.mic_profiles.timeout = Instant::now();
+        Ok(())
+    }
+}
+
+async fn get_profile_path(
+    settings: &SettingsHandle,
+    profile_type: ProfileType,
+    name: &str,
+) -> Result<PathBuf> {
+    check_file_name("profile", name)?;
+    Ok(match profile_type {
+        ProfileType::Profile => settings
+            .get_profile_directory()
+            .await
+            .join(format!("{}.goxlr", name)),
+        ProfileType::MicProfile => settings
+            .get_mic_profile_directory()
+            .await
+            .join(format!("{}.goxlrMicProfile", name)),
+    })
+}
+
+// Names from clients become a single file name, so they can't be empty or contain a path.
+pub fn check_file_name(kind: &str, name: &str) -> Result<()> {
+    let mut components = Path::new(name).components();
+    let is_valid = matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\']);
+
+    if !is_valid {
+        bail!("Invalid {} name: {}", kind, name);
+    }
+    Ok(())
+}
+
+// Paths from clients must stay inside the samples directory.
+fn get_sample_path(directory: &Path, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    let is_valid = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+
+    if path.is_empty() || !is_valid {
+        bail!("Invalid sample path: {}", path);
+    }
+    Ok(directory.join(relative))
+}
+
+fn check_sample_extension(path: &Path) -> Result<()> {
+    if get_sample_format(path).is_none() {
+        bail!("Samples must be one of: {}", SAMPLE_EXTENSIONS.join(", "));
+    }
+    Ok(())
+}
+
+fn get_sample_format(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    SAMPLE_EXTENSIONS
+        .contains(&extension.as_str())
+        .then_some(extension)
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow!("Invalid sample path"))?;
+    create_dir_all(parent).context(format!(
+        "Could not create sample folder at {}",
+        parent.to_string_lossy()
+    ))
+}
+
+fn find_samples(root: &Path, directory: &Path, samples: &mut Vec<SampleFile>) -> Result<()> {
+    for entry in directory.read_dir()? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_samples(root, &path, samples)?;
+            continue;
+        }
+
+        let format = match get_sample_format(&path) {
+            Some(format) => format,
+            None => continue,
+        };
+
+        let relative = path.strip_prefix(root)?;
+        let wav_info = match format.as_str() {
+            "wav" => wav::read_header(&path).ok(),
+            _ => None,
+        };
+
+        samples.push(SampleFile {
+            path: relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            size: path.metadata()?.len(),
+            format,
+            channels: wav_info.as_ref().map(|info| info.channels),
+            sample_rate: wav_info.as_ref().map(|info| info.sample_rate),
+            duration_ms: wav_info.as_ref().map(|info| info.duration_ms()),
+        });
+    }
+    Ok(())
 }
@@ -0,0 +1,83 @@
+use crate::SettingsHandle;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Device events which can run user configured hooks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    MicMuted,
+    ProfileLoaded,
+    SamplePlayed,
+    DeviceConnected,
+    FxEnabled,
+}
+
+/**
+ Runs every executable configured for an event, with a JSON description of the event on stdin.
+ The hooks run in the background, so a slow or broken script can't hold up the device.
+*/
+pub async fn run_hooks(settings: &SettingsHandle, event: HookEvent, serial: &str, data: Value) {
+    let hooks = settings.get_hooks(event).await;
+    if hooks.is_empty() {
+        return;
+    }
+
+    let payload = json!({
+        "event": event,
+        "serial": serial,
+        "data": data,
+    })
+    .to_string();
+
+    for hook in hooks {
+        debug!("Running {:?} hook: {}", event, hook.to_string_lossy());
+        let child = Command::new(&hook)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn();
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(error) => {
+                warn!(
+                    "Unable to run {:?} hook {}: {}",
+                    event,
+                    hook.to_string_lossy(),
+                    error
+                );
+                continue;
+            }
+        };
+
+        let payload = payload.clone();
+        tokio::spawn(async move {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The hook doesn't have to read the payload, so a closed pipe is fine.
+                let _ = stdin.write_all(payload.as_bytes()).await;
+            }
+
+            match child.wait().await {
+                Ok(status) if !status.success() => {
+                    warn!(
+                        "{:?} hook {} exited with {}",
+                        event,
+                        hook.to_string_lossy(),
+                        status
+                    )
+                }
+                Err(error) => warn!(
+                    "{:?} hook {} failed: {}",
+                    event,
+                    hook.to_string_lossy(),
+                    error
+                ),
+                _ => {}
+            }
+        });
+    }
+}
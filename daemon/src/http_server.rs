@@ -1,35 +1,67 @@
 use actix::{
-    Actor, ActorContext, AsyncContext, ContextFutureSpawner, Handler, Message, StreamHandler,
-    WrapFuture,
+    Actor, ActorContext, AsyncContext, ContextFutureSpawner, Handler, Message, SpawnHandle,
+    StreamHandler, WrapFuture,
 };
 use actix_cors::Cors;
 use actix_plus_static_files::{build_hashmap_from_included_dir, include_dir, Dir, ResourceFiles};
 use actix_web::dev::ServerHandle;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH, RETRY_AFTER};
+use actix_web::middleware::Logger;
 use actix_web::web::Data;
-use actix_web::{get, post, web, App, HttpRequest, HttpResponse, HttpServer};
+use actix_web::{delete, get, post, web, App, HttpRequest, HttpResponse, HttpServer};
 use actix_web_actors::ws;
 use actix_web_actors::ws::CloseCode;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener};
 use std::ops::DerefMut;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
 
 use anyhow::{anyhow, Result};
+use futures::future::{ready, Either};
 use futures::lock::Mutex;
+use futures::TryFutureExt;
+use json_patch::patch;
 use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use strum::IntoEnumIterator;
 use tokio::sync::oneshot::Sender;
+use tokio::time::{timeout_at, Instant};
 
-use goxlr_ipc::{DaemonRequest, DaemonResponse, DaemonStatus, GoXLRCommand};
+use goxlr_ipc::{
+    ClientIdentity, DaemonRequest, DaemonResponse, DaemonStatus, GoXLRCommand, Lighting,
+    SampleQuery, MAX_SAMPLE_SIZE,
+};
 use goxlr_types::{
     ChannelName, CompressorAttackTime, CompressorRatio, CompressorReleaseTime, FaderName,
     GateTimes, InputDevice, MuteFunction, OutputDevice,
 };
 
-use crate::communication::handle_packet;
+use crate::communication::{
+    handle_packet, next_notification, next_patch, next_update, subscribe, subscribe_notifications,
+    to_daemon_error,
+};
 use crate::primary_worker::DeviceSender;
+use crate::rate_limit::RateLimiter;
+use crate::SettingsHandle;
 
 const WEB_CONTENT: Dir = include_dir!("./web-content/");
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_WAVEFORM_POINTS: u16 = 512;
+const MAX_WAVEFORM_POINTS: u16 = 4096;
+
+pub const HTTP_PORT: u16 = 14564;
 
 struct Websocket {
     sender: DeviceSender,
+    client: ClientIdentity,
+
+    // The tasks forwarding status patches and notifications, subscribing again replaces them.
+    subscription: Option<SpawnHandle>,
+    notifications: Option<SpawnHandle>,
 }
 
 impl Actor for Websocket {
@@ -56,11 +88,71 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Websocket {
             Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
             Ok(ws::Message::Text(text)) => {
                 match serde_json::from_slice::<DaemonRequest>(text.as_ref()) {
+                    Ok(DaemonRequest::Subscribe) => {
+                        let recipient = ctx.address().recipient();
+                        let mut usb_tx = self.sender.clone();
+                        let future = async move {
+                            let mut subscription = match subscribe(&mut usb_tx).await {
+                                Ok((status, receiver)) => {
+                                    recipient
+                                        .do_send(WsResponse(DaemonResponse::StatusPatch(status)));
+                                    Some(receiver)
+                                }
+                                Err(error) => {
+                                    recipient.do_send(WsResponse(DaemonResponse::Error(
+                                        to_daemon_error(error),
+                                    )));
+                                    return;
+                                }
+                            };
+
+                            while let Some(update) = next_update(&mut subscription).await {
+                                recipient.do_send(WsResponse(update));
+                            }
+                        };
+                        if let Some(handle) = self.subscription.take() {
+                            ctx.cancel_future(handle);
+                        }
+                        self.subscription = Some(ctx.spawn(future.into_actor(self)));
+                    }
+                    Ok(DaemonRequest::SubscribeNotifications) => {
+                        let recipient = ctx.address().recipient();
+                        let mut usb_tx = self.sender.clone();
+                        let future = async move {
+                            let mut notifications = match subscribe_notifications(&mut usb_tx).await
+                            {
+                                Ok(receiver) => {
+                                    recipient.do_send(WsResponse(DaemonResponse::Ok));
+                                    Some(receiver)
+                                }
+                                Err(error) => {
+                                    recipient.do_send(WsResponse(DaemonResponse::Error(
+                                        to_daemon_error(error),
+                                    )));
+                                    return;
+                                }
+                            };
+
+                            while let Some(notification) =
+                                next_notification(&mut notifications).await
+                            {
+                                recipient.do_send(WsResponse(notification));
+                            }
+                        };
+                        if let Some(handle) = self.notifications.take() {
+                            ctx.cancel_future(handle);
+                        }
+                        self.notifications = Some(ctx.spawn(future.into_actor(self)));
+                    }
+                    Ok(DaemonRequest::SetClientName(name)) => {
+                        self.client.name = Some(name);
+                    }
                     Ok(request) => {
                         let recipient = ctx.address().recipient();
                         let mut usb_tx = self.sender.clone();
+                        let mut client = self.client.clone();
                         let future = async move {
-                            let result = handle_packet(request, &mut usb_tx).await;
+                            let result = handle_packet(request, &mut usb_tx, &mut client).await;
                             match result {
                                 Ok(resp) => match resp {
                                     DaemonResponse::Ok => {}
@@ -71,10 +163,11 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Websocket {
                                         recipient
                                             .do_send(WsResponse(DaemonResponse::Status(status)));
                                     }
+                                    response => recipient.do_send(WsResponse(response)),
                                 },
                                 Err(error) => {
                                     recipient.do_send(WsResponse(DaemonResponse::Error(
-                                        error.to_string(),
+                                        to_daemon_error(error),
                                     )));
                                 }
                             }
@@ -98,9 +191,66 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Websocket {
     }
 }
 
-pub async fn launch_httpd(usb_tx: DeviceSender, handle_tx: Sender<ServerHandle>) -> Result<()> {
+struct UiDirectory(PathBuf);
+
+// An address the HTTP server can listen on, set in `http_addresses` in the daemon's settings.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpAddress {
+    pub address: IpAddr,
+    pub enabled: bool,
+}
+
+pub fn default_http_addresses() -> Vec<HttpAddress> {
+    vec![
+        HttpAddress {
+            address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            enabled: true,
+        },
+        HttpAddress {
+            address: IpAddr::V6(Ipv6Addr::LOCALHOST),
+            enabled: true,
+        },
+    ]
+}
+
+pub struct HttpOptions {
+    pub addresses: Vec<IpAddr>,
+
+    // Serves the Web UI from here, rather than the copy built into the daemon
+    pub ui_path: Option<PathBuf>,
+
+    // API requests a second for each client on the network, 0 for no limit
+    pub rate_limit: u32,
+}
+
+pub async fn launch_httpd(
+    usb_tx: DeviceSender,
+    settings: SettingsHandle,
+    options: HttpOptions,
+    handle_tx: Sender<(ServerHandle, Vec<SocketAddr>)>,
+) -> Result<()> {
+    let HttpOptions {
+        addresses,
+        ui_path,
+        rate_limit,
+    } = options;
+    let rate_limiter = RateLimiter::new(rate_limit);
+
+    // An address which can't be used (such as ::1 with IPv6 disabled) shouldn't stop the others.
+    let mut listeners = vec![];
+    for address in addresses {
+        let address = SocketAddr::new(address, HTTP_PORT);
+        match TcpListener::bind(address) {
+            Ok(listener) => listeners.push((address, listener)),
+            Err(error) => warn!("Unable to listen for HTTP on {}: {}", address, error),
+        }
+    }
+    if listeners.is_empty() {
+        return Err(anyhow!("The HTTP server couldn't listen on any address"));
+    }
+
     let server = HttpServer::new(move || {
-        let static_files = build_hashmap_from_included_dir(&WEB_CONTENT);
+        let rate_limiter = rate_limiter.clone();
         let cors = Cors::default()
             .allowed_origin("http://127.0.0.1")
             .allowed_origin("http://localhost")
@@ -112,8 +262,26 @@ pub async fn launch_httpd(usb_tx: DeviceSender, handle_tx: Sender<ServerHandle>)
             .allow_any_header()
             .max_age(3600);
         App::new()
+            .wrap_fn(move |req, srv| {
+                let is_api = req.path().starts_with("/api/");
+                let address = req.peer_addr().map(|address| address.ip());
+                if is_api && address.is_some_and(|address| !rate_limiter.allow(address)) {
+                    let response = HttpResponse::TooManyRequests()
+                        .insert_header((RETRY_AFTER, "1"))
+                        .finish();
+                    return Either::Right(ready(Ok(req
+                        .into_response(response)
+                        .map_into_right_body())));
+                }
+                Either::Left(srv.call(req).map_ok(ServiceResponse::map_into_left_body))
+            })
             .wrap(cors)
+            .wrap(Logger::new(
+                "client=%a request=\"%r\" status=%s bytes=%b time_ms=%D",
+            ))
             .app_data(Data::new(Mutex::new(usb_tx.clone())))
+            .app_data(Data::new(settings.clone()))
+            .app_data(web::PayloadConfig::new(MAX_SAMPLE_SIZE))
             .service(get_devices)
             .service(set_volume)
             .service(get_devices)
@@ -121,6 +289,7 @@ pub async fn launch_httpd(usb_tx: DeviceSender, handle_tx: Sender<ServerHandle>)
             .service(set_bleep_volume)
             .service(set_fader_channel)
             .service(set_fader_mute_function)
+            .service(set_channel_muted)
             .service(set_routing)
             .service(set_profile)
             .service(set_cough_behaviour)
@@ -133,12 +302,37 @@ pub async fn launch_httpd(usb_tx: DeviceSender, handle_tx: Sender<ServerHandle>)
             .service(set_noise_gate_attenuation)
             .service(set_noise_gate_attack)
             .service(set_noise_gate_release)
+            .service(get_button_state)
+            .service(apply_lighting_theme)
+            .service(export_mic_profile)
+            .service(import_mic_profile)
+            .service(get_samples)
+            .service(find_samples)
+            .service(add_sample)
+            .service(move_sample)
+            .service(delete_sample)
+            .service(get_sample_waveform)
             .service(websocket)
-            .service(ResourceFiles::new("/", static_files))
-    })
-    .bind(("127.0.0.1", 14564))?
-    .run();
-    let _ = handle_tx.send(server.handle());
+            .service(get_custom_ui_file)
+            .configure(|config| match &ui_path {
+                Some(path) => {
+                    config
+                        .app_data(Data::new(UiDirectory(path.clone())))
+                        .route("/{path:.*}", web::get().to(get_ui_file));
+                }
+                None => {
+                    let static_files = build_hashmap_from_included_dir(&WEB_CONTENT);
+                    config.service(ResourceFiles::new("/", static_files));
+                }
+            })
+    });
+
+    let bound = listeners.iter().map(|(address, _)| *address).collect();
+    let server = listeners
+        .into_iter()
+        .try_fold(server, |server, (_, listener)| server.listen(listener))?
+        .run();
+    let _ = handle_tx.send((server.handle(), bound));
     server.await?;
     Ok(())
 }
@@ -152,6 +346,12 @@ async fn websocket(
     ws::start(
         Websocket {
             sender: usb_mutex.lock().await.clone(),
+            client: ClientIdentity {
+                address: req.peer_addr().map(|address| address.ip().to_string()),
+                ..Default::default()
+            },
+            subscription: None,
+            notifications: None,
         },
         &req,
         stream,
@@ -166,6 +366,334 @@ async fn get_devices(usb_mutex: Data<Mutex<DeviceSender>>) -> HttpResponse {
     HttpResponse::InternalServerError().finish()
 }
 
+/**
+ A cut down view of a single device, holding just the profile names, fader assignments and which
+ buttons are lit. This is intended for things like Stream Deck plugins, which only need enough to
+ render their icons.
+
+ Responses carry an ETag, if a request is made with a matching If-None-Match header the request
+ will be held until the state changes (or LONG_POLL_TIMEOUT passes, returning 304 Not Modified).
+*/
+#[get("/api/button-state/{serial}")]
+async fn get_button_state(
+    path: web::Path<String>,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let serial = path.into_inner();
+    let known_tag = req
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    // Clone the sender, so we're not holding the lock while waiting for changes.
+    let mut usb_tx = usb_mutex.lock().await.clone();
+    let deadline = Instant::now() + LONG_POLL_TIMEOUT;
+
+    let mut status = Value::Null;
+    let mut sequence = 0;
+    let mut subscription = None;
+
+    loop {
+        // (Re)subscribe if we've not got a status yet, or have missed a patch.
+        if subscription.is_none() {
+            match subscribe(&mut usb_tx).await {
+                Ok((initial, receiver)) => {
+                    status = Value::Null;
+                    if patch(&mut status, &initial.patch).is_err() {
+                        return HttpResponse::InternalServerError().finish();
+                    }
+                    sequence = initial.sequence;
+                    subscription = Some(receiver);
+                }
+                Err(error) => {
+                    warn!("Unable to subscribe to status: {}", error);
+                    return HttpResponse::InternalServerError().finish();
+                }
+            }
+        }
+
+        let state = match create_button_state(&status, &serial) {
+            Some(state) => state,
+            None => return HttpResponse::NotFound().finish(),
+        };
+        let tag = get_entity_tag(&state);
+
+        if known_tag.as_deref() != Some(tag.as_str()) {
+            return HttpResponse::Ok()
+                .insert_header((ETAG, tag))
+                .insert_header((CACHE_CONTROL, "no-cache"))
+                .json(state);
+        }
+
+        match timeout_at(deadline, next_patch(&mut subscription)).await {
+            Err(_) => {
+                return HttpResponse::NotModified()
+                    .insert_header((ETAG, tag))
+                    .finish()
+            }
+            Ok(None) => subscription = None,
+            Ok(Some(next)) => {
+                if next.sequence != sequence + 1 || patch(&mut status, &next.patch).is_err() {
+                    subscription = None;
+                    continue;
+                }
+                sequence = next.sequence;
+            }
+        }
+    }
+}
+
+fn create_button_state(status: &Value, serial: &str) -> Option<Value> {
+    let mixer = status.get("mixers")?.get(serial)?;
+    Some(json!({
+        "profile_name": mixer["profile_name"],
+        "mic_profile_name": mixer["mic_profile_name"],
+        "fader_status": mixer["fader_status"],
+        "button_states": mixer["button_states"],
+    }))
+}
+
+// serde_json sorts object keys, so the same state will always produce the same tag.
+fn get_entity_tag(state: &Value) -> String {
+    let mut hasher = DefaultHasher::new();
+    state.to_string().hash(&mut hasher);
+    format!("\"{:x}\"", hasher.finish())
+}
+
+// Unlike the rest of the API, the theme is sent as JSON in the request body.
+#[post("/api/apply-lighting-theme/{serial}")]
+async fn apply_lighting_theme(
+    path: web::Path<String>,
+    theme: web::Json<Lighting>,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    let serial = path.into_inner();
+    send_cmd(
+        usb_mutex,
+        serial,
+        GoXLRCommand::ApplyLightingTheme(theme.into_inner()),
+    )
+    .await
+}
+
+#[get("/api/mic-profile/{serial}")]
+async fn export_mic_profile(
+    path: web::Path<String>,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    let request = DaemonRequest::ExportMicProfile(path.into_inner());
+
+    let mut guard = usb_mutex.lock().await;
+    match handle_packet(request, guard.deref_mut(), &mut api_client()).await {
+        Ok(DaemonResponse::MicProfileJson(json)) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(json),
+        _ => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[post("/api/mic-profile/{serial}")]
+async fn import_mic_profile(
+    path: web::Path<String>,
+    json: String,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    let serial = path.into_inner();
+    send_cmd(usb_mutex, serial, GoXLRCommand::ImportMicProfile(json)).await
+}
+
+/**
+ The sample library. Paths are relative to the samples directory and may contain folders, the
+ file itself is sent as the request body when adding a sample. Failures return the DaemonError
+ as JSON, so the UI can explain what went wrong.
+*/
+#[get("/api/samples")]
+async fn get_samples(usb_mutex: Data<Mutex<DeviceSender>>) -> HttpResponse {
+    send_request(usb_mutex, DaemonRequest::GetSamples).await
+}
+
+// Filtered and paged, with the query's fields as parameters (?filter=kick&offset=0&limit=50).
+#[get("/api/samples/search")]
+async fn find_samples(
+    query: web::Query<SampleQuery>,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    send_request(usb_mutex, DaemonRequest::FindSamples(query.into_inner())).await
+}
+
+#[post("/api/samples/{path:.*}")]
+async fn add_sample(
+    path: web::Path<String>,
+    data: web::Bytes,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    let request = DaemonRequest::AddSample(path.into_inner(), data.to_vec());
+    send_request(usb_mutex, request).await
+}
+
+#[delete("/api/samples/{path:.*}")]
+async fn delete_sample(
+    path: web::Path<String>,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    send_request(usb_mutex, DaemonRequest::DeleteSample(path.into_inner())).await
+}
+
+#[derive(Deserialize)]
+struct MoveSample {
+    from: String,
+    to: String,
+}
+
+#[post("/api/move-sample")]
+async fn move_sample(
+    request: web::Json<MoveSample>,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    let MoveSample { from, to } = request.into_inner();
+    send_request(usb_mutex, DaemonRequest::MoveSample(from, to)).await
+}
+
+#[derive(Deserialize)]
+struct WaveformQuery {
+    points: Option<u16>,
+}
+
+// The peak level of each section of a WAV sample, for drawing it when setting trim points.
+#[get("/api/sample-waveform/{path:.*}")]
+async fn get_sample_waveform(
+    path: web::Path<String>,
+    query: web::Query<WaveformQuery>,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    let points = query.points.unwrap_or(DEFAULT_WAVEFORM_POINTS);
+    let points = points.clamp(1, MAX_WAVEFORM_POINTS);
+    let request = DaemonRequest::GetSampleWaveform(path.into_inner(), points);
+    send_request(usb_mutex, request).await
+}
+
+// Files from the custom UI directory, so a replacement UI (or a theme for the built in one) can
+// be served alongside the built in UI.
+#[get("/custom/{path:.*}")]
+async fn get_custom_ui_file(
+    path: web::Path<String>,
+    req: HttpRequest,
+    settings: Data<SettingsHandle>,
+) -> HttpResponse {
+    let directory = match settings.get_custom_ui_directory().await {
+        Some(directory) => directory,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    serve_file(&directory, path.into_inner(), &req).await
+}
+
+// The Web UI from the directory given with --ui-path, in place of the built in copy.
+async fn get_ui_file(
+    path: web::Path<String>,
+    req: HttpRequest,
+    ui_directory: Data<UiDirectory>,
+) -> HttpResponse {
+    serve_file(&ui_directory.0, path.into_inner(), &req).await
+}
+
+/**
+ Serves a file from a directory, with a directory's index.html served for its own path. Files on
+ disk can be edited at any time, so browsers are told to check the ETag rather than caching them.
+*/
+async fn serve_file(directory: &Path, mut path: String, req: &HttpRequest) -> HttpResponse {
+    if path.is_empty() || path.ends_with('/') {
+        path.push_str("index.html");
+    }
+    let relative = Path::new(&path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let file = directory.join(relative);
+    let metadata = match file.metadata() {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return HttpResponse::NotFound().finish(),
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|time| time.as_millis())
+        .unwrap_or_default();
+    let tag = format!("\"{:x}-{:x}\"", metadata.len(), modified);
+
+    let known_tag = req
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    if known_tag == Some(tag.as_str()) {
+        return HttpResponse::NotModified()
+            .insert_header((ETAG, tag))
+            .finish();
+    }
+
+    match tokio::fs::read(&file).await {
+        Ok(data) => HttpResponse::Ok()
+            .content_type(get_content_type(&file))
+            .insert_header((ETAG, tag))
+            .insert_header((CACHE_CONTROL, "no-cache"))
+            .body(data),
+        Err(error) => {
+            warn!("Unable to read {}: {}", file.to_string_lossy(), error);
+            HttpResponse::NotFound().finish()
+        }
+    }
+}
+
+fn get_content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") | Some("mjs") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        _ => "application/octet-stream",
+    }
+}
+
+// The REST endpoints don't know who's calling them, unlike the websocket.
+fn api_client() -> ClientIdentity {
+    ClientIdentity {
+        name: Some("HTTP API".to_string()),
+        ..Default::default()
+    }
+}
+
+async fn send_request(usb_tx: Data<Mutex<DeviceSender>>, request: DaemonRequest) -> HttpResponse {
+    let mut guard = usb_tx.lock().await;
+    match handle_packet(request, guard.deref_mut(), &mut api_client()).await {
+        Ok(DaemonResponse::Ok) => HttpResponse::Ok().finish(),
+        Ok(DaemonResponse::Samples(samples)) => HttpResponse::Ok().json(samples),
+        Ok(DaemonResponse::SamplePage(page)) => HttpResponse::Ok().json(page),
+        Ok(DaemonResponse::SampleWaveform(peaks)) => HttpResponse::Ok().json(peaks),
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(error) => HttpResponse::InternalServerError().json(to_daemon_error(error)),
+    }
+}
+
 /**
  API / IPC related stuff, I know that you shouldn't really send parameters as URL segments,
  however, I'm using it to get some quick and easy type coercion, rather than having to create
@@ -222,6 +750,23 @@ async fn set_fader_mute_function(
     HttpResponse::InternalServerError().finish()
 }
 
+#[post("/api/set-channel-muted/{serial}/{channel}/{muted}")]
+async fn set_channel_muted(
+    path: web::Path<(String, u8, bool)>,
+    usb_mutex: Data<Mutex<DeviceSender>>,
+) -> HttpResponse {
+    let (serial, channel, muted) = path.into_inner();
+    if let Some(channel) = ChannelName::iter().nth(channel.into()) {
+        return send_cmd(
+            usb_mutex,
+            serial,
+            GoXLRCommand::SetChannelMuted(channel, muted),
+        )
+        .await;
+    }
+    HttpResponse::InternalServerError().finish()
+}
+
 #[post("/api/set-routing/{serial}/{input}/{output}/{value}")]
 async fn set_routing(
     path: web::Path<(String, u8, u8, bool)>,
@@ -414,7 +959,7 @@ async fn send_cmd(
 
     // Because most request are going to either send a 200 Ok, or 500 Internal Server error,
     // we might as well intercept any errors here, and straight up return the status.
-    let result = handle_packet(request, sender).await;
+    let result = handle_packet(request, sender, &mut api_client()).await;
     if result.is_err() {
         warn!("Error Handling Request, {:?}", result.as_ref().err());
         return HttpResponse::InternalServerError().finish();
@@ -430,7 +975,7 @@ async fn get_status(usb_tx: Data<Mutex<DeviceSender>>) -> Result<DaemonStatus> {
 
     let request = DaemonRequest::GetStatus;
 
-    let result = handle_packet(request, sender).await?;
+    let result = handle_packet(request, sender, &mut api_client()).await?;
     return match result {
         DaemonResponse::Status(status) => Ok(status),
         _ => Err(anyhow!("Unexpected Daemon Status Result: {:?}", result)),
@@ -1,36 +1,70 @@
 mod audio;
+mod audit;
+mod backups;
 mod cli;
+mod command_queue;
 mod communication;
 mod device;
 mod files;
+mod hooks;
 mod http_server;
+mod mdns;
 mod mic_profile;
+mod mute;
+mod obs;
 mod primary_worker;
 mod profile;
+mod profile_rules;
+mod rate_limit;
+mod scripts;
 mod settings;
 mod shutdown;
+mod sleep;
+mod snapshot;
+mod status;
+mod themes;
+mod updates;
+mod wav;
 
-use crate::cli::{Cli, LevelFilter};
+use crate::cli::{Cli, LevelFilter, MockDeviceType};
 use crate::files::FileManager;
-use crate::http_server::launch_httpd;
-use crate::primary_worker::handle_changes;
+use crate::http_server::{launch_httpd, HttpOptions, HTTP_PORT};
+use crate::mdns::Advertisement;
+use crate::obs::{run_obs, ObsHandle};
+use crate::primary_worker::{handle_changes, WorkerOptions};
+use crate::scripts::{run_scripts, ScriptHandle};
 use crate::settings::SettingsHandle;
 use crate::shutdown::Shutdown;
+use crate::updates::{run_update_checker, UpdateHandle};
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use communication::listen_for_connections;
 use goxlr_ipc::Socket;
-use goxlr_ipc::{DaemonRequest, DaemonResponse};
-use log::{info, warn};
+use goxlr_ipc::{DaemonRequest, DaemonResponse, HttpServerStatus};
+use goxlr_usb::capture::UsbCapture;
+use goxlr_usb::device::GoXlrDevice;
+use goxlr_usb::goxlr::RetryPolicy;
+use goxlr_usb::mock::{parse_mock_script, MockGoXLR};
+use goxlr_usb::trace::{SharedTrace, TraceRecorder, UsbTrace};
+use log::{error, info, warn};
 use simplelog::{ColorChoice, CombinedLogger, Config, TermLogger, TerminalMode};
 use std::fs;
 use std::fs::remove_file;
+use std::net::IpAddr;
 use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
 use std::path::Path;
+use std::time::Duration;
+use std::{env, process};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::mpsc;
 use tokio::{join, signal};
 
+const SOCKET_PATH: &str = "/tmp/goxlr.socket";
+
+// Accepts newline delimited JSON, for clients which can't easily handle length prefixed frames.
+const JSON_SOCKET_PATH: &str = "/tmp/goxlr-json.socket";
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args: Cli = Cli::parse();
@@ -50,46 +84,214 @@ async fn main() -> Result<()> {
     )])
     .context("Could not configure the logger")?;
 
+    let trace = SharedTrace::default();
+    let mut mock_device: Option<Box<dyn GoXlrDevice>> = None;
+    if let Some(device_type) = args.mock_device {
+        let script = match &args.mock_script {
+            Some(path) => parse_mock_script(&fs::read_to_string(path).context(format!(
+                "Could not read mock script at {}",
+                path.to_string_lossy()
+            ))?)?,
+            None => vec![],
+        };
+        let mock = MockGoXLR::new(device_type == MockDeviceType::Mini, script);
+        mock_device = match args.record_trace.is_some() || args.expect_trace.is_some() {
+            true => Some(Box::new(TraceRecorder::new(mock, trace.clone()))),
+            false => Some(Box::new(mock)),
+        };
+    }
+
+    if let Some(path) = &args.ui_path {
+        if !path.join("index.html").is_file() {
+            return Err(anyhow!(
+                "No index.html found in the UI path {}",
+                path.to_string_lossy()
+            ));
+        }
+    }
+
+    let usb_capture = match &args.capture_usb {
+        Some(path) => Some(UsbCapture::create(path).context("Could not create the USB capture")?),
+        None => None,
+    };
+
     let settings = SettingsHandle::load(args.config).await?;
-    let listener = create_listener("/tmp/goxlr.socket").await?;
+    let listener = create_listener(SOCKET_PATH).await?;
+    let json_listener = create_json_listener(JSON_SOCKET_PATH)?;
 
-    let mut perms = fs::metadata("/tmp/goxlr.socket")?.permissions();
-    perms.set_mode(0o777);
-    fs::set_permissions("/tmp/goxlr.socket", perms)?;
+    for path in [SOCKET_PATH, JSON_SOCKET_PATH] {
+        let mut perms = fs::metadata(path)?.permissions();
+        perms.set_mode(0o777);
+        fs::set_permissions(path, perms)?;
+    }
 
     let mut shutdown = Shutdown::new();
     let file_manager = FileManager::new();
     let (usb_tx, usb_rx) = mpsc::channel(32);
+    let (obs, obs_rx) = ObsHandle::new();
+    let obs_handle = tokio::spawn(run_obs(
+        obs.clone(),
+        obs_rx,
+        settings.clone(),
+        usb_tx.clone(),
+        shutdown.clone(),
+    ));
+    let mut http_server = None;
+    let mut http_addresses = vec![];
+    if !args.disable_http {
+        let addresses = match args.http_address.is_empty() {
+            true => settings.get_http_addresses().await,
+            false => args.http_address.clone(),
+        };
+        let options = HttpOptions {
+            addresses,
+            ui_path: args.ui_path.clone(),
+            rate_limit: args.http_rate_limit,
+        };
+        let (httpd_tx, httpd_rx) = tokio::sync::oneshot::channel();
+        let httpd = launch_httpd(usb_tx.clone(), settings.clone(), options, httpd_tx);
+        tokio::spawn(async move {
+            if let Err(error) = httpd.await {
+                error!("{:#}", error);
+            }
+        });
+        let (handle, addresses) = httpd_rx.await.context("The HTTP server failed to start")?;
+        http_server = Some(handle);
+        http_addresses = addresses;
+    }
+
+    let (updates, updates_rx) = UpdateHandle::new();
+    let updates_handle = tokio::spawn(run_update_checker(
+        updates.clone(),
+        updates_rx,
+        settings.clone(),
+        shutdown.clone(),
+    ));
+    let (scripts, scripts_rx) = ScriptHandle::new();
+    let scripts_handle = tokio::spawn(run_scripts(
+        scripts.clone(),
+        scripts_rx,
+        settings.clone(),
+        usb_tx.clone(),
+        shutdown.clone(),
+    ));
     let usb_handle = tokio::spawn(handle_changes(
         usb_rx,
         shutdown.clone(),
-        settings,
+        settings.clone(),
         file_manager,
+        obs,
+        updates,
+        scripts,
+        WorkerOptions {
+            retry_policy: RetryPolicy {
+                timeout: Duration::from_millis(args.usb_timeout),
+                retries: args.usb_retries,
+                ..Default::default()
+            },
+            poll_interval: Duration::from_millis(args.poll_interval),
+            http: HttpServerStatus {
+                enabled: !args.disable_http,
+                address: http_addresses.first().map(|address| address.to_string()),
+                addresses: http_addresses.iter().map(|a| a.to_string()).collect(),
+            },
+            mock_device,
+            usb_capture,
+            locked: args.locked,
+        },
     ));
     let communications_handle = tokio::spawn(listen_for_connections(
         listener,
+        json_listener,
         usb_tx.clone(),
         shutdown.clone(),
     ));
 
-    let (httpd_tx, httpd_rx) = tokio::sync::oneshot::channel();
-    tokio::spawn(launch_httpd(usb_tx.clone(), httpd_tx));
-    let http_server = httpd_rx.await?;
+    let mut advertisement = None;
+    let network_addresses: Vec<IpAddr> = http_addresses
+        .iter()
+        .map(|address| address.ip())
+        .filter(|address| !address.is_loopback())
+        .collect();
+    if !args.disable_mdns && !network_addresses.is_empty() {
+        match Advertisement::new(&network_addresses, HTTP_PORT) {
+            Ok(started) => advertisement = Some(started),
+            Err(error) => warn!("{:#}", error),
+        }
+    }
 
     await_ctrl_c(shutdown.clone()).await;
 
     info!("Shutting down daemon");
-    let _ = join!(usb_handle, communications_handle, http_server.stop(true));
+    if let Some(advertisement) = advertisement {
+        advertisement.stop();
+    }
+    let _ = join!(
+        usb_handle,
+        communications_handle,
+        obs_handle,
+        updates_handle,
+        scripts_handle,
+        async {
+            if let Some(http_server) = http_server {
+                http_server.stop(true).await;
+            }
+        }
+    );
+
+    // Anything saved in the last moments before shutting down is still waiting to be written.
+    if let Err(error) = settings.flush().await {
+        error!("{:#}", error);
+    }
 
-    info!("Removing Socket");
-    remove_file("/tmp/goxlr.socket")?;
+    if let Some(path) = &args.record_trace {
+        let trace = trace.lock().unwrap().to_string();
+        fs::write(path, trace).context("Could not write the USB trace")?;
+    }
+    if let Some(path) = &args.expect_trace {
+        check_trace(path, &trace.lock().unwrap())?;
+    }
+
+    info!("Removing Sockets");
+    remove_file(SOCKET_PATH)?;
+    remove_file(JSON_SOCKET_PATH)?;
     shutdown.recv().await;
+
+    if shutdown.restart_requested() {
+        info!("Restarting daemon");
+        let error = process::Command::new(env::current_exe()?)
+            .args(env::args_os().skip(1))
+            .exec();
+        return Err(error).context("Could not restart the daemon");
+    }
     Ok(())
 }
 
-async fn await_ctrl_c(shutdown: Shutdown) {
-    if signal::ctrl_c().await.is_ok() {
-        shutdown.trigger();
+fn check_trace(path: &Path, actual: &UsbTrace) -> Result<()> {
+    let expected = fs::read_to_string(path).context("Could not read the expected USB trace")?;
+    let expected = UsbTrace::parse(&expected).map_err(|e| anyhow!(e))?;
+    if let Some(difference) = expected.first_difference(actual) {
+        let describe = |entry: Option<String>| entry.unwrap_or_else(|| "(end of trace)".into());
+        return Err(anyhow!(
+            "USB writes differ from the trace at write {}, expected {} but got {}",
+            difference.index + 1,
+            describe(difference.expected.map(|entry| entry.to_string())),
+            describe(difference.actual.map(|entry| entry.to_string())),
+        ));
+    }
+    info!("USB writes match the trace");
+    Ok(())
+}
+
+// Stops on ctrl-c, or when something else (such as a restart request) triggers the shutdown.
+async fn await_ctrl_c(mut shutdown: Shutdown) {
+    tokio::select! {
+        result = signal::ctrl_c() => {
+            if result.is_ok() {
+                shutdown.trigger();
+            }
+        }
+        () = shutdown.recv() => {}
     }
 }
 
@@ -117,6 +319,17 @@ async fn create_listener<P: AsRef<Path>>(path: P) -> Result<UnixListener> {
     Err(error)
 }
 
+fn create_json_listener<P: AsRef<Path>>(path: P) -> Result<UnixListener> {
+    // By this point we know no other daemon is running, so any existing socket is stale.
+    let path = path.as_ref();
+    if path.exists() {
+        warn!("Removing unused socket file {}", path.to_string_lossy());
+        let _ = remove_file(path);
+    }
+
+    UnixListener::bind(path).context("Could not bind the JSON Unix socket")
+}
+
 async fn is_already_running(path: &Path) -> bool {
     let stream = match UnixStream::connect(path).await {
         Ok(stream) => stream,
@@ -0,0 +1,87 @@
+/*
+Advertises the HTTP API on the local network as _goxlr._tcp, so apps on phones and tablets can
+find the daemon without its address being typed in. This is only done when the HTTP server
+listens on something other than loopback, as nothing else on the network could reach it.
+
+The TXT record has the daemon's version, its IPC protocol version, and the websocket's path.
+ */
+
+use anyhow::{Context, Result};
+use goxlr_ipc::PROTOCOL_VERSION;
+use log::{debug, warn};
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use std::ffi::CStr;
+use std::net::IpAddr;
+
+const SERVICE_TYPE: &str = "_goxlr._tcp.local.";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Advertisement {
+    pub fn new(addresses: &[IpAddr], port: u16) -> Result<Self> {
+        let host = get_host_name();
+        let properties = [
+            ("version", VERSION.to_string()),
+            ("protocol", PROTOCOL_VERSION.to_string()),
+            ("websocket", "/api/websocket".to_string()),
+        ];
+
+        // Listening everywhere, so every address the machine has (and gains) is advertised.
+        let everywhere = addresses.iter().any(|address| address.is_unspecified());
+        let ip = match everywhere {
+            true => String::new(),
+            false => addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+        let mut service = ServiceInfo::new(
+            SERVICE_TYPE,
+            &format!("GoXLR Utility on {}", host),
+            &format!("{}.local.", host),
+            ip,
+            port,
+            &properties[..],
+        )
+        .context("Could not create the mDNS service")?;
+        if everywhere {
+            service = service.enable_addr_auto();
+        }
+
+        let fullname = service.get_fullname().to_string();
+        let daemon = ServiceDaemon::new().context("Could not start mDNS")?;
+        daemon
+            .register(service)
+            .context("Could not advertise the HTTP server over mDNS")?;
+        debug!("Advertising {} over mDNS", fullname);
+
+        Ok(Self { daemon, fullname })
+    }
+
+    pub fn stop(self) {
+        if let Err(error) = self.daemon.unregister(&self.fullname) {
+            warn!("Unable to stop the mDNS advertisement: {}", error);
+        }
+        let _ = self.daemon.shutdown();
+    }
+}
+
+fn get_host_name() -> String {
+    let mut buffer = [0u8; 256];
+
+    // SAFETY: gethostname writes at most the buffer's length, and the last byte is kept as a nul.
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len() - 1) };
+    if result != 0 {
+        return "goxlr".to_string();
+    }
+    CStr::from_bytes_until_nul(&buffer)
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "goxlr".to_string())
+}
@@ -1,15 +1,16 @@
-use crate::profile::ProfileAdapter;
-use crate::SettingsHandle;
+use crate::backups::{backup_file, backup_file_for_autosave};
+use crate::profile::{invalid_profile, ProfileAdapter};
 use anyhow::{anyhow, Context, Result};
 use byteorder::{ByteOrder, LittleEndian};
-use futures::executor::block_on;
-use goxlr_ipc::{Compressor, Equaliser, EqualiserMini, NoiseGate};
+use goxlr_ipc::{Compressor, DaemonError, Equaliser, EqualiserMini, MicEditorModes, NoiseGate};
 use goxlr_profile_loader::mic_profile::MicProfileSettings;
+use goxlr_profile_loader::microphone::equalizer::Equalizer;
+use goxlr_profile_loader::microphone::equalizer_mini::EqualizerMini;
 use goxlr_types::{
     CompressorAttackTime, CompressorRatio, CompressorReleaseTime, EffectKey, EqFrequencies,
-    GateTimes, MicrophoneParamKey, MicrophoneType, MiniEqFrequencies,
+    EqPreset, GateTimes, MicrophoneParamKey, MicrophoneType, MiniEqFrequencies,
 };
-use log::error;
+use log::{debug, error};
 use std::collections::{HashMap, HashSet};
 use std::fs::{create_dir_all, File};
 use std::io::{Cursor, Read, Seek};
@@ -58,11 +59,17 @@ impl MicProfileAdapter {
             return Ok(MicProfileAdapter::default());
         }
 
-        Err(anyhow!(
-            "Mic profile {} does not exist inside {}",
-            name,
-            dir_list
-        ))
+        debug!("Mic profile {} does not exist inside {}", name, dir_list);
+        Err(DaemonError::MicProfileNotFound(name).into())
+    }
+
+    pub fn exists(name: &str, directories: Vec<&Path>) -> bool {
+        name == DEFAULT_MIC_PROFILE_NAME
+            || directories.iter().any(|directory| {
+                directory
+                    .join(format!("{}.goxlrMicProfile", name))
+                    .is_file()
+            })
     }
 
     pub fn default() -> Self {
@@ -74,11 +81,17 @@ impl MicProfileAdapter {
     }
 
     pub fn from_reader<R: Read + Seek>(name: String, reader: R) -> Result<Self> {
-        let profile = MicProfileSettings::load(reader)?;
+        let profile = MicProfileSettings::load(reader).map_err(|e| invalid_profile(&name, e))?;
         Ok(Self { name, profile })
     }
 
-    pub fn write_profile(&mut self, name: String, directory: &Path, overwrite: bool) -> Result<()> {
+    pub fn write_profile(
+        &mut self,
+        name: String,
+        directory: &Path,
+        overwrite: bool,
+        autosave: bool,
+    ) -> Result<()> {
         let path = directory.join(format!("{}.goxlrMicProfile", name));
         if !directory.exists() {
             // Attempt to create the profile directory..
@@ -94,6 +107,10 @@ impl MicProfileAdapter {
             return Err(anyhow!("Profile exists, will not overwrite"));
         }
 
+        match autosave {
+            true => backup_file_for_autosave(&path)?,
+            false => backup_file(&path)?,
+        }
         self.profile.save(path)?;
 
         // Keep our names in sync (in case it was changed)
@@ -105,6 +122,34 @@ impl MicProfileAdapter {
         Ok(())
     }
 
+    pub fn get_contents(&self) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.profile.write_to(&mut contents)?;
+        Ok(contents)
+    }
+
+    pub fn try_clone(&self) -> Result<Self> {
+        let mut buffer = Vec::new();
+        self.profile.write_to(&mut buffer)?;
+        MicProfileAdapter::from_reader(self.name.clone(), Cursor::new(buffer))
+    }
+
+    // JSON is easier to edit and diff than the XML, but the official app can only read the XML.
+    pub fn from_json(name: String, json: &str) -> Result<Self> {
+        let profile = serde_json::from_str(json).context("Couldn't parse mic profile JSON")?;
+        Ok(Self { name, profile })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.profile)?)
+    }
+
+    // Writes the live profile to an arbitrary path, without changing its name.
+    pub fn write_snapshot(&self, path: &Path) -> Result<()> {
+        self.profile.save(path)?;
+        Ok(())
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -137,6 +182,17 @@ impl MicProfileAdapter {
                 .unwrap(),
             enabled: self.profile.gate().enabled(),
             attenuation: self.profile.gate().attenuation(),
+            amount: self.profile.gate().amount(),
+        }
+    }
+
+    pub fn editor_modes_ipc(&self) -> MicEditorModes {
+        let ui_setup = self.profile.ui_setup();
+        MicEditorModes {
+            eq_advanced: ui_setup.eq_advanced(),
+            eq_fine_tune: ui_setup.eq_fine_tune(),
+            gate_advanced: ui_setup.gate_advanced(),
+            compressor_advanced: ui_setup.comp_advanced(),
         }
     }
 
@@ -263,91 +319,49 @@ impl MicProfileAdapter {
         }
     }
 
-    pub fn set_eq_freq(&mut self, freq: EqFrequencies, value: f32) -> Result<EffectKey> {
-        return match freq {
+    pub fn set_eq_freq(&mut self, freq: EqFrequencies, value: f32) -> EffectKey {
+        match freq {
             EqFrequencies::Equalizer31Hz => {
-                if !(30.0..=300.0).contains(&value) {
-                    return Err(anyhow!("31Hz Frequency must be between 30.0 and 300.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_31h_freq(value);
-                Ok(EffectKey::Equalizer31HzFrequency)
+                EffectKey::Equalizer31HzFrequency
             }
             EqFrequencies::Equalizer63Hz => {
-                if !(30.0..=300.0).contains(&value) {
-                    return Err(anyhow!("63Hz Frequency must be between 30.0 and 300.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_63h_freq(value);
-                Ok(EffectKey::Equalizer63HzFrequency)
+                EffectKey::Equalizer63HzFrequency
             }
             EqFrequencies::Equalizer125Hz => {
-                if !(30.0..=300.0).contains(&value) {
-                    return Err(anyhow!("125Hz Frequency must be between 30.0 and 300.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_125h_freq(value);
-                Ok(EffectKey::Equalizer125HzFrequency)
+                EffectKey::Equalizer125HzFrequency
             }
             EqFrequencies::Equalizer250Hz => {
-                if !(30.0..=300.0).contains(&value) {
-                    return Err(anyhow!("250Hz Frequency must be between 30.0 and 300.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_250h_freq(value);
-                Ok(EffectKey::Equalizer250HzFrequency)
+                EffectKey::Equalizer250HzFrequency
             }
             EqFrequencies::Equalizer500Hz => {
-                if !(300.0..=2000.0).contains(&value) {
-                    return Err(anyhow!("500Hz Frequency must be between 300.0 and 2000.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_500h_freq(value);
-                Ok(EffectKey::Equalizer500HzFrequency)
+                EffectKey::Equalizer500HzFrequency
             }
             EqFrequencies::Equalizer1KHz => {
-                if !(300.0..=2000.0).contains(&value) {
-                    return Err(anyhow!("1KHz Frequency must be between 300.0 and 2000.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_1k_freq(value);
-                Ok(EffectKey::Equalizer1KHzFrequency)
+                EffectKey::Equalizer1KHzFrequency
             }
             EqFrequencies::Equalizer2KHz => {
-                if !(300.0..=2000.0).contains(&value) {
-                    return Err(anyhow!("2KHz Frequency must be between 300.0 and 2000.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_2k_freq(value);
-                Ok(EffectKey::Equalizer2KHzFrequency)
+                EffectKey::Equalizer2KHzFrequency
             }
             EqFrequencies::Equalizer4KHz => {
-                if !(2000.0..=18000.0).contains(&value) {
-                    return Err(anyhow!("4KHz Frequency must be between 2000.0 and 18000.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_4k_freq(value);
-                Ok(EffectKey::Equalizer4KHzFrequency)
+                EffectKey::Equalizer4KHzFrequency
             }
             EqFrequencies::Equalizer8KHz => {
-                if !(2000.0..=18000.0).contains(&value) {
-                    return Err(anyhow!("8KHz Frequency must be between 2000.0 and 18000.0"));
-                }
-
                 self.profile.equalizer_mut().set_eq_8k_freq(value);
-                Ok(EffectKey::Equalizer8KHzFrequency)
+                EffectKey::Equalizer8KHzFrequency
             }
             EqFrequencies::Equalizer16KHz => {
-                if !(2000.0..=18000.0).contains(&value) {
-                    return Err(anyhow!(
-                        "16KHz Frequency must be between 2000.0 and 18000.0"
-                    ));
-                }
-
                 self.profile.equalizer_mut().set_eq_16k_freq(value);
-                Ok(EffectKey::Equalizer16KHzFrequency)
+                EffectKey::Equalizer16KHzFrequency
             }
-        };
+        }
     }
 
     pub fn get_eq_freq(&self, freq: EqFrequencies) -> f32 {
@@ -488,13 +502,59 @@ impl MicProfileAdapter {
         self.profile.compressor_mut().set_makeup_gain(value);
     }
 
+    pub fn set_deesser(&mut self, amount: u8) {
+        self.profile.set_deess(amount);
+    }
+
+    // Puts both EQs back to their default frequencies, with no gain.
+    pub fn reset_equalisers(&mut self) {
+        *self.profile.equalizer_mut() = Equalizer::new();
+        *self.profile.equalizer_mini_mut() = EqualizerMini::new();
+    }
+
+    // Presets are applied on top of the default frequencies, as the curves are built for them.
+    pub fn set_eq_preset(&mut self, preset: EqPreset) {
+        self.reset_equalisers();
+
+        let (gains, mini_gains) = get_eq_preset_gains(preset);
+        for (freq, gain) in EqFrequencies::iter().zip(gains) {
+            self.set_eq_gain(freq, gain);
+        }
+        for (freq, gain) in MiniEqFrequencies::iter().zip(mini_gains) {
+            self.set_mini_eq_gain(freq, gain);
+        }
+    }
+
+    /**
+     The simple gate mode from the official app, where a single amount (0 - 100%) raises the
+     threshold and attenuation together, with a fixed attack and release.
+    */
+    pub fn set_gate_amount(&mut self, amount: u8) {
+        self.profile.gate_mut().set_amount(amount.min(100));
+        self.set_gate_threshold(scale_amount(amount, -59, -20) as i8);
+        self.set_gate_attenuation(amount.min(100));
+        self.set_gate_attack(GateTimes::Gate10ms);
+        self.set_gate_release(GateTimes::Gate200ms);
+    }
+
+    /**
+     The simple compressor mode, as above the amount (0 - 100%) lowers the threshold while
+     raising the ratio and makeup gain, with a fixed attack and release.
+    */
+    pub fn set_compressor_amount(&mut self, amount: u8) {
+        let ratio = scale_amount(amount, 0, CompressorRatio::Ratio8_0 as i32);
+        if let Some(ratio) = CompressorRatio::iter().nth(ratio as usize) {
+            self.set_compressor_ratio(ratio);
+        }
+
+        self.set_compressor_threshold(scale_amount(amount, 0, -24) as i8);
+        self.set_compressor_makeup(scale_amount(amount, 0, 12) as u8);
+        self.set_compressor_attack(CompressorAttackTime::Comp2ms);
+        self.set_compressor_release(CompressorReleaseTime::Comp100ms);
+    }
+
     /// The uber method, fetches the relevant setting from the profile and returns it..
-    pub fn get_param_value(
-        &self,
-        param: MicrophoneParamKey,
-        serial: &str,
-        settings: &SettingsHandle,
-    ) -> [u8; 4] {
+    pub fn get_param_value(&self, param: MicrophoneParamKey, bleep_volume: i8) -> [u8; 4] {
         match param {
             MicrophoneParamKey::MicType => {
                 let microphone_type: MicrophoneType = self.mic_type();
@@ -534,8 +594,7 @@ impl MicProfileAdapter {
             }
             MicrophoneParamKey::BleepLevel => {
                 // Hopefully we can eventually move this to the profile, it's a little obnoxious right now!
-                let bleep_value = block_on(settings.get_device_bleep_volume(serial)).unwrap_or(-20);
-                self.calculate_bleep(bleep_value)
+                self.calculate_bleep(bleep_volume)
             }
             MicrophoneParamKey::Equalizer90HzFrequency => {
                 self.f32_to_f32(self.profile.equalizer_mini().eq_90h_freq())
@@ -587,8 +646,7 @@ impl MicProfileAdapter {
     pub fn get_effect_value(
         &self,
         effect: EffectKey,
-        serial: &str,
-        settings: &SettingsHandle,
+        bleep_volume: i8,
         main_profile: &ProfileAdapter,
     ) -> i32 {
         match effect {
@@ -599,9 +657,7 @@ impl MicProfileAdapter {
                 // need to correctly send this when the mic gets muted / unmuted.
                 0
             }
-            EffectKey::BleepLevel => block_on(settings.get_device_bleep_volume(serial))
-                .unwrap_or(-20)
-                .into(),
+            EffectKey::BleepLevel => bleep_volume.into(),
             EffectKey::GateMode => 2, // Not a profile setting, hard coded in Windows
             EffectKey::GateEnabled => 1, // Used for 'Mic Testing' in the UI
             EffectKey::GateThreshold => self.profile.gate().threshold().into(),
@@ -917,6 +973,50 @@ impl MicProfileAdapter {
     }
 
     // These are specific Group Key sets, useful for applying a specific effect at once.
+    pub fn get_eq_keyset(&self) -> HashSet<EffectKey> {
+        let mut set = HashSet::new();
+        set.insert(EffectKey::Equalizer31HzFrequency);
+        set.insert(EffectKey::Equalizer31HzGain);
+        set.insert(EffectKey::Equalizer63HzFrequency);
+        set.insert(EffectKey::Equalizer63HzGain);
+        set.insert(EffectKey::Equalizer125HzFrequency);
+        set.insert(EffectKey::Equalizer125HzGain);
+        set.insert(EffectKey::Equalizer250HzFrequency);
+        set.insert(EffectKey::Equalizer250HzGain);
+        set.insert(EffectKey::Equalizer500HzFrequency);
+        set.insert(EffectKey::Equalizer500HzGain);
+        set.insert(EffectKey::Equalizer1KHzFrequency);
+        set.insert(EffectKey::Equalizer1KHzGain);
+        set.insert(EffectKey::Equalizer2KHzFrequency);
+        set.insert(EffectKey::Equalizer2KHzGain);
+        set.insert(EffectKey::Equalizer4KHzFrequency);
+        set.insert(EffectKey::Equalizer4KHzGain);
+        set.insert(EffectKey::Equalizer8KHzFrequency);
+        set.insert(EffectKey::Equalizer8KHzGain);
+        set.insert(EffectKey::Equalizer16KHzFrequency);
+        set.insert(EffectKey::Equalizer16KHzGain);
+
+        set
+    }
+
+    pub fn get_mini_eq_keyset(&self) -> HashSet<MicrophoneParamKey> {
+        let mut set = HashSet::new();
+        set.insert(MicrophoneParamKey::Equalizer90HzFrequency);
+        set.insert(MicrophoneParamKey::Equalizer90HzGain);
+        set.insert(MicrophoneParamKey::Equalizer250HzFrequency);
+        set.insert(MicrophoneParamKey::Equalizer250HzGain);
+        set.insert(MicrophoneParamKey::Equalizer500HzFrequency);
+        set.insert(MicrophoneParamKey::Equalizer500HzGain);
+        set.insert(MicrophoneParamKey::Equalizer1KHzFrequency);
+        set.insert(MicrophoneParamKey::Equalizer1KHzGain);
+        set.insert(MicrophoneParamKey::Equalizer3KHzFrequency);
+        set.insert(MicrophoneParamKey::Equalizer3KHzGain);
+        set.insert(MicrophoneParamKey::Equalizer8KHzFrequency);
+        set.insert(MicrophoneParamKey::Equalizer8KHzGain);
+
+        set
+    }
+
     pub fn get_reverb_keyset(&self) -> HashSet<EffectKey> {
         let mut set = HashSet::new();
         set.insert(EffectKey::ReverbAmount);
@@ -1027,3 +1127,34 @@ impl MicProfileAdapter {
         self.profile.deess() as i32
     }
 }
+
+// Gains for the full EQ (31Hz - 16KHz) and the mini EQ (90Hz - 8KHz) for each preset.
+fn get_eq_preset_gains(preset: EqPreset) -> ([i8; 10], [i8; 6]) {
+    match preset {
+        EqPreset::Flat => ([0; 10], [0; 6]),
+        EqPreset::Warm => ([2, 3, 3, 2, 1, 0, -1, -1, -2, -2], [3, 2, 1, 0, -1, -2]),
+        EqPreset::Bright => ([-2, -2, -1, -1, 0, 0, 1, 2, 3, 3], [-2, -1, 0, 0, 2, 3]),
+        EqPreset::Broadcast => ([-6, -4, 0, 1, 0, 0, 2, 3, 2, 0], [-4, 1, 0, 0, 3, 2]),
+    }
+}
+
+// Maps a 0 - 100% amount onto a range, for the simple gate and compressor modes.
+fn scale_amount(amount: u8, min: i32, max: i32) -> i32 {
+    min + (max - min) * amount.min(100) as i32 / 100
+}
+
+// The frequency range each band of the full EQ can be moved within.
+pub fn get_eq_freq_range(freq: EqFrequencies) -> (f32, f32) {
+    match freq {
+        EqFrequencies::Equalizer31Hz
+        | EqFrequencies::Equalizer63Hz
+        | EqFrequencies::Equalizer125Hz
+        | EqFrequencies::Equalizer250Hz => (30.0, 300.0),
+        EqFrequencies::Equalizer500Hz
+        | EqFrequencies::Equalizer1KHz
+        | EqFrequencies::Equalizer2KHz => (300.0, 2000.0),
+        EqFrequencies::Equalizer4KHz
+        | EqFrequencies::Equalizer8KHz
+        | EqFrequencies::Equalizer16KHz => (2000.0, 18000.0),
+    }
+}
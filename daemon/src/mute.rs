@@ -0,0 +1,432 @@
+/*
+The mute buttons, as a state machine. The state lives in the profile (the button's light being on
+means muted to its function, flashing means muted to all), Device loads it into one of these,
+passes the button event through, stores the state back and then carries out the returned actions
+on the hardware. Nothing in here touches the device, so the transitions can be tested on their own.
+ */
+
+use goxlr_profile_loader::components::mute::MuteFunction;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MuteAction {
+    // Set the channel's volume, in the profile and on the hardware
+    SetVolume(u8),
+
+    // Mute or unmute the channel on the hardware
+    SetChannelMuted(bool),
+
+    // Rebuild the channel's routing, to add or remove a mute to a single output
+    ApplyRouting,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FaderMute {
+    pub muted_to_x: bool,
+    pub muted_to_all: bool,
+    pub mute_function: MuteFunction,
+
+    // The function used by a hold, when it's different to the button's own
+    pub held_function: Option<MuteFunction>,
+
+    // The volume to go back to when a mute to all is released
+    pub previous_volume: u8,
+}
+
+impl FaderMute {
+    pub fn active_function(&self) -> MuteFunction {
+        match self.muted_to_x {
+            true => self.held_function.unwrap_or(self.mute_function),
+            false => self.mute_function,
+        }
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted_to_x || self.muted_to_all
+    }
+
+    pub fn is_muted_to_all(&self) -> bool {
+        self.muted_to_all || (self.muted_to_x && self.active_function() == MuteFunction::All)
+    }
+
+    // The single output this is muting through the routing table, if any.
+    pub fn transient_function(&self) -> Option<MuteFunction> {
+        (self.muted_to_x && !self.is_muted_to_all()).then_some(self.active_function())
+    }
+
+    // A short press toggles between unmuted and the button's function. `muted_elsewhere` is set
+    // when something else (the cough button) is holding the channel muted to all.
+    pub fn tap(&mut self, volume: u8, muted_elsewhere: bool) -> Vec<MuteAction> {
+        let before = self.transient_function();
+        let mut actions = vec![];
+
+        if self.is_muted() {
+            if self.is_muted_to_all() {
+                actions.push(MuteAction::SetVolume(self.previous_volume));
+                if !muted_elsewhere {
+                    actions.push(MuteAction::SetChannelMuted(false));
+                }
+            }
+            self.muted_to_x = false;
+            self.muted_to_all = false;
+            self.held_function = None;
+        } else {
+            self.muted_to_x = true;
+            if self.mute_function == MuteFunction::All {
+                actions.append(&mut self.mute_to_all(volume));
+            }
+        }
+
+        self.refresh_routing(before, actions)
+    }
+
+    // Holding the button mutes with the hold's function, it never unmutes.
+    pub fn hold(&mut self, volume: u8, function: MuteFunction) -> Vec<MuteAction> {
+        let before = self.transient_function();
+        let mut actions = vec![];
+
+        if function != MuteFunction::All {
+            if !self.muted_to_x {
+                self.muted_to_x = true;
+                self.held_function = Some(function);
+            }
+        } else if !self.muted_to_all {
+            // Already being muted to all means the volume's already been stored and zeroed.
+            if !self.is_muted_to_all() {
+                actions.append(&mut self.mute_to_all(volume));
+            }
+            self.muted_to_x = true;
+            self.muted_to_all = true;
+        }
+
+        self.refresh_routing(before, actions)
+    }
+
+    fn mute_to_all(&mut self, volume: u8) -> Vec<MuteAction> {
+        self.previous_volume = volume;
+        vec![MuteAction::SetVolume(0), MuteAction::SetChannelMuted(true)]
+    }
+
+    fn refresh_routing(
+        &self,
+        before: Option<MuteFunction>,
+        mut actions: Vec<MuteAction>,
+    ) -> Vec<MuteAction> {
+        if self.transient_function() != before {
+            actions.push(MuteAction::ApplyRouting);
+        }
+        actions
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CoughMute {
+    pub is_toggle: bool,
+    pub is_hybrid: bool,
+    pub muted_to_x: bool,
+    pub muted_to_all: bool,
+    pub mute_function: MuteFunction,
+
+    // Whether the current press of a hybrid button muted the mic
+    pub press_muted: bool,
+}
+
+impl CoughMute {
+    pub fn is_muted(&self) -> bool {
+        self.muted_to_x || self.muted_to_all
+    }
+
+    pub fn is_muted_to_all(&self) -> bool {
+        self.muted_to_all || (self.muted_to_x && self.mute_function == MuteFunction::All)
+    }
+
+    pub fn transient_function(&self) -> Option<MuteFunction> {
+        (self.muted_to_x && !self.is_muted_to_all()).then_some(self.mute_function)
+    }
+
+    // A hold button mutes while it's down, a toggle flips when it's released, and a hybrid mutes
+    // when pressed, then unmutes when released after a hold or on the next tap.
+    pub fn press(&mut self) -> Vec<MuteAction> {
+        let before = self.transient_function();
+        let mut actions = vec![];
+
+        if self.is_hybrid {
+            // Pressing while already muted doesn't change anything until the button is released.
+            self.press_muted = !self.is_muted();
+            if self.press_muted {
+                actions.append(&mut self.mute());
+            }
+        } else if !self.is_toggle {
+            actions.append(&mut self.mute());
+        }
+
+        self.refresh_routing(before, actions)
+    }
+
+    // Holding a toggle button mutes to all, until it's next tapped.
+    pub fn hold(&mut self) -> Vec<MuteAction> {
+        let before = self.transient_function();
+        let mut actions = vec![];
+
+        if self.is_toggle && !self.is_hybrid {
+            self.muted_to_x = true;
+            self.muted_to_all = true;
+            actions.push(MuteAction::SetChannelMuted(true));
+        }
+
+        self.refresh_routing(before, actions)
+    }
+
+    // `held` is whether the hold for this press has already been handled, and `muted_by_fader`
+    // whether the mic's fader is keeping it muted to all.
+    pub fn release(&mut self, held: bool, muted_by_fader: bool) -> Vec<MuteAction> {
+        let before = self.transient_function();
+        let mut actions = vec![];
+
+        if self.is_hybrid {
+            if held || !self.press_muted {
+                actions.append(&mut self.unmute(muted_by_fader));
+            }
+        } else if !self.is_toggle {
+            actions.append(&mut self.unmute(muted_by_fader));
+        } else if !held {
+            if self.is_muted() {
+                actions.append(&mut self.unmute(muted_by_fader));
+            } else {
+                actions.append(&mut self.mute());
+            }
+        }
+
+        self.refresh_routing(before, actions)
+    }
+
+    fn mute(&mut self) -> Vec<MuteAction> {
+        self.muted_to_x = true;
+        match self.mute_function {
+            MuteFunction::All => vec![MuteAction::SetChannelMuted(true)],
+            _ => vec![],
+        }
+    }
+
+    fn unmute(&mut self, muted_by_fader: bool) -> Vec<MuteAction> {
+        let was_muted_to_all = self.is_muted_to_all();
+        self.muted_to_x = false;
+        self.muted_to_all = false;
+        match was_muted_to_all && !muted_by_fader {
+            true => vec![MuteAction::SetChannelMuted(false)],
+            false => vec![],
+        }
+    }
+
+    fn refresh_routing(
+        &self,
+        before: Option<MuteFunction>,
+        mut actions: Vec<MuteAction>,
+    ) -> Vec<MuteAction> {
+        if self.transient_function() != before {
+            actions.push(MuteAction::ApplyRouting);
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const FUNCTIONS: [MuteFunction; 5] = [
+        MuteFunction::All,
+        MuteFunction::ToStream,
+        MuteFunction::ToVoiceChat,
+        MuteFunction::ToPhones,
+        MuteFunction::ToLineOut,
+    ];
+
+    const RUNS: u64 = 2000;
+    const STEPS: usize = 50;
+
+    // What the hardware would be doing for the mic, which has a fader and the cough button.
+    struct Mic {
+        fader: FaderMute,
+        cough: CoughMute,
+        hold_function: MuteFunction,
+        volume: u8,
+        hardware_muted: bool,
+
+        // The single output mutes the routing table was last built with
+        routed: Vec<MuteFunction>,
+
+        // The volume when the fader last started muting to all
+        volume_before_mute: Option<u8>,
+    }
+
+    impl Mic {
+        fn new(rng: &mut StdRng) -> Self {
+            let cough_mode = rng.gen_range(0..3);
+            Self {
+                fader: FaderMute {
+                    muted_to_x: false,
+                    muted_to_all: false,
+                    mute_function: FUNCTIONS[rng.gen_range(0..FUNCTIONS.len())],
+                    held_function: None,
+                    previous_volume: 0,
+                },
+                cough: CoughMute {
+                    is_toggle: cough_mode == 1,
+                    is_hybrid: cough_mode == 2,
+                    muted_to_x: false,
+                    muted_to_all: false,
+                    mute_function: FUNCTIONS[rng.gen_range(0..FUNCTIONS.len())],
+                    press_muted: false,
+                },
+                hold_function: FUNCTIONS[rng.gen_range(0..FUNCTIONS.len())],
+                volume: rng.gen(),
+                hardware_muted: false,
+                routed: vec![],
+                volume_before_mute: None,
+            }
+        }
+
+        fn transient_mutes(&self) -> Vec<MuteFunction> {
+            let mut mutes = vec![];
+            mutes.extend(self.fader.transient_function());
+            mutes.extend(self.cough.transient_function());
+            mutes
+        }
+
+        fn apply(&mut self, actions: Vec<MuteAction>) {
+            for action in actions {
+                match action {
+                    MuteAction::SetVolume(volume) => self.volume = volume,
+                    MuteAction::SetChannelMuted(muted) => self.hardware_muted = muted,
+                    MuteAction::ApplyRouting => self.routed = self.transient_mutes(),
+                }
+            }
+        }
+
+        fn tap_fader(&mut self) {
+            let (volume, was_muted_to_all) = (self.volume, self.fader.is_muted_to_all());
+            let actions = self.fader.tap(self.volume, self.cough.is_muted_to_all());
+            self.apply(actions);
+            self.check_fader_volume(volume, was_muted_to_all);
+        }
+
+        fn hold_fader(&mut self) {
+            let (volume, was_muted_to_all) = (self.volume, self.fader.is_muted_to_all());
+            let actions = self.fader.hold(self.volume, self.hold_function);
+            self.apply(actions);
+            self.check_fader_volume(volume, was_muted_to_all);
+        }
+
+        fn tap_cough(&mut self) {
+            let actions = self.cough.press();
+            self.apply(actions);
+            let actions = self.cough.release(false, self.fader.is_muted_to_all());
+            self.apply(actions);
+        }
+
+        fn hold_cough(&mut self) {
+            let actions = self.cough.press();
+            self.apply(actions);
+            self.check();
+            let actions = self.cough.hold();
+            self.apply(actions);
+            self.check();
+            let actions = self.cough.release(true, self.fader.is_muted_to_all());
+            self.apply(actions);
+        }
+
+        fn check_fader_volume(&mut self, volume: u8, was_muted_to_all: bool) {
+            match (was_muted_to_all, self.fader.is_muted_to_all()) {
+                (false, true) => self.volume_before_mute = Some(volume),
+                (true, false) => {
+                    assert_eq!(Some(self.volume), self.volume_before_mute.take());
+                }
+                _ => {}
+            }
+        }
+
+        fn check(&self) {
+            let muted_to_all = self.fader.is_muted_to_all() || self.cough.is_muted_to_all();
+            assert_eq!(self.hardware_muted, muted_to_all);
+            assert_eq!(self.routed, self.transient_mutes());
+            if self.fader.is_muted_to_all() {
+                assert_eq!(self.volume, 0);
+            }
+        }
+    }
+
+    fn run(test: impl Fn(&mut StdRng, &mut Mic)) {
+        for seed in 0..RUNS {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut mic = Mic::new(&mut rng);
+            test(&mut rng, &mut mic);
+        }
+    }
+
+    #[test]
+    fn hardware_and_routing_follow_the_buttons() {
+        run(|rng, mic| {
+            for _ in 0..STEPS {
+                match rng.gen_range(0..5) {
+                    0 => mic.tap_fader(),
+                    1 => mic.hold_fader(),
+                    2 => mic.tap_cough(),
+                    3 => mic.hold_cough(),
+                    _ => {
+                        if !mic.fader.is_muted_to_all() {
+                            mic.volume = rng.gen();
+                        }
+                    }
+                }
+                mic.check();
+            }
+        });
+    }
+
+    #[test]
+    fn unmute_restores_the_volume() {
+        run(|rng, mic| {
+            let volume = mic.volume;
+            for _ in 0..rng.gen_range(1..10) {
+                match rng.gen_bool(0.5) {
+                    true => mic.tap_fader(),
+                    false => mic.hold_fader(),
+                }
+            }
+            while mic.fader.is_muted() {
+                mic.tap_fader();
+            }
+            assert_eq!(mic.volume, volume);
+            mic.check();
+        });
+    }
+
+    #[test]
+    fn cough_never_sticks() {
+        run(|rng, mic| {
+            for _ in 0..STEPS {
+                match rng.gen_bool(0.5) {
+                    true => mic.tap_cough(),
+                    false => mic.hold_cough(),
+                }
+
+                // A hold button only mutes while it's down.
+                if !mic.cough.is_toggle && !mic.cough.is_hybrid {
+                    assert!(!mic.cough.is_muted());
+                }
+            }
+
+            // Whatever state it's been left in, a tap or two always unmutes.
+            for _ in 0..2 {
+                if mic.cough.is_muted() {
+                    mic.tap_cough();
+                }
+            }
+            assert!(!mic.cough.is_muted());
+            assert!(!mic.hardware_muted);
+            assert!(mic.routed.is_empty());
+        });
+    }
+}
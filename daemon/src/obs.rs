@@ -0,0 +1,307 @@
+use crate::primary_worker::{DeviceCommand, DeviceSender};
+use crate::{SettingsHandle, Shutdown};
+use anyhow::{anyhow, bail, Context, Result};
+use futures::{SinkExt, StreamExt};
+use goxlr_ipc::{ClientIdentity, DaemonStatus, GoXLRCommand, MuteState, ObsSettings};
+use goxlr_types::{ChannelName, OutputDevice};
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use strum::IntoEnumIterator;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::sleep;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+type ObsSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+// obs-websocket op codes and event subscriptions, from the version 5 protocol.
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_EVENT: u64 = 5;
+const OP_REQUEST: u64 = 6;
+const EVENT_SUBSCRIPTION_SCENES: u64 = 1 << 2;
+
+#[derive(Debug)]
+pub enum ObsMessage {
+    ChannelMuted(ChannelName, bool),
+    SettingsChanged,
+}
+
+pub type ObsReceiver = mpsc::UnboundedReceiver<ObsMessage>;
+
+/**
+ Used by the device worker to pass mute changes to the OBS connection, and to report whether
+ it's connected in the status.
+*/
+#[derive(Debug, Clone)]
+pub struct ObsHandle {
+    sender: mpsc::UnboundedSender<ObsMessage>,
+    connected: Arc<AtomicBool>,
+}
+
+impl ObsHandle {
+    pub fn new() -> (Self, ObsReceiver) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let handle = Self {
+            sender,
+            connected: Arc::new(AtomicBool::new(false)),
+        };
+        (handle, receiver)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub fn channel_muted(&self, channel: ChannelName, muted: bool) {
+        // If the OBS task has stopped we're shutting down, so this can be ignored.
+        let _ = self.sender.send(ObsMessage::ChannelMuted(channel, muted));
+    }
+
+    pub fn settings_changed(&self) {
+        let _ = self.sender.send(ObsMessage::SettingsChanged);
+    }
+}
+
+pub async fn run_obs(
+    handle: ObsHandle,
+    mut receiver: ObsReceiver,
+    settings: SettingsHandle,
+    usb_tx: DeviceSender,
+    mut shutdown: Shutdown,
+) {
+    loop {
+        let config = settings.get_obs_settings().await;
+        if config.enabled {
+            match connect(&config).await {
+                Ok(socket) => {
+                    info!("Connected to OBS at {}", config.address);
+                    handle.connected.store(true, Ordering::Relaxed);
+                    let result =
+                        run_session(socket, &config, &mut receiver, &usb_tx, &mut shutdown).await;
+                    handle.connected.store(false, Ordering::Relaxed);
+
+                    match result {
+                        Ok(true) => return,
+                        Ok(false) => continue,
+                        Err(error) => warn!("Lost connection to OBS: {}", error),
+                    }
+                }
+                Err(error) => warn!("Unable to connect to OBS at {}: {}", config.address, error),
+            }
+        }
+
+        // Wait for the settings to change, or retry the connection after a while.
+        loop {
+            tokio::select! {
+                () = shutdown.recv() => return,
+                () = sleep(RECONNECT_DELAY), if config.enabled => break,
+                message = receiver.recv() => match message {
+                    None => return,
+                    Some(ObsMessage::SettingsChanged) => break,
+
+                    // Not connected, so there's nothing to mute.
+                    Some(ObsMessage::ChannelMuted(..)) => {}
+                },
+            }
+        }
+    }
+}
+
+// Handles a connected session, returning true if the daemon is shutting down.
+async fn run_session(
+    mut socket: ObsSocket,
+    config: &ObsSettings,
+    receiver: &mut ObsReceiver,
+    usb_tx: &DeviceSender,
+    shutdown: &mut Shutdown,
+) -> Result<bool> {
+    let mut request_id: u64 = 0;
+
+    // Changes are only sent as they happen, so bring OBS up to date with anything from before.
+    for (channel, muted) in get_stream_mutes(usb_tx).await? {
+        set_input_muted(&mut socket, &mut request_id, config, channel, muted).await?;
+    }
+
+    loop {
+        tokio::select! {
+            () = shutdown.recv() => {
+                let _ = socket.close(None).await;
+                return Ok(true);
+            }
+            message = receiver.recv() => match message {
+                None => return Ok(true),
+                Some(ObsMessage::SettingsChanged) => {
+                    let _ = socket.close(None).await;
+                    return Ok(false);
+                }
+                Some(ObsMessage::ChannelMuted(channel, muted)) => {
+                    set_input_muted(&mut socket, &mut request_id, config, channel, muted).await?;
+                }
+            },
+            message = read(&mut socket) => {
+                let message = message?;
+                if message["op"] != OP_EVENT
+                    || message["d"]["eventType"] != "CurrentProgramSceneChanged"
+                {
+                    continue;
+                }
+
+                let scene = message["d"]["eventData"]["sceneName"].as_str().unwrap_or_default();
+                if let Some(profile) = config.scene_profiles.get(scene) {
+                    info!("OBS switched to {}, loading profile {}", scene, profile);
+                    let command = GoXLRCommand::LoadProfile(profile.to_string());
+                    if let Err(error) = run_command(usb_tx, command).await {
+                        warn!("Unable to load profile {} for OBS scene {}: {}", profile, scene, error);
+                    }
+                }
+
+                // After the profile, so the theme replaces the profile's own lighting.
+                if let Some(theme) = config.scene_themes.get(scene) {
+                    info!("OBS switched to {}, loading lighting theme {}", scene, theme);
+                    let command = GoXLRCommand::LoadLightingTheme(theme.to_string());
+                    if let Err(error) = run_command(usb_tx, command).await {
+                        warn!("Unable to load theme {} for OBS scene {}: {}", theme, scene, error);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn connect(config: &ObsSettings) -> Result<ObsSocket> {
+    let (mut socket, _) = connect_async(config.address.as_str()).await?;
+
+    let hello = read(&mut socket).await?;
+    if hello["op"] != OP_HELLO {
+        bail!("Expected a Hello message from OBS");
+    }
+
+    let mut identify = json!({
+        "rpcVersion": 1,
+        "eventSubscriptions": EVENT_SUBSCRIPTION_SCENES,
+    });
+
+    let authentication = &hello["d"]["authentication"];
+    if !authentication.is_null() {
+        let password = config
+            .password
+            .as_deref()
+            .context("OBS requires a password, but none is configured")?;
+        let salt = authentication["salt"].as_str().unwrap_or_default();
+        let challenge = authentication["challenge"].as_str().unwrap_or_default();
+        identify["authentication"] = json!(get_auth_string(password, salt, challenge));
+    }
+
+    send(&mut socket, OP_IDENTIFY, identify).await?;
+    if read(&mut socket).await?["op"] != OP_IDENTIFIED {
+        bail!("OBS did not accept the connection");
+    }
+
+    Ok(socket)
+}
+
+// As defined by obs-websocket: base64(sha256(base64(sha256(password + salt)) + challenge))
+fn get_auth_string(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = base64::encode(Sha256::digest(format!("{}{}", password, salt)));
+    base64::encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+async fn send(socket: &mut ObsSocket, op: u64, data: Value) -> Result<()> {
+    let message = json!({ "op": op, "d": data });
+    socket.send(Message::Text(message.to_string())).await?;
+    Ok(())
+}
+
+async fn read(socket: &mut ObsSocket) -> Result<Value> {
+    loop {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => return Ok(serde_json::from_str(&text)?),
+            Some(Ok(Message::Close(_))) | None => bail!("Connection closed"),
+            Some(Ok(_)) => continue,
+            Some(Err(error)) => return Err(error.into()),
+        }
+    }
+}
+
+async fn set_input_muted(
+    socket: &mut ObsSocket,
+    request_id: &mut u64,
+    config: &ObsSettings,
+    channel: ChannelName,
+    muted: bool,
+) -> Result<()> {
+    let input = match config.channel_inputs.get(&channel) {
+        Some(input) => input,
+        None => return Ok(()),
+    };
+
+    debug!("Setting OBS input {} muted: {}", input, muted);
+    *request_id += 1;
+    let request = json!({
+        "requestType": "SetInputMute",
+        "requestId": request_id.to_string(),
+        "requestData": {
+            "inputName": input,
+            "inputMuted": muted,
+        },
+    });
+    send(socket, OP_REQUEST, request).await
+}
+
+async fn get_status(usb_tx: &DeviceSender) -> Result<DaemonStatus> {
+    let (tx, rx) = oneshot::channel();
+    usb_tx
+        .send(DeviceCommand::SendDaemonStatus(tx))
+        .await
+        .map_err(|e| anyhow!(e.to_string()))?;
+    Ok(rx.await?)
+}
+
+// Whether each channel is muted to the stream, on any of the connected devices.
+async fn get_stream_mutes(usb_tx: &DeviceSender) -> Result<Vec<(ChannelName, bool)>> {
+    let status = get_status(usb_tx).await?;
+    let mutes = ChannelName::iter()
+        .map(|channel| {
+            let muted = status.mixers.values().any(|mixer| {
+                mixer.stream_panic
+                    || match mixer.get_channel_mute_state(channel) {
+                        MuteState::Unmuted => false,
+                        MuteState::MutedToAll => true,
+                        MuteState::MutedTo(outputs) => outputs.contains(OutputDevice::BroadcastMix),
+                    }
+            });
+            (channel, muted)
+        })
+        .collect();
+    Ok(mutes)
+}
+
+// Runs the command on every connected device.
+async fn run_command(usb_tx: &DeviceSender, command: GoXLRCommand) -> Result<()> {
+    for serial in get_status(usb_tx).await?.mixers.keys() {
+        let (tx, rx) = oneshot::channel();
+        usb_tx
+            .send(DeviceCommand::RunDeviceCommand(
+                serial.to_owned(),
+                command.clone(),
+                ClientIdentity {
+                    name: Some("OBS".to_string()),
+                    ..Default::default()
+                },
+                tx,
+            ))
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+        rx.await??;
+    }
+    Ok(())
+}
@@ -1,49 +1,267 @@
-use crate::device::Device;
+use crate::audit::AuditLog;
+use crate::command_queue::CommandQueue;
+use crate::device::{get_device_features, Device};
+use crate::hooks::{run_hooks, HookEvent};
+use crate::mic_profile::MicProfileAdapter;
+use crate::obs::ObsHandle;
+use crate::profile::ProfileAdapter;
+use crate::profile_rules::find_profile_rule;
+use crate::scripts::ScriptHandle;
+use crate::settings::validate_device_settings;
+use crate::sleep::SleepDetector;
+use crate::snapshot::{load_snapshot, write_snapshot};
+use crate::status::{get_device_summary, Notifications, StatusPublisher, Subscription};
+use crate::updates::UpdateHandle;
 use crate::{FileManager, SettingsHandle, Shutdown};
 use anyhow::{anyhow, Result};
 use goxlr_ipc::{
-    DaemonStatus, DeviceType, Files, GoXLRCommand, HardwareStatus, Paths, UsbProductInformation,
+    AuditEntry, ClientIdentity, DaemonError, DaemonHealth, DaemonStatus, DeviceHealth,
+    DeviceSettings, DeviceSummary, DeviceType, DiagnosticReport, Files, GoXLRCommand, HardwareInfo,
+    HardwareStatus, HttpServerStatus, MuteStateReport, ObsSettings, ObsStatus, Paths,
+    ProfileBackup, ProfileType, SampleFile, SamplePage, SampleQuery, SettingsInfo,
+    StateVerification, StatusPatch, UpdateSettings, UpdateStatus, UsbProductInformation,
 };
-use goxlr_usb::goxlr::{GoXLR, PID_GOXLR_FULL, PID_GOXLR_MINI, VID_GOXLR};
-use goxlr_usb::rusb::{DeviceDescriptor, GlobalContext};
-use goxlr_usb::{goxlr, rusb};
-use log::{error, info};
+use goxlr_types::MicrophoneType;
+use goxlr_usb::capture::UsbCapture;
+use goxlr_usb::device::GoXlrDevice;
+use goxlr_usb::error::CommandError;
+use goxlr_usb::goxlr::{find_devices, DetectedDevice, RetryPolicy};
+use log::{error, info, warn};
+use serde_json::json;
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
-use tokio::time::sleep;
+use tokio::time::{interval, MissedTickBehavior};
 
+#[allow(clippy::enum_variant_names)]
 pub enum DeviceCommand {
     SendDaemonStatus(oneshot::Sender<DaemonStatus>),
-    RunDeviceCommand(String, GoXLRCommand, oneshot::Sender<Result<()>>),
+    RunDeviceCommand(
+        String,
+        GoXLRCommand,
+        ClientIdentity,
+        oneshot::Sender<Result<()>>,
+    ),
+    ValidateDeviceCommand(String, GoXLRCommand, oneshot::Sender<Result<()>>),
+    RunDeviceCommandBatch(
+        String,
+        Vec<GoXLRCommand>,
+        bool,
+        ClientIdentity,
+        oneshot::Sender<Result<()>>,
+    ),
+    StartMicCalibration(String, MicrophoneType, bool, oneshot::Sender<Result<()>>),
+    Subscribe(oneshot::Sender<(StatusPatch, Subscription)>),
+    SetObsSettings(ObsSettings, oneshot::Sender<Result<()>>),
+    SetUpdateSettings(UpdateSettings, oneshot::Sender<Result<()>>),
+    SetCustomUiDirectory(Option<PathBuf>, oneshot::Sender<Result<()>>),
+    RunDiagnostics(String, oneshot::Sender<Result<DiagnosticReport>>),
+    VerifyState(String, oneshot::Sender<Result<StateVerification>>),
+    GetMuteState(String, oneshot::Sender<Result<MuteStateReport>>),
+    GetHardwareInfo(String, oneshot::Sender<Result<HardwareInfo>>),
+    SendRawCommand(String, u32, Vec<u8>, oneshot::Sender<Result<Vec<u8>>>),
+    ExportMicProfile(String, oneshot::Sender<Result<String>>),
+    GetSamples(oneshot::Sender<Result<Vec<SampleFile>>>),
+    FindSamples(SampleQuery, oneshot::Sender<Result<SamplePage>>),
+    AddSample(String, Vec<u8>, oneshot::Sender<Result<()>>),
+    MoveSample(String, String, oneshot::Sender<Result<()>>),
+    DeleteSample(String, oneshot::Sender<Result<()>>),
+    GetSampleWaveform(String, u16, oneshot::Sender<Result<Vec<f32>>>),
+    GetProfileBackups(
+        ProfileType,
+        String,
+        oneshot::Sender<Result<Vec<ProfileBackup>>>,
+    ),
+    RestoreProfileBackup(ProfileType, String, String, oneshot::Sender<Result<()>>),
+    GetSettingsInfo(oneshot::Sender<SettingsInfo>),
+    FlushSettings(oneshot::Sender<Result<()>>),
+    GetDaemonHealth(oneshot::Sender<DaemonHealth>),
+    GetAuditLog(oneshot::Sender<Vec<AuditEntry>>),
+    SetScriptEnabled(String, bool, oneshot::Sender<Result<()>>),
+    ReloadScripts(oneshot::Sender<()>),
+    GetUiUrl(oneshot::Sender<Result<String>>),
+    RestartDaemon(oneshot::Sender<()>),
+    GetDeviceSummary(oneshot::Sender<Vec<DeviceSummary>>),
+    SubscribeNotifications(oneshot::Sender<Notifications>),
+
+    // A serial of None refers to the defaults for new devices
+    GetDeviceSettings(Option<String>, oneshot::Sender<DeviceSettings>),
+    SetDeviceSettings(
+        Option<String>,
+        Box<DeviceSettings>,
+        oneshot::Sender<Result<()>>,
+    ),
 }
 
 pub type DeviceSender = mpsc::Sender<DeviceCommand>;
 pub type DeviceReceiver = mpsc::Receiver<DeviceCommand>;
 
+// How often the interrupt thread checks whether the device has been removed.
+const INTERRUPT_TIMEOUT: Duration = Duration::from_millis(500);
+
+// How often a rising bleep is stepped up, far more often than the device is polled.
+const BLEEP_RAMP_STEP: Duration = Duration::from_millis(5);
+
+// After this many consecutive USB failures, a device which is still
+// connected is dropped so it can be detected and initialised again.
+const WATCHDOG_FAILURE_LIMIT: u32 = 10;
+
+#[derive(Default)]
+struct Watchdog {
+    failures: HashMap<String, u32>,
+    errors: HashMap<String, UsbErrors>,
+}
+
+#[derive(Default)]
+struct UsbErrors {
+    count: u64,
+    last: Option<String>,
+}
+
+impl Watchdog {
+    fn record<T>(&mut self, serial: &str, result: &Result<T>) {
+        match result {
+            Ok(_) => {
+                self.failures.remove(serial);
+            }
+            Err(error) if is_usb_error(error) => {
+                *self.failures.entry(serial.to_owned()).or_insert(0) += 1;
+
+                let errors = self.errors.entry(serial.to_owned()).or_default();
+                errors.count += 1;
+                errors.last = Some(error.to_string());
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn take_failed_devices(&mut self) -> Vec<String> {
+        let failed: Vec<String> = self
+            .failures
+            .iter()
+            .filter(|(_, count)| **count >= WATCHDOG_FAILURE_LIMIT)
+            .map(|(serial, _)| serial.to_owned())
+            .collect();
+        for serial in &failed {
+            self.failures.remove(serial);
+        }
+        failed
+    }
+}
+
+pub fn is_usb_error(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<CommandError>().is_some()
+}
+
+pub struct WorkerOptions {
+    pub retry_policy: RetryPolicy,
+    pub poll_interval: Duration,
+    pub http: HttpServerStatus,
+
+    // When set, this is the only device, and real ones aren't looked for.
+    pub mock_device: Option<Box<dyn GoXlrDevice>>,
+
+    // Where USB traffic with real devices is captured to, if anywhere
+    pub usb_capture: Option<UsbCapture>,
+
+    // Whether devices start with their controls locked
+    pub locked: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_changes(
-    mut rx: DeviceReceiver,
+    rx: DeviceReceiver,
     mut shutdown: Shutdown,
     settings: SettingsHandle,
     mut file_manager: FileManager,
+    obs: ObsHandle,
+    updates: UpdateHandle,
+    scripts: ScriptHandle,
+    mut options: WorkerOptions,
 ) {
-    let detect_count = 10;
-    let mut loop_count = 10;
+    let mock_mode = options.mock_device.is_some();
+    let started = Instant::now();
+    let retry_policy = options.retry_policy;
+    let poll_interval = options.poll_interval;
+
+    let detect_interval = Duration::from_secs(1);
+    let mut next_detect = Instant::now();
+
+    // Snapshot the device state every 30 seconds.
+    let snapshot_interval = Duration::from_secs(30);
+    let mut next_snapshot = Instant::now() + snapshot_interval;
+
+    // Devices decide for themselves whether they're due an autosave, this is how often they check.
+    let autosave_interval = Duration::from_secs(5);
+    let mut next_autosave = Instant::now() + autosave_interval;
 
-    let sleep_duration = Duration::from_millis(100);
     let mut devices = HashMap::new();
     let mut ignore_list = HashMap::new();
+    let mut watchdog = Watchdog::default();
+    let mut audit = AuditLog::default();
+    let mut commands = CommandQueue::new(rx);
+    let mut status_publisher = StatusPublisher::new();
+    let mut sleep_detector = SleepDetector::new();
+
+    // Devices which support interrupts report changes here, so they can be read straight away
+    // rather than waiting for the next poll.
+    let (interrupt_tx, mut interrupt_rx) = mpsc::unbounded_channel::<String>();
+    let mut interrupt_watchers = HashMap::new();
+
+    // A burst of commands (such as a UI applying a profile one setting at a time) mustn't hold up
+    // reacting to the device itself, so the branches below are checked in order: the device's
+    // interrupts, shutting down, polling the device when it's due, and then the next command. The
+    // device only raises interrupts and is polled so often, so commands are never starved.
+    let mut poll = interval(poll_interval);
+    poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut bleep_ramp = interval(BLEEP_RAMP_STEP);
+    bleep_ramp.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
     loop {
+        let bleep_rising = devices.values().any(Device::is_bleep_ramping);
         tokio::select! {
-            () = sleep(sleep_duration) => {
-                if loop_count == detect_count {
-                    if let Some((device, descriptor)) = find_new_device(&devices, &ignore_list) {
+            biased;
+
+            Some(serial) = interrupt_rx.recv() => {
+                if let Some(device) = devices.get_mut(&serial) {
+                    if !monitor_device(&serial, device, &mut watchdog, &obs).await {
+                        remove_failed_devices(&mut devices, &mut watchdog, &settings).await;
+                        interrupt_watchers.retain(|serial, _| devices.contains_key(serial));
+                    }
+                    publish_status(&mut status_publisher, &mut devices, &settings, &mut file_manager, &obs, &updates, &scripts).await;
+                }
+            },
+            () = shutdown.recv() => {
+                info!("Shutting down device worker");
+                snapshot_devices(&devices, &settings).await;
+                return;
+            },
+            _ = poll.tick() => {
+                if let Some(mock) = options.mock_device.take() {
+                    match load_device(mock, &settings, options.locked).await {
+                        Ok(device) => {
+                            devices.insert(device.serial().to_owned(), device);
+                        }
+                        Err(e) => error!("Couldn't load the mock device: {}", e),
+                    }
+                }
+
+                if !mock_mode && Instant::now() >= next_detect {
+                    if let Some(device) = find_new_device(&devices, &ignore_list) {
                     let bus_number = device.bus_number();
                     let address = device.address();
-                        match load_device(device, descriptor, &settings).await {
+                        match load_usb_device(device, &settings, retry_policy, options.usb_capture.clone(), options.locked).await {
                             Ok(device) => {
-                                devices.insert(device.serial().to_owned(), device);
+                                let serial = device.serial().to_owned();
+                                if let Some(watcher) = watch_interrupts(&device, &interrupt_tx) {
+                                    interrupt_watchers.insert(serial.clone(), watcher);
+                                }
+                                devices.insert(serial, device);
                             }
                             Err(e) => {
                                 error!(
@@ -55,134 +273,651 @@ pub async fn handle_changes(
                             }
                         };
                     }
-                    loop_count = -1;
+                    next_detect = Instant::now() + detect_interval;
                 }
-                loop_count += 1;
-                let mut found_error = false;
-                for device in devices.values_mut() {
-                    if let Err(e) = device.monitor_inputs().await {
-                        error!("Couldn't monitor device for inputs: {}", e);
-                        found_error = true;
+
+                if let Some(slept) = sleep_detector.check() {
+                    info!("Woke from sleep after {}s, re-applying device settings", slept.as_secs());
+                    for (serial, device) in devices.iter_mut() {
+                        let result = device.resume();
+                        if let Err(e) = &result {
+                            warn!("Couldn't re-apply the settings to {}: {}", serial, e);
+                        }
+                        watchdog.record(serial, &result);
                     }
                 }
+
+                let mut found_error = false;
+                for (serial, device) in devices.iter_mut() {
+                    found_error |= !monitor_device(serial, device, &mut watchdog, &obs).await;
+                }
                 if found_error {
-                    devices.retain(|_, d| d.is_connected());
+                    remove_failed_devices(&mut devices, &mut watchdog, &settings).await;
+                    interrupt_watchers.retain(|serial, _| devices.contains_key(serial));
+                }
+
+                publish_status(&mut status_publisher, &mut devices, &settings, &mut file_manager, &obs, &updates, &scripts).await;
+
+                if Instant::now() >= next_snapshot {
+                    snapshot_devices(&devices, &settings).await;
+                    next_snapshot = Instant::now() + snapshot_interval;
+                }
+
+                if Instant::now() >= next_autosave {
+                    autosave_devices(&mut devices).await;
+                    next_autosave = Instant::now() + autosave_interval;
                 }
             },
-            () = shutdown.recv() => {
-                info!("Shutting down device worker");
-                return;
+            _ = bleep_ramp.tick(), if bleep_rising => {
+                for (serial, device) in devices.iter_mut() {
+                    if let Err(e) = device.update_bleep_ramp() {
+                        warn!("Couldn't raise the bleep on {}: {}", serial, e);
+                    }
+                }
             },
-            Some(command) = rx.recv() => {
+            Some(command) = commands.recv() => {
                 match command {
                     DeviceCommand::SendDaemonStatus(sender) => {
-                        let mut status = DaemonStatus {
-                            paths: Paths {
-                                profile_directory: settings.get_profile_directory().await,
-                                mic_profile_directory: settings.get_mic_profile_directory().await,
-                                samples_directory: settings.get_samples_directory().await,
-                            },
-                            files: Files {
-                                profiles: file_manager.get_profiles(&settings),
-                                mic_profiles: file_manager.get_mic_profiles(&settings),
-                            },
-                            ..Default::default()
+                        let status = get_daemon_status(&devices, &settings, &mut file_manager, &obs, &updates, &scripts).await;
+                        let _ = sender.send(status);
+                    },
+                    DeviceCommand::Subscribe(sender) => {
+                        let status = get_daemon_status(&devices, &settings, &mut file_manager, &obs, &updates, &scripts).await;
+                        let _ = sender.send(status_publisher.subscribe(&status));
+                    },
+                    DeviceCommand::RunDeviceCommand(serial, command, client, sender) => {
+                        if let Some(device) = devices.get_mut(&serial) {
+                            let result = device.perform_command(command.clone()).await;
+                            watchdog.record(&serial, &result);
+                            audit.record(&client, &serial, &[command], &result);
+                            let _ = sender.send(result);
+                            device.check_hook_state().await;
+                            send_mute_changes(device, &obs);
+                        } else {
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
+                        }
+                    },
+                    DeviceCommand::RunDeviceCommandBatch(serial, commands, rollback, client, sender) => {
+                        if let Some(device) = devices.get_mut(&serial) {
+                            let result = device.perform_batch(commands.clone(), rollback).await;
+                            watchdog.record(&serial, &result);
+                            audit.record(&client, &serial, &commands, &result);
+                            let _ = sender.send(result);
+                            device.check_hook_state().await;
+                            send_mute_changes(device, &obs);
+                        } else {
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
+                        }
+                    },
+                    DeviceCommand::ValidateDeviceCommand(serial, command, sender) => {
+                        if let Some(device) = devices.get(&serial) {
+                            let _ = sender.send(device.validate_command(&command).await);
+                        } else {
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
+                        }
+                    },
+                    DeviceCommand::SetObsSettings(mut obs_settings, sender) => {
+                        // The status never includes the password, so keep it unless replaced.
+                        match obs_settings.password.as_deref() {
+                            None => obs_settings.password = settings.get_obs_settings().await.password,
+                            Some("") => obs_settings.password = None,
+                            Some(_) => {}
+                        }
+                        settings.set_obs_settings(obs_settings).await;
+                        settings.save().await;
+                        obs.settings_changed();
+                        let _ = sender.send(Ok(()));
+                    },
+                    DeviceCommand::SetUpdateSettings(update_settings, sender) => {
+                        settings.set_update_settings(update_settings).await;
+                        settings.save().await;
+                        updates.settings_changed();
+                        let _ = sender.send(Ok(()));
+                    },
+                    DeviceCommand::SetScriptEnabled(name, enabled, sender) => {
+                        if scripts.status().iter().any(|script| script.name == name) {
+                            settings.set_script_enabled(&name, enabled).await;
+                            settings.save().await;
+                            scripts.reload();
+                            let _ = sender.send(Ok(()));
+                        } else {
+                            let _ = sender.send(Err(anyhow!("There's no script named {}", name)));
+                        }
+                    },
+                    DeviceCommand::ReloadScripts(sender) => {
+                        scripts.reload();
+                        let _ = sender.send(());
+                    },
+                    DeviceCommand::SetCustomUiDirectory(directory, sender) => {
+                        let result = match directory {
+                            Some(directory) if !directory.is_dir() => {
+                                Err(anyhow!("{} is not a directory", directory.to_string_lossy()))
+                            }
+                            directory => {
+                                settings.set_custom_ui_directory(directory).await;
+                                settings.save().await;
+                                Ok(())
+                            }
                         };
-                        for (serial, device) in &devices {
-                            status.mixers.insert(serial.to_owned(), device.status().clone());
+                        let _ = sender.send(result);
+                    },
+                    DeviceCommand::GetDaemonHealth(sender) => {
+                        let health = get_daemon_health(&devices, &watchdog, &settings, started, &options.http).await;
+                        let _ = sender.send(health);
+                    },
+                    DeviceCommand::GetAuditLog(sender) => {
+                        let _ = sender.send(audit.entries());
+                    },
+                    DeviceCommand::GetUiUrl(sender) => {
+                        let _ = sender.send(get_ui_url(&options.http));
+                    },
+                    DeviceCommand::RestartDaemon(sender) => {
+                        info!("Restart requested");
+                        let _ = sender.send(());
+                        shutdown.trigger_restart();
+                    },
+                    DeviceCommand::GetDeviceSummary(sender) => {
+                        let mut summary: Vec<DeviceSummary> = devices.values().map(|device| get_device_summary(&device.status())).collect();
+                        summary.sort_by(|a, b| a.serial.cmp(&b.serial));
+                        let _ = sender.send(summary);
+                    },
+                    DeviceCommand::SubscribeNotifications(sender) => {
+                        let status = get_daemon_status(&devices, &settings, &mut file_manager, &obs, &updates, &scripts).await;
+                        let _ = sender.send(status_publisher.subscribe_notifications(&status));
+                    },
+                    DeviceCommand::GetSettingsInfo(sender) => {
+                        let _ = sender.send(settings.get_settings_info().await);
+                    },
+                    DeviceCommand::FlushSettings(sender) => {
+                        let _ = sender.send(settings.flush().await);
+                    },
+                    DeviceCommand::GetDeviceSettings(serial, sender) => {
+                        let device_settings = match serial {
+                            Some(serial) => settings.get_device_settings(&serial).await,
+                            None => settings.get_default_device_settings().await,
+                        };
+                        let _ = sender.send(device_settings);
+                    },
+                    DeviceCommand::SetDeviceSettings(serial, device_settings, sender) => {
+                        let result = set_device_settings(&mut devices, &settings, serial, *device_settings).await;
+                        let _ = sender.send(result);
+                    },
+                    DeviceCommand::VerifyState(serial, sender) => {
+                        if let Some(device) = devices.get_mut(&serial) {
+                            let result = device.verify_state();
+                            watchdog.record(&serial, &result);
+                            let _ = sender.send(result);
+                        } else {
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
                         }
-                        let _ = sender.send(status);
                     },
-                    DeviceCommand::RunDeviceCommand(serial, command, sender) => {
+                    DeviceCommand::GetMuteState(serial, sender) => {
+                        match devices.get(&serial) {
+                            Some(device) => {
+                                let _ = sender.send(Ok(device.get_mute_state_report()));
+                            }
+                            None => {
+                                let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
+                            }
+                        }
+                    },
+                    DeviceCommand::GetHardwareInfo(serial, sender) => {
+                        if let Some(device) = devices.get_mut(&serial) {
+                            let _ = sender.send(device.hardware_info());
+                        } else {
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
+                        }
+                    },
+                    DeviceCommand::SendRawCommand(serial, command_id, body, sender) => {
+                        if !settings.get_allow_raw_usb_commands().await {
+                            let message = "Raw USB commands are disabled in the daemon's settings";
+                            let _ = sender.send(Err(DaemonError::Unsupported(message.to_string()).into()));
+                        } else if let Some(device) = devices.get_mut(&serial) {
+                            let result = device.send_raw_command(command_id, &body);
+                            watchdog.record(&serial, &result);
+                            let _ = sender.send(result);
+                        } else {
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
+                        }
+                    },
+                    DeviceCommand::RunDiagnostics(serial, sender) => {
+                        if let Some(device) = devices.get_mut(&serial) {
+                            let _ = sender.send(Ok(device.run_diagnostics().await));
+                        } else {
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
+                        }
+                    },
+                    DeviceCommand::ExportMicProfile(serial, sender) => {
+                        if let Some(device) = devices.get(&serial) {
+                            let _ = sender.send(device.mic_profile().to_json());
+                        } else {
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
+                        }
+                    },
+                    DeviceCommand::GetSamples(sender) => {
+                        let _ = sender.send(file_manager.get_samples(&settings).await);
+                    },
+                    DeviceCommand::FindSamples(query, sender) => {
+                        let _ = sender.send(file_manager.find_samples(&settings, &query).await);
+                    },
+                    DeviceCommand::AddSample(path, data, sender) => {
+                        let _ = sender.send(file_manager.add_sample(&settings, &path, &data).await);
+                    },
+                    DeviceCommand::MoveSample(from, to, sender) => {
+                        let _ = sender.send(file_manager.move_sample(&settings, &from, &to).await);
+                    },
+                    DeviceCommand::DeleteSample(path, sender) => {
+                        let _ = sender.send(file_manager.delete_sample(&settings, &path).await);
+                    },
+                    DeviceCommand::GetSampleWaveform(path, points, sender) => {
+                        let waveform = file_manager.get_sample_waveform(&settings, &path, points).await;
+                        let _ = sender.send(waveform);
+                    },
+                    DeviceCommand::GetProfileBackups(profile_type, name, sender) => {
+                        let backups = file_manager.get_profile_backups(&settings, profile_type, &name).await;
+                        let _ = sender.send(backups);
+                    },
+                    DeviceCommand::RestoreProfileBackup(profile_type, name, id, sender) => {
+                        let result = file_manager.restore_profile_backup(&settings, profile_type, &name, &id).await;
+                        let _ = sender.send(result);
+                    },
+                    DeviceCommand::StartMicCalibration(serial, mic_type, apply, sender) => {
                         if let Some(device) = devices.get_mut(&serial) {
-                            let _ = sender.send(device.perform_command(command).await);
+                            let _ = sender.send(device.start_mic_calibration(mic_type, apply));
                         } else {
-                            let _ = sender.send(Err(anyhow!("Device {} is not connected", serial)));
+                            let _ = sender.send(Err(DaemonError::DeviceNotFound(serial).into()));
                         }
                     },
                 }
+                publish_status(&mut status_publisher, &mut devices, &settings, &mut file_manager, &obs, &updates, &scripts).await;
             },
         };
     }
 }
 
-fn find_new_device(
-    existing_devices: &HashMap<String, Device<GlobalContext>>,
-    devices_to_ignore: &HashMap<(u8, u8), Instant>,
-) -> Option<(rusb::Device<GlobalContext>, DeviceDescriptor)> {
-    let now = Instant::now();
-    if let Ok(devices) = rusb::devices() {
-        for device in devices.iter() {
-            if let Ok(descriptor) = device.device_descriptor() {
-                let bus_number = device.bus_number();
-                let address = device.address();
-                if descriptor.vendor_id() == VID_GOXLR
-                    && (descriptor.product_id() == PID_GOXLR_FULL
-                        || descriptor.product_id() == PID_GOXLR_MINI)
-                    && !existing_devices.values().any(|d| {
-                        d.status().hardware.usb_device.bus_number == bus_number
-                            && d.status().hardware.usb_device.address == address
-                    })
-                    && !devices_to_ignore
-                        .iter()
-                        .any(|((bus_number, address), expires)| {
-                            *bus_number == device.bus_number()
-                                && *address == device.address()
-                                && expires > &now
-                        })
-                {
-                    return Some((device, descriptor));
+// Somewhere the HTTP server can be reached from this machine, preferring loopback.
+fn get_ui_url(http: &HttpServerStatus) -> Result<String> {
+    if !http.enabled {
+        return Err(anyhow!("The HTTP server is disabled"));
+    }
+
+    let addresses: Vec<SocketAddr> = http
+        .addresses
+        .iter()
+        .filter_map(|address| address.parse().ok())
+        .collect();
+    let address = addresses
+        .iter()
+        .find(|address| address.ip().is_loopback() || address.ip().is_unspecified())
+        .or_else(|| addresses.first())
+        .ok_or_else(|| anyhow!("The HTTP server has no address"))?;
+
+    match address.ip().is_loopback() || address.ip().is_unspecified() {
+        true => Ok(format!("http://localhost:{}/", address.port())),
+        false => Ok(format!("http://{}/", address)),
+    }
+}
+
+async fn get_daemon_status(
+    devices: &HashMap<String, Device<'_>>,
+    settings: &SettingsHandle,
+    file_manager: &mut FileManager,
+    obs: &ObsHandle,
+    updates: &UpdateHandle,
+    scripts: &ScriptHandle,
+) -> DaemonStatus {
+    let mut status = DaemonStatus {
+        paths: Paths {
+            profile_directory: settings.get_profile_directory().await,
+            mic_profile_directory: settings.get_mic_profile_directory().await,
+            samples_directory: settings.get_samples_directory().await,
+            theme_directory: settings.get_theme_directory().await,
+            script_directory: settings.get_script_directory().await,
+            custom_ui_directory: settings.get_custom_ui_directory().await,
+        },
+        files: Files {
+            profiles: file_manager.get_profiles(settings).await,
+            mic_profiles: file_manager.get_mic_profiles(settings).await,
+            themes: file_manager.get_themes(settings).await,
+            embedded_profiles: ProfileAdapter::get_embedded_names(),
+        },
+        obs: ObsStatus {
+            connected: obs.is_connected(),
+            settings: ObsSettings {
+                password: None,
+                ..settings.get_obs_settings().await
+            },
+        },
+        updates: UpdateStatus {
+            settings: settings.get_update_settings().await,
+            ..updates.status()
+        },
+        scripts: scripts.status(),
+        ..Default::default()
+    };
+    for (serial, device) in devices {
+        status
+            .mixers
+            .insert(serial.to_owned(), device.status().clone());
+    }
+    status
+}
+
+async fn publish_status(
+    publisher: &mut StatusPublisher,
+    devices: &mut HashMap<String, Device<'_>>,
+    settings: &SettingsHandle,
+    file_manager: &mut FileManager,
+    obs: &ObsHandle,
+    updates: &UpdateHandle,
+    scripts: &ScriptHandle,
+) {
+    // No point building the status if nobody is listening for changes.
+    if publisher.has_subscribers() {
+        let status =
+            get_daemon_status(devices, settings, file_manager, obs, updates, scripts).await;
+        publisher.update(&status);
+    }
+
+    // Events go out after the status, so anything they refer to is already there.
+    for device in devices.values_mut() {
+        for event in device.take_events() {
+            publisher.publish_event(event);
+        }
+    }
+}
+
+fn send_mute_changes(device: &mut Device<'_>, obs: &ObsHandle) {
+    for (channel, muted) in device.take_mute_changes() {
+        obs.channel_muted(channel, muted);
+    }
+}
+
+async fn snapshot_devices(devices: &HashMap<String, Device<'_>>, settings: &SettingsHandle) {
+    let state_directory = settings.get_state_directory().await;
+    for (serial, device) in devices {
+        if let Err(e) = write_snapshot(
+            &state_directory,
+            serial,
+            device.profile(),
+            device.mic_profile(),
+        ) {
+            error!("Couldn't write state snapshot for {}: {}", serial, e);
+        }
+    }
+}
+
+async fn autosave_devices(devices: &mut HashMap<String, Device<'_>>) {
+    for (serial, device) in devices.iter_mut() {
+        if let Err(e) = device.autosave().await {
+            error!("Couldn't autosave profiles for {}: {}", serial, e);
+        }
+    }
+}
+
+// Returns false if the device couldn't be read.
+async fn monitor_device(
+    serial: &str,
+    device: &mut Device<'_>,
+    watchdog: &mut Watchdog,
+    obs: &ObsHandle,
+) -> bool {
+    let result = device.monitor_inputs().await;
+    if let Err(e) = &result {
+        error!("Couldn't monitor device for inputs: {}", e);
+    }
+    watchdog.record(serial, &result);
+    send_mute_changes(device, obs);
+    result.is_ok()
+}
+
+async fn get_daemon_health(
+    devices: &HashMap<String, Device<'_>>,
+    watchdog: &Watchdog,
+    settings: &SettingsHandle,
+    started: Instant,
+    http: &HttpServerStatus,
+) -> DaemonHealth {
+    let mut devices: Vec<DeviceHealth> = devices
+        .values()
+        .map(|device| {
+            let errors = watchdog.errors.get(device.serial());
+            DeviceHealth {
+                serial: device.serial().to_owned(),
+                device_type: device.hardware().device_type.clone(),
+                usb_errors: errors.map(|e| e.count).unwrap_or(0),
+                last_error: errors.and_then(|e| e.last.clone()),
+            }
+        })
+        .collect();
+    devices.sort_by(|a, b| a.serial.cmp(&b.serial));
+
+    DaemonHealth {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_secs: started.elapsed().as_secs(),
+        devices,
+        http: http.clone(),
+        settings_path: settings.get_settings_info().await.path,
+    }
+}
+
+async fn set_device_settings(
+    devices: &mut HashMap<String, Device<'_>>,
+    settings: &SettingsHandle,
+    serial: Option<String>,
+    device_settings: DeviceSettings,
+) -> Result<()> {
+    validate_device_settings(&device_settings)?;
+    match serial {
+        Some(serial) => {
+            settings.set_device_settings(&serial, device_settings).await;
+            settings.save().await;
+            if let Some(device) = devices.get_mut(&serial) {
+                device.reload_device_settings().await?;
+            }
+        }
+        None => {
+            settings.set_default_device_settings(device_settings).await;
+            settings.save().await;
+        }
+    }
+    Ok(())
+}
+
+async fn remove_failed_devices(
+    devices: &mut HashMap<String, Device<'_>>,
+    watchdog: &mut Watchdog,
+    settings: &SettingsHandle,
+) {
+    devices.retain(|_, d| d.is_connected());
+
+    let state_directory = settings.get_state_directory().await;
+    for serial in watchdog.take_failed_devices() {
+        if let Some(device) = devices.remove(&serial) {
+            warn!(
+                "GoXLR {} has stopped responding, re-initialising the device",
+                serial
+            );
+
+            // The device is loaded from the snapshot when it's detected again.
+            if let Err(e) = write_snapshot(
+                &state_directory,
+                &serial,
+                device.profile(),
+                device.mic_profile(),
+            ) {
+                error!("Couldn't write state snapshot for {}: {}", serial, e);
+            }
+        }
+    }
+}
+
+struct InterruptWatcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for InterruptWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// Spawns a thread which waits for interrupts from the device, returning None if the device
+// can't provide them (for example, when the interface is held by another driver).
+fn watch_interrupts(
+    device: &Device,
+    sender: &mpsc::UnboundedSender<String>,
+) -> Option<InterruptWatcher> {
+    let serial = device.serial().to_owned();
+    let reader = match device.interrupt_reader() {
+        Ok(reader) => reader,
+        Err(e) => {
+            info!(
+                "Interrupts unavailable for {}, polling instead: {}",
+                serial, e
+            );
+            return None;
+        }
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let sender = sender.clone();
+    thread::spawn(move || {
+        while !thread_stop.load(Ordering::Relaxed) {
+            match reader.wait(INTERRUPT_TIMEOUT) {
+                Ok(true) => {
+                    if sender.send(serial.clone()).is_err() {
+                        return;
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    info!(
+                        "Stopped reading interrupts from {}, polling instead: {}",
+                        serial, e
+                    );
+                    return;
                 }
             }
         }
+    });
+
+    Some(InterruptWatcher { stop })
+}
+
+fn find_new_device(
+    existing_devices: &HashMap<String, Device>,
+    devices_to_ignore: &HashMap<(u8, u8), Instant>,
+) -> Option<DetectedDevice> {
+    let now = Instant::now();
+    find_devices().ok()?.into_iter().find(|device| {
+        let bus_number = device.bus_number();
+        let address = device.address();
+        !existing_devices.values().any(|d| {
+            d.status().hardware.usb_device.bus_number == bus_number
+                && d.status().hardware.usb_device.address == address
+        }) && !devices_to_ignore
+            .iter()
+            .any(|((bus_number, address), expires)| {
+                *bus_number == device.bus_number() && *address == device.address() && expires > &now
+            })
+    })
+}
+
+async fn load_usb_device(
+    device: DetectedDevice,
+    settings: &SettingsHandle,
+    retry_policy: RetryPolicy,
+    capture: Option<UsbCapture>,
+    locked: bool,
+) -> Result<Device<'_>> {
+    let mut device = device.open()?;
+    device.set_retry_policy(retry_policy);
+    if let Some(capture) = capture {
+        device.set_capture(capture);
     }
-    None
+    load_device(Box::new(device), settings, locked).await
 }
 
 async fn load_device(
-    device: rusb::Device<GlobalContext>,
-    descriptor: DeviceDescriptor,
+    mut device: Box<dyn GoXlrDevice>,
     settings: &SettingsHandle,
-) -> Result<Device<'_, GlobalContext>> {
-    let mut device = GoXLR::from_device(device.open()?, descriptor)?;
-    let descriptor = device.usb_device_descriptor();
-    let device_type = match descriptor.product_id() {
-        goxlr::PID_GOXLR_FULL => DeviceType::Full,
-        goxlr::PID_GOXLR_MINI => DeviceType::Mini,
-        _ => DeviceType::Unknown,
+    locked: bool,
+) -> Result<Device<'_>> {
+    let usb_info = device.get_usb_info()?;
+    let device_type = match usb_info.is_mini {
+        true => DeviceType::Mini,
+        false => DeviceType::Full,
     };
-    let device_version = descriptor.device_version();
-    let version = (device_version.0, device_version.1, device_version.2);
     let usb_device = UsbProductInformation {
-        manufacturer_name: device.usb_device_manufacturer()?,
-        product_name: device.usb_device_product_name()?,
-        is_claimed: device.usb_device_is_claimed(),
-        has_kernel_driver_attached: device.usb_device_has_kernel_driver_active()?,
-        bus_number: device.usb_bus_number(),
-        address: device.usb_address(),
-        version,
+        manufacturer_name: usb_info.manufacturer_name,
+        product_name: usb_info.product_name,
+        is_claimed: usb_info.is_claimed,
+        has_kernel_driver_attached: usb_info.has_kernel_driver_attached,
+        bus_number: usb_info.bus_number,
+        address: usb_info.address,
+        version: usb_info.version,
     };
     let (serial_number, manufactured_date) = device.get_serial_number()?;
+    let versions = device.get_firmware_version()?;
     let hardware = HardwareStatus {
-        versions: device.get_firmware_version()?,
         serial_number: serial_number.clone(),
         manufactured_date,
+        features: get_device_features(&device_type, &versions.firmware),
         device_type,
+        versions,
         usb_device,
     };
     let profile_directory = settings.get_profile_directory().await;
-    let profile_name = settings.get_device_profile_name(&serial_number).await;
-    let mic_profile_name = settings.get_device_mic_profile_name(&serial_number).await;
+
+    // Devices which haven't been seen before get the default device settings.
+    let mut device_settings = settings.get_device_settings(&serial_number).await;
+    let profile_rules = settings.get_profile_rules().await;
+    if let Some(rule) = find_profile_rule(&profile_rules, &serial_number, &hardware.device_type) {
+        info!("Choosing startup profiles with rule {:?}", rule);
+        if let Some(profile) = &rule.profile {
+            device_settings.profile = profile.clone();
+        }
+        if let Some(mic_profile) = &rule.mic_profile {
+            device_settings.mic_profile = mic_profile.clone();
+        }
+    }
+    let profile_name = device_settings.profile.clone();
+    let mic_profile_name = device_settings.mic_profile.clone();
     let mic_profile_directory = settings.get_mic_profile_directory().await;
-    let device = Device::new(
+    let state_directory = settings.get_state_directory().await;
+
+    let (profile, mic_profile) = match load_snapshot(
+        &state_directory,
+        &serial_number,
+        Some(&profile_name),
+        Some(&mic_profile_name),
+    ) {
+        Some(snapshot) => snapshot,
+        None => {
+            info!("Loading Profile: {}", profile_name);
+            info!("Loading Mic Profile: {}", mic_profile_name);
+            (
+                ProfileAdapter::from_named_or_default(Some(profile_name), vec![&profile_directory]),
+                MicProfileAdapter::from_named_or_default(
+                    Some(mic_profile_name),
+                    vec![&mic_profile_directory],
+                ),
+            )
+        }
+    };
+    let mut device = Device::new(
         device,
         hardware,
-        profile_name,
-        mic_profile_name,
-        &profile_directory,
-        &mic_profile_directory,
+        profile,
+        mic_profile,
         settings,
+        device_settings,
     )?;
+    if locked {
+        device.set_locked(true)?;
+    }
     settings
         .set_device_profile_name(&serial_number, device.profile().name())
         .await;
@@ -190,5 +925,72 @@ async fn load_device(
         .set_device_mic_profile_name(&serial_number, device.mic_profile().name())
         .await;
     settings.save().await;
+
+    run_hooks(
+        settings,
+        HookEvent::DeviceConnected,
+        &serial_number,
+        json!({}),
+    )
+    .await;
     Ok(device)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goxlr_types::{ChannelName, FaderName, InputDevice, MuteFunction, OutputDevice};
+    use goxlr_usb::mock::MockGoXLR;
+    use goxlr_usb::trace::{SharedTrace, TraceRecorder, UsbTrace};
+    use std::fs;
+
+    // Recorded with --record-trace and --mock-device full, sending the same commands over HTTP.
+    const EXPECTED_TRACE: &str = include_str!("../test-data/mock_full.trace");
+
+    #[tokio::test]
+    async fn mock_device_writes_match_the_trace() {
+        // Everything is kept in a directory of its own, so nothing from a real install is loaded.
+        let directory = std::env::temp_dir().join(format!("goxlr-trace-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let config = json!({
+            "profile_directory": directory.join("profiles"),
+            "mic_profile_directory": directory.join("mic-profiles"),
+            "samples_directory": directory.join("samples"),
+            "state_directory": directory.join("state"),
+            "theme_directory": directory.join("themes"),
+            "script_directory": directory.join("scripts"),
+            "devices": {},
+        });
+        let settings_path = directory.join("settings.json");
+        fs::write(&settings_path, config.to_string()).unwrap();
+        let settings = SettingsHandle::load(settings_path).await.unwrap();
+
+        let trace = SharedTrace::default();
+        let mock = TraceRecorder::new(MockGoXLR::new(false, vec![]), trace.clone());
+        let mut device = load_device(Box::new(mock), &settings, false).await.unwrap();
+
+        let commands = [
+            GoXLRCommand::SetVolume(ChannelName::Music, 128),
+            GoXLRCommand::SetFader(FaderName::A, ChannelName::Game),
+            GoXLRCommand::SetFaderMuteFunction(FaderName::B, MuteFunction::ToStream),
+            GoXLRCommand::SetRouter(InputDevice::Microphone, OutputDevice::Headphones, false),
+        ];
+        for command in commands {
+            device.perform_command(command).await.unwrap();
+        }
+        drop(device);
+        let _ = fs::remove_dir_all(&directory);
+
+        let expected = UsbTrace::parse(EXPECTED_TRACE).unwrap();
+        let actual = trace.lock().unwrap().clone();
+        if let Some(difference) = expected.first_difference(&actual) {
+            panic!(
+                "Write {} differs from the trace, expected {:?} but sent {:?}\n\n{}",
+                difference.index + 1,
+                difference.expected.map(|e| e.to_string()),
+                difference.actual.map(|e| e.to_string()),
+                actual
+            );
+        }
+    }
+}
@@ -1,7 +1,11 @@
+use crate::backups::{backup_file, backup_file_for_autosave};
 use anyhow::{anyhow, Context, Result};
 use enum_map::EnumMap;
 use enumset::EnumSet;
-use goxlr_ipc::{ButtonLighting, CoughButton, FaderLighting, Lighting, TwoColours};
+use goxlr_ipc::{
+    ButtonLighting, CoughButton, DaemonError, FaderLighting, HsvColour, Lighting, SampleTrack,
+    SamplerStatus, SubMixes, TwoColours,
+};
 use goxlr_profile_loader::components::colours::{
     Colour, ColourDisplay, ColourMap, ColourOffStyle, ColourState,
 };
@@ -15,19 +19,21 @@ use goxlr_profile_loader::components::mute_chat::{CoughToggle, MuteChat};
 use goxlr_profile_loader::components::pitch::{PitchEncoder, PitchStyle};
 use goxlr_profile_loader::components::reverb::ReverbEncoder;
 use goxlr_profile_loader::components::robot::RobotEffect;
-use goxlr_profile_loader::components::sample::SampleBank;
+use goxlr_profile_loader::components::sample::{SampleBank, SampleStack, Track};
 use goxlr_profile_loader::components::simple::SimpleElements;
+use goxlr_profile_loader::error::ParseError;
 use goxlr_profile_loader::profile::{Profile, ProfileSettings};
 use goxlr_profile_loader::SampleButtons;
 use goxlr_profile_loader::SampleButtons::{BottomLeft, BottomRight, Clear, TopLeft, TopRight};
 use goxlr_types::{
     ButtonColourGroups, ButtonColourOffStyle as BasicColourOffStyle, ButtonColourTargets,
-    ChannelName, EffectBankPresets, FaderDisplayStyle as BasicColourDisplay, FaderName,
-    InputDevice, MuteFunction as BasicMuteFunction, OutputDevice, VersionNumber,
+    ChannelName, CoughButtonMode, EffectBankPresets, FaderDisplayStyle as BasicColourDisplay,
+    FaderName, InputDevice, MuteFunction as BasicMuteFunction, OutputDevice, RgbColour,
+    VersionNumber,
 };
 use goxlr_usb::buttonstate::{ButtonStates, Buttons};
 use goxlr_usb::colouring::ColourTargets;
-use log::error;
+use log::{debug, error};
 use std::collections::HashMap;
 use std::fs::{create_dir_all, File};
 use std::io::{Cursor, Read, Seek};
@@ -36,7 +42,25 @@ use strum::EnumCount;
 use strum::IntoEnumIterator;
 
 pub const DEFAULT_PROFILE_NAME: &str = "Default - Vaporwave";
-const DEFAULT_PROFILE: &[u8] = include_bytes!("../profiles/Default - Vaporwave.goxlr");
+
+// Profiles built into the daemon, these can be loaded by name (or set as the profile for new
+// devices) without being copied into the profile directory first. A profile in the directory
+// with the same name takes priority.
+const EMBEDDED_PROFILES: [(&str, &[u8]); 4] = [
+    (
+        DEFAULT_PROFILE_NAME,
+        include_bytes!("../profiles/Default - Vaporwave.goxlr"),
+    ),
+    (
+        "Default - Red",
+        include_bytes!("../profiles/Default - Red.goxlr"),
+    ),
+    (
+        "Default - Teal Blue",
+        include_bytes!("../profiles/Default - Teal Blue.goxlr"),
+    ),
+    ("Sleep", include_bytes!("../profiles/Sleep.goxlr")),
+];
 
 #[derive(Debug)]
 pub struct ProfileAdapter {
@@ -70,31 +94,59 @@ impl ProfileAdapter {
             dir_list = format!("{}, {}", dir_list, directory.to_string_lossy());
         }
 
-        if name == DEFAULT_PROFILE_NAME {
-            return Ok(ProfileAdapter::default());
+        if ProfileAdapter::is_embedded(&name) {
+            return ProfileAdapter::from_embedded(&name);
         }
 
-        Err(anyhow!(
-            "Profile {} does not exist inside {:?}",
-            name,
-            dir_list
-        ))
+        debug!("Profile {} does not exist inside {:?}", name, dir_list);
+        Err(DaemonError::ProfileNotFound(name).into())
+    }
+
+    pub fn exists(name: &str, directories: Vec<&Path>) -> bool {
+        ProfileAdapter::is_embedded(name)
+            || directories
+                .iter()
+                .any(|directory| directory.join(format!("{}.goxlr", name)).is_file())
     }
 
     pub fn default() -> Self {
-        ProfileAdapter::from_reader(
-            DEFAULT_PROFILE_NAME.to_string(),
-            Cursor::new(DEFAULT_PROFILE),
-        )
-        .expect("Default profile isn't available")
+        ProfileAdapter::from_embedded(DEFAULT_PROFILE_NAME)
+            .expect("Default profile isn't available")
+    }
+
+    pub fn from_embedded(name: &str) -> Result<Self> {
+        let (name, profile) = EMBEDDED_PROFILES
+            .iter()
+            .find(|(embedded, _)| *embedded == name)
+            .ok_or_else(|| DaemonError::ProfileNotFound(name.to_owned()))?;
+        ProfileAdapter::from_reader(name.to_string(), Cursor::new(profile))
+    }
+
+    pub fn is_embedded(name: &str) -> bool {
+        EMBEDDED_PROFILES
+            .iter()
+            .any(|(embedded, _)| *embedded == name)
+    }
+
+    pub fn get_embedded_names() -> Vec<String> {
+        EMBEDDED_PROFILES
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect()
     }
 
     pub fn from_reader<R: Read + Seek>(name: String, reader: R) -> Result<Self> {
-        let profile = Profile::load(reader)?;
+        let profile = Profile::load(reader).map_err(|e| invalid_profile(&name, e))?;
         Ok(Self { name, profile })
     }
 
-    pub fn write_profile(&mut self, name: String, directory: &Path, overwrite: bool) -> Result<()> {
+    pub fn write_profile(
+        &mut self,
+        name: String,
+        directory: &Path,
+        overwrite: bool,
+        autosave: bool,
+    ) -> Result<()> {
         let path = directory.join(format!("{}.goxlr", name));
         if !directory.exists() {
             // Attempt to create the profile directory..
@@ -110,6 +162,10 @@ impl ProfileAdapter {
             return Err(anyhow!("Profile exists, will not overwrite"));
         }
 
+        match autosave {
+            true => backup_file_for_autosave(&path)?,
+            false => backup_file(&path)?,
+        }
         self.profile.save(path)?;
 
         // Keep our names in sync (in case it was changed)
@@ -121,6 +177,27 @@ impl ProfileAdapter {
         Ok(())
     }
 
+    // The profile's settings as they'd be saved, to tell whether anything has changed.
+    pub fn get_contents(&self) -> Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.profile.settings().write_to(&mut contents)?;
+        Ok(contents)
+    }
+
+    // Copies the profile by writing it out and reading it back, the loader's types can't be cloned.
+    pub fn try_clone(&self) -> Result<Self> {
+        let mut buffer = Cursor::new(Vec::new());
+        self.profile.write_to(&mut buffer)?;
+        buffer.set_position(0);
+        ProfileAdapter::from_reader(self.name.clone(), buffer)
+    }
+
+    // Writes the live profile to an arbitrary path, without changing its name.
+    pub fn write_snapshot(&self, path: &Path) -> Result<()> {
+        self.profile.save(path)?;
+        Ok(())
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -209,6 +286,25 @@ impl ProfileAdapter {
             .swap(fader_one as usize, fader_two as usize);
     }
 
+    // Colours the top of the fader, its scribble and its mute button, for a channel's colour.
+    pub fn set_fader_channel_colour(&mut self, fader: FaderName, colour: &str) -> Result<()> {
+        let colour = Colour::fromrgb(colour)?;
+        let settings = self.profile.settings_mut();
+        settings
+            .fader_mut(fader as usize)
+            .colour_map_mut()
+            .set_colour(0, colour);
+        settings
+            .scribble_mut(fader as usize)
+            .colour_map_mut()
+            .set_colour(0, colour);
+        settings
+            .mute_button_mut(fader as usize)
+            .colour_map_mut()
+            .set_colour(0, colour);
+        Ok(())
+    }
+
     pub fn set_fader_display(&mut self, fader: FaderName, display: BasicColourDisplay) {
         let colours = self
             .profile
@@ -230,14 +326,6 @@ impl ProfileAdapter {
             .settings_mut()
             .fader_mut(fader as usize)
             .colour_map_mut();
-        if top.len() != 6 || bottom.len() != 6 {
-            return Err(anyhow!(
-                "Expected Length: 6 (RRGGBB), Top: {}, Bottom: {}",
-                top.len(),
-                bottom.len()
-            ));
-        }
-
         colours.set_colour(0, Colour::fromrgb(top.as_str())?);
         colours.set_colour(1, Colour::fromrgb(bottom.as_str())?);
         Ok(())
@@ -266,6 +354,53 @@ impl ProfileAdapter {
             .set_channel_volume(standard_to_profile_channel(channel), volume);
     }
 
+    pub fn get_submix_volume(&self, channel: ChannelName) -> Option<u8> {
+        let channel = standard_to_profile_submix_channel(channel)?;
+        Some(self.profile.settings().mixer().submix(channel).volume())
+    }
+
+    pub fn is_submix_linked(&self, channel: ChannelName) -> bool {
+        match standard_to_profile_submix_channel(channel) {
+            Some(channel) => self.profile.settings().mixer().submix(channel).linked(),
+            None => false,
+        }
+    }
+
+    pub fn set_submix_volume(&mut self, channel: ChannelName, volume: u8) -> Result<()> {
+        let submix_channel = standard_to_profile_submix_channel(channel)
+            .ok_or_else(|| anyhow!("{} does not have a sub mix", channel))?;
+        self.profile
+            .settings_mut()
+            .mixer_mut()
+            .set_submix_volume(submix_channel, volume);
+        Ok(())
+    }
+
+    pub fn set_submix_linked(&mut self, channel: ChannelName, linked: bool) -> Result<()> {
+        let submix_channel = standard_to_profile_submix_channel(channel)
+            .ok_or_else(|| anyhow!("{} does not have a sub mix", channel))?;
+        self.profile
+            .settings_mut()
+            .mixer_mut()
+            .set_submix_linked(submix_channel, linked);
+        Ok(())
+    }
+
+    pub fn get_submixes_ipc(&self) -> SubMixes {
+        let mut submixes = SubMixes {
+            volumes: [0; ChannelName::COUNT],
+            linked: [false; ChannelName::COUNT],
+        };
+
+        for channel in ChannelName::iter() {
+            if let Some(volume) = self.get_submix_volume(channel) {
+                submixes.volumes[channel as usize] = volume;
+                submixes.linked[channel as usize] = self.is_submix_linked(channel);
+            }
+        }
+        submixes
+    }
+
     pub fn get_colour_map(&self, use_format_1_3_40: bool) -> [u8; 520] {
         let mut colour_array = [0; 520];
 
@@ -283,13 +418,8 @@ impl ProfileAdapter {
                     | ColourTargets::SamplerBottomRight
                     | ColourTargets::SamplerTopLeft
                     | ColourTargets::SamplerTopRight => {
-                        if i == 0 {
-                            colour_array[position..position + 4]
-                                .copy_from_slice(&self.get_sampler_lighting(colour));
-                        } else {
-                            colour_array[position..position + 4]
-                                .copy_from_slice(&colour_map.colour(i).to_reverse_bytes());
-                        }
+                        colour_array[position..position + 4]
+                            .copy_from_slice(&self.get_sampler_lighting(colour, i));
                     }
                     _ => {
                         // Update the correct 4 bytes in the map..
@@ -303,25 +433,35 @@ impl ProfileAdapter {
         colour_array
     }
 
-    fn get_sampler_lighting(&self, target: ColourTargets) -> [u8; 4] {
+    fn get_sampler_lighting(&self, target: ColourTargets, index: u8) -> [u8; 4] {
         match target {
-            ColourTargets::SamplerBottomLeft => self.get_colour_array(target, BottomLeft),
-            ColourTargets::SamplerBottomRight => self.get_colour_array(target, BottomRight),
-            ColourTargets::SamplerTopLeft => self.get_colour_array(target, TopLeft),
-            ColourTargets::SamplerTopRight => self.get_colour_array(target, TopRight),
+            ColourTargets::SamplerBottomLeft => self.get_colour_array(target, BottomLeft, index),
+            ColourTargets::SamplerBottomRight => self.get_colour_array(target, BottomRight, index),
+            ColourTargets::SamplerTopLeft => self.get_colour_array(target, TopLeft, index),
+            ColourTargets::SamplerTopRight => self.get_colour_array(target, TopRight, index),
 
             // Honestly, we should never reach this, return nothing.
             _ => [00, 00, 00, 00],
         }
     }
 
-    fn get_colour_array(&self, target: ColourTargets, button: SampleButtons) -> [u8; 4] {
-        if self.current_sample_bank_has_samples(button) {
-            return get_profile_colour_map(self.profile.settings(), target)
-                .colour(0)
-                .to_reverse_bytes();
-        } else {
-            [00, 00, 00, 00]
+    fn get_colour_array(&self, target: ColourTargets, button: SampleButtons, index: u8) -> [u8; 4] {
+        if index == 0 && !self.current_sample_bank_has_samples(button) {
+            return [00, 00, 00, 00];
+        }
+
+        // The selected bank's own colours take the place of the button's.
+        let bank = self.profile.settings().context().selected_sample();
+        let stack = self
+            .profile
+            .settings()
+            .sample_button(button)
+            .get_stack(bank);
+        match stack.colours() {
+            Some(colours) => colours[index as usize].to_reverse_bytes(),
+            None => get_profile_colour_map(self.profile.settings(), target)
+                .colour(index)
+                .to_reverse_bytes(),
         }
     }
 
@@ -386,6 +526,29 @@ impl ProfileAdapter {
         }
     }
 
+    // Applies the colours and styles from a lighting theme, any faders or buttons missing from the
+    // theme are left alone.
+    pub fn set_lighting_ipc(&mut self, lighting: &Lighting) -> Result<()> {
+        for (fader, fader_lighting) in &lighting.faders {
+            self.set_fader_display(*fader, fader_lighting.style);
+            self.set_fader_colours(
+                *fader,
+                fader_lighting.colours.colour_one.clone(),
+                fader_lighting.colours.colour_two.clone(),
+            )?;
+        }
+
+        for (button, button_lighting) in &lighting.buttons {
+            self.set_button_colours(
+                *button,
+                button_lighting.colours.colour_one.clone(),
+                Some(&button_lighting.colours.colour_two),
+            )?;
+            self.set_button_off_style(*button, button_lighting.off_style);
+        }
+        Ok(())
+    }
+
     /** Regular Mute button handlers */
     fn get_mute_button(&self, fader: FaderName) -> &MuteButton {
         self.profile.settings().mute_button(fader as usize)
@@ -465,6 +628,19 @@ impl ProfileAdapter {
         }
     }
 
+    pub fn set_chat_mute_button_mode(&mut self, mode: CoughButtonMode) {
+        self.get_chat_mute_button_mut()
+            .set_cough_behaviour(match mode {
+                CoughButtonMode::Hold => CoughToggle::Hold,
+                CoughButtonMode::Toggle => CoughToggle::Toggle,
+                CoughButtonMode::Hybrid => CoughToggle::Hybrid,
+            });
+    }
+
+    pub fn is_chat_mute_button_hybrid(&self) -> bool {
+        self.get_chat_mute_button().cough_behaviour() == &CoughToggle::Hybrid
+    }
+
     pub fn get_mute_chat_button_state(&self) -> (bool, bool, bool, MuteFunction) {
         let mute_config = self.profile.settings().mute_chat();
 
@@ -524,6 +700,7 @@ impl ProfileAdapter {
             mute_type: profile_to_standard_mute_function(
                 *self.profile.settings().mute_chat().cough_mute_source(),
             ),
+            is_hybrid: self.is_chat_mute_button_hybrid(),
         }
     }
 
@@ -939,6 +1116,20 @@ impl ProfileAdapter {
             .set_state_on(true);
     }
 
+    // Replaces the samples assigned to every button and bank, returning true if any changed.
+    pub fn copy_samples_from(&mut self, other: &ProfileAdapter) -> bool {
+        let mut changed = false;
+        for button in SampleButtons::iter() {
+            let stacks = other.profile.settings().sample_button(button).get_stacks();
+            let sample_button = self.profile.settings_mut().sample_button_mut(button);
+            if sample_button.get_stacks() != stacks {
+                sample_button.set_stacks(stacks.clone());
+                changed = true;
+            }
+        }
+        changed
+    }
+
     pub fn current_sample_bank_has_samples(&self, button: SampleButtons) -> bool {
         let bank = self.profile.settings().context().selected_sample();
         let stack = self
@@ -953,7 +1144,7 @@ impl ProfileAdapter {
         true
     }
 
-    pub fn get_sample_file(&self, button: SampleButtons) -> String {
+    pub fn get_sample_track(&self, button: SampleButtons) -> SampleTrack {
         let bank = self.profile.settings().context().selected_sample();
         let stack = self
             .profile
@@ -961,7 +1152,197 @@ impl ProfileAdapter {
             .sample_button(button)
             .get_stack(bank);
 
-        stack.get_first_sample_file()
+        track_to_ipc(&stack.get_tracks()[0])
+    }
+
+    // Whether the official app has the button's sample set to loop in the current bank.
+    pub fn is_sample_looping(&self, button: SampleButtons) -> bool {
+        let bank = self.profile.settings().context().selected_sample();
+        self.profile
+            .settings()
+            .sample_button(button)
+            .get_stack(bank)
+            .is_looping()
+    }
+
+    pub fn get_active_sample_bank(&self) -> goxlr_types::SampleBank {
+        profile_to_standard_sample_bank(self.profile.settings().context().selected_sample())
+    }
+
+    // Adds a sample to the end of a button's stack, with no trimming.
+    pub fn add_sample_file(
+        &mut self,
+        bank: goxlr_types::SampleBank,
+        button: SampleButtons,
+        file: String,
+    ) {
+        self.profile
+            .settings_mut()
+            .sample_button_mut(button)
+            .add_track(
+                standard_to_profile_sample_bank(bank),
+                Track::new(file, 0, 100, 1.0),
+            );
+    }
+
+    pub fn get_sampler_ipc(&self) -> SamplerStatus {
+        let mut banks = HashMap::new();
+        for bank in SampleBank::iter() {
+            let mut buttons = HashMap::new();
+            for button in goxlr_types::SampleButtons::iter() {
+                let tracks = self
+                    .profile
+                    .settings()
+                    .sample_button(standard_to_profile_sample_button(button))
+                    .get_stacks()
+                    .get(&bank)
+                    .map(|stack| stack.get_tracks().iter().map(track_to_ipc).collect())
+                    .unwrap_or_default();
+                buttons.insert(button, tracks);
+            }
+            banks.insert(profile_to_standard_sample_bank(bank), buttons);
+        }
+
+        let mut bank_colours = HashMap::new();
+        for bank in SampleBank::iter() {
+            let stack = self
+                .profile
+                .settings()
+                .sample_button(TopLeft)
+                .get_stacks()
+                .get(&bank);
+            if let Some([one, two]) = stack.and_then(|stack| stack.colours()) {
+                let colours = TwoColours {
+                    colour_one: one.to_rgb(),
+                    colour_two: two.to_rgb(),
+                };
+                bank_colours.insert(profile_to_standard_sample_bank(bank), colours);
+            }
+        }
+
+        SamplerStatus {
+            active_bank: self.get_active_sample_bank(),
+            banks,
+            bank_colours,
+            playing: HashMap::new(),
+        }
+    }
+
+    pub fn has_sample_track(
+        &self,
+        bank: goxlr_types::SampleBank,
+        button: goxlr_types::SampleButtons,
+        track: usize,
+    ) -> bool {
+        self.profile
+            .settings()
+            .sample_button(standard_to_profile_sample_button(button))
+            .get_stacks()
+            .get(&standard_to_profile_sample_bank(bank))
+            .is_some_and(|stack| track < stack.get_sample_count())
+    }
+
+    pub fn set_sample_trim(
+        &mut self,
+        bank: goxlr_types::SampleBank,
+        button: goxlr_types::SampleButtons,
+        track: usize,
+        start_pct: u8,
+        end_pct: u8,
+    ) -> Result<()> {
+        let track = self
+            .profile
+            .settings_mut()
+            .sample_button_mut(standard_to_profile_sample_button(button))
+            .get_stack_mut(standard_to_profile_sample_bank(bank))
+            .and_then(|stack| stack.get_track_mut(track))
+            .ok_or_else(|| anyhow!("No sample assigned at that position"))?;
+
+        track.set_positions(start_pct, end_pct);
+        Ok(())
+    }
+
+    // Every sampler button uses these colours while the bank is selected. Without a second
+    // colour, the opposite hue is used so pads stand out when they're lit.
+    pub fn set_sample_bank_colours(
+        &mut self,
+        bank: goxlr_types::SampleBank,
+        colour_one: &str,
+        colour_two: Option<&String>,
+    ) -> Result<()> {
+        let colour_one = Colour::fromrgb(colour_one)?;
+        let colour_two = match colour_two {
+            Some(colour_two) => Colour::fromrgb(colour_two)?,
+            None => {
+                let (hue, saturation, value) = colour_one.to_hsv();
+                Colour::from_hsv(hue + 180.0, saturation, value)
+            }
+        };
+        self.apply_sample_bank_colours(bank, Some([colour_one, colour_two]));
+        Ok(())
+    }
+
+    pub fn clear_sample_bank_colours(&mut self, bank: goxlr_types::SampleBank) {
+        self.apply_sample_bank_colours(bank, None);
+    }
+
+    fn apply_sample_bank_colours(
+        &mut self,
+        bank: goxlr_types::SampleBank,
+        colours: Option<[Colour; 2]>,
+    ) {
+        let bank = standard_to_profile_sample_bank(bank);
+        for button in SampleButtons::iter() {
+            let sample_button = self.profile.settings_mut().sample_button_mut(button);
+            if sample_button.get_stack_mut(bank).is_none() {
+                sample_button.set_stack(bank, SampleStack::new());
+            }
+            if let Some(stack) = sample_button.get_stack_mut(bank) {
+                stack.set_colours(colours);
+            }
+        }
+    }
+
+    // Removes every sample from the bank, leaving each button's playback settings.
+    pub fn clear_sample_bank(&mut self, bank: goxlr_types::SampleBank) {
+        let bank = standard_to_profile_sample_bank(bank);
+        for button in SampleButtons::iter() {
+            let sample_button = self.profile.settings_mut().sample_button_mut(button);
+            if let Some(stack) = sample_button.get_stack_mut(bank) {
+                stack.clear_tracks();
+            }
+        }
+    }
+
+    // Replaces the samples and playback settings of one bank with those of another.
+    pub fn copy_sample_bank(&mut self, from: goxlr_types::SampleBank, to: goxlr_types::SampleBank) {
+        let (from, to) = (
+            standard_to_profile_sample_bank(from),
+            standard_to_profile_sample_bank(to),
+        );
+        for button in SampleButtons::iter() {
+            let sample_button = self.profile.settings_mut().sample_button_mut(button);
+            let stack = sample_button.get_stacks().get(&from).cloned();
+            sample_button.set_stack(to, stack.unwrap_or_default());
+        }
+    }
+
+    pub fn swap_sample_banks(
+        &mut self,
+        first: goxlr_types::SampleBank,
+        second: goxlr_types::SampleBank,
+    ) {
+        let (first, second) = (
+            standard_to_profile_sample_bank(first),
+            standard_to_profile_sample_bank(second),
+        );
+        for button in SampleButtons::iter() {
+            let sample_button = self.profile.settings_mut().sample_button_mut(button);
+            let first_stack = sample_button.get_stacks().get(&first).cloned();
+            let second_stack = sample_button.get_stacks().get(&second).cloned();
+            sample_button.set_stack(first, second_stack.unwrap_or_default());
+            sample_button.set_stack(second, first_stack.unwrap_or_default());
+        }
     }
 
     pub fn is_sample_active(&self, button: SampleButtons) -> bool {
@@ -990,20 +1371,7 @@ impl ProfileAdapter {
         let colour_target = standard_to_colour_target(target);
         let colours = get_profile_colour_map_mut(self.profile.settings_mut(), colour_target);
 
-        if colour_one.len() != 6 {
-            return Err(anyhow!(
-                "Expected Length: 6 (RRGGBB), Colour One: {}",
-                colour_one.len()
-            ));
-        }
-
         if let Some(two) = colour_two {
-            if two.len() != 6 {
-                return Err(anyhow!(
-                    "Expected Length: 6 (RRGGBB), Colour Two: {}",
-                    two.len()
-                ));
-            }
             colours.set_colour(1, Colour::fromrgb(two.as_str())?);
         }
         colours.set_colour(0, Colour::fromrgb(colour_one.as_str())?);
@@ -1166,6 +1534,47 @@ impl ProfileAdapter {
         }
     }
 
+    pub fn adjust_button_brightness(&mut self, target: ButtonColourTargets, delta: i8) {
+        let colour_target = standard_to_colour_target(target);
+        get_profile_colour_map_mut(self.profile.settings_mut(), colour_target)
+            .adjust_colours(|colour| adjust_brightness(colour, delta));
+    }
+
+    // This covers everything with a colour, including the faders and encoders.
+    pub fn adjust_all_brightness(&mut self, delta: i8) {
+        for colour_target in ColourTargets::iter() {
+            get_profile_colour_map_mut(self.profile.settings_mut(), colour_target)
+                .adjust_colours(|colour| adjust_brightness(colour, delta));
+        }
+    }
+
+    pub fn shift_group_hue(&mut self, group: ButtonColourGroups, degrees: f32) {
+        for target in get_button_group_targets(group) {
+            let colour_target = standard_to_colour_target(target);
+            get_profile_colour_map_mut(self.profile.settings_mut(), colour_target).adjust_colours(
+                |colour| {
+                    let (hue, saturation, value) = colour.to_hsv();
+                    Colour::from_hsv(hue + degrees, saturation, value)
+                },
+            );
+        }
+    }
+
+    // Copies the colours, off styles and fader displays from another profile, without changing
+    // whether anything is currently lit. Returns true if anything changed.
+    pub fn copy_lighting_from(&mut self, other: &ProfileAdapter) -> bool {
+        let mut changed = false;
+        for target in ColourTargets::iter() {
+            let source = get_profile_colour_map(other.profile.settings(), target);
+            let colour_map = get_profile_colour_map_mut(self.profile.settings_mut(), target);
+            if !colour_map.has_same_colours(source) {
+                colour_map.copy_colours_from(source);
+                changed = true;
+            }
+        }
+        changed
+    }
+
     /** Generic Stuff **/
     pub fn get_button_colour_state(&self, button: Buttons) -> ButtonStates {
         let colour_map = self.get_button_colour_map(button);
@@ -1237,7 +1646,7 @@ fn standard_output_to_profile(value: OutputDevice) -> OutputChannels {
     }
 }
 
-fn profile_to_standard_mute_function(value: MuteFunction) -> BasicMuteFunction {
+pub fn profile_to_standard_mute_function(value: MuteFunction) -> BasicMuteFunction {
     match value {
         MuteFunction::All => BasicMuteFunction::All,
         MuteFunction::ToStream => BasicMuteFunction::ToStream,
@@ -1247,7 +1656,7 @@ fn profile_to_standard_mute_function(value: MuteFunction) -> BasicMuteFunction {
     }
 }
 
-fn standard_to_profile_mute_function(value: BasicMuteFunction) -> MuteFunction {
+pub fn standard_to_profile_mute_function(value: BasicMuteFunction) -> MuteFunction {
     match value {
         BasicMuteFunction::All => MuteFunction::All,
         BasicMuteFunction::ToStream => MuteFunction::ToStream,
@@ -1266,13 +1675,12 @@ fn standard_to_profile_fader_display(value: BasicColourDisplay) -> ColourDisplay
     }
 }
 
-#[allow(dead_code)]
 fn profile_to_standard_fader_display(value: ColourDisplay) -> BasicColourDisplay {
     match value {
         ColourDisplay::TwoColour => BasicColourDisplay::TwoColour,
         ColourDisplay::Gradient => BasicColourDisplay::Gradient,
         ColourDisplay::Meter => BasicColourDisplay::Meter,
-        ColourDisplay::GradientMeter => BasicColourDisplay::Gradient,
+        ColourDisplay::GradientMeter => BasicColourDisplay::GradientMeter,
     }
 }
 
@@ -1324,7 +1732,21 @@ fn standard_to_profile_channel(value: ChannelName) -> FullChannelList {
     }
 }
 
-#[allow(dead_code)]
+// Only the input channels have a volume on the sub mix.
+fn standard_to_profile_submix_channel(value: ChannelName) -> Option<InputChannels> {
+    match value {
+        ChannelName::Mic => Some(InputChannels::Mic),
+        ChannelName::Chat => Some(InputChannels::Chat),
+        ChannelName::Music => Some(InputChannels::Music),
+        ChannelName::Game => Some(InputChannels::Game),
+        ChannelName::Console => Some(InputChannels::Console),
+        ChannelName::LineIn => Some(InputChannels::LineIn),
+        ChannelName::System => Some(InputChannels::System),
+        ChannelName::Sample => Some(InputChannels::Sample),
+        ChannelName::Headphones | ChannelName::MicMonitor | ChannelName::LineOut => None,
+    }
+}
+
 fn profile_to_standard_sample_bank(bank: SampleBank) -> goxlr_types::SampleBank {
     match bank {
         SampleBank::A => goxlr_types::SampleBank::A,
@@ -1341,6 +1763,35 @@ fn standard_to_profile_sample_bank(bank: goxlr_types::SampleBank) -> SampleBank
     }
 }
 
+pub fn profile_to_standard_sample_button(
+    button: SampleButtons,
+) -> Option<goxlr_types::SampleButtons> {
+    match button {
+        TopLeft => Some(goxlr_types::SampleButtons::TopLeft),
+        TopRight => Some(goxlr_types::SampleButtons::TopRight),
+        BottomLeft => Some(goxlr_types::SampleButtons::BottomLeft),
+        BottomRight => Some(goxlr_types::SampleButtons::BottomRight),
+        Clear => None,
+    }
+}
+
+fn standard_to_profile_sample_button(button: goxlr_types::SampleButtons) -> SampleButtons {
+    match button {
+        goxlr_types::SampleButtons::TopLeft => TopLeft,
+        goxlr_types::SampleButtons::TopRight => TopRight,
+        goxlr_types::SampleButtons::BottomLeft => BottomLeft,
+        goxlr_types::SampleButtons::BottomRight => BottomRight,
+    }
+}
+
+fn track_to_ipc(track: &Track) -> SampleTrack {
+    SampleTrack {
+        file: track.track().to_string(),
+        start_pct: track.start_position(),
+        end_pct: track.end_position(),
+    }
+}
+
 fn sample_bank_to_simple_element(bank: SampleBank) -> SimpleElements {
     match bank {
         SampleBank::A => SimpleElements::SampleBankA,
@@ -1405,6 +1856,35 @@ fn map_button_to_colour_target(button: Buttons) -> ColourTargets {
     }
 }
 
+pub fn map_button_to_standard_colour_target(button: Buttons) -> ButtonColourTargets {
+    match button {
+        Buttons::Fader1Mute => ButtonColourTargets::Fader1Mute,
+        Buttons::Fader2Mute => ButtonColourTargets::Fader2Mute,
+        Buttons::Fader3Mute => ButtonColourTargets::Fader3Mute,
+        Buttons::Fader4Mute => ButtonColourTargets::Fader4Mute,
+        Buttons::Bleep => ButtonColourTargets::Bleep,
+        Buttons::MicrophoneMute => ButtonColourTargets::Cough,
+        Buttons::EffectSelect1 => ButtonColourTargets::EffectSelect1,
+        Buttons::EffectSelect2 => ButtonColourTargets::EffectSelect2,
+        Buttons::EffectSelect3 => ButtonColourTargets::EffectSelect3,
+        Buttons::EffectSelect4 => ButtonColourTargets::EffectSelect4,
+        Buttons::EffectSelect5 => ButtonColourTargets::EffectSelect5,
+        Buttons::EffectSelect6 => ButtonColourTargets::EffectSelect6,
+        Buttons::EffectFx => ButtonColourTargets::EffectFx,
+        Buttons::EffectMegaphone => ButtonColourTargets::EffectMegaphone,
+        Buttons::EffectRobot => ButtonColourTargets::EffectRobot,
+        Buttons::EffectHardTune => ButtonColourTargets::EffectHardTune,
+        Buttons::SamplerSelectA => ButtonColourTargets::SamplerSelectA,
+        Buttons::SamplerSelectB => ButtonColourTargets::SamplerSelectB,
+        Buttons::SamplerSelectC => ButtonColourTargets::SamplerSelectC,
+        Buttons::SamplerTopLeft => ButtonColourTargets::SamplerTopLeft,
+        Buttons::SamplerTopRight => ButtonColourTargets::SamplerTopRight,
+        Buttons::SamplerBottomLeft => ButtonColourTargets::SamplerBottomLeft,
+        Buttons::SamplerBottomRight => ButtonColourTargets::SamplerBottomRight,
+        Buttons::SamplerClear => ButtonColourTargets::SamplerClear,
+    }
+}
+
 fn map_fader_to_colour_target(fader: FaderName) -> ColourTargets {
     match fader {
         FaderName::A => ColourTargets::FadeMeter1,
@@ -1556,6 +2036,47 @@ pub fn standard_to_colour_target(target: ButtonColourTargets) -> ColourTargets {
     }
 }
 
+pub fn hsv_to_rgb(colour: &HsvColour) -> String {
+    RgbColour::from_hsv(colour.hue, colour.saturation, colour.value).to_string()
+}
+
+// Scales the brightness (HSV value) of a colour by a percentage.
+fn adjust_brightness(colour: &Colour, delta: i8) -> Colour {
+    let (hue, saturation, value) = colour.to_hsv();
+    Colour::from_hsv(hue, saturation, value * (100.0 + delta as f32) / 100.0)
+}
+
+fn get_button_group_targets(group: ButtonColourGroups) -> Vec<ButtonColourTargets> {
+    match group {
+        ButtonColourGroups::FaderMute => vec![
+            ButtonColourTargets::Fader1Mute,
+            ButtonColourTargets::Fader2Mute,
+            ButtonColourTargets::Fader3Mute,
+            ButtonColourTargets::Fader4Mute,
+        ],
+        ButtonColourGroups::EffectSelector => vec![
+            ButtonColourTargets::EffectSelect1,
+            ButtonColourTargets::EffectSelect2,
+            ButtonColourTargets::EffectSelect3,
+            ButtonColourTargets::EffectSelect4,
+            ButtonColourTargets::EffectSelect5,
+            ButtonColourTargets::EffectSelect6,
+        ],
+        ButtonColourGroups::SampleBankSelector => vec![
+            ButtonColourTargets::SamplerSelectA,
+            ButtonColourTargets::SamplerSelectB,
+            ButtonColourTargets::SamplerSelectC,
+        ],
+        ButtonColourGroups::SamplerButtons => vec![
+            ButtonColourTargets::SamplerTopLeft,
+            ButtonColourTargets::SamplerTopRight,
+            ButtonColourTargets::SamplerBottomLeft,
+            ButtonColourTargets::SamplerBottomRight,
+            ButtonColourTargets::SamplerClear,
+        ],
+    }
+}
+
 pub fn get_mini_colour_targets() -> Vec<ButtonColourTargets> {
     vec![
         ButtonColourTargets::Fader1Mute,
@@ -1568,6 +2089,46 @@ pub fn get_mini_colour_targets() -> Vec<ButtonColourTargets> {
 }
 
 #[allow(clippy::comparison_chain)]
+// Keeps the location of the problem separate, so clients can point the user straight at it.
+pub fn invalid_profile(name: &str, error: ParseError) -> DaemonError {
+    match error {
+        ParseError::InvalidElement {
+            element,
+            attribute,
+            line,
+            column,
+            source,
+        } => DaemonError::InvalidProfile {
+            name: name.to_string(),
+            element: Some(element),
+            attribute,
+            line: Some(line),
+            column: Some(column),
+            message: source.to_string(),
+        },
+        ParseError::InvalidXml {
+            line,
+            column,
+            message,
+        } => DaemonError::InvalidProfile {
+            name: name.to_string(),
+            element: None,
+            attribute: None,
+            line: Some(line),
+            column: Some(column),
+            message,
+        },
+        error => DaemonError::InvalidProfile {
+            name: name.to_string(),
+            element: None,
+            attribute: None,
+            line: None,
+            column: None,
+            message: error.to_string(),
+        },
+    }
+}
+
 pub fn version_newer_or_equal_to(version: &VersionNumber, comparison: VersionNumber) -> bool {
     if version.0 > comparison.0 {
         return true;
@@ -0,0 +1,110 @@
+/*
+Rules for choosing the profiles loaded when a device connects, rather than the ones it last used.
+They're set in `profile_rules` in the daemon's settings.json, and are checked in order with the
+first rule matching the device and the current (local) time being used:
+
+    "profile_rules": [
+      {"device_type": "Mini", "days": ["Sat", "Sun"], "profile": "Weekend"},
+      {"serial": "S201200586CQK", "from": "18:00", "until": "23:30", "mic_profile": "Stream"}
+    ]
+
+Conditions which are left out match anything, as do profiles (leaving the last used one loaded).
+A rule from 22:00 until 02:00 runs over midnight, with the day being the one it started on.
+ */
+
+use goxlr_ipc::DeviceType;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weekday {
+    Sun,
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileRule {
+    pub serial: Option<String>,
+    pub device_type: Option<DeviceType>,
+    pub days: Vec<Weekday>,
+
+    // Times of day as HH:MM
+    pub from: Option<String>,
+    pub until: Option<String>,
+
+    pub profile: Option<String>,
+    pub mic_profile: Option<String>,
+}
+
+impl ProfileRule {
+    // The day is 0 for Sunday, and the time is in minutes since midnight.
+    fn matches(&self, serial: &str, device_type: &DeviceType, day: u8, time: u16) -> bool {
+        if self.serial.as_ref().is_some_and(|s| s != serial) {
+            return false;
+        }
+        if self.device_type.as_ref().is_some_and(|t| t != device_type) {
+            return false;
+        }
+
+        let (from, until) = match (parse_time(&self.from, 0), parse_time(&self.until, 24 * 60)) {
+            (Some(from), Some(until)) => (from, until),
+            _ => return false,
+        };
+
+        // Past midnight in a rule which started the day before.
+        let (day, in_time) = if from <= until {
+            (day, from <= time && time < until)
+        } else if time >= from {
+            (day, true)
+        } else {
+            ((day + 6) % 7, time < until)
+        };
+        in_time && (self.days.is_empty() || self.days.iter().any(|d| *d as u8 == day))
+    }
+}
+
+// Parses a HH:MM time into minutes since midnight, using the default if there's no time.
+fn parse_time(time: &Option<String>, default: u16) -> Option<u16> {
+    let time = match time {
+        Some(time) => time,
+        None => return Some(default),
+    };
+    let parsed = time.split_once(':').and_then(|(hours, minutes)| {
+        let hours: u16 = hours.parse().ok()?;
+        let minutes: u16 = minutes.parse().ok()?;
+        (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+    });
+    if parsed.is_none() {
+        warn!("Ignoring profile rule with invalid time {}", time);
+    }
+    parsed
+}
+
+// The first rule which matches the device right now.
+pub fn find_profile_rule<'a>(
+    rules: &'a [ProfileRule],
+    serial: &str,
+    device_type: &DeviceType,
+) -> Option<&'a ProfileRule> {
+    let (day, time) = local_time();
+    rules
+        .iter()
+        .find(|rule| rule.matches(serial, device_type, day, time))
+}
+
+// The current day of the week (0 being Sunday) and minutes since midnight, in local time.
+fn local_time() -> (u8, u16) {
+    // SAFETY: localtime_r only writes to the tm it's given.
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        (tm.tm_wday as u8, (tm.tm_hour * 60 + tm.tm_min) as u16)
+    }
+}
@@ -0,0 +1,61 @@
+/*
+Limits how often each address can call the HTTP API, so a misbehaving client on the network
+can't keep the daemon (and so the GoXLR) busy. Every address gets a bucket holding a second's
+worth of requests, which refills at the configured rate. Requests from loopback aren't limited,
+as they're from this machine's own UI and tools.
+ */
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Buckets which haven't been used for this long are full again, so can be forgotten.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    requests_per_second: u32,
+    buckets: Arc<Mutex<HashMap<IpAddr, Bucket>>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    // A limit of 0 allows every request.
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            requests_per_second,
+            buckets: Default::default(),
+        }
+    }
+
+    pub fn allow(&self, address: IpAddr) -> bool {
+        if self.requests_per_second == 0 || address.is_loopback() {
+            return true;
+        }
+
+        let now = Instant::now();
+        let capacity = self.requests_per_second as f64;
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.retain(|_, bucket| now - bucket.updated < IDLE_TIMEOUT);
+
+        let bucket = buckets.entry(address).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = (now - bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * capacity).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
@@ -0,0 +1,339 @@
+/*
+Scripts written in Rhai (https://rhai.rs), for automating things the daemon doesn't do itself.
+Every .rhai file in the script directory is loaded when the daemon starts (or the scripts are
+reloaded) unless it's been disabled. A script is run once when it's loaded, and its on_event
+function (if it has one) is then called with every event from the devices:
+
+    fn on_event(event) {
+        if event.Input?.input?.ButtonDown == "Bleep" {
+            command(event.Input.serial, #{ SetVolume: ["Music", 64] });
+        }
+    }
+
+Events, commands and the status are laid out as they are in the JSON IPC. Besides what Rhai
+provides, scripts can use:
+
+    command(serial, command)    Runs a command on a device, throwing an error if it fails
+    status()                    The daemon's status
+    print(text)                 Writes to the daemon's log
+
+Scripts can't reach files or the network. They run one at a time on their own thread, so a slow
+script holds up the others but not the devices, and one which runs for too long is stopped.
+ */
+
+use crate::communication::subscribe;
+use crate::primary_worker::{DeviceCommand, DeviceSender};
+use crate::{SettingsHandle, Shutdown};
+use anyhow::{anyhow, Result};
+use goxlr_ipc::{ClientIdentity, DaemonEvent, GoXLRCommand, ScriptStatus};
+use log::{debug, info, warn};
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Dynamic, Engine, EvalAltResult, NativeCallContext, Scope, AST};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::thread;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::{mpsc, oneshot};
+
+const SCRIPT_EXTENSION: &str = "rhai";
+const EVENT_FUNCTION: &str = "on_event";
+
+// Enough for anything reasonable, but stops a script which is stuck in a loop.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+// Rhai's limits on how deeply expressions nest are lower in debug builds, this keeps them the same.
+const MAX_EXPRESSION_DEPTH: usize = 64;
+
+pub type ScriptReceiver = mpsc::UnboundedReceiver<()>;
+
+// Used by the device worker to list the scripts in the status, and to reload them.
+#[derive(Debug, Clone)]
+pub struct ScriptHandle {
+    sender: mpsc::UnboundedSender<()>,
+    status: Arc<Mutex<Vec<ScriptStatus>>>,
+}
+
+impl ScriptHandle {
+    pub fn new() -> (Self, ScriptReceiver) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let handle = Self {
+            sender,
+            status: Default::default(),
+        };
+        (handle, receiver)
+    }
+
+    pub fn status(&self) -> Vec<ScriptStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn reload(&self) {
+        // If the script task has stopped we're shutting down, so this can be ignored.
+        let _ = self.sender.send(());
+    }
+}
+
+struct ScriptFile {
+    name: String,
+    path: PathBuf,
+    enabled: bool,
+}
+
+enum ScriptJob {
+    Load(Vec<ScriptFile>),
+    Event(DaemonEvent),
+}
+
+struct Script {
+    name: String,
+    enabled: bool,
+    loaded: Option<(AST, Scope<'static>)>,
+    error: Option<String>,
+}
+
+pub async fn run_scripts(
+    handle: ScriptHandle,
+    mut receiver: ScriptReceiver,
+    settings: SettingsHandle,
+    mut usb_tx: DeviceSender,
+    mut shutdown: Shutdown,
+) {
+    // Scripts are run away from the async runtime, so they can wait on the commands they send.
+    let (jobs, job_receiver) = std_mpsc::channel();
+    let status = handle.status.clone();
+    let engine_tx = usb_tx.clone();
+    thread::spawn(move || run_engine(job_receiver, status, engine_tx));
+
+    // Only the events are needed, dropping the patches saves building them for nobody.
+    let mut events = match subscribe(&mut usb_tx).await {
+        Ok((_, subscription)) => Some(subscription.events),
+        Err(error) => {
+            warn!("Scripts won't receive events: {:#}", error);
+            None
+        }
+    };
+
+    let _ = jobs.send(ScriptJob::Load(find_scripts(&settings).await));
+    loop {
+        tokio::select! {
+            () = shutdown.recv() => return,
+            message = receiver.recv() => match message {
+                None => return,
+                Some(()) => {
+                    info!("Reloading scripts");
+                    let _ = jobs.send(ScriptJob::Load(find_scripts(&settings).await));
+                }
+            },
+            Some(event) = next_event(&mut events) => {
+                let _ = jobs.send(ScriptJob::Event(event));
+            }
+        }
+    }
+}
+
+async fn next_event(events: &mut Option<Receiver<DaemonEvent>>) -> Option<DaemonEvent> {
+    let receiver = events.as_mut()?;
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Some(event),
+            Err(RecvError::Lagged(count)) => warn!("Scripts missed {} events", count),
+            Err(RecvError::Closed) => {
+                *events = None;
+                return None;
+            }
+        }
+    }
+}
+
+async fn find_scripts(settings: &SettingsHandle) -> Vec<ScriptFile> {
+    let directory = settings.get_script_directory().await;
+    let disabled = settings.get_disabled_scripts().await;
+
+    let entries = match fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            if error.kind() != ErrorKind::NotFound {
+                warn!(
+                    "Unable to read the script directory {}: {}",
+                    directory.to_string_lossy(),
+                    error
+                );
+            }
+            return vec![];
+        }
+    };
+
+    let mut scripts: Vec<ScriptFile> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| path.extension().is_some_and(|ext| ext == SCRIPT_EXTENSION))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            Some(ScriptFile {
+                enabled: !disabled.contains(&name),
+                name,
+                path,
+            })
+        })
+        .collect();
+    scripts.sort_by(|a, b| a.name.cmp(&b.name));
+    scripts
+}
+
+fn run_engine(
+    jobs: std_mpsc::Receiver<ScriptJob>,
+    status: Arc<Mutex<Vec<ScriptStatus>>>,
+    usb_tx: DeviceSender,
+) {
+    let engine = create_engine(usb_tx);
+    let mut scripts = vec![];
+
+    // This ends when the script task stops, and drops its sender.
+    for job in jobs {
+        match job {
+            ScriptJob::Load(files) => {
+                scripts = files
+                    .into_iter()
+                    .map(|file| load_script(&engine, file))
+                    .collect();
+            }
+            ScriptJob::Event(event) => {
+                let event = match to_dynamic(&event) {
+                    Ok(event) => event,
+                    Err(error) => {
+                        warn!("Unable to pass an event to the scripts: {}", error);
+                        continue;
+                    }
+                };
+                for script in &mut scripts {
+                    script.handle_event(&engine, &event);
+                }
+            }
+        }
+
+        *status.lock().unwrap() = scripts.iter().map(Script::status).collect();
+    }
+}
+
+fn load_script(engine: &Engine, file: ScriptFile) -> Script {
+    let mut script = Script {
+        name: file.name,
+        enabled: file.enabled,
+        loaded: None,
+        error: None,
+    };
+    if !script.enabled {
+        return script;
+    }
+
+    let loaded = fs::read_to_string(&file.path)
+        .map_err(|error| error.to_string())
+        .and_then(|source| {
+            let mut ast = engine.compile(source).map_err(|e| e.to_string())?;
+            ast.set_source(script.name.as_str());
+
+            let mut scope = Scope::new();
+            engine
+                .run_ast_with_scope(&mut scope, &ast)
+                .map_err(|error| error.to_string())?;
+            Ok((ast, scope))
+        });
+
+    match loaded {
+        Ok(loaded) => {
+            info!("Loaded script {}", script.name);
+            script.loaded = Some(loaded);
+        }
+        Err(error) => {
+            warn!("Unable to load script {}: {}", script.name, error);
+            script.error = Some(error);
+        }
+    }
+    script
+}
+
+impl Script {
+    fn handle_event(&mut self, engine: &Engine, event: &Dynamic) {
+        let (ast, scope) = match &mut self.loaded {
+            Some(loaded) => loaded,
+            None => return,
+        };
+
+        let has_handler = ast
+            .iter_functions()
+            .any(|function| function.name == EVENT_FUNCTION && function.params.len() == 1);
+        if !has_handler {
+            return;
+        }
+
+        if let Err(error) = engine.call_fn::<Dynamic>(scope, ast, EVENT_FUNCTION, (event.clone(),))
+        {
+            warn!("Script {} failed handling an event: {}", self.name, error);
+            self.error = Some(error.to_string());
+        }
+    }
+
+    fn status(&self) -> ScriptStatus {
+        ScriptStatus {
+            name: self.name.clone(),
+            enabled: self.enabled,
+            error: self.error.clone(),
+        }
+    }
+}
+
+fn create_engine(usb_tx: DeviceSender) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_expr_depths(MAX_EXPRESSION_DEPTH, MAX_EXPRESSION_DEPTH);
+    engine.on_print(|text| info!("Script: {}", text));
+    engine.on_debug(|text, source, _| debug!("Script {}: {}", source.unwrap_or_default(), text));
+
+    let command_tx = usb_tx.clone();
+    engine.register_fn(
+        "command",
+        move |context: NativeCallContext,
+              serial: &str,
+              command: Dynamic|
+              -> Result<(), Box<EvalAltResult>> {
+            let command: GoXLRCommand = from_dynamic(&command)?;
+            let script = context.call_source().unwrap_or_default();
+            run_command(&command_tx, serial, command, script).map_err(|e| format!("{:#}", e).into())
+        },
+    );
+    engine.register_fn("status", move || -> Result<Dynamic, Box<EvalAltResult>> {
+        let (tx, rx) = oneshot::channel();
+        usb_tx
+            .blocking_send(DeviceCommand::SendDaemonStatus(tx))
+            .map_err(|e| e.to_string())?;
+        let status = rx.blocking_recv().map_err(|e| e.to_string())?;
+        to_dynamic(status)
+    });
+    engine
+}
+
+fn run_command(
+    usb_tx: &DeviceSender,
+    serial: &str,
+    command: GoXLRCommand,
+    script: &str,
+) -> Result<()> {
+    let (tx, rx) = oneshot::channel();
+    let identity = ClientIdentity {
+        name: Some(format!("Script {}", script)),
+        ..Default::default()
+    };
+    usb_tx
+        .blocking_send(DeviceCommand::RunDeviceCommand(
+            serial.to_owned(),
+            command,
+            identity,
+            tx,
+        ))
+        .map_err(|e| anyhow!(e.to_string()))?;
+    rx.blocking_recv()?
+}
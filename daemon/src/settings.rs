@@ -1,20 +1,47 @@
-use crate::mic_profile::DEFAULT_MIC_PROFILE_NAME;
-use crate::profile::DEFAULT_PROFILE_NAME;
-use anyhow::{Context, Result};
+use crate::device::check_range;
+use crate::hooks::HookEvent;
+use crate::http_server::{default_http_addresses, HttpAddress};
+use crate::profile_rules::ProfileRule;
+use anyhow::{anyhow, bail, Context, Result};
 use directories::ProjectDirs;
-use log::error;
+use enumset::EnumSet;
+use goxlr_ipc::{
+    Autosave, DeviceSettings, ObsSettings, RoutingPreset, SampleBankSwitch, SettingsInfo,
+    UpdateSettings,
+};
+use goxlr_profile_loader::write_atomically;
+use goxlr_types::{ChannelName, EncoderName, OutputDevice, RgbColour};
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::fs::{create_dir_all, File};
-use std::io::ErrorKind;
+use std::fs::{copy, create_dir_all, File};
+use std::io::{ErrorKind, Write};
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::time::{sleep_until, Instant};
+
+// Bump this when the format of the settings file changes, and add a migration for the old format
+// to migrate_settings.
+const SETTINGS_VERSION: u32 = 1;
+
+// Saves made within this long of each other are written to disk together.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+enum SettingsWrite {
+    Save,
+    Flush(oneshot::Sender<Result<()>>),
+}
 
 #[derive(Debug, Clone)]
 pub struct SettingsHandle {
     path: PathBuf,
     settings: Arc<RwLock<Settings>>,
+    writer: mpsc::UnboundedSender<SettingsWrite>,
 }
 
 impl SettingsHandle {
@@ -25,10 +52,23 @@ impl SettingsHandle {
         let data_dir = proj_dirs.data_dir();
 
         let mut settings = Settings::read(&path)?.unwrap_or_else(|| Settings {
+            version: SETTINGS_VERSION,
             profile_directory: Some(data_dir.join("profiles")),
             mic_profile_directory: Some(data_dir.join("mic-profiles")),
             samples_directory: Some(data_dir.join("samples")),
+            state_directory: Some(data_dir.join("state")),
+            theme_directory: Some(data_dir.join("themes")),
+            script_directory: Some(data_dir.join("scripts")),
+            custom_ui_directory: None,
+            http_addresses: default_http_addresses(),
+            allow_raw_usb_commands: false,
+            hooks: Default::default(),
+            profile_rules: Default::default(),
+            obs: Default::default(),
+            updates: Default::default(),
+            disabled_scripts: Default::default(),
             devices: Default::default(),
+            device_defaults: Default::default(),
         });
 
         // Set these values if they're missing from the configuration
@@ -44,22 +84,51 @@ impl SettingsHandle {
             settings.samples_directory = Some(data_dir.join("samples"));
         }
 
+        if settings.state_directory.is_none() {
+            settings.state_directory = Some(data_dir.join("state"));
+        }
+
+        if settings.theme_directory.is_none() {
+            settings.theme_directory = Some(data_dir.join("themes"));
+        }
+
+        if settings.script_directory.is_none() {
+            settings.script_directory = Some(data_dir.join("scripts"));
+        }
+
+        let settings = Arc::new(RwLock::new(settings));
+        let (writer, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run_writer(path.clone(), settings.clone(), receiver));
+
         let handle = SettingsHandle {
             path,
-            settings: Arc::new(RwLock::new(settings)),
+            settings,
+            writer,
         };
-        handle.save().await;
+        if let Err(e) = handle.flush().await {
+            error!("{:#}", e);
+        }
         Ok(handle)
     }
 
+    // Writes happen shortly afterwards on the writer task, use flush to wait for one.
     pub async fn save(&self) {
-        let settings = self.settings.write().await;
-        if let Err(e) = settings.write(&self.path) {
-            error!(
-                "Couldn't save settings to {}: {}",
-                self.path.to_string_lossy(),
-                e
-            );
+        let _ = self.writer.send(SettingsWrite::Save);
+    }
+
+    pub async fn flush(&self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.writer
+            .send(SettingsWrite::Flush(tx))
+            .map_err(|_| anyhow!("The settings writer has stopped"))?;
+        rx.await.context("The settings writer has stopped")?
+    }
+
+    pub async fn get_settings_info(&self) -> SettingsInfo {
+        let settings = self.settings.read().await;
+        SettingsInfo {
+            path: self.path.clone(),
+            version: settings.version,
         }
     }
 
@@ -78,6 +147,89 @@ impl SettingsHandle {
         settings.samples_directory.clone().unwrap()
     }
 
+    pub async fn get_state_directory(&self) -> PathBuf {
+        let settings = self.settings.read().await;
+        settings.state_directory.clone().unwrap()
+    }
+
+    pub async fn get_theme_directory(&self) -> PathBuf {
+        let settings = self.settings.read().await;
+        settings.theme_directory.clone().unwrap()
+    }
+
+    pub async fn get_script_directory(&self) -> PathBuf {
+        let settings = self.settings.read().await;
+        settings.script_directory.clone().unwrap()
+    }
+
+    pub async fn get_allow_raw_usb_commands(&self) -> bool {
+        let settings = self.settings.read().await;
+        settings.allow_raw_usb_commands
+    }
+
+    pub async fn get_custom_ui_directory(&self) -> Option<PathBuf> {
+        let settings = self.settings.read().await;
+        settings.custom_ui_directory.clone()
+    }
+
+    pub async fn set_custom_ui_directory(&self, directory: Option<PathBuf>) {
+        let mut settings = self.settings.write().await;
+        settings.custom_ui_directory = directory;
+    }
+
+    pub async fn get_hooks(&self, event: HookEvent) -> Vec<PathBuf> {
+        let settings = self.settings.read().await;
+        settings.hooks.get(&event).cloned().unwrap_or_default()
+    }
+
+    pub async fn get_http_addresses(&self) -> Vec<IpAddr> {
+        let settings = self.settings.read().await;
+        settings
+            .http_addresses
+            .iter()
+            .filter(|address| address.enabled)
+            .map(|address| address.address)
+            .collect()
+    }
+
+    pub async fn get_profile_rules(&self) -> Vec<ProfileRule> {
+        let settings = self.settings.read().await;
+        settings.profile_rules.clone()
+    }
+
+    pub async fn get_obs_settings(&self) -> ObsSettings {
+        let settings = self.settings.read().await;
+        settings.obs.clone()
+    }
+
+    pub async fn set_obs_settings(&self, obs: ObsSettings) {
+        let mut settings = self.settings.write().await;
+        settings.obs = obs;
+    }
+
+    pub async fn get_update_settings(&self) -> UpdateSettings {
+        let settings = self.settings.read().await;
+        settings.updates.clone()
+    }
+
+    pub async fn set_update_settings(&self, updates: UpdateSettings) {
+        let mut settings = self.settings.write().await;
+        settings.updates = updates;
+    }
+
+    pub async fn get_disabled_scripts(&self) -> Vec<String> {
+        let settings = self.settings.read().await;
+        settings.disabled_scripts.clone()
+    }
+
+    pub async fn set_script_enabled(&self, name: &str, enabled: bool) {
+        let mut settings = self.settings.write().await;
+        settings.disabled_scripts.retain(|script| script != name);
+        if !enabled {
+            settings.disabled_scripts.push(name.to_owned());
+        }
+    }
+
     pub async fn get_device_profile_name(&self, device_serial: &str) -> Option<String> {
         let settings = self.settings.read().await;
         settings
@@ -94,54 +246,248 @@ impl SettingsHandle {
             .map(|d| d.mic_profile.clone())
     }
 
-    pub async fn get_device_bleep_volume(&self, device_serial: &str) -> Option<i8> {
+    pub async fn get_device_settings(&self, device_serial: &str) -> DeviceSettings {
         let settings = self.settings.read().await;
-        settings.devices.get(device_serial).map(|d| d.bleep_volume)
+        settings
+            .devices
+            .get(device_serial)
+            .unwrap_or(&settings.device_defaults)
+            .clone()
     }
 
-    pub async fn set_device_profile_name(&self, device_serial: &str, profile_name: &str) {
+    pub async fn set_device_settings(&self, device_serial: &str, device_settings: DeviceSettings) {
         let mut settings = self.settings.write().await;
-        let entry = settings
+        settings
             .devices
-            .entry(device_serial.to_owned())
-            .or_insert_with(DeviceSettings::default);
+            .insert(device_serial.to_owned(), device_settings);
+    }
+
+    pub async fn get_default_device_settings(&self) -> DeviceSettings {
+        let settings = self.settings.read().await;
+        settings.device_defaults.clone()
+    }
+
+    pub async fn set_default_device_settings(&self, device_settings: DeviceSettings) {
+        let mut settings = self.settings.write().await;
+        settings.device_defaults = device_settings;
+    }
+
+    pub async fn set_device_profile_name(&self, device_serial: &str, profile_name: &str) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
         entry.profile = profile_name.to_owned();
     }
 
     pub async fn set_device_mic_profile_name(&self, device_serial: &str, mic_profile_name: &str) {
         let mut settings = self.settings.write().await;
-        let entry = settings
-            .devices
-            .entry(device_serial.to_owned())
-            .or_insert_with(DeviceSettings::default);
+        let entry = settings.get_device_mut(device_serial);
         entry.mic_profile = mic_profile_name.to_owned();
     }
 
     pub async fn set_device_bleep_volume(&self, device_serial: &str, bleep_volume: i8) {
         let mut settings = self.settings.write().await;
-        let entry = settings
-            .devices
-            .entry(device_serial.to_owned())
-            .or_insert_with(DeviceSettings::default);
+        let entry = settings.get_device_mut(device_serial);
         entry.bleep_volume = bleep_volume;
     }
+
+    pub async fn set_device_bleep_outputs(
+        &self,
+        device_serial: &str,
+        bleep_outputs: EnumSet<OutputDevice>,
+    ) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        entry.bleep_outputs = bleep_outputs;
+    }
+
+    pub async fn set_device_sample_bank_switch(
+        &self,
+        device_serial: &str,
+        sample_bank_switch: SampleBankSwitch,
+    ) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        entry.sample_bank_switch = sample_bank_switch;
+    }
+
+    // Removing a preset also unbinds any buttons which applied it.
+    pub async fn set_device_routing_preset(
+        &self,
+        device_serial: &str,
+        name: &str,
+        preset: Option<RoutingPreset>,
+    ) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        match preset {
+            Some(preset) => {
+                entry.routing_presets.insert(name.to_string(), preset);
+            }
+            None => {
+                entry.routing_presets.remove(name);
+                entry
+                    .routing_preset_buttons
+                    .retain(|_, preset| preset != name);
+            }
+        }
+    }
+
+    pub async fn set_device_volume_limit(
+        &self,
+        device_serial: &str,
+        channel: ChannelName,
+        limit: Option<u8>,
+    ) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        match limit {
+            Some(limit) => entry.volume_limits.insert(channel, limit),
+            None => entry.volume_limits.remove(&channel),
+        };
+    }
+
+    pub async fn set_device_channel_name(
+        &self,
+        device_serial: &str,
+        channel: ChannelName,
+        name: Option<String>,
+    ) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        match name {
+            Some(name) => entry.channel_names.insert(channel, name),
+            None => entry.channel_names.remove(&channel),
+        };
+    }
+
+    pub async fn set_device_output_name(
+        &self,
+        device_serial: &str,
+        output: OutputDevice,
+        name: Option<String>,
+    ) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        match name {
+            Some(name) => entry.output_names.insert(output, name),
+            None => entry.output_names.remove(&output),
+        };
+    }
+
+    pub async fn set_device_channel_colour(
+        &self,
+        device_serial: &str,
+        channel: ChannelName,
+        colour: Option<String>,
+    ) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        match colour {
+            Some(colour) => entry.channel_colours.insert(channel, colour),
+            None => entry.channel_colours.remove(&channel),
+        };
+    }
+
+    pub async fn set_device_fader_pages(&self, device_serial: &str, pages: Vec<[ChannelName; 4]>) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        entry.fader_pages = pages;
+    }
+
+    pub async fn set_device_volume_links(&self, device_serial: &str, links: Vec<Vec<ChannelName>>) {
+        let mut settings = self.settings.write().await;
+        let entry = settings.get_device_mut(device_serial);
+        entry.volume_links = links;
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Settings {
+    // Files written before versioning was added don't have this, and are treated as version 0.
+    #[serde(default)]
+    version: u32,
+
     profile_directory: Option<PathBuf>,
     mic_profile_directory: Option<PathBuf>,
     samples_directory: Option<PathBuf>,
+    state_directory: Option<PathBuf>,
+
+    #[serde(default)]
+    theme_directory: Option<PathBuf>,
+
+    #[serde(default)]
+    script_directory: Option<PathBuf>,
+
+    #[serde(default)]
+    custom_ui_directory: Option<PathBuf>,
+
+    // Where the HTTP server listens, unless overridden on the command line
+    #[serde(default = "default_http_addresses")]
+    http_addresses: Vec<HttpAddress>,
+
+    // Lets clients send any command to a device with SendRawCommand. This can only be turned on
+    // by editing the settings file, as a bad command can leave the device in an odd state.
+    #[serde(default)]
+    allow_raw_usb_commands: bool,
+
+    // Executables to run when an event occurs on a device
+    #[serde(default)]
+    hooks: HashMap<HookEvent, Vec<PathBuf>>,
+
+    // Checked in order when a device connects, to pick the profiles it starts with
+    #[serde(default)]
+    profile_rules: Vec<ProfileRule>,
+
+    #[serde(default)]
+    obs: ObsSettings,
+
+    #[serde(default)]
+    updates: UpdateSettings,
+
+    // Scripts in the script directory which shouldn't be run
+    #[serde(default)]
+    disabled_scripts: Vec<String>,
+
     devices: HashMap<String, DeviceSettings>,
+
+    // Copied for any device which doesn't have its own settings yet
+    #[serde(default)]
+    device_defaults: DeviceSettings,
 }
 
 impl Settings {
+    fn get_device_mut(&mut self, device_serial: &str) -> &mut DeviceSettings {
+        let defaults = &self.device_defaults;
+        self.devices
+            .entry(device_serial.to_owned())
+            .or_insert_with(|| defaults.clone())
+    }
+
     pub fn read(path: &Path) -> Result<Option<Settings>> {
         match File::open(path) {
-            Ok(reader) => Ok(Some(serde_json::from_reader(reader).context(format!(
-                "Could not parse daemon settings file at {}",
-                path.to_string_lossy()
-            ))?)),
+            Ok(reader) => {
+                let mut settings: Value = serde_json::from_reader(reader).context(format!(
+                    "Could not parse daemon settings file at {}",
+                    path.to_string_lossy()
+                ))?;
+
+                let version = settings["version"].as_u64().unwrap_or(0) as u32;
+                if version > SETTINGS_VERSION {
+                    warn!(
+                        "Settings file is version {}, but this daemon only understands version {}. \
+                        Newer settings may be lost.",
+                        version, SETTINGS_VERSION
+                    );
+                } else if version < SETTINGS_VERSION {
+                    backup_settings(path, version)?;
+                    migrate_settings(&mut settings, version)?;
+                }
+
+                Ok(Some(serde_json::from_value(settings).context(format!(
+                    "Could not read daemon settings file at {}",
+                    path.to_string_lossy()
+                ))?))
+            }
             Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
             Err(error) => Err(error).context(format!(
                 "Could not open daemon settings file for reading at {}",
@@ -150,43 +496,169 @@ impl Settings {
         }
     }
 
-    pub fn write(&self, path: &Path) -> Result<()> {
-        if let Some(parent) = path.parent() {
-            if let Err(e) = create_dir_all(parent) {
-                if e.kind() != ErrorKind::AlreadyExists {
-                    return Err(e).context(format!(
-                        "Could not create settings directory at {}",
-                        parent.to_string_lossy()
-                    ))?;
+    fn to_json(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec_pretty(self).context("Could not serialise the daemon settings")
+    }
+}
+
+// Only one write happens at a time, and several saves in quick succession become one write.
+async fn run_writer(
+    path: PathBuf,
+    settings: Arc<RwLock<Settings>>,
+    mut receiver: mpsc::UnboundedReceiver<SettingsWrite>,
+) {
+    let mut deadline = None;
+    loop {
+        let message = match deadline {
+            Some(time) => tokio::select! {
+                message = receiver.recv() => message,
+                () = sleep_until(time) => {
+                    deadline = None;
+                    if let Err(e) = write_settings(&path, &settings).await {
+                        error!("{:#}", e);
+                    }
+                    continue;
+                }
+            },
+            None => receiver.recv().await,
+        };
+
+        match message {
+            Some(SettingsWrite::Save) => {
+                deadline.get_or_insert_with(|| Instant::now() + SAVE_DELAY);
+            }
+            Some(SettingsWrite::Flush(sender)) => {
+                deadline = None;
+                let _ = sender.send(write_settings(&path, &settings).await);
+            }
+            None => {
+                if deadline.is_some() {
+                    if let Err(e) = write_settings(&path, &settings).await {
+                        error!("{:#}", e);
+                    }
                 }
+                return;
             }
         }
-        let writer = File::create(path).context(format!(
-            "Could not open daemon settings file for writing at {}",
-            path.to_string_lossy()
-        ))?;
-        serde_json::to_writer_pretty(writer, self).context(format!(
-            "Could not write to daemon settings file at {}",
-            path.to_string_lossy()
-        ))?;
-        Ok(())
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(default)]
-struct DeviceSettings {
-    profile: String,
-    mic_profile: String,
-    bleep_volume: i8,
+async fn write_settings(path: &Path, settings: &RwLock<Settings>) -> Result<()> {
+    let contents = settings.read().await.to_json()?;
+    let path = path.to_owned();
+    tokio::task::spawn_blocking(move || write_settings_file(&path, &contents)).await?
+}
+
+// Losing power part way through leaves the old settings or the new ones, never a truncated file.
+fn write_settings_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty());
+    if let Some(parent) = parent {
+        if let Err(e) = create_dir_all(parent) {
+            if e.kind() != ErrorKind::AlreadyExists {
+                return Err(e).context(format!(
+                    "Could not create settings directory at {}",
+                    parent.to_string_lossy()
+                ))?;
+            }
+        }
+    }
+
+    write_atomically(path, |file| Ok(file.write_all(contents)?)).context(format!(
+        "Could not write daemon settings file at {}",
+        path.to_string_lossy()
+    ))
+}
+
+pub fn validate_device_settings(settings: &DeviceSettings) -> Result<()> {
+    check_range("Mute volume", settings.bleep_volume, -34, 0)?;
+    check_range("Hold time", settings.hold_time, 100, 5000)?;
+    check_range("Bleep attack", settings.bleep_attack, 0, 1000)?;
+    check_range("Bleep release", settings.bleep_release, 0, 2000)?;
+    check_range("Phantom power mute", settings.phantom_power_mute, 0, 2000)?;
+    if let Some(path) = &settings.bleep_sound {
+        if !path.is_file() {
+            bail!("Bleep sound {} does not exist", path.to_string_lossy());
+        }
+    }
+    for (encoder, step) in &settings.encoder_steps {
+        if *encoder == EncoderName::Pitch {
+            bail!("The pitch encoder's step can't be changed");
+        }
+        check_range("Encoder step", *step, 1, 12)?;
+    }
+    for (encoder, acceleration) in &settings.encoder_acceleration {
+        if *encoder == EncoderName::Pitch {
+            bail!("The pitch encoder can't be accelerated");
+        }
+        check_range("Acceleration window", acceleration.window, 10, 1000)?;
+        check_range("Acceleration multiplier", acceleration.multiplier, 1, 10)?;
+    }
+    for hold in settings.fader_hold.values() {
+        if let Some(hold_time) = hold.hold_time {
+            check_range("Fader hold time", hold_time, 100, 5000)?;
+        }
+    }
+    if let Autosave::Every(minutes) = settings.autosave {
+        check_range("Autosave interval", minutes, 1, 1440)?;
+    }
+    if let SampleBankSwitch::FadeOut(milliseconds) = settings.sample_bank_switch {
+        check_range("Sample fade time", milliseconds, 1, 10000)?;
+    }
+    for colour in settings.channel_colours.values() {
+        if let Err(error) = colour.parse::<RgbColour>() {
+            bail!("Invalid channel colour: {}", error);
+        }
+    }
+    if settings.volume_links.iter().any(|group| group.len() < 2) {
+        bail!("Linked channels must be in groups of at least two");
+    }
+    for preset in settings.routing_preset_buttons.values() {
+        if !settings.routing_presets.contains_key(preset) {
+            bail!("Routing preset {} does not exist", preset);
+        }
+    }
+    Ok(())
 }
 
-impl Default for DeviceSettings {
-    fn default() -> Self {
-        DeviceSettings {
-            profile: DEFAULT_PROFILE_NAME.to_owned(),
-            mic_profile: DEFAULT_MIC_PROFILE_NAME.to_owned(),
-            bleep_volume: -20,
+// Keeps a copy of the settings in their old format, in case something goes wrong migrating them.
+fn backup_settings(path: &Path, version: u32) -> Result<()> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", version));
+    let backup = PathBuf::from(backup);
+
+    copy(path, &backup).context(format!(
+        "Could not back up daemon settings file to {}",
+        backup.to_string_lossy()
+    ))?;
+    info!("Backed up old settings to {}", backup.to_string_lossy());
+    Ok(())
+}
+
+// Runs each migration in turn, from the version the settings were written with up to the current.
+fn migrate_settings(settings: &mut Value, from: u32) -> Result<()> {
+    for version in from..SETTINGS_VERSION {
+        info!(
+            "Migrating settings from version {} to {}",
+            version,
+            version + 1
+        );
+        match version {
+            0 => migrate_from_unversioned(settings)?,
+            _ => unreachable!("No migration from settings version {}", version),
         }
     }
+
+    settings["version"] = json!(SETTINGS_VERSION);
+    Ok(())
+}
+
+// Before versioning, a settings file without a list of devices couldn't be loaded.
+fn migrate_from_unversioned(settings: &mut Value) -> Result<()> {
+    let settings = settings
+        .as_object_mut()
+        .context("Daemon settings file is not a JSON object")?;
+    settings.entry("devices").or_insert_with(|| json!({}));
+    Ok(())
 }
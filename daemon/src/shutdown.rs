@@ -1,9 +1,14 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tokio::sync::broadcast;
 
 pub struct Shutdown {
     shutdown: bool,
     sender: broadcast::Sender<()>,
     receiver: broadcast::Receiver<()>,
+
+    // Shared between every clone, so main knows to start the daemon again once it's stopped.
+    restart: Arc<AtomicBool>,
 }
 
 impl Shutdown {
@@ -13,6 +18,7 @@ impl Shutdown {
             shutdown: false,
             sender,
             receiver,
+            restart: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -20,6 +26,15 @@ impl Shutdown {
         let _ = self.sender.send(());
     }
 
+    pub fn trigger_restart(&self) {
+        self.restart.store(true, Ordering::Relaxed);
+        self.trigger();
+    }
+
+    pub fn restart_requested(&self) -> bool {
+        self.restart.load(Ordering::Relaxed)
+    }
+
     pub async fn recv(&mut self) {
         if self.shutdown {
             return;
@@ -38,6 +53,7 @@ impl Clone for Shutdown {
             shutdown: self.shutdown,
             sender,
             receiver,
+            restart: self.restart.clone(),
         }
     }
 }
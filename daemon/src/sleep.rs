@@ -0,0 +1,50 @@
+/*
+Works out when the computer has been asleep, as the GoXLR can come back from a suspend having
+lost some or all of its settings without ever being disconnected. The monotonic clock stops while
+the computer sleeps but the boot time clock doesn't, so the gap between them grows by however
+long it was asleep for. Devices which do drop off the bus are detected again as usual, and
+restored from their snapshot.
+ */
+
+use std::time::Duration;
+
+// Smaller jumps could just be the clocks being read a moment apart.
+const MINIMUM_SLEEP: Duration = Duration::from_secs(2);
+
+pub struct SleepDetector {
+    offset: Option<Duration>,
+}
+
+impl SleepDetector {
+    pub fn new() -> Self {
+        Self {
+            offset: clock_offset(),
+        }
+    }
+
+    // How long the computer slept for, if it has since the last check.
+    pub fn check(&mut self) -> Option<Duration> {
+        let offset = clock_offset()?;
+        let previous = self.offset.replace(offset)?;
+        offset
+            .checked_sub(previous)
+            .filter(|slept| *slept >= MINIMUM_SLEEP)
+    }
+}
+
+fn clock_offset() -> Option<Duration> {
+    let boot_time = read_clock(libc::CLOCK_BOOTTIME)?;
+    let monotonic = read_clock(libc::CLOCK_MONOTONIC)?;
+    Some(boot_time.saturating_sub(monotonic))
+}
+
+fn read_clock(clock: libc::clockid_t) -> Option<Duration> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(clock, &mut time) } != 0 {
+        return None;
+    }
+    Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
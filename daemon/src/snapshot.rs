@@ -0,0 +1,106 @@
+use crate::mic_profile::MicProfileAdapter;
+use crate::profile::ProfileAdapter;
+use anyhow::{Context, Result};
+use goxlr_profile_loader::write_atomically;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{create_dir_all, remove_file, File};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/**
+ A snapshot is a copy of the live profile and mic profile for a device, this contains all the
+ state which isn't normally saved (mute states, the active effect bank, encoder positions, etc)
+ so that a restart of the daemon can put the device back exactly how it was.
+*/
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DeviceSnapshot {
+    profile_name: String,
+    mic_profile_name: String,
+}
+
+pub fn write_snapshot(
+    directory: &Path,
+    serial: &str,
+    profile: &ProfileAdapter,
+    mic_profile: &MicProfileAdapter,
+) -> Result<()> {
+    create_dir_all(directory).context(format!(
+        "Could not create state directory at {}",
+        directory.to_string_lossy()
+    ))?;
+
+    // The profiles are only loaded alongside this, so removing it first and writing it last means
+    // stopping part way through never leaves a mix of old and new files to be loaded.
+    let snapshot_path = get_path(directory, serial, "json");
+    if let Err(error) = remove_file(&snapshot_path) {
+        if error.kind() != ErrorKind::NotFound {
+            return Err(error).context("Could not remove the previous state snapshot");
+        }
+    }
+
+    profile.write_snapshot(&get_path(directory, serial, "goxlr"))?;
+    mic_profile.write_snapshot(&get_path(directory, serial, "goxlrMicProfile"))?;
+
+    let snapshot = DeviceSnapshot {
+        profile_name: profile.name().to_owned(),
+        mic_profile_name: mic_profile.name().to_owned(),
+    };
+    write_atomically(&snapshot_path, |file| {
+        Ok(serde_json::to_writer_pretty(file, &snapshot).map_err(std::io::Error::from)?)
+    })?;
+
+    Ok(())
+}
+
+pub fn load_snapshot(
+    directory: &Path,
+    serial: &str,
+    profile_name: Option<&str>,
+    mic_profile_name: Option<&str>,
+) -> Option<(ProfileAdapter, MicProfileAdapter)> {
+    let file = File::open(get_path(directory, serial, "json")).ok()?;
+    let snapshot: DeviceSnapshot = match serde_json::from_reader(file) {
+        Ok(snapshot) => snapshot,
+        Err(error) => {
+            warn!("Unable to parse state snapshot for {}: {}", serial, error);
+            return None;
+        }
+    };
+
+    // If the profiles have been changed since the snapshot was made, it's no longer relevant.
+    if Some(snapshot.profile_name.as_str()) != profile_name
+        || Some(snapshot.mic_profile_name.as_str()) != mic_profile_name
+    {
+        info!(
+            "Ignoring state snapshot for {}, profiles have changed",
+            serial
+        );
+        return None;
+    }
+
+    let result = File::open(get_path(directory, serial, "goxlr"))
+        .map_err(anyhow::Error::from)
+        .and_then(|file| ProfileAdapter::from_reader(snapshot.profile_name, file))
+        .and_then(|profile| {
+            let file = File::open(get_path(directory, serial, "goxlrMicProfile"))?;
+            let mic_profile = MicProfileAdapter::from_reader(snapshot.mic_profile_name, file)?;
+            Ok((profile, mic_profile))
+        });
+
+    match result {
+        Ok(result) => {
+            info!("Restoring state snapshot for {}", serial);
+            Some(result)
+        }
+        Err(error) => {
+            warn!("Unable to load state snapshot for {}: {}", serial, error);
+            None
+        }
+    }
+}
+
+fn get_path(directory: &Path, serial: &str, extension: &str) -> PathBuf {
+    directory.join(format!("{}.{}", serial, extension))
+}
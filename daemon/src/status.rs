@@ -0,0 +1,230 @@
+use goxlr_ipc::{
+    DaemonEvent, DaemonStatus, DeviceSummary, DeviceType, MixerStatus, MuteState, Notification,
+    NotificationKind, StatusPatch,
+};
+use goxlr_types::ChannelName;
+use json_patch::{diff, Patch, PatchOperation, ReplaceOperation};
+use log::error;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+pub struct Subscription {
+    pub patches: broadcast::Receiver<StatusPatch>,
+    pub events: broadcast::Receiver<DaemonEvent>,
+}
+
+pub type Notifications = broadcast::Receiver<Notification>;
+
+/**
+ Keeps track of the last status sent to subscribers, and sends a patch containing only the
+ differences whenever it changes (apart from the mic level, which is sent as events). Events are
+ passed straight on to subscribers.
+
+ Notifications are worked out by comparing a summary of the status with the last one, so they
+ cover changes however they were made.
+*/
+pub struct StatusPublisher {
+    sender: broadcast::Sender<StatusPatch>,
+    events: broadcast::Sender<DaemonEvent>,
+    notifications: broadcast::Sender<Notification>,
+    last_status: Value,
+    last_summary: Option<Summary>,
+    sequence: u64,
+}
+
+struct Summary {
+    devices: Vec<DeviceSummary>,
+    latest_version: Option<String>,
+}
+
+impl StatusPublisher {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(64);
+        let (events, _) = broadcast::channel(64);
+        let (notifications, _) = broadcast::channel(64);
+        Self {
+            sender,
+            events,
+            notifications,
+            last_status: Value::Null,
+            last_summary: None,
+            sequence: 0,
+        }
+    }
+
+    pub fn publish_event(&self, event: DaemonEvent) {
+        // As with patches, an error just means nobody is listening.
+        let _ = self.events.send(event);
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0 || self.notifications.receiver_count() > 0
+    }
+
+    pub fn update(&mut self, status: &DaemonStatus) {
+        self.notify(status);
+
+        let mut status = match serde_json::to_value(status) {
+            Ok(status) => status,
+            Err(e) => {
+                error!("Unable to serialise status: {}", e);
+                return;
+            }
+        };
+
+        // Otherwise nearly every poll would send a patch, the level goes out as events instead.
+        if let Some(mixers) = status.get_mut("mixers").and_then(Value::as_object_mut) {
+            for mixer in mixers.values_mut() {
+                if let Some(mic_status) = mixer
+                    .pointer_mut("/mic_status")
+                    .and_then(Value::as_object_mut)
+                {
+                    mic_status.remove("level");
+                }
+            }
+        }
+
+        let patch = diff(&self.last_status, &status);
+        self.last_status = status;
+
+        if patch.0.is_empty() {
+            return;
+        }
+
+        self.sequence += 1;
+
+        // An error here just means there are no subscribers, which is fine.
+        let _ = self.sender.send(StatusPatch {
+            sequence: self.sequence,
+            patch,
+        });
+    }
+
+    pub fn subscribe(&mut self, status: &DaemonStatus) -> (StatusPatch, Subscription) {
+        // Bring any existing subscribers up to date, so the sequence matches this status.
+        self.update(status);
+
+        let full_status = StatusPatch {
+            sequence: self.sequence,
+            patch: Patch(vec![PatchOperation::Replace(ReplaceOperation {
+                path: String::new(),
+                value: self.last_status.clone(),
+            })]),
+        };
+
+        let subscription = Subscription {
+            patches: self.sender.subscribe(),
+            events: self.events.subscribe(),
+        };
+        (full_status, subscription)
+    }
+
+    pub fn subscribe_notifications(&mut self, status: &DaemonStatus) -> Notifications {
+        let receiver = self.notifications.subscribe();
+        self.last_summary = Some(get_summary(status));
+        receiver
+    }
+
+    fn notify(&mut self, status: &DaemonStatus) {
+        // Nobody to tell, and the next subscriber starts from the status when they subscribe.
+        if self.notifications.receiver_count() == 0 {
+            self.last_summary = None;
+            return;
+        }
+
+        let summary = get_summary(status);
+        if let Some(last) = &self.last_summary {
+            for notification in get_notifications(last, &summary) {
+                let _ = self.notifications.send(notification);
+            }
+        }
+        self.last_summary = Some(summary);
+    }
+}
+
+pub fn get_device_summary(mixer: &MixerStatus) -> DeviceSummary {
+    DeviceSummary {
+        serial: mixer.hardware.serial_number.clone(),
+        device_type: mixer.hardware.device_type.clone(),
+        profile_name: mixer.profile_name.clone(),
+        mic_profile_name: mixer.mic_profile_name.clone(),
+        mic_muted: mixer.get_channel_mute_state(ChannelName::Mic) != MuteState::Unmuted,
+        stream_panic: mixer.stream_panic,
+        locked: mixer.locked,
+    }
+}
+
+fn get_summary(status: &DaemonStatus) -> Summary {
+    let mut devices: Vec<DeviceSummary> = status.mixers.values().map(get_device_summary).collect();
+    devices.sort_by(|a, b| a.serial.cmp(&b.serial));
+
+    let updates = &status.updates;
+    Summary {
+        devices,
+        latest_version: updates
+            .latest_version
+            .clone()
+            .filter(|_| updates.update_available),
+    }
+}
+
+fn get_notifications(last: &Summary, current: &Summary) -> Vec<Notification> {
+    let mut notifications = vec![];
+    let mut notify = |kind, serial: Option<&str>, message: String| {
+        notifications.push(Notification {
+            kind,
+            serial: serial.map(str::to_owned),
+            message,
+        })
+    };
+
+    for device in &current.devices {
+        let serial = Some(device.serial.as_str());
+        let last = match last.devices.iter().find(|d| d.serial == device.serial) {
+            Some(last) => last,
+            None => {
+                let message = format!("{} {} connected", get_name(device), device.serial);
+                notify(NotificationKind::DeviceConnected, serial, message);
+                continue;
+            }
+        };
+
+        if device.profile_name != last.profile_name {
+            let message = format!("Loaded profile {}", device.profile_name);
+            notify(NotificationKind::ProfileLoaded, serial, message);
+        }
+        if device.mic_profile_name != last.mic_profile_name {
+            let message = format!("Loaded mic profile {}", device.mic_profile_name);
+            notify(NotificationKind::MicProfileLoaded, serial, message);
+        }
+        if device.mic_muted != last.mic_muted {
+            match device.mic_muted {
+                true => notify(NotificationKind::MicMuted, serial, "Mic muted".into()),
+                false => notify(NotificationKind::MicUnmuted, serial, "Mic unmuted".into()),
+            }
+        }
+    }
+
+    for device in &last.devices {
+        if !current.devices.iter().any(|d| d.serial == device.serial) {
+            let message = format!("{} {} disconnected", get_name(device), device.serial);
+            let serial = Some(device.serial.as_str());
+            notify(NotificationKind::DeviceDisconnected, serial, message);
+        }
+    }
+
+    if let Some(version) = &current.latest_version {
+        if last.latest_version.as_ref() != Some(version) {
+            let message = format!("Version {} of the GoXLR Utility is available", version);
+            notify(NotificationKind::UpdateAvailable, None, message);
+        }
+    }
+    notifications
+}
+
+fn get_name(device: &DeviceSummary) -> &'static str {
+    match device.device_type {
+        DeviceType::Mini => "GoXLR Mini",
+        _ => "GoXLR",
+    }
+}
@@ -0,0 +1,46 @@
+/*
+A lighting theme is just the button and fader colours (and their styles) from a profile, stored
+as JSON so they can be shared and applied without touching anything else on the device.
+ */
+
+use crate::files::check_file_name;
+use anyhow::{Context, Result};
+use goxlr_ipc::Lighting;
+use std::fs::{create_dir_all, File};
+use std::path::{Path, PathBuf};
+
+pub const THEME_EXTENSION: &str = "goxlrTheme";
+
+pub fn load_theme(directory: &Path, name: &str) -> Result<Lighting> {
+    let path = get_path(directory, name)?;
+    let file = File::open(&path).context(format!(
+        "Could not open theme at {}",
+        path.to_string_lossy()
+    ))?;
+    serde_json::from_reader(file).context(format!(
+        "Could not parse theme at {}",
+        path.to_string_lossy()
+    ))
+}
+
+pub fn save_theme(directory: &Path, name: &str, theme: &Lighting) -> Result<()> {
+    let path = get_path(directory, name)?;
+    create_dir_all(directory).context(format!(
+        "Could not create theme directory at {}",
+        directory.to_string_lossy()
+    ))?;
+
+    let writer = File::create(path)?;
+    serde_json::to_writer_pretty(writer, theme)?;
+    Ok(())
+}
+
+pub fn theme_exists(directory: &Path, name: &str) -> bool {
+    get_path(directory, name).is_ok_and(|path| path.is_file())
+}
+
+// Names come from clients, so they're checked before going anywhere near the filesystem.
+fn get_path(directory: &Path, name: &str) -> Result<PathBuf> {
+    check_file_name("theme", name)?;
+    Ok(directory.join(format!("{}.{}", name, THEME_EXTENSION)))
+}
@@ -0,0 +1,273 @@
+/*
+An optional check for new releases of the utility. When enabled, the latest release on GitHub is
+checked once a day and reported in the status if it's newer than this daemon. If a download
+directory is set, the release's .deb package is saved there as well (but never installed), once
+it's been checked against the SHA-256 published with the release.
+
+Requests are made with curl, rather than building an HTTP client and TLS into the daemon. It's
+only needed when checking is enabled, so the package recommends it rather than depending on it,
+and its absence is reported in the update status.
+ */
+
+use crate::{SettingsHandle, Shutdown};
+use anyhow::{bail, Context, Result};
+use goxlr_ipc::{UpdateSettings, UpdateStatus};
+use log::{info, warn};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs::{create_dir_all, remove_file, rename};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const PACKAGE_EXTENSION: &str = ".deb";
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: String,
+
+    // "sha256:<hex>", worked out by GitHub when the file is uploaded
+    #[serde(default)]
+    digest: Option<String>,
+}
+
+pub type UpdateReceiver = mpsc::UnboundedReceiver<()>;
+
+// Used by the device worker to include the result of the last check in the status, and to
+// trigger a new check when the settings change.
+#[derive(Debug, Clone)]
+pub struct UpdateHandle {
+    sender: mpsc::UnboundedSender<()>,
+    status: Arc<Mutex<UpdateStatus>>,
+}
+
+impl UpdateHandle {
+    pub fn new() -> (Self, UpdateReceiver) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let handle = Self {
+            sender,
+            status: Arc::new(Mutex::new(get_unchecked_status())),
+        };
+        (handle, receiver)
+    }
+
+    pub fn status(&self) -> UpdateStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn settings_changed(&self) {
+        // If the update task has stopped we're shutting down, so this can be ignored.
+        let _ = self.sender.send(());
+    }
+}
+
+pub async fn run_update_checker(
+    handle: UpdateHandle,
+    mut receiver: UpdateReceiver,
+    settings: SettingsHandle,
+    mut shutdown: Shutdown,
+) {
+    loop {
+        let config = settings.get_update_settings().await;
+        let mut wait = CHECK_INTERVAL;
+        if config.enabled {
+            match check_for_update(&config).await {
+                Ok(status) => *handle.status.lock().unwrap() = status,
+                Err(error) => {
+                    warn!("Unable to check for updates: {:#}", error);
+                    handle.status.lock().unwrap().error = Some(format!("{:#}", error));
+                    wait = RETRY_INTERVAL;
+                }
+            }
+        } else {
+            *handle.status.lock().unwrap() = get_unchecked_status();
+        }
+
+        tokio::select! {
+            () = shutdown.recv() => return,
+            () = sleep(wait), if config.enabled => {},
+            message = receiver.recv() => {
+                if message.is_none() {
+                    return;
+                }
+            },
+        }
+    }
+}
+
+fn get_unchecked_status() -> UpdateStatus {
+    UpdateStatus {
+        current_version: env!("CARGO_PKG_VERSION").to_string(),
+        ..Default::default()
+    }
+}
+
+async fn check_for_update(config: &UpdateSettings) -> Result<UpdateStatus> {
+    let repository = env!("CARGO_PKG_REPOSITORY").trim_start_matches("https://github.com/");
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/latest",
+        repository
+    );
+    let response = curl(&["--header", "Accept: application/vnd.github+json", &url]).await?;
+    let release: Release =
+        serde_json::from_slice(&response).context("Could not parse the latest release")?;
+
+    let mut status = get_unchecked_status();
+    status.update_available = is_newer(&release.tag_name, &status.current_version);
+    status.latest_version = Some(release.tag_name.clone());
+    status.release_url = Some(release.html_url.clone());
+    status.last_checked = Some(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    );
+
+    if status.update_available {
+        info!(
+            "Version {} of the utility is available at {}",
+            release.tag_name, release.html_url
+        );
+        if let Some(directory) = &config.download_directory {
+            status.downloaded_package = download_package(&release, directory).await?;
+        }
+    }
+    Ok(status)
+}
+
+// Returns where the package was saved, or None if the release doesn't have one (or it can't be
+// verified).
+async fn download_package(release: &Release, directory: &Path) -> Result<Option<PathBuf>> {
+    let asset = match release
+        .assets
+        .iter()
+        .find(|asset| asset.name.ends_with(PACKAGE_EXTENSION))
+    {
+        Some(asset) => asset,
+        None => return Ok(None),
+    };
+
+    // The name comes from GitHub, so make sure it can't point outside the directory.
+    let name = Path::new(&asset.name)
+        .file_name()
+        .context("Invalid package name")?;
+    let path = directory.join(name);
+
+    let checksum = match get_published_checksum(release, asset).await? {
+        Some(checksum) => checksum,
+        None => {
+            warn!(
+                "{} has no published checksum, so it won't be downloaded",
+                asset.name
+            );
+            return Ok(None);
+        }
+    };
+    if path.exists() && get_file_checksum(&path)? == checksum {
+        return Ok(Some(path));
+    }
+
+    create_dir_all(directory).context(format!(
+        "Could not create download directory at {}",
+        directory.to_string_lossy()
+    ))?;
+
+    // Download to a temporary name, so an interrupted download isn't mistaken for the package.
+    let partial = path.with_extension("part");
+    let partial_str = partial.to_string_lossy();
+    curl(&["--output", &partial_str, &asset.browser_download_url]).await?;
+    if get_file_checksum(&partial)? != checksum {
+        let _ = remove_file(&partial);
+        bail!("{} doesn't match its published checksum", asset.name);
+    }
+    rename(&partial, &path)?;
+
+    info!("Downloaded {} to {}", asset.name, path.to_string_lossy());
+    Ok(Some(path))
+}
+
+// GitHub's digest for the asset, or failing that a <package>.sha256 file uploaded alongside it.
+async fn get_published_checksum(release: &Release, asset: &ReleaseAsset) -> Result<Option<String>> {
+    if let Some(digest) = &asset.digest {
+        if let Some(checksum) = digest.strip_prefix("sha256:") {
+            return Ok(Some(checksum.to_ascii_lowercase()));
+        }
+    }
+
+    let checksum_name = format!("{}.sha256", asset.name);
+    let checksum_asset = match release.assets.iter().find(|a| a.name == checksum_name) {
+        Some(checksum_asset) => checksum_asset,
+        None => return Ok(None),
+    };
+
+    // In the format sha256sum writes, the checksum followed by the file name.
+    let contents = curl(&[&checksum_asset.browser_download_url]).await?;
+    Ok(String::from_utf8_lossy(&contents)
+        .split_whitespace()
+        .next()
+        .map(str::to_ascii_lowercase))
+}
+
+fn get_file_checksum(path: &Path) -> Result<String> {
+    let contents =
+        std::fs::read(path).context(format!("Could not read {}", path.to_string_lossy()))?;
+    Ok(format!("{:x}", Sha256::digest(contents)))
+}
+
+async fn curl(args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--max-time", "300"])
+        .args(args)
+        .output()
+        .await;
+    let output = match output {
+        Ok(output) => output,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            bail!("curl isn't installed, it's needed to check for updates")
+        }
+        Err(error) => return Err(error).context("Could not run curl"),
+    };
+
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+// Compares dotted version numbers, ignoring a leading 'v' and any pre-release suffix.
+fn is_newer(version: &str, current: &str) -> bool {
+    parse_version(version) > parse_version(current)
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    let version = version.trim_start_matches('v');
+    let mut parts: Vec<u64> = version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+
+    // So 1.2 and 1.2.0 compare as equal.
+    while parts.last() == Some(&0) {
+        parts.pop();
+    }
+    parts
+}
@@ -0,0 +1,231 @@
+/*
+Just enough WAV handling for the sampler: reading the format of a file, working out its peak
+levels so a waveform can be drawn, and cutting it down to its trim points for playback.
+
+A WAV file is a list of chunks, the format comes from the 'fmt ' chunk and the audio itself is in
+the 'data' chunk. Anything else (such as metadata) is skipped, and not copied when trimming.
+ */
+
+use anyhow::{bail, Context, Result};
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Encoding {
+    Integer,
+    Float,
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct WavHeader {
+    pub channels: u16,
+    pub sample_rate: u32,
+    bits_per_sample: u16,
+    byte_rate: u32,
+    block_align: u16,
+    encoding: Encoding,
+
+    // The 'fmt ' chunk as it was read, so a trimmed copy has exactly the same format.
+    format_chunk: Vec<u8>,
+    data_offset: u64,
+    data_length: u64,
+}
+
+impl WavHeader {
+    pub fn duration_ms(&self) -> u64 {
+        if self.byte_rate == 0 {
+            return 0;
+        }
+        self.data_length * 1000 / u64::from(self.byte_rate)
+    }
+
+    fn frames(&self) -> u64 {
+        self.data_length / u64::from(self.block_align)
+    }
+
+    fn decode(&self, sample: &[u8]) -> Option<f32> {
+        let value = match (self.encoding, self.bits_per_sample) {
+            (Encoding::Integer, 8) => (f32::from(sample[0]) - 128.0) / 128.0,
+            (Encoding::Integer, 16) => f32::from(LittleEndian::read_i16(sample)) / 32768.0,
+            (Encoding::Integer, 24) => LittleEndian::read_i24(sample) as f32 / 8388608.0,
+            (Encoding::Integer, 32) => LittleEndian::read_i32(sample) as f32 / 2147483648.0,
+            (Encoding::Float, 32) => LittleEndian::read_f32(sample),
+            (Encoding::Float, 64) => LittleEndian::read_f64(sample) as f32,
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+pub fn read_header(path: &Path) -> Result<WavHeader> {
+    let mut file = File::open(path).context(format!(
+        "Could not open sample at {}",
+        path.to_string_lossy()
+    ))?;
+    read_header_from(&mut file)
+}
+
+fn read_header_from(file: &mut File) -> Result<WavHeader> {
+    let file_length = file.metadata()?.len();
+
+    let mut header = [0; 12];
+    file.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        bail!("Not a WAV file");
+    }
+
+    let mut format_chunk = None;
+    loop {
+        let mut chunk = [0; 8];
+        file.read_exact(&mut chunk)
+            .context("No audio data found in WAV file")?;
+        let size = LittleEndian::read_u32(&chunk[4..8]);
+
+        match &chunk[0..4] {
+            b"fmt " => {
+                if size < 16 {
+                    bail!("Invalid WAV format");
+                }
+                let mut fmt = vec![0; size as usize];
+                file.read_exact(&mut fmt)?;
+                if size & 1 == 1 {
+                    file.seek(SeekFrom::Current(1))?;
+                }
+                format_chunk = Some(fmt);
+            }
+            b"data" => {
+                let format_chunk = format_chunk.context("Invalid WAV format")?;
+                let data_offset = file.stream_position()?;
+
+                // Some recorders leave the size unset when streaming, so don't trust it too far.
+                let data_length = u64::from(size).min(file_length - data_offset);
+                return parse_format(format_chunk, data_offset, data_length);
+            }
+            _ => {
+                let remaining = i64::from(size) + i64::from(size & 1);
+                file.seek(SeekFrom::Current(remaining))?;
+            }
+        }
+    }
+}
+
+fn parse_format(format_chunk: Vec<u8>, data_offset: u64, data_length: u64) -> Result<WavHeader> {
+    let mut format = LittleEndian::read_u16(&format_chunk[0..2]);
+    if format == FORMAT_EXTENSIBLE && format_chunk.len() >= 26 {
+        // The actual format is the first two bytes of the sub format GUID.
+        format = LittleEndian::read_u16(&format_chunk[24..26]);
+    }
+
+    let header = WavHeader {
+        channels: LittleEndian::read_u16(&format_chunk[2..4]),
+        sample_rate: LittleEndian::read_u32(&format_chunk[4..8]),
+        byte_rate: LittleEndian::read_u32(&format_chunk[8..12]),
+        block_align: LittleEndian::read_u16(&format_chunk[12..14]),
+        bits_per_sample: LittleEndian::read_u16(&format_chunk[14..16]),
+        encoding: match format {
+            FORMAT_PCM => Encoding::Integer,
+            FORMAT_FLOAT => Encoding::Float,
+            _ => Encoding::Unknown,
+        },
+        format_chunk,
+        data_offset,
+        data_length,
+    };
+
+    if header.channels == 0 || header.block_align == 0 {
+        bail!("Invalid WAV format");
+    }
+    Ok(header)
+}
+
+/**
+ Splits the audio into (up to) `points` equal sections, and returns the loudest level in each
+ across all channels, from 0 to 1.
+*/
+pub fn get_peaks(path: &Path, points: usize) -> Result<Vec<f32>> {
+    let mut file = File::open(path)?;
+    let header = read_header_from(&mut file)?;
+
+    let sample_size = usize::from(header.bits_per_sample / 8);
+    let frame_size = usize::from(header.block_align);
+    if header.decode(&vec![0; sample_size]).is_none()
+        || sample_size * usize::from(header.channels) > frame_size
+    {
+        bail!("Unsupported WAV format, only PCM and floating point audio can be read");
+    }
+
+    let frames = header.frames();
+    let points = points.max(1) as u64;
+    let frames_per_point = frames.div_ceil(points).max(1);
+
+    file.seek(SeekFrom::Start(header.data_offset))?;
+    let mut reader = BufReader::new(file);
+    let mut frame = vec![0; frame_size];
+
+    let mut peaks = vec![];
+    let mut peak: f32 = 0.0;
+    for index in 0..frames {
+        reader.read_exact(&mut frame)?;
+        for sample in frame.chunks_exact(sample_size).take(header.channels.into()) {
+            if let Some(value) = header.decode(sample) {
+                peak = peak.max(value.abs());
+            }
+        }
+
+        if (index + 1) % frames_per_point == 0 || index + 1 == frames {
+            peaks.push(peak.min(1.0));
+            peak = 0.0;
+        }
+    }
+    Ok(peaks)
+}
+
+// Writes the part of the source between the start and end percentages as a new WAV file.
+pub fn write_trimmed(source: &Path, destination: &Path, start_pct: u8, end_pct: u8) -> Result<()> {
+    let mut file = File::open(source)?;
+    let header = read_header_from(&mut file)?;
+
+    let frames = header.frames();
+    let start = frames * u64::from(start_pct.min(100)) / 100;
+    let end = frames * u64::from(end_pct.min(100)) / 100;
+    let frame_size = u64::from(header.block_align);
+    let length = end.saturating_sub(start) * frame_size;
+
+    let format_length = header.format_chunk.len() as u64;
+    let format_padding = format_length & 1;
+    let data_padding = length & 1;
+    let riff_length = 4 + 8 + format_length + format_padding + 8 + length + data_padding;
+    if riff_length > u64::from(u32::MAX) {
+        bail!("Sample is too large to trim");
+    }
+
+    let mut writer = BufWriter::new(File::create(destination)?);
+    writer.write_all(b"RIFF")?;
+    writer.write_u32::<LittleEndian>(riff_length as u32)?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_u32::<LittleEndian>(format_length as u32)?;
+    writer.write_all(&header.format_chunk)?;
+    if format_padding == 1 {
+        writer.write_u8(0)?;
+    }
+
+    writer.write_all(b"data")?;
+    writer.write_u32::<LittleEndian>(length as u32)?;
+    file.seek(SeekFrom::Start(header.data_offset + start * frame_size))?;
+    std::io::copy(&mut file.take(length), &mut writer)?;
+    if data_padding == 1 {
+        writer.write_u8(0)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
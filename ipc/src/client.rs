@@ -1,11 +1,26 @@
-use crate::{DaemonRequest, DaemonResponse, DaemonStatus, GoXLRCommand, Socket};
+use crate::{
+    AuditEntry, DaemonEvent, DaemonHealth, DaemonRequest, DaemonResponse, DaemonStatus,
+    DeviceSettings, DeviceSummary, DiagnosticReport, GoXLRCommand, HardwareInfo, MuteStateReport,
+    Notification, ProfileBackup, ProfileType, SampleFile, SamplePage, SampleQuery, SettingsInfo,
+    Socket, StateVerification, StatusPatch, UpdateSettings,
+};
 use anyhow::{anyhow, Context, Result};
-//use goxlr_ipc::{DaemonRequest, DaemonResponse, DaemonStatus, GoXLRCommand, Socket};
+use json_patch::{PatchOperation, ReplaceOperation};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::time::timeout;
+
+// A daemon which predates versioning never answers, so don't wait long for it.
+const PROTOCOL_VERSION_TIMEOUT: Duration = Duration::from_millis(500);
+
+//use goxlr_ipc::{DaemonHealth, DaemonRequest, DaemonResponse, DaemonStatus, GoXLRCommand, Socket};
 
 #[derive(Debug)]
 pub struct Client {
     socket: Socket<DaemonResponse, DaemonRequest>,
     status: DaemonStatus,
+    sequence: Option<u64>,
+    dry_run: bool,
 }
 
 impl Client {
@@ -13,28 +28,48 @@ impl Client {
         Self {
             socket,
             status: DaemonStatus::default(),
+            sequence: None,
+            dry_run: false,
         }
     }
 
+    /// When enabled, commands are only validated by the daemon and never reach the device.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
     pub async fn send(&mut self, request: DaemonRequest) -> Result<()> {
+        self.request(request).await?;
+        Ok(())
+    }
+
+    async fn request(&mut self, request: DaemonRequest) -> Result<DaemonResponse> {
+        let is_subscribe = matches!(request, DaemonRequest::Subscribe);
         self.socket
             .send(request)
             .await
             .context("Failed to send a command to the GoXLR daemon process")?;
-        let result = self
-            .socket
-            .read()
-            .await
-            .context("Failed to retrieve the command result from the GoXLR daemon process")?
-            .context("Failed to parse the command result from the GoXLR daemon process")?;
 
-        match result {
-            DaemonResponse::Status(status) => {
-                self.status = status;
-                Ok(())
+        loop {
+            match self.read().await? {
+                DaemonResponse::Status(status) => {
+                    self.status = status.clone();
+                    return Ok(DaemonResponse::Status(status));
+                }
+                DaemonResponse::StatusPatch(patch) => {
+                    // If we're subscribed, patches may arrive before our response..
+                    let is_full_status = is_full_status(&patch);
+                    self.apply_patch(patch.clone()).await?;
+                    if is_subscribe && is_full_status {
+                        return Ok(DaemonResponse::StatusPatch(patch));
+                    }
+                }
+
+                // Events are only useful to something waiting for changes.
+                DaemonResponse::Event(_) => {}
+                DaemonResponse::Error(error) => return Err(error.into()),
+                response => return Ok(response),
             }
-            DaemonResponse::Ok => Ok(()),
-            DaemonResponse::Error(error) => Err(anyhow!("{}", error)),
         }
     }
 
@@ -43,11 +78,348 @@ impl Client {
     }
 
     pub async fn command(&mut self, serial: &str, command: GoXLRCommand) -> Result<()> {
+        if self.dry_run {
+            return self.validate(serial, command).await;
+        }
         self.send(DaemonRequest::Command(serial.to_string(), command))
             .await
     }
 
+    pub async fn command_batch(
+        &mut self,
+        serial: &str,
+        commands: Vec<GoXLRCommand>,
+        rollback: bool,
+    ) -> Result<()> {
+        if self.dry_run {
+            for command in commands {
+                self.validate(serial, command).await?;
+            }
+            return Ok(());
+        }
+        self.send(DaemonRequest::CommandBatch(
+            serial.to_string(),
+            commands,
+            rollback,
+        ))
+        .await
+    }
+
+    pub async fn validate(&mut self, serial: &str, command: GoXLRCommand) -> Result<()> {
+        self.send(DaemonRequest::Validate(serial.to_string(), command))
+            .await
+    }
+
+    /// Returns the protocol version of the daemon, daemons which predate versioning return 0.
+    ///
+    /// Those daemons can't parse the request, and either drop the connection or never reply, so
+    /// the client shouldn't be reused once this has returned 0.
+    pub async fn protocol_version(&mut self) -> u32 {
+        let request = self.request(DaemonRequest::GetProtocolVersion);
+        match timeout(PROTOCOL_VERSION_TIMEOUT, request).await {
+            Ok(Ok(DaemonResponse::ProtocolVersion(version))) => version,
+            _ => 0,
+        }
+    }
+
+    pub async fn export_mic_profile(&mut self, serial: &str) -> Result<String> {
+        match self
+            .request(DaemonRequest::ExportMicProfile(serial.to_string()))
+            .await?
+        {
+            DaemonResponse::MicProfileJson(json) => Ok(json),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn run_diagnostics(&mut self, serial: &str) -> Result<DiagnosticReport> {
+        match self
+            .request(DaemonRequest::RunDiagnostics(serial.to_string()))
+            .await?
+        {
+            DaemonResponse::Diagnostics(report) => Ok(report),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn set_client_name(&mut self, name: &str) -> Result<()> {
+        self.send(DaemonRequest::SetClientName(name.to_string()))
+            .await
+    }
+
+    pub async fn audit_log(&mut self) -> Result<Vec<AuditEntry>> {
+        match self.request(DaemonRequest::GetAuditLog).await? {
+            DaemonResponse::AuditLog(entries) => Ok(entries),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn ui_url(&mut self) -> Result<String> {
+        match self.request(DaemonRequest::GetUiUrl).await? {
+            DaemonResponse::UiUrl(url) => Ok(url),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn device_summary(&mut self) -> Result<Vec<DeviceSummary>> {
+        match self.request(DaemonRequest::GetDeviceSummary).await? {
+            DaemonResponse::DeviceSummary(summary) => Ok(summary),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn restart_daemon(&mut self) -> Result<()> {
+        self.send(DaemonRequest::RestartDaemon).await
+    }
+
+    pub async fn mute_state(&mut self, serial: &str) -> Result<MuteStateReport> {
+        match self
+            .request(DaemonRequest::GetMuteState(serial.to_string()))
+            .await?
+        {
+            DaemonResponse::MuteState(report) => Ok(report),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn hardware_info(&mut self, serial: &str) -> Result<HardwareInfo> {
+        match self
+            .request(DaemonRequest::GetHardwareInfo(serial.to_string()))
+            .await?
+        {
+            DaemonResponse::HardwareInfo(info) => Ok(info),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn verify_state(&mut self, serial: &str) -> Result<StateVerification> {
+        match self
+            .request(DaemonRequest::VerifyState(serial.to_string()))
+            .await?
+        {
+            DaemonResponse::StateVerification(report) => Ok(report),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn send_raw_command(
+        &mut self,
+        serial: &str,
+        command_id: u32,
+        body: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let request = DaemonRequest::SendRawCommand(serial.to_string(), command_id, body);
+        match self.request(request).await? {
+            DaemonResponse::RawResponse(response) => Ok(response),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn settings_info(&mut self) -> Result<SettingsInfo> {
+        match self.request(DaemonRequest::GetSettingsInfo).await? {
+            DaemonResponse::SettingsInfo(info) => Ok(info),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn flush_settings(&mut self) -> Result<()> {
+        self.send(DaemonRequest::FlushSettings).await
+    }
+
+    pub async fn daemon_health(&mut self) -> Result<DaemonHealth> {
+        match self.request(DaemonRequest::GetDaemonStatus).await? {
+            DaemonResponse::DaemonHealth(health) => Ok(health),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn device_settings(&mut self, serial: &str) -> Result<DeviceSettings> {
+        self.request_device_settings(DaemonRequest::GetDeviceSettings(serial.to_string()))
+            .await
+    }
+
+    pub async fn set_device_settings(
+        &mut self,
+        serial: &str,
+        settings: DeviceSettings,
+    ) -> Result<()> {
+        self.send(DaemonRequest::SetDeviceSettings(
+            serial.to_string(),
+            settings,
+        ))
+        .await
+    }
+
+    pub async fn default_device_settings(&mut self) -> Result<DeviceSettings> {
+        self.request_device_settings(DaemonRequest::GetDefaultDeviceSettings)
+            .await
+    }
+
+    pub async fn set_default_device_settings(&mut self, settings: DeviceSettings) -> Result<()> {
+        self.send(DaemonRequest::SetDefaultDeviceSettings(settings))
+            .await
+    }
+
+    async fn request_device_settings(&mut self, request: DaemonRequest) -> Result<DeviceSettings> {
+        match self.request(request).await? {
+            DaemonResponse::DeviceSettings(settings) => Ok(settings),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn samples(&mut self) -> Result<Vec<SampleFile>> {
+        match self.request(DaemonRequest::GetSamples).await? {
+            DaemonResponse::Samples(samples) => Ok(samples),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn find_samples(&mut self, query: SampleQuery) -> Result<SamplePage> {
+        match self.request(DaemonRequest::FindSamples(query)).await? {
+            DaemonResponse::SamplePage(page) => Ok(page),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn add_sample(&mut self, path: &str, data: Vec<u8>) -> Result<()> {
+        self.send(DaemonRequest::AddSample(path.to_string(), data))
+            .await
+    }
+
+    pub async fn move_sample(&mut self, from: &str, to: &str) -> Result<()> {
+        self.send(DaemonRequest::MoveSample(from.to_string(), to.to_string()))
+            .await
+    }
+
+    pub async fn delete_sample(&mut self, path: &str) -> Result<()> {
+        self.send(DaemonRequest::DeleteSample(path.to_string()))
+            .await
+    }
+
+    pub async fn sample_waveform(&mut self, path: &str, points: u16) -> Result<Vec<f32>> {
+        let request = DaemonRequest::GetSampleWaveform(path.to_string(), points);
+        match self.request(request).await? {
+            DaemonResponse::SampleWaveform(peaks) => Ok(peaks),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn profile_backups(
+        &mut self,
+        profile_type: ProfileType,
+        name: &str,
+    ) -> Result<Vec<ProfileBackup>> {
+        let request = DaemonRequest::GetProfileBackups(profile_type, name.to_string());
+        match self.request(request).await? {
+            DaemonResponse::ProfileBackups(backups) => Ok(backups),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn restore_profile_backup(
+        &mut self,
+        profile_type: ProfileType,
+        name: &str,
+        id: &str,
+    ) -> Result<()> {
+        self.send(DaemonRequest::RestoreProfileBackup(
+            profile_type,
+            name.to_string(),
+            id.to_string(),
+        ))
+        .await
+    }
+
+    pub async fn set_script_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        self.send(DaemonRequest::SetScriptEnabled(name.to_string(), enabled))
+            .await
+    }
+
+    pub async fn reload_scripts(&mut self) -> Result<()> {
+        self.send(DaemonRequest::ReloadScripts).await
+    }
+
+    pub async fn set_update_settings(&mut self, settings: UpdateSettings) -> Result<()> {
+        self.send(DaemonRequest::SetUpdateSettings(settings)).await
+    }
+
+    pub async fn set_custom_ui_directory(&mut self, directory: Option<PathBuf>) -> Result<()> {
+        self.send(DaemonRequest::SetCustomUiDirectory(directory))
+            .await
+    }
+
+    pub async fn subscribe(&mut self) -> Result<()> {
+        self.sequence = None;
+        self.send(DaemonRequest::Subscribe).await
+    }
+
+    /// Waits for the next status change from a subscribed daemon, and applies it to the status.
+    /// Events are returned as they arrive, rather than changing the status.
+    pub async fn wait_for_change(&mut self) -> Result<Option<DaemonEvent>> {
+        match self.read().await? {
+            DaemonResponse::StatusPatch(patch) => self.apply_patch(patch).await.map(|_| None),
+            DaemonResponse::Event(event) => Ok(Some(event)),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
+    pub async fn subscribe_notifications(&mut self) -> Result<()> {
+        self.send(DaemonRequest::SubscribeNotifications).await
+    }
+
+    /// Waits for the next notification, after subscribe_notifications.
+    pub async fn wait_for_notification(&mut self) -> Result<Notification> {
+        match self.read().await? {
+            DaemonResponse::Notification(notification) => Ok(notification),
+            response => Err(anyhow!("Unexpected response from daemon: {:?}", response)),
+        }
+    }
+
     pub fn status(&self) -> &DaemonStatus {
         &self.status
     }
+
+    async fn read(&mut self) -> Result<DaemonResponse> {
+        self.socket
+            .read()
+            .await
+            .context("Failed to retrieve the command result from the GoXLR daemon process")?
+            .context("Failed to parse the command result from the GoXLR daemon process")
+    }
+
+    async fn apply_patch(&mut self, patch: StatusPatch) -> Result<()> {
+        if !is_full_status(&patch) {
+            match self.sequence {
+                // Waiting for a full status, this patch can't be applied yet.
+                None => return Ok(()),
+
+                // We've missed an update, request a full status to get back in sync.
+                Some(sequence) if patch.sequence != sequence + 1 => {
+                    self.sequence = None;
+                    return self
+                        .socket
+                        .send(DaemonRequest::Subscribe)
+                        .await
+                        .context("Failed to resubscribe to the GoXLR daemon process");
+                }
+                _ => {}
+            }
+        }
+
+        let mut status =
+            serde_json::to_value(&self.status).context("Failed to serialise the status")?;
+        json_patch::patch(&mut status, &patch.patch).context("Failed to apply status patch")?;
+        self.status = serde_json::from_value(status).context("Failed to parse patched status")?;
+        self.sequence = Some(patch.sequence);
+
+        Ok(())
+    }
+}
+
+// The first patch sent after subscribing replaces the entire status.
+fn is_full_status(patch: &StatusPatch) -> bool {
+    matches!(
+        patch.patch.0.as_slice(),
+        [PatchOperation::Replace(ReplaceOperation { path, .. })] if path.is_empty()
+    )
 }
@@ -1,19 +1,28 @@
 use enumset::EnumSet;
 use goxlr_types::{
     ButtonColourOffStyle, ButtonColourTargets, ChannelName, CompressorAttackTime, CompressorRatio,
-    CompressorReleaseTime, EqFrequencies, FaderDisplayStyle, FaderName, FirmwareVersions,
-    GateTimes, InputDevice, MicrophoneType, MiniEqFrequencies, MuteFunction, OutputDevice,
+    CompressorReleaseTime, EncoderName, EqFrequencies, FaderDisplayStyle, FaderName,
+    FirmwareVersions, GateTimes, InputDevice, MicrophoneType, MiniEqFrequencies, MuteFunction,
+    OutputDevice, SampleBank, SampleButtons,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use strum::EnumCount;
 
+use crate::GoXLRCommand;
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DaemonStatus {
     pub mixers: HashMap<String, MixerStatus>,
     pub paths: Paths,
     pub files: Files,
+    #[serde(default)]
+    pub obs: ObsStatus,
+    #[serde(default)]
+    pub updates: UpdateStatus,
+    #[serde(default)]
+    pub scripts: Vec<ScriptStatus>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,13 +31,63 @@ pub struct MixerStatus {
     pub fader_status: [FaderStatus; 4],
     pub mic_status: MicSettings,
     pub volumes: [u8; ChannelName::COUNT],
+    #[serde(default)]
+    pub volumes_db: [f32; ChannelName::COUNT],
+    #[serde(default)]
+    pub volume_limits: [Option<u8>; ChannelName::COUNT],
+    #[serde(default)]
+    pub volume_links: Vec<Vec<ChannelName>>,
+
+    // Names the user has given to channels and outputs, to show in place of the built in ones
+    #[serde(default)]
+    pub channel_names: [Option<String>; ChannelName::COUNT],
+    #[serde(default)]
+    pub output_names: [Option<String>; OutputDevice::COUNT],
+
+    // Colours (RRGGBB) which follow their channels between faders
+    #[serde(default)]
+    pub channel_colours: [Option<String>; ChannelName::COUNT],
+
+    // Whether each channel is muted, by its fader's mute button or (for the Mic) the cough button
+    #[serde(default)]
+    pub mute_states: [MuteState; ChannelName::COUNT],
     pub router: [EnumSet<OutputDevice>; InputDevice::COUNT],
     pub router_table: [[bool; OutputDevice::COUNT]; InputDevice::COUNT],
     pub cough_button: CoughButton,
     pub bleep_volume: i8,
     pub lighting: Lighting,
+    #[serde(default)]
+    pub button_states: HashMap<ButtonColourTargets, ButtonLightState>,
     pub profile_name: String,
     pub mic_profile_name: String,
+
+    // The profile QuickSwitchProfile will switch to
+    #[serde(default)]
+    pub previous_profile_name: Option<String>,
+
+    // Whether everything is being kept off the stream by SetStreamPanic
+    #[serde(default)]
+    pub stream_panic: bool,
+
+    // Channels soloed with SetSolo, in the order they were soloed
+    #[serde(default)]
+    pub soloed_channels: Vec<ChannelName>,
+
+    // The fader page the faders were last moved to, None if they haven't been
+    #[serde(default)]
+    pub fader_page: Option<usize>,
+
+    // Whether the physical controls are locked by SetLocked
+    #[serde(default)]
+    pub locked: bool,
+
+    // Only present when the device firmware supports sub mixes.
+    #[serde(default)]
+    pub submixes: Option<SubMixes>,
+
+    // Only present on devices with a sampler.
+    #[serde(default)]
+    pub sampler: Option<SamplerStatus>,
 }
 
 impl MixerStatus {
@@ -43,6 +102,27 @@ impl MixerStatus {
     pub fn set_channel_volume(&mut self, channel: ChannelName, volume: u8) {
         self.volumes[channel as usize] = volume;
     }
+
+    pub fn get_channel_mute_state(&self, channel: ChannelName) -> MuteState {
+        self.mute_states[channel as usize]
+    }
+}
+
+/// Returns false for routes which the GoXLR can't perform, because they'd feed a channel back
+/// into itself (Voice Chat back into the Chat Mic, or the Sampler recording its own playback).
+pub fn is_valid_route(input: InputDevice, output: OutputDevice) -> bool {
+    !matches!(
+        (input, output),
+        (InputDevice::Chat, OutputDevice::ChatMic) | (InputDevice::Samples, OutputDevice::Sampler)
+    )
+}
+
+/// Volumes on the second (monitor) mix, indexed by ChannelName. Only the input channels have a
+/// sub mix, the entries for Headphones, MicMonitor and LineOut are unused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubMixes {
+    pub volumes: [u8; ChannelName::COUNT],
+    pub linked: [bool; ChannelName::COUNT],
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,18 +132,191 @@ pub struct HardwareStatus {
     pub manufactured_date: String,
     pub device_type: DeviceType,
     pub usb_device: UsbProductInformation,
+    #[serde(default)]
+    pub features: DeviceFeatures,
+}
+
+/**
+ What a device supports, worked out from its type and firmware version when it's connected.
+ Commands for a feature the device doesn't have are rejected with DaemonError::Unsupported.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceFeatures {
+    // The effect presets, and the megaphone, robot, hardtune and fx buttons
+    pub effects: bool,
+
+    // The pitch, gender, reverb and echo knobs
+    pub encoders: bool,
+    pub sampler: bool,
+
+    // The ten band EQ, the Mini only has the six band EQ
+    pub full_eq: bool,
+
+    // The gradient and meter fader styles, the Mini's faders only show two colours
+    pub fader_display_modes: bool,
+    pub submixes: bool,
+
+    // Firmware which takes the larger colour map, older firmware uses a shorter one
+    pub extended_colour_map: bool,
+}
+
+/// The result of DaemonRequest::RunDiagnostics, intended to be attached to bug reports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticReport {
+    pub hardware: HardwareStatus,
+    pub checks: Vec<DiagnosticCheck>,
+    pub latency: Option<CommandLatency>,
+}
+
+/// The result of DaemonRequest::GetHardwareInfo, everything the device reports about itself beyond
+/// the summary in HardwareStatus, for support requests. The colour of the device isn't reported by
+/// the hardware, so it can't be included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareInfo {
+    pub hardware: HardwareStatus,
+    pub usb_descriptor: UsbDescriptorInfo,
+
+    // The DCP categories the firmware says it supports
+    pub dcp_categories: Vec<String>,
+
+    // Only partly understood, so these are passed on as hex
+    pub firmware_version_response: String,
+    pub system_info_response: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsbDescriptorInfo {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub usb_version: (u8, u8, u8),
+    pub speed: String,
+}
+
+/// The result of DaemonRequest::VerifyState. Only the state the device can report back is
+/// compared, the rest is listed in unverified.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateVerification {
+    pub drift: Vec<StateDrift>,
+    pub unverified: Vec<String>,
+}
+
+/// The result of DaemonRequest::GetMuteState, everything the mute buttons and cough button
+/// decide what's muted with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MuteStateReport {
+    // Faders A to D
+    pub faders: Vec<MuteButtonState>,
+
+    // Muted channels which have been paged off the faders, with the state they'll return with
+    pub paged: Vec<MuteButtonState>,
+    pub cough: CoughMuteState,
+
+    // The outcome for each channel, as in MixerStatus::mute_states
+    pub channels: [MuteState; ChannelName::COUNT],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MuteButtonState {
+    pub channel: ChannelName,
+
+    // The function the button is set to, and the one it's muted with, which differ after a hold
+    pub mute_function: MuteFunction,
+    pub active_function: MuteFunction,
+    pub muted_to_x: bool,
+    pub muted_to_all: bool,
+
+    // The volume put back when a mute to all is undone
+    pub previous_volume: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoughMuteState {
+    pub is_toggle: bool,
+    pub is_hybrid: bool,
+    pub mute_function: MuteFunction,
+    pub muted_to_x: bool,
+    pub muted_to_all: bool,
+
+    // Whether the last press of a hybrid button muted the mic
+    pub press_muted: bool,
+}
+
+// A value which the device and the daemon disagree on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateDrift {
+    pub name: String,
+    pub device: i32,
+    pub daemon: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+impl DiagnosticCheck {
+    pub fn passed(name: &str, message: String) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: true,
+            message,
+        }
+    }
+
+    pub fn failed(name: &str, message: String) -> Self {
+        Self {
+            name: name.to_string(),
+            passed: false,
+            message,
+        }
+    }
+
+    pub fn from_result<E: std::fmt::Display>(name: &str, result: Result<String, E>) -> Self {
+        match result {
+            Ok(message) => Self::passed(name, message),
+            Err(error) => Self::failed(name, error.to_string()),
+        }
+    }
+}
+
+/// Round trip times for reading the button states from the device, in microseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLatency {
+    pub samples: u32,
+    pub min_us: u64,
+    pub average_us: u64,
+    pub max_us: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Copy)]
 pub struct FaderStatus {
     pub channel: ChannelName,
     pub mute_type: MuteFunction,
+    #[serde(default)]
+    pub mute_state: MuteState,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum MuteState {
+    #[default]
+    Unmuted,
+    MutedToAll,
+
+    // Muted to some outputs (depending on the mute button's behaviour), but heard on the others
+    MutedTo(EnumSet<OutputDevice>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Copy)]
 pub struct CoughButton {
     pub is_toggle: bool,
     pub mute_type: MuteFunction,
+
+    // A tap toggles the mute, holding mutes until release (is_toggle is also set)
+    #[serde(default)]
+    pub is_hybrid: bool,
 }
 
 impl Default for FaderStatus {
@@ -71,6 +324,7 @@ impl Default for FaderStatus {
         FaderStatus {
             channel: ChannelName::Mic,
             mute_type: MuteFunction::All,
+            mute_state: MuteState::Unmuted,
         }
     }
 }
@@ -84,6 +338,54 @@ pub struct MicSettings {
     pub equaliser_mini: EqualiserMini,
     pub noise_gate: NoiseGate,
     pub compressor: Compressor,
+    #[serde(default)]
+    pub level: MicLevel,
+    #[serde(default)]
+    pub calibration: Option<MicCalibration>,
+
+    #[serde(default)]
+    pub deesser: u8,
+    #[serde(default)]
+    pub bleep_volume: i8,
+    #[serde(default)]
+    pub editor_modes: MicEditorModes,
+}
+
+/// Which of the official app's simple or advanced views were last used for each part of the
+/// mic settings, stored in the mic profile so editors can open on the same view.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct MicEditorModes {
+    pub eq_advanced: bool,
+    pub eq_fine_tune: bool,
+    pub gate_advanced: bool,
+    pub compressor_advanced: bool,
+}
+
+/// The microphone input level, in dBFS. `peak` and `clipping` are held for a short time
+/// after they occur so they can be displayed by clients polling the status. Subscribers get it
+/// from MicLevel events instead, it's left out of the status patches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MicLevel {
+    pub level: f32,
+    pub peak: f32,
+    pub clipping: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MicCalibration {
+    pub mic_type: MicrophoneType,
+    pub state: MicCalibrationState,
+    pub progress: u8,
+    pub peak: f32,
+    pub recommended_gain: Option<u16>,
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum MicCalibrationState {
+    Running,
+    Complete,
+    Failed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +407,10 @@ pub struct NoiseGate {
     pub release: GateTimes,
     pub enabled: bool,
     pub attenuation: u8,
+
+    // The simple mode's single percentage, the settings above are worked out from it
+    #[serde(default)]
+    pub amount: u8,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -122,6 +428,14 @@ pub struct Lighting {
     pub buttons: HashMap<ButtonColourTargets, ButtonLighting>,
 }
 
+/// Whether a button's light is currently lit, regardless of the colours it's configured to use.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ButtonLightState {
+    Off,
+    On,
+    Flashing,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ButtonLighting {
     pub off_style: ButtonColourOffStyle,
@@ -140,17 +454,498 @@ pub struct TwoColours {
     pub colour_two: String,
 }
 
+/// Hue is in degrees (0 - 360), saturation and value are between 0 and 1.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct HsvColour {
+    pub hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Paths {
     pub profile_directory: PathBuf,
     pub mic_profile_directory: PathBuf,
     pub samples_directory: PathBuf,
+    #[serde(default)]
+    pub theme_directory: PathBuf,
+    #[serde(default)]
+    pub script_directory: PathBuf,
+
+    // Served by the HTTP server under /custom/, alongside the built in UI
+    #[serde(default)]
+    pub custom_ui_directory: Option<PathBuf>,
+}
+
+/// The result of DaemonRequest::GetDaemonStatus, describing the daemon rather than the mixers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonHealth {
+    pub version: String,
+    pub uptime_secs: u64,
+    pub devices: Vec<DeviceHealth>,
+    pub http: HttpServerStatus,
+    pub settings_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceHealth {
+    pub serial: String,
+    pub device_type: DeviceType,
+
+    // USB errors since the daemon started, and the most recent one
+    pub usb_errors: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpServerStatus {
+    pub enabled: bool,
+
+    // The first of the addresses, kept for older clients
+    pub address: Option<String>,
+
+    #[serde(default)]
+    pub addresses: Vec<String>,
+}
+
+// Who sent a request to the daemon, as far as it can tell
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientIdentity {
+    // Set by the client with SetClientName
+    pub name: Option<String>,
+
+    // The process and user of a client on the IPC socket
+    pub pid: Option<i32>,
+    pub uid: Option<u32>,
+
+    // The address of a client using the HTTP server
+    pub address: Option<String>,
+}
+
+/// What a tray icon or launcher needs to know about a device, without the whole status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSummary {
+    pub serial: String,
+    pub device_type: DeviceType,
+    pub profile_name: String,
+    pub mic_profile_name: String,
+    pub mic_muted: bool,
+    pub stream_panic: bool,
+    pub locked: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationKind {
+    DeviceConnected,
+    DeviceDisconnected,
+    ProfileLoaded,
+    MicProfileLoaded,
+    MicMuted,
+    MicUnmuted,
+    UpdateAvailable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+
+    // The device it's about, None for the daemon itself
+    pub serial: Option<String>,
+
+    // A description to show the user, such as 'Loaded profile Stream'
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    // Milliseconds since the Unix epoch
+    pub time: u64,
+    pub client: ClientIdentity,
+    pub serial: String,
+    pub command: GoXLRCommand,
+
+    // Why the command failed, if it did
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsInfo {
+    pub path: PathBuf,
+    pub version: u32,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Files {
     pub profiles: Vec<String>,
     pub mic_profiles: Vec<String>,
+    #[serde(default)]
+    pub themes: Vec<String>,
+
+    // Profiles built into the daemon, available even if they're not in the profile directory
+    #[serde(default)]
+    pub embedded_profiles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplerStatus {
+    pub active_bank: SampleBank,
+    pub banks: HashMap<SampleBank, HashMap<SampleButtons, Vec<SampleTrack>>>,
+
+    // The button colours of banks which have their own
+    #[serde(default)]
+    pub bank_colours: HashMap<SampleBank, TwoColours>,
+
+    // The samples playing right now, by the button which started them
+    #[serde(default)]
+    pub playing: HashMap<SampleButtons, SamplePlayback>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplePlayback {
+    pub file: String,
+    pub elapsed_ms: u64,
+
+    // Only known for WAV files, a looping sample's elapsed time goes back to 0 each time round
+    pub duration_ms: Option<u64>,
+    pub looping: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleTrack {
+    pub file: String,
+
+    // Where playback starts and stops, as a percentage of the way through the file
+    pub start_pct: u8,
+    pub end_pct: u8,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProfileType {
+    Profile,
+    MicProfile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileBackup {
+    // The backup's file name, used to restore it
+    pub id: String,
+
+    // Milliseconds since the Unix epoch
+    pub created: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleFile {
+    // Relative to the samples directory, with folders separated by '/'
+    pub path: String,
+    pub size: u64,
+    pub format: String,
+
+    // These are only known for formats the daemon can read the header of
+    pub channels: Option<u16>,
+    pub sample_rate: Option<u32>,
+    pub duration_ms: Option<u64>,
+}
+
+// Narrows down the sample list, for libraries too large to send in one go
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SampleQuery {
+    // Only samples whose path contains this, ignoring case
+    pub filter: Option<String>,
+    pub extension: Option<String>,
+
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SamplePage {
+    // How many samples matched the query, including those outside the page
+    pub total: usize,
+    pub samples: Vec<SampleFile>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ObsStatus {
+    pub connected: bool,
+
+    // The password is never included in the status
+    pub settings: ObsSettings,
+}
+
+/**
+ Configuration for the connection to obs-websocket (version 5). Channels muted on the GoXLR mute
+ the matching OBS input (and are brought in line when OBS connects), and switching to a scene in
+ OBS can load a GoXLR profile, a lighting theme, or both.
+
+ When setting these, a password of None keeps the existing password and an empty one removes it.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObsSettings {
+    pub enabled: bool,
+    pub address: String,
+    pub password: Option<String>,
+    pub channel_inputs: HashMap<ChannelName, String>,
+    pub scene_profiles: HashMap<String, String>,
+
+    // Scene names to lighting themes, applied after any profile for the scene
+    pub scene_themes: HashMap<String, String>,
+}
+
+impl Default for ObsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "ws://localhost:4455".to_string(),
+            password: None,
+            channel_inputs: HashMap::new(),
+            scene_profiles: HashMap::new(),
+            scene_themes: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptStatus {
+    pub name: String,
+    pub enabled: bool,
+
+    // Why the script failed to load, or the last error from one of its event handlers
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateStatus {
+    pub settings: UpdateSettings,
+    pub current_version: String,
+
+    // These are only set once a check has succeeded
+    pub latest_version: Option<String>,
+    pub release_url: Option<String>,
+    pub update_available: bool,
+
+    // Where the new version's package was saved, if it has been downloaded
+    pub downloaded_package: Option<PathBuf>,
+
+    // Seconds since the Unix epoch
+    pub last_checked: Option<u64>,
+
+    // Why the last check failed, such as curl not being installed
+    pub error: Option<String>,
+}
+
+/**
+ Checking for new releases of the utility is off unless enabled. When a download directory is set,
+ the package for a new release is saved there, but never installed.
+*/
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub enabled: bool,
+    pub download_directory: Option<PathBuf>,
+}
+
+/**
+ The daemon's settings for a single device, by serial. Devices which haven't been seen before
+ start with a copy of the default device settings.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    // The profiles loaded when the device is connected
+    pub profile: String,
+    pub mic_profile: String,
+
+    pub bleep_volume: i8,
+
+    // How long a button must be held, in milliseconds, before it counts as a hold
+    pub hold_time: u16,
+
+    // Where to find samples for this device, instead of the daemon's samples directory
+    pub samples_directory: Option<PathBuf>,
+
+    // A sound played through the sampler while the bleep button is held, in place of the
+    // hardware bleep. None uses the hardware bleep.
+    pub bleep_sound: Option<PathBuf>,
+
+    // The outputs which hear the bleep, the others hear nothing from the mic while it's held
+    pub bleep_outputs: EnumSet<OutputDevice>,
+
+    // How long the hardware bleep takes to rise to its volume, in milliseconds, 0 starts it at
+    // full volume. The bleep stops as soon as the button is released either way.
+    pub bleep_attack: u16,
+
+    // How long a custom bleep sound takes to fade out once the button is released, in
+    // milliseconds, 0 stops it straight away. The device ends its own bleep on release, so the
+    // hardware bleep can't be faded out.
+    pub bleep_release: u16,
+
+    // Switching to a microphone type with phantom power has to be confirmed
+    pub phantom_power_interlock: bool,
+
+    // How long the mic channel is muted for while phantom power turns on or off, 0 to not mute
+    pub phantom_power_mute: u16,
+
+    // What happens to samples which are still playing when the sample bank is changed
+    pub sample_bank_switch: SampleBankSwitch,
+
+    // The maximum volume a channel can be set to, regardless of fader or profile
+    pub volume_limits: HashMap<ChannelName, u8>,
+
+    // Groups of channels whose volumes move together
+    pub volume_links: Vec<Vec<ChannelName>>,
+
+    // Display names for channels and outputs, which the client also accepts in their place
+    pub channel_names: HashMap<ChannelName, String>,
+    pub output_names: HashMap<OutputDevice, String>,
+
+    // Colours (RRGGBB) given to the fader, scribble and mute button of whichever fader each
+    // channel is on, in place of the fader's own
+    pub channel_colours: HashMap<ChannelName, String>,
+
+    // Buttons claimed by other tools, the daemon only sends input events when these are pressed
+    pub passthrough_buttons: HashSet<ButtonColourTargets>,
+
+    // Buttons which act as a different button, keyed by the button pressed. Everything the
+    // daemon does with a button, including its light, follows its function
+    pub button_remap: HashMap<ButtonColourTargets, ButtonColourTargets>,
+
+    // How far the gender, reverb and echo encoders move for each click, defaulting to 1
+    pub encoder_steps: HashMap<EncoderName, u8>,
+
+    // Larger steps for the gender, reverb and echo encoders while they're turned quickly
+    pub encoder_acceleration: HashMap<EncoderName, EncoderAcceleration>,
+
+    // Ignores the encoder steps and acceleration, so every click moves the encoder by one
+    pub encoder_fine_mode: bool,
+
+    // Whether the active profile and mic profile are saved automatically when they change
+    pub autosave: Autosave,
+
+    // Holding this button switches back to the previously loaded profile
+    pub quick_switch_button: Option<ButtonColourTargets>,
+
+    // Holding this button toggles the stream panic mute
+    pub stream_panic_button: Option<ButtonColourTargets>,
+
+    // Channels for faders A to D, the faders can be moved between these pages
+    pub fader_pages: Vec<[ChannelName; 4]>,
+
+    // Holding this button moves the faders to the next page
+    pub fader_page_button: Option<ButtonColourTargets>,
+
+    // How each fader's mute button behaves when held, faders not listed mute to all
+    pub fader_hold: HashMap<FaderName, FaderHold>,
+
+    // Saved routing, which can be applied without changing profile
+    pub routing_presets: HashMap<String, RoutingPreset>,
+
+    // Holding one of these buttons applies the routing preset with that name
+    pub routing_preset_buttons: HashMap<ButtonColourTargets, String>,
+}
+
+// The outputs each input is routed to, indexed the same way as MixerStatus::router
+pub type RoutingPreset = [EnumSet<OutputDevice>; InputDevice::COUNT];
+
+impl Default for DeviceSettings {
+    fn default() -> Self {
+        Self {
+            profile: "DEFAULT".to_string(),
+            mic_profile: "DEFAULT".to_string(),
+            bleep_volume: -20,
+            hold_time: 500,
+            samples_directory: None,
+            bleep_sound: None,
+            bleep_outputs: EnumSet::all(),
+            bleep_attack: 0,
+            bleep_release: 0,
+            phantom_power_interlock: false,
+            phantom_power_mute: 0,
+            sample_bank_switch: SampleBankSwitch::Continue,
+            volume_limits: HashMap::new(),
+            volume_links: Vec::new(),
+            channel_names: HashMap::new(),
+            output_names: HashMap::new(),
+            channel_colours: HashMap::new(),
+            passthrough_buttons: HashSet::new(),
+            button_remap: HashMap::new(),
+            encoder_steps: HashMap::new(),
+            encoder_acceleration: HashMap::new(),
+            encoder_fine_mode: false,
+            autosave: Autosave::Off,
+            quick_switch_button: None,
+            stream_panic_button: None,
+            fader_pages: Vec::new(),
+            fader_page_button: None,
+            fader_hold: HashMap::new(),
+            routing_presets: HashMap::new(),
+            routing_preset_buttons: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncoderAcceleration {
+    // Clicks within this many milliseconds of the previous one are accelerated
+    pub window: u16,
+
+    // What the encoder's step is multiplied by for those clicks
+    pub multiplier: u8,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FaderHold {
+    // How long the mute button must be held, in milliseconds, instead of the device's hold time
+    pub hold_time: Option<u16>,
+    pub action: FaderHoldAction,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FaderHoldAction {
+    // Mutes the channel with this function, rather than the one set on the button
+    Mute(MuteFunction),
+
+    // Holding is ignored, the button acts on release however long it was held
+    Disabled,
+
+    // Solos the channel on the fader, or stops soloing it
+    Solo,
+}
+
+impl Default for FaderHoldAction {
+    fn default() -> Self {
+        FaderHoldAction::Mute(MuteFunction::All)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SampleBankSwitch {
+    // They carry on playing
+    #[default]
+    Continue,
+
+    // They're stopped straight away
+    Stop,
+
+    // They fade out over this many milliseconds, then stop
+    FadeOut(u16),
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Autosave {
+    #[default]
+    Off,
+
+    // Saved a few seconds after anything changes
+    OnChange,
+
+    // Saved every this many minutes, if anything has changed
+    Every(u16),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -166,9 +961,12 @@ pub struct UsbProductInformation {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum DeviceType {
-    Unknown,
     Full,
     Mini,
+
+    // Devices a client doesn't know about will be treated as Unknown.
+    #[serde(other)]
+    Unknown,
 }
 
 impl Default for DeviceType {
@@ -0,0 +1,170 @@
+//! Errors returned by the daemon. Clients can match on these to handle a failure, or to show
+//! their own (possibly translated) message, the Display implementation gives an English one.
+
+use goxlr_types::{ChannelName, ColourError, InputDevice, OutputDevice};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DaemonError {
+    // Serial of the device which isn't connected
+    DeviceNotFound(String),
+
+    // A value was outside of the range the setting accepts, inclusive of min and max
+    InvalidValueRange {
+        setting: String,
+        min: f64,
+        max: f64,
+    },
+
+    ProfileNotFound(String),
+    MicProfileNotFound(String),
+
+    // A profile couldn't be read, the location is included when the problem is inside the XML
+    InvalidProfile {
+        name: String,
+        element: Option<String>,
+        attribute: Option<String>,
+        line: Option<u64>,
+        column: Option<u64>,
+        message: String,
+    },
+
+    // The request isn't possible on this device, or in its current state
+    Unsupported(String),
+
+    // The request was rejected, the reason has whatever values are needed to describe it
+    InvalidRequest(InvalidRequest),
+
+    // Communication with the device failed
+    UsbFailure(String),
+
+    // The command at this index of a batch failed, commands after it were not run
+    BatchFailed {
+        index: usize,
+        error: Box<DaemonError>,
+    },
+
+    Other(String),
+}
+
+impl fmt::Display for DaemonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DaemonError::DeviceNotFound(serial) => write!(f, "Device {} is not connected", serial),
+            DaemonError::InvalidValueRange { setting, min, max } => {
+                write!(f, "{} must be between {} and {}", setting, min, max)
+            }
+            DaemonError::ProfileNotFound(name) => write!(f, "Profile {} does not exist", name),
+            DaemonError::MicProfileNotFound(name) => {
+                write!(f, "Mic profile {} does not exist", name)
+            }
+            DaemonError::InvalidProfile {
+                name,
+                element,
+                attribute,
+                line,
+                column,
+                message,
+            } => {
+                write!(f, "Profile {} is invalid", name)?;
+                if let Some(element) = element {
+                    write!(f, " in <{}>", element)?;
+                }
+                if let Some(attribute) = attribute {
+                    write!(f, " attribute {}", attribute)?;
+                }
+                if let (Some(line), Some(column)) = (line, column) {
+                    write!(f, " at line {}, column {}", line, column)?;
+                }
+                write!(f, ": {}", message)
+            }
+            DaemonError::Unsupported(message) => write!(f, "Not supported: {}", message),
+            DaemonError::InvalidRequest(reason) => write!(f, "{}", reason),
+            DaemonError::UsbFailure(message) => write!(f, "USB error: {}", message),
+            DaemonError::BatchFailed { index, error } => {
+                write!(f, "Command {} of the batch failed: {}", index + 1, error)
+            }
+            DaemonError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DaemonError {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InvalidRequest {
+    // The colour as given, and why it couldn't be parsed
+    InvalidColour(String, ColourError),
+    InvalidRoute {
+        input: InputDevice,
+        output: OutputDevice,
+    },
+    VolumeAboveZeroDb,
+    TooFewChannelsToLink,
+    ChannelNotOnFader(ChannelName),
+    FaderPageNotFound(usize),
+    FaderPageRepeatsChannel(ChannelName),
+    NoFaderPages,
+    RoutingPresetNameEmpty,
+    DisplayNameInUse(String),
+    RoutingPresetNotFound(String),
+    LightingThemeNotFound(String),
+    SampleStartsAfterEnd,
+    NoSampleAtPosition,
+    SameSampleBanks,
+    MicCalibrationRunning,
+    CannotSolo(ChannelName),
+    PhantomPowerNotConfirmed,
+}
+
+impl fmt::Display for InvalidRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // The error includes the colour
+            InvalidRequest::InvalidColour(_, error) => write!(f, "Invalid colour: {}", error),
+            InvalidRequest::InvalidRoute { input, output } => {
+                write!(f, "{} can't be routed to {}", input, output)
+            }
+            InvalidRequest::VolumeAboveZeroDb => write!(f, "Volume should be at or below 0dB"),
+            InvalidRequest::TooFewChannelsToLink => {
+                write!(f, "At least two channels are needed to link")
+            }
+            InvalidRequest::ChannelNotOnFader(channel) => {
+                write!(f, "Channel {} is not assigned to a fader", channel)
+            }
+            InvalidRequest::FaderPageNotFound(page) => {
+                write!(f, "Fader page {} does not exist", page)
+            }
+            InvalidRequest::FaderPageRepeatsChannel(channel) => {
+                write!(f, "Channel {} is on more than one fader of a page", channel)
+            }
+            InvalidRequest::NoFaderPages => write!(f, "There are no fader pages"),
+            InvalidRequest::RoutingPresetNameEmpty => write!(f, "Routing presets need a name"),
+            InvalidRequest::DisplayNameInUse(name) => {
+                write!(f, "The name {} is already in use", name)
+            }
+            InvalidRequest::RoutingPresetNotFound(name) => {
+                write!(f, "Routing preset {} does not exist", name)
+            }
+            InvalidRequest::LightingThemeNotFound(name) => {
+                write!(f, "Lighting theme {} does not exist", name)
+            }
+            InvalidRequest::SampleStartsAfterEnd => {
+                write!(f, "The sample must start before it ends")
+            }
+            InvalidRequest::NoSampleAtPosition => write!(f, "No sample assigned at that position"),
+            InvalidRequest::SameSampleBanks => write!(f, "Two different sample banks are needed"),
+            InvalidRequest::MicCalibrationRunning => {
+                write!(f, "Microphone calibration is already running")
+            }
+            InvalidRequest::CannotSolo(channel) => {
+                write!(f, "Channel {} isn't an input, so can't be soloed", channel)
+            }
+            InvalidRequest::PhantomPowerNotConfirmed => write!(
+                f,
+                "This turns on phantom power, which has to be confirmed by setting the microphone type"
+            ),
+        }
+    }
+}
@@ -1,49 +1,320 @@
+use enumset::EnumSet;
 use futures::{SinkExt, StreamExt, TryStreamExt};
+use json_patch::Patch;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use strum::EnumCount;
 
 pub mod client;
 mod device;
+mod error;
 mod socket;
+mod volume;
 
 pub use device::*;
+pub use error::*;
 use goxlr_types::{
     ButtonColourGroups, ButtonColourOffStyle, ButtonColourTargets, ChannelName,
-    CompressorAttackTime, CompressorRatio, CompressorReleaseTime, EqFrequencies, FaderDisplayStyle,
-    FaderName, GateTimes, InputDevice, MicrophoneType, MiniEqFrequencies, MuteFunction,
-    OutputDevice,
+    CompressorAttackTime, CompressorRatio, CompressorReleaseTime, CoughButtonMode, EncoderName,
+    EqFrequencies, EqPreset, FaderDisplayStyle, FaderName, GateTimes, InputDevice, MicrophoneType,
+    MiniEqFrequencies, MuteFunction, OutputDevice, ProfileSection, SampleBank, SampleButtons,
 };
 pub use socket::*;
+pub use volume::*;
+
+/**
+ The version of the IPC protocol, this should be incremented whenever a change is made which an
+ older client or daemon wouldn't understand. New fields on structs should be marked with
+ #[serde(default)] so that older daemons sending the status can still be parsed.
+
+ Two versions weren't incremented when they should have been, so a peer on either may have any
+ or none of their changes:
+ - Version 1 covers every build from when versioning was added up to the typed errors of version
+   2, over which the protocol gained bulk routing, sub mix volumes, percent and dB volumes, volume
+   limits, linked channels, the OBS status, button states, profile sections, lighting themes, HSV
+   colours, fader display modes, diagnostics, per-device settings and the daemon status.
+ - Version 4 also covers the gate and compressor amounts and the EQ presets, which were only
+   given versions of their own (54 and 55) later on.
+*/
+pub const PROTOCOL_VERSION: u32 = 55;
+
+// The largest sample file which can be added to the samples directory, in bytes
+pub const MAX_SAMPLE_SIZE: usize = 16 * 1024 * 1024;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DaemonRequest {
     Ping,
     GetStatus,
+    GetProtocolVersion,
+
+    // Receive a full status, followed by a StatusPatch every time the status changes, and an
+    // Event when something happens which isn't part of the status. Sending this again on a
+    // subscribed connection will resend the full status.
+    Subscribe,
+
     Command(String, GoXLRCommand),
+
+    // Checks whether a command would be accepted by the device, without running it
+    Validate(String, GoXLRCommand),
+
+    // Serial, commands to run in order stopping at the first failure, and whether a failure
+    // should restore the profile and mic profile from before the batch
+    CommandBatch(String, Vec<GoXLRCommand>, bool),
+
+    // Serial, Microphone Type, Apply the recommended gain when complete
+    StartMicCalibration(String, MicrophoneType, bool),
+
+    SetObsSettings(ObsSettings),
+    SetUpdateSettings(UpdateSettings),
+
+    // A directory of files for the HTTP server to serve under /custom/, or None to stop
+    SetCustomUiDirectory(Option<PathBuf>),
+
+    // Serial, exercises the device and returns a DiagnosticReport
+    RunDiagnostics(String),
+
+    // Serial, reads back what it can from the device and compares it with the daemon's state
+    VerifyState(String),
+
+    // Serial, returns the state behind the mute buttons as a MuteStateReport
+    GetMuteState(String),
+
+    // Serial, reads everything the device reports about itself as HardwareInfo
+    GetHardwareInfo(String),
+
+    // Serial, command id and body, sends the command to the device as it is and returns the raw
+    // response. Only allowed when allow_raw_usb_commands is set in the daemon's settings.
+    SendRawCommand(String, u32, Vec<u8>),
+
+    // Where the daemon's settings are stored, and which version of the format they use
+    GetSettingsInfo,
+
+    // Settings are written to disk shortly after they change, this answers once they have been
+    FlushSettings,
+
+    // The daemon's version, uptime, devices and error counts, for troubleshooting
+    GetDaemonStatus,
+
+    // Serial, the daemon's settings for a device
+    GetDeviceSettings(String),
+    SetDeviceSettings(String, DeviceSettings),
+
+    // Serial, the device's current mic profile as JSON
+    ExportMicProfile(String),
+
+    // The settings given to devices the daemon hasn't seen before
+    GetDefaultDeviceSettings,
+    SetDefaultDeviceSettings(DeviceSettings),
+
+    // Sample files in the samples directory, and any folders inside it
+    GetSamples,
+
+    // The samples matching the query, returned as a SamplePage
+    FindSamples(SampleQuery),
+
+    // Path relative to the samples directory and the file's contents, folders are created as
+    // needed. Existing files are not overwritten.
+    AddSample(String, Vec<u8>),
+
+    // From and to, relative to the samples directory. Folders can be moved as well as files.
+    MoveSample(String, String),
+
+    // Deletes a sample, or an empty folder
+    DeleteSample(String),
+
+    // Path relative to the samples directory and the number of points, returns the peak level
+    // (0 to 1) of each equally sized section of the sample
+    GetSampleWaveform(String, u16),
+
+    // The kind of profile and its name, returns the backups kept of the profile, newest first
+    GetProfileBackups(ProfileType, String),
+
+    // The kind of profile, its name and the id of the backup. The profile's current file is
+    // backed up before it is replaced.
+    RestoreProfileBackup(ProfileType, String, String),
+
+    // Names this connection (for example 'Stream Deck') in the audit log
+    SetClientName(String),
+
+    // The most recent commands sent to devices and who sent them, oldest first
+    GetAuditLog,
+
+    // The script's name (its file name, without .rhai) and whether it should run. Scripts are
+    // listed in the status.
+    SetScriptEnabled(String, bool),
+
+    // Reads the scripts directory again, and restarts every enabled script
+    ReloadScripts,
+
+    // Where the web UI can be opened, an error if the HTTP server is disabled
+    GetUiUrl,
+
+    // Stops the daemon and starts it again with the same arguments, once this has been answered
+    RestartDaemon,
+
+    // A DeviceSummary for each device, smaller than the full status
+    GetDeviceSummary,
+
+    // Receive a Notification whenever something happens which a user might want to be told
+    // about, until the connection is closed
+    SubscribeNotifications,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum DaemonResponse {
     Ok,
-    Error(String),
+    Error(DaemonError),
     Status(DaemonStatus),
+    StatusPatch(StatusPatch),
+    ProtocolVersion(u32),
+    Diagnostics(DiagnosticReport),
+    StateVerification(StateVerification),
+    MuteState(MuteStateReport),
+    HardwareInfo(HardwareInfo),
+    RawResponse(Vec<u8>),
+    SettingsInfo(SettingsInfo),
+    DaemonHealth(DaemonHealth),
+    DeviceSettings(DeviceSettings),
+    MicProfileJson(String),
+    Samples(Vec<SampleFile>),
+    SamplePage(SamplePage),
+    SampleWaveform(Vec<f32>),
+    ProfileBackups(Vec<ProfileBackup>),
+    AuditLog(Vec<AuditEntry>),
+    UiUrl(String),
+    DeviceSummary(Vec<DeviceSummary>),
+    Event(DaemonEvent),
+    Notification(Notification),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DaemonEvent {
+    // A recording made with a sampler button has been assigned to it. The file is relative to
+    // the 'Recorded' folder in the samples directory.
+    SampleRecorded {
+        serial: String,
+        bank: SampleBank,
+        button: SampleButtons,
+        file: String,
+    },
+
+    // Something was physically done on the device, whether or not the daemon does anything with
+    // it. The time is in milliseconds since the Unix epoch.
+    Input {
+        serial: String,
+        time: u64,
+        input: InputEvent,
+    },
+
+    // The computer woke from sleep, and the device's settings have been sent to it again.
+    Resumed {
+        serial: String,
+    },
+
+    // Phantom power was turned on or off by changing the microphone type, a warning for
+    // anything plugged into the XLR input which could be harmed by it.
+    PhantomPower {
+        serial: String,
+        enabled: bool,
+    },
+
+    // The mic level changes on nearly every poll, so rather than being in the status patches it's
+    // sent this way, at most ten times a second and only when it's changed.
+    MicLevel {
+        serial: String,
+        level: MicLevel,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputEvent {
+    ButtonDown(ButtonColourTargets),
+    ButtonUp(ButtonColourTargets),
+
+    // The button has been held down for longer than the device's hold time.
+    ButtonHeld(ButtonColourTargets),
+
+    // The raw position of the fader (0-255), before any volume limit is applied.
+    FaderMoved(FaderName, u8),
+    EncoderTurned(EncoderName, i8),
+}
+
+/**
+ A JSON Patch (RFC 6902) to apply to the previously received status. Sequence numbers increase by
+ one for each patch, if one is missed the client should Subscribe again to get a full status.
+ The first patch after subscribing replaces the entire status.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusPatch {
+    pub sequence: u64,
+    pub patch: Patch,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GoXLRCommand {
     SetFader(FaderName, ChannelName),
     SetFaderMuteFunction(FaderName, MuteFunction),
+    SetFaderMuted(FaderName, bool),
+    SetChannelMuted(ChannelName, bool),
+
+    // Sets of channels for faders A to D, which can be switched between to control more channels
+    SetFaderPages(Vec<[ChannelName; 4]>),
+    SetFaderPage(usize),
+    NextFaderPage(),
+
+    // While locked, the device's buttons, faders and encoders are ignored (but still reported)
+    SetLocked(bool),
 
     SetVolume(ChannelName, u8),
-    SetMicrophoneType(MicrophoneType),
+    SetVolumePercent(ChannelName, u8),
+    SetVolumeDb(ChannelName, f32),
+
+    // Caps the volume of a channel, None removes the cap
+    SetVolumeLimit(ChannelName, Option<u8>),
+
+    // Linked channels follow each other's volume changes proportionally
+    LinkChannels(Vec<ChannelName>),
+    UnlinkChannel(ChannelName),
+
+    // Names shown in place of the channel or output's own, None goes back to the built in name
+    SetChannelDisplayName(ChannelName, Option<String>),
+    SetOutputDisplayName(OutputDevice, Option<String>),
+
+    SetSubMixVolume(ChannelName, u8),
+    SetSubMixLinked(ChannelName, bool),
+    // The bool confirms turning on phantom power, which is needed with the phantom power
+    // interlock on. SetMicrophoneGain can't turn phantom power on while the interlock is on.
+    SetMicrophoneType(MicrophoneType, bool),
     SetMicrophoneGain(MicrophoneType, u16),
     SetRouter(InputDevice, OutputDevice, bool),
 
+    // Bulk routing changes, indexed the same way as MixerStatus::router_table
+    SetRouterRow(InputDevice, [bool; OutputDevice::COUNT]),
+    SetRouterColumn(OutputDevice, [bool; InputDevice::COUNT]),
+    ResetRouterToProfile(),
+
+    // Named copies of the routing, kept in the device settings rather than the profile
+    SaveRoutingPreset(String),
+    ApplyRoutingPreset(String),
+    DeleteRoutingPreset(String),
+
     // Cough Button
     SetCoughMuteFunction(MuteFunction),
     SetCoughIsHold(bool),
+    SetCoughMode(CoughButtonMode),
+    SetCoughMuted(bool),
+
+    // Cuts every channel from the Broadcast Mix until it's turned off again
+    SetStreamPanic(bool),
+
+    // Keeps every other channel out of the headphones while any channel is soloed, without
+    // changing the routing in the profile. Only input channels can be soloed.
+    SetSolo(ChannelName, bool),
 
     // Bleep Button
     SetSwearButtonVolume(i8),
+    SetSwearButtonOutputs(EnumSet<OutputDevice>),
 
     // EQ Settings
     SetEqMiniGain(MiniEqFrequencies, i8),
@@ -51,6 +322,10 @@ pub enum GoXLRCommand {
     SetEqGain(EqFrequencies, i8),
     SetEqFreq(EqFrequencies, f32),
 
+    // Sets both the full and mini EQ, Reset also returns the frequencies to their defaults
+    SetEqPreset(EqPreset),
+    ResetEqualiser(),
+
     // Gate Settings
     SetGateThreshold(i8),
     SetGateAttenuation(u8),
@@ -58,6 +333,10 @@ pub enum GoXLRCommand {
     SetGateRelease(GateTimes),
     SetGateActive(bool),
 
+    // The official app's simple mode, a percentage which sets the threshold, attenuation,
+    // attack and release together
+    SetGateAmount(u8),
+
     // Compressor..
     SetCompressorThreshold(i8),
     SetCompressorRatio(CompressorRatio),
@@ -65,23 +344,80 @@ pub enum GoXLRCommand {
     SetCompressorReleaseTime(CompressorReleaseTime),
     SetCompressorMakeupGain(u8),
 
+    // Simple mode, a percentage which sets all of the above
+    SetCompressorAmount(u8),
+
+    // The de-esser's amount, as a percentage
+    SetDeEsser(u8),
+
     // Colour Related Settings..
     SetFaderDisplayStyle(FaderName, FaderDisplayStyle),
+
+    // The same as SetFaderDisplayStyle, but with the gradient and meter set individually. The
+    // gradient always runs between the fader's two colours, swap them to reverse it.
+    SetFaderDisplayMode(FaderName, bool, bool),
     SetFaderColours(FaderName, String, String),
     SetAllFaderColours(String, String),
     SetAllFaderDisplayStyle(FaderDisplayStyle),
 
+    // A colour (RRGGBB) which follows the channel to whichever fader it's on, colouring the
+    // fader, scribble and mute button. None leaves the fader's own colours alone.
+    SetChannelColour(ChannelName, Option<String>),
+
     SetButtonColours(ButtonColourTargets, String, Option<String>),
     SetButtonOffStyle(ButtonColourTargets, ButtonColourOffStyle),
     SetButtonGroupColours(ButtonColourGroups, String, Option<String>),
     SetButtonGroupOffStyle(ButtonColourGroups, ButtonColourOffStyle),
 
+    // Colours by HSV, and adjustments relative to the current colours (deltas are percentages)..
+    SetFaderColoursHsv(FaderName, HsvColour, HsvColour),
+    SetButtonColoursHsv(ButtonColourTargets, HsvColour, Option<HsvColour>),
+    AdjustButtonBrightness(ButtonColourTargets, i8),
+    AdjustAllBrightness(i8),
+    ShiftHue(ButtonColourGroups, f32),
+
+    // Lighting Themes..
+    ApplyLightingTheme(Lighting),
+    LoadLightingTheme(String),
+    SaveLightingTheme(String),
+
+    // Sampler..
+    // Bank, Button, Track, and the start and end positions as a percentage of the sample
+    SetSampleTrim(SampleBank, SampleButtons, usize, u8, u8),
+    ClearSampleBank(SampleBank),
+
+    // Colours used by the sampler buttons while the bank is selected, a contrasting second
+    // colour is picked if it's not given. Clearing them goes back to the buttons' own colours.
+    SetSampleBankColours(SampleBank, String, Option<String>),
+    ClearSampleBankColours(SampleBank),
+
+    // Replaces the second bank with a copy of the first
+    CopySampleBank(SampleBank, SampleBank),
+    SwapSampleBanks(SampleBank, SampleBank),
+
+    // What happens to samples still playing when the bank changes, kept in the device settings
+    SetSampleBankSwitch(SampleBankSwitch),
+
     // Profile Handling..
     LoadProfile(String),
+
+    // Loads one of the profiles built into the daemon, even if one with the same name is saved
+    LoadEmbeddedProfile(String),
+    ApplyProfileSections(String, Vec<ProfileSection>),
     SaveProfile(),
     SaveProfileAs(String),
 
+    // Swaps to the previously loaded profile, as it was left, keeping the current volumes
+    QuickSwitchProfile(),
+
+    // Sends the daemon's entire state to the device again, for when it's been changed elsewhere
+    SyncToHardware(),
+
     LoadMicProfile(String),
+
+    // Replaces the current mic profile with one exported as JSON, keeping the profile's name. It
+    // isn't saved until SaveMicProfile, or an autosave.
+    ImportMicProfile(String),
     SaveMicProfile(),
     SaveMicProfileAs(String),
 }
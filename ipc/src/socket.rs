@@ -1,25 +1,21 @@
 use crate::{SinkExt, StreamExt, TryStreamExt};
+use bytes::{BufMut, Bytes, BytesMut};
 use serde::{Deserialize, Serialize};
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf, SocketAddr};
 use tokio::net::UnixStream;
 use tokio_serde::formats::SymmetricalJson;
 use tokio_serde::SymmetricallyFramed;
-use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite, LengthDelimitedCodec};
+
+// Samples are sent as a JSON array of bytes, which takes up to 4 characters per byte.
+const MAX_FRAME_LENGTH: usize = crate::MAX_SAMPLE_SIZE * 5;
 
 #[derive(Debug)]
-pub struct Socket<In, Out> {
+pub struct Socket<In, Out, Codec = LengthDelimitedCodec> {
     address: SocketAddr,
-    reader: SymmetricallyFramed<
-        FramedRead<OwnedReadHalf, LengthDelimitedCodec>,
-        In,
-        SymmetricalJson<In>,
-    >,
-    writer: SymmetricallyFramed<
-        FramedWrite<OwnedWriteHalf, LengthDelimitedCodec>,
-        Out,
-        SymmetricalJson<Out>,
-    >,
+    reader: SymmetricallyFramed<FramedRead<OwnedReadHalf, Codec>, In, SymmetricalJson<In>>,
+    writer: SymmetricallyFramed<FramedWrite<OwnedWriteHalf, Codec>, Out, SymmetricalJson<Out>>,
 }
 
 impl<In, Out> Socket<In, Out>
@@ -28,15 +24,39 @@ where
     Out: Serialize + Unpin,
 {
     pub fn new(address: SocketAddr, stream: UnixStream) -> Self {
+        Self::with_codec(address, stream, || {
+            LengthDelimitedCodec::builder()
+                .max_frame_length(MAX_FRAME_LENGTH)
+                .new_codec()
+        })
+    }
+}
+
+impl<In, Out> Socket<In, Out, LineDelimitedCodec>
+where
+    for<'a> In: Deserialize<'a> + Unpin,
+    Out: Serialize + Unpin,
+{
+    /// Creates a socket where each message is a single line of JSON, for non-Rust clients.
+    pub fn new_line_delimited(address: SocketAddr, stream: UnixStream) -> Self {
+        Self::with_codec(address, stream, LineDelimitedCodec::default)
+    }
+}
+
+impl<In, Out, Codec> Socket<In, Out, Codec>
+where
+    for<'a> In: Deserialize<'a> + Unpin,
+    Out: Serialize + Unpin,
+    Codec: Decoder<Item = BytesMut, Error = Error> + Encoder<Bytes, Error = Error> + Unpin,
+{
+    fn with_codec(address: SocketAddr, stream: UnixStream, codec: fn() -> Codec) -> Self {
         let (stream_read, stream_write) = stream.into_split();
-        let length_delimited_read = FramedRead::new(stream_read, LengthDelimitedCodec::new());
         let reader = tokio_serde::SymmetricallyFramed::new(
-            length_delimited_read,
+            FramedRead::new(stream_read, codec()),
             SymmetricalJson::default(),
         );
-        let length_delimited_write = FramedWrite::new(stream_write, LengthDelimitedCodec::new());
         let writer = tokio_serde::SymmetricallyFramed::new(
-            length_delimited_write,
+            FramedWrite::new(stream_write, codec()),
             SymmetricalJson::default(),
         );
 
@@ -63,3 +83,182 @@ where
         &self.address
     }
 }
+
+/// Frames messages by newlines, JSON never contains a raw newline so this is always safe.
+#[derive(Debug)]
+pub struct LineDelimitedCodec {
+    next_index: usize,
+    max_length: usize,
+}
+
+impl Default for LineDelimitedCodec {
+    fn default() -> Self {
+        Self {
+            next_index: 0,
+            max_length: MAX_FRAME_LENGTH,
+        }
+    }
+}
+
+impl LineDelimitedCodec {
+    fn check_length(&self, length: usize) -> Result<(), Error> {
+        if length > self.max_length {
+            return Err(Error::new(ErrorKind::InvalidData, "line too long"));
+        }
+        Ok(())
+    }
+}
+
+impl Decoder for LineDelimitedCodec {
+    type Item = BytesMut;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, Error> {
+        loop {
+            // Don't rescan the part of the buffer we've already checked.
+            let newline = src[self.next_index..].iter().position(|b| *b == b'\n');
+            let position = match newline {
+                Some(offset) => self.next_index + offset,
+                None => {
+                    self.check_length(src.len())?;
+                    self.next_index = src.len();
+                    return Ok(None);
+                }
+            };
+            self.next_index = 0;
+            self.check_length(position)?;
+
+            let mut line = src.split_to(position + 1);
+            line.truncate(position);
+            if line.last() == Some(&b'\r') {
+                line.truncate(position - 1);
+            }
+
+            // Skip blank lines, rather than failing to parse them.
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                return Ok(Some(line));
+            }
+        }
+    }
+
+    // A client may close the connection without ending its last message with a newline.
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, Error> {
+        if let Some(line) = self.decode(src)? {
+            return Ok(Some(line));
+        }
+        self.next_index = 0;
+        let mut line = src.split();
+        if line.last() == Some(&b'\r') {
+            line.truncate(line.len() - 1);
+        }
+        match line.iter().all(u8::is_ascii_whitespace) {
+            true => Ok(None),
+            false => Ok(Some(line)),
+        }
+    }
+}
+
+impl Encoder<Bytes> for LineDelimitedCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> Result<(), Error> {
+        dst.reserve(item.len() + 1);
+        dst.put(item);
+        dst.put_u8(b'\n');
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codec(max_length: usize) -> LineDelimitedCodec {
+        LineDelimitedCodec {
+            next_index: 0,
+            max_length,
+        }
+    }
+
+    fn decode_all(codec: &mut LineDelimitedCodec, src: &mut BytesMut) -> Vec<BytesMut> {
+        let mut lines = vec![];
+        while let Some(line) = codec.decode(src).unwrap() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    #[test]
+    fn a_message_split_across_reads() {
+        let mut codec = LineDelimitedCodec::default();
+        let mut src = BytesMut::from(&b"{\"Command\":"[..]);
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+
+        src.extend_from_slice(b"[\"S1\"]}\n");
+        assert_eq!(
+            decode_all(&mut codec, &mut src),
+            vec![&b"{\"Command\":[\"S1\"]}"[..]]
+        );
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn several_messages_in_one_buffer() {
+        let mut codec = LineDelimitedCodec::default();
+        let mut src = BytesMut::from(&b"\"Ping\"\n\n\"GetStatus\"\n\"Subscribe\"\n\"Pi"[..]);
+        let expected = vec![&b"\"Ping\""[..], b"\"GetStatus\"", b"\"Subscribe\""];
+        assert_eq!(decode_all(&mut codec, &mut src), expected);
+        assert_eq!(src, &b"\"Pi"[..]);
+    }
+
+    #[test]
+    fn a_trailing_carriage_return_is_removed() {
+        let mut codec = LineDelimitedCodec::default();
+        let mut src = BytesMut::from(&b"\"Ping\"\r\n\r\n\"GetStatus\"\r\n"[..]);
+        let expected = vec![&b"\"Ping\""[..], b"\"GetStatus\""];
+        assert_eq!(decode_all(&mut codec, &mut src), expected);
+    }
+
+    #[test]
+    fn a_line_over_the_limit_is_rejected() {
+        // Whether or not the end of the line has arrived yet.
+        let mut src = BytesMut::from(&b"0123456789"[..]);
+        let error = codec(8).decode(&mut src).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let mut src = BytesMut::from(&b"0123456789\n"[..]);
+        let error = codec(8).decode(&mut src).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let mut src = BytesMut::from(&b"01234567\n"[..]);
+        assert_eq!(decode_all(&mut codec(8), &mut src), vec![&b"01234567"[..]]);
+    }
+
+    #[test]
+    fn a_partial_line_at_eof_is_still_read() {
+        let mut codec = LineDelimitedCodec::default();
+        let mut src = BytesMut::from(&b"\"Ping\"\n\"GetStatus\"\r"[..]);
+        assert_eq!(
+            codec.decode_eof(&mut src).unwrap().unwrap(),
+            &b"\"Ping\""[..]
+        );
+        assert_eq!(
+            codec.decode_eof(&mut src).unwrap().unwrap(),
+            &b"\"GetStatus\""[..]
+        );
+        assert_eq!(codec.decode_eof(&mut src).unwrap(), None);
+
+        let mut src = BytesMut::from(&b"  \r\n "[..]);
+        assert_eq!(codec.decode_eof(&mut src).unwrap(), None);
+    }
+
+    #[test]
+    fn messages_are_written_as_lines() {
+        let mut codec = LineDelimitedCodec::default();
+        let mut dst = BytesMut::new();
+        codec
+            .encode(Bytes::from_static(b"\"Ok\""), &mut dst)
+            .unwrap();
+        assert_eq!(decode_all(&mut codec, &mut dst), vec![&b"\"Ok\""[..]]);
+    }
+}
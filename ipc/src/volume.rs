@@ -0,0 +1,85 @@
+//! The GoXLR treats a channel volume as a linear gain, where 255 is unity (0dB). These convert
+//! between that raw value and the more familiar percent and dB scales, so that every client
+//! (and the daemon) agree on the mapping.
+
+/// The lowest dB value reported, a raw volume of 0 is silence and is reported as this.
+pub const VOLUME_DB_FLOOR: f32 = -60.0;
+
+pub fn volume_to_db(volume: u8) -> f32 {
+    if volume == 0 {
+        return VOLUME_DB_FLOOR;
+    }
+    (20.0 * (volume as f32 / 255.0).log10()).max(VOLUME_DB_FLOOR)
+}
+
+pub fn db_to_volume(db: f32) -> u8 {
+    if db.is_nan() || db <= VOLUME_DB_FLOOR {
+        return 0;
+    }
+    (255.0 * 10f32.powf(db.min(0.0) / 20.0)).round() as u8
+}
+
+pub fn percent_to_volume(percent: u8) -> u8 {
+    ((255 * percent.min(100) as u16 + 50) / 100) as u8
+}
+
+pub fn volume_to_percent(volume: u8) -> u8 {
+    ((volume as u16 * 100 + 127) / 255) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints() {
+        assert_eq!(volume_to_db(0), VOLUME_DB_FLOOR);
+        assert_eq!(volume_to_db(255), 0.0);
+        assert_eq!(db_to_volume(VOLUME_DB_FLOOR), 0);
+        assert_eq!(db_to_volume(0.0), 255);
+
+        assert_eq!(percent_to_volume(0), 0);
+        assert_eq!(percent_to_volume(100), 255);
+        assert_eq!(volume_to_percent(0), 0);
+        assert_eq!(volume_to_percent(255), 100);
+    }
+
+    #[test]
+    fn out_of_range_input_is_clamped() {
+        assert_eq!(db_to_volume(6.0), 255);
+        assert_eq!(db_to_volume(f32::INFINITY), 255);
+        assert_eq!(db_to_volume(-90.0), 0);
+        assert_eq!(db_to_volume(f32::NEG_INFINITY), 0);
+        assert_eq!(db_to_volume(f32::NAN), 0);
+
+        assert_eq!(percent_to_volume(101), 255);
+        assert_eq!(percent_to_volume(u8::MAX), 255);
+
+        // The quietest volume above silence is still above the floor.
+        assert!(volume_to_db(1) > VOLUME_DB_FLOOR);
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        for volume in 0..=u8::MAX {
+            assert_eq!(
+                db_to_volume(volume_to_db(volume)),
+                volume,
+                "volume {}",
+                volume
+            );
+        }
+        for percent in 0..=100 {
+            let volume = percent_to_volume(percent);
+            assert_eq!(volume_to_percent(volume), percent, "percent {}", percent);
+        }
+    }
+
+    #[test]
+    fn louder_is_always_higher() {
+        for volume in 1..=u8::MAX {
+            assert!(volume_to_db(volume) > volume_to_db(volume - 1));
+            assert!(volume_to_percent(volume) >= volume_to_percent(volume - 1));
+        }
+    }
+}
@@ -12,8 +12,12 @@ pub enum ParseError {
 
     #[error("Expected enum: {0}")]
     ExpectedEnum(#[from] strum::ParseError),
+
+    #[error("Invalid colour: {0}")]
+    InvalidColour(#[from] goxlr_types::ColourError),
 }
 use crate::components::colours::ColourDisplay::{Gradient, GradientMeter, Meter};
+use goxlr_types::{ColourError, RgbColour};
 use strum::{Display, EnumString};
 use xml::attribute::OwnedAttribute;
 
@@ -287,6 +291,27 @@ impl ColourMap {
     pub fn fader_display(&self) -> &Option<ColourDisplay> {
         &self.colour_display
     }
+
+    pub fn has_same_colours(&self, other: &ColourMap) -> bool {
+        self.off_style == other.off_style
+            && self.colour_list == other.colour_list
+            && self.colour_display == other.colour_display
+    }
+
+    pub fn adjust_colours(&mut self, adjust: impl Fn(&Colour) -> Colour) {
+        if let Some(colour_list) = &mut self.colour_list {
+            for colour in colour_list.iter_mut().flatten() {
+                *colour = adjust(colour);
+            }
+        }
+    }
+
+    // Copies the colours and styles from another map, leaving the on and blink states alone.
+    pub fn copy_colours_from(&mut self, other: &ColourMap) {
+        self.off_style = other.off_style;
+        self.colour_list = other.colour_list.clone();
+        self.colour_display = other.colour_display;
+    }
 }
 
 const DEFAULT_COLOUR: Colour = Colour {
@@ -332,7 +357,7 @@ pub enum ColourState {
     On,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Colour {
     red: u8,
     green: u8,
@@ -345,6 +370,9 @@ impl Colour {
         // I'm pretty sure now that this is actually wrong, I'll need to do more profile tests to
         // confirm, but I'm relatively sure the config format is ARGB and not RGBA, but because
         // the ordering is correct despite naming being wrong, it works.
+        if rgba.len() != 8 || !rgba.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColourError::InvalidHex(rgba.to_string()).into());
+        }
         Ok(Self {
             red: u8::from_str_radix(&rgba[0..2], 16)?,
             green: u8::from_str_radix(&rgba[2..4], 16)?,
@@ -353,14 +381,10 @@ impl Colour {
         })
     }
 
-    // Until I test and fix the above issue, this methods creates based on an RGBA.
+    // Until I test and fix the above issue, this methods creates based on an RGBA. Any of the
+    // formats RgbColour takes (RRGGBB, rgb(), names..) are accepted.
     pub fn fromrgb(rgb: &str) -> Result<Self, ParseError> {
-        Ok(Self {
-            red: u8::from_str_radix("00", 16)?,
-            green: u8::from_str_radix(&rgb[0..2], 16)?,
-            blue: u8::from_str_radix(&rgb[2..4], 16)?,
-            alpha: u8::from_str_radix(&rgb[4..6], 16)?,
-        })
+        Ok(RgbColour::from_str(rgb)?.into())
     }
 
     pub fn to_rgba(&self) -> String {
@@ -370,11 +394,37 @@ impl Colour {
         );
     }
 
+    // As above, the fields are really ARGB, so the RGB values are the last three.
     pub fn to_rgb(&self) -> String {
-        return format!("{:02X}{:02X}{:02X}", self.red, self.green, self.blue);
+        self.to_rgb_colour().to_string()
     }
 
     pub fn to_reverse_bytes(&self) -> [u8; 4] {
         [self.alpha, self.blue, self.green, self.red]
     }
+
+    pub fn to_rgb_colour(&self) -> RgbColour {
+        RgbColour::new(self.green, self.blue, self.alpha)
+    }
+
+    // Hue is in degrees, saturation and value are between 0 and 1.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        RgbColour::from_hsv(hue, saturation, value).into()
+    }
+
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        self.to_rgb_colour().to_hsv()
+    }
+}
+
+impl From<RgbColour> for Colour {
+    // The same field layout as fromrgb..
+    fn from(colour: RgbColour) -> Self {
+        Self {
+            red: 0,
+            green: colour.red,
+            blue: colour.green,
+            alpha: colour.blue,
+        }
+    }
 }
@@ -30,9 +30,39 @@ pub enum ParseError {
 pub struct Mixers {
     mixer_table: EnumMap<InputChannels, EnumMap<OutputChannels, u16>>,
     volume_table: EnumMap<FullChannelList, u8>,
+    submix_table: EnumMap<InputChannels, SubMix>,
     colour_map: ColourMap,
 }
 
+/**
+ * The volume of a channel on the second (monitor) mix, present on newer firmwares. When linked
+ * the sub mix volume follows the main volume of the channel.
+ */
+#[derive(Debug, Copy, Clone)]
+pub struct SubMix {
+    volume: u8,
+    linked: bool,
+}
+
+impl Default for SubMix {
+    fn default() -> Self {
+        Self {
+            volume: 255,
+            linked: true,
+        }
+    }
+}
+
+impl SubMix {
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn linked(&self) -> bool {
+        self.linked
+    }
+}
+
 impl Default for Mixers {
     fn default() -> Self {
         Self::new()
@@ -44,12 +74,31 @@ impl Mixers {
         Self {
             mixer_table: EnumMap::default(),
             volume_table: EnumMap::default(),
+            submix_table: EnumMap::default(),
             colour_map: ColourMap::new("mixerTree".to_string()),
         }
     }
 
     pub fn parse_mixers(&mut self, attributes: &[OwnedAttribute]) -> Result<(), ParseError> {
         for attr in attributes {
+            // These need checking first, as the Level suffix would otherwise match..
+            if let Some(channel) = attr.name.local_name.strip_suffix("SubMixLevel") {
+                let value: u8 = attr.value.parse()?;
+                match find_input_channel(channel) {
+                    Some(channel) => self.submix_table[channel].volume = value,
+                    None => println!("Unable to find Sub Mix Channel: {}", channel),
+                }
+                continue;
+            }
+
+            if let Some(channel) = attr.name.local_name.strip_suffix("SubMixLinked") {
+                match find_input_channel(channel) {
+                    Some(channel) => self.submix_table[channel].linked = attr.value == "1",
+                    None => println!("Unable to find Sub Mix Channel: {}", channel),
+                }
+                continue;
+            }
+
             if attr.name.local_name.ends_with("Level") {
                 let mut found = false;
 
@@ -126,6 +175,16 @@ impl Mixers {
             attributes.insert(key, value);
         }
 
+        for input in InputChannels::iter() {
+            let name = input.get_str("Name").unwrap();
+            let submix = self.submix_table[input];
+            attributes.insert(format!("{}SubMixLevel", name), format!("{}", submix.volume));
+            attributes.insert(
+                format!("{}SubMixLinked", name),
+                format!("{}", submix.linked as u8),
+            );
+        }
+
         for input in InputChannels::iter() {
             // Get the map for this channel..
             let input_text = input.get_str("Name").unwrap();
@@ -167,6 +226,22 @@ impl Mixers {
     pub fn set_channel_volume(&mut self, channel: FullChannelList, volume: u8) {
         self.volume_table[channel] = volume;
     }
+
+    pub fn submix(&self, channel: InputChannels) -> SubMix {
+        self.submix_table[channel]
+    }
+
+    pub fn set_submix_volume(&mut self, channel: InputChannels, volume: u8) {
+        self.submix_table[channel].volume = volume;
+    }
+
+    pub fn set_submix_linked(&mut self, channel: InputChannels, linked: bool) {
+        self.submix_table[channel].linked = linked;
+    }
+}
+
+fn find_input_channel(name: &str) -> Option<InputChannels> {
+    InputChannels::iter().find(|channel| channel.get_str("Name").unwrap() == name)
 }
 
 #[derive(Debug, EnumIter, Enum, EnumProperty, Clone, Copy)]
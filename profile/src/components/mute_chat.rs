@@ -62,6 +62,7 @@ impl MuteChat {
     }
 
     pub fn parse_mute_chat(&mut self, attributes: &[OwnedAttribute]) -> Result<(), ParseError> {
+        let mut hybrid = false;
         for attr in attributes {
             if attr.name.local_name == "micIsAnActiveFader" {
                 self.mic_fader_id = attr.value.parse()?;
@@ -77,6 +78,12 @@ impl MuteChat {
                 continue;
             }
 
+            // Not part of the official format, other apps will see a hybrid button as a toggle.
+            if attr.name.local_name == "coughButtonHybrid" {
+                hybrid = attr.value != "0";
+                continue;
+            }
+
             if attr.name.local_name == "coughButtonMuteSourceSelection" {
                 self.cough_mute_source = MuteFunction::from_usize(attr.value.parse()?);
                 continue;
@@ -97,6 +104,9 @@ impl MuteChat {
             }
         }
 
+        if hybrid && self.cough_behaviour == CoughToggle::Toggle {
+            self.cough_behaviour = CoughToggle::Hybrid;
+        }
         Ok(())
     }
 
@@ -121,6 +131,9 @@ impl MuteChat {
                 "1".to_string()
             },
         );
+        if self.cough_behaviour == CoughToggle::Hybrid {
+            attributes.insert("coughButtonHybrid".to_string(), "1".to_string());
+        }
         attributes.insert(
             "coughButtonMuteSourceSelection".to_string(),
             self.cough_mute_source
@@ -155,8 +168,9 @@ impl MuteChat {
     pub fn colour_map_mut(&mut self) -> &mut ColourMap {
         &mut self.colour_map
     }
+    // A hybrid button can stay muted after release, like a toggle.
     pub fn is_cough_toggle(&self) -> bool {
-        self.cough_behaviour == CoughToggle::Toggle
+        self.cough_behaviour != CoughToggle::Hold
     }
 
     pub fn mic_fader_id(&self) -> u8 {
@@ -213,4 +227,7 @@ impl MuteChat {
 pub enum CoughToggle {
     Hold,
     Toggle,
+
+    // A tap toggles the mute, holding mutes until the button is released
+    Hybrid,
 }
@@ -10,7 +10,7 @@ use xml::writer::events::StartElementBuilder;
 use xml::writer::XmlEvent as XmlWriterEvent;
 use xml::EventWriter;
 
-use crate::components::colours::ColourMap;
+use crate::components::colours::{Colour, ColourMap};
 
 #[derive(thiserror::Error, Debug)]
 #[allow(clippy::enum_variant_names)]
@@ -96,6 +96,11 @@ impl SampleBase {
             sample_stack.play_order = Option::Some(PlayOrder::from_usize(value.parse::<usize>()?));
         }
 
+        // The bank's own button colours aren't part of the official format, and are ignored by it.
+        if let (Some(one), Some(two)) = (map.get("bankColour0"), map.get("bankColour1")) {
+            sample_stack.colours = Some([Colour::new(one)?, Colour::new(two)?]);
+        }
+
         // Ok, somewhere in here we should have a key that tells us how many tracks are configured..
         let key = format!("sampleStack{}stackSize", id);
 
@@ -206,6 +211,11 @@ impl SampleBase {
                 );
             }
 
+            if let Some([one, two]) = &value.colours {
+                sub_attributes.insert("bankColour0".to_string(), one.to_rgba());
+                sub_attributes.insert("bankColour1".to_string(), two.to_rgba());
+            }
+
             // Write the attributes into the tag, and close it.
             for (key, value) in &sub_attributes {
                 sub_element = sub_element.attr(key.as_str(), value.as_str());
@@ -229,13 +239,40 @@ impl SampleBase {
     pub fn get_stack(&self, bank: SampleBank) -> &SampleStack {
         self.sample_stack.get(&bank).unwrap()
     }
+
+    pub fn get_stack_mut(&mut self, bank: SampleBank) -> Option<&mut SampleStack> {
+        self.sample_stack.get_mut(&bank)
+    }
+
+    pub fn add_track(&mut self, bank: SampleBank, track: Track) {
+        self.sample_stack
+            .entry(bank)
+            .or_default()
+            .tracks
+            .push(track);
+    }
+
+    pub fn get_stacks(&self) -> &HashMap<SampleBank, SampleStack> {
+        &self.sample_stack
+    }
+
+    pub fn set_stacks(&mut self, stacks: HashMap<SampleBank, SampleStack>) {
+        self.sample_stack = stacks;
+    }
+
+    pub fn set_stack(&mut self, bank: SampleBank, stack: SampleStack) {
+        self.sample_stack.insert(bank, stack);
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SampleStack {
     tracks: Vec<Track>,
     playback_mode: Option<PlaybackMode>,
     play_order: Option<PlayOrder>,
+
+    // Used in place of the button's colours while this bank is selected
+    colours: Option<[Colour; 2]>,
 }
 
 impl Default for SampleStack {
@@ -250,6 +287,7 @@ impl SampleStack {
             tracks: vec![],
             playback_mode: None,
             play_order: None,
+            colours: None,
         }
     }
 
@@ -260,10 +298,34 @@ impl SampleStack {
     pub fn get_first_sample_file(&self) -> String {
         self.tracks[0].track.to_string()
     }
+
+    pub fn get_tracks(&self) -> &Vec<Track> {
+        &self.tracks
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.playback_mode == Some(PlaybackMode::Loop)
+    }
+
+    pub fn get_track_mut(&mut self, index: usize) -> Option<&mut Track> {
+        self.tracks.get_mut(index)
+    }
+
+    pub fn clear_tracks(&mut self) {
+        self.tracks.clear();
+    }
+
+    pub fn colours(&self) -> Option<&[Colour; 2]> {
+        self.colours.as_ref()
+    }
+
+    pub fn set_colours(&mut self, colours: Option<[Colour; 2]>) {
+        self.colours = colours;
+    }
 }
 
-#[derive(Debug)]
-struct Track {
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
     track: String,
     start_position: u8,
     end_position: u8,
@@ -279,9 +341,27 @@ impl Track {
             normalized_gain,
         }
     }
+
+    pub fn track(&self) -> &str {
+        &self.track
+    }
+
+    // The trim points, as a percentage of the way through the file.
+    pub fn start_position(&self) -> u8 {
+        self.start_position
+    }
+
+    pub fn end_position(&self) -> u8 {
+        self.end_position
+    }
+
+    pub fn set_positions(&mut self, start_position: u8, end_position: u8) {
+        self.start_position = start_position;
+        self.end_position = end_position;
+    }
 }
 
-#[derive(Debug, Enum, EnumProperty)]
+#[derive(Debug, Copy, Clone, PartialEq, Enum, EnumProperty)]
 enum PlaybackMode {
     #[strum(props(index = "0"))]
     PlayNext,
@@ -297,7 +377,7 @@ enum PlaybackMode {
     Loop,
 }
 
-#[derive(Debug, Enum, EnumProperty)]
+#[derive(Debug, Copy, Clone, PartialEq, Enum, EnumProperty)]
 enum PlayOrder {
     #[strum(props(index = "0"))]
     Sequential,
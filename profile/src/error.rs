@@ -1,3 +1,6 @@
+use xml::attribute::OwnedAttribute;
+use xml::common::{Position, TextPosition};
+
 #[derive(thiserror::Error, Debug)]
 pub enum ParseError {
     #[error("Expected int: {0}")]
@@ -89,6 +92,78 @@ pub enum ParseError {
 
     #[error("Profile zip error: {0}")]
     ZipError(#[from] zip::result::ZipError),
+
+    #[error("Invalid XML at line {line}, column {column}: {message}")]
+    InvalidXml {
+        line: u64,
+        column: u64,
+        message: String,
+    },
+
+    #[error("Unsupported profile version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error(
+        "Invalid <{element}>{} at line {line}, column {column}: {source}",
+        describe_attribute(.attribute)
+    )]
+    InvalidElement {
+        element: String,
+        attribute: Option<String>,
+        line: u64,
+        column: u64,
+        source: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    // Positions from the XML reader start at 0, but people count lines from 1.
+    pub(crate) fn invalid_xml(error: &xml::reader::Error) -> Self {
+        let position = error.position();
+        ParseError::InvalidXml {
+            line: position.row + 1,
+            column: position.column + 1,
+            message: error.msg().to_string(),
+        }
+    }
+}
+
+fn describe_attribute(attribute: &Option<String>) -> String {
+    match attribute {
+        Some(attribute) => format!(" attribute {}", attribute),
+        None => String::new(),
+    }
+}
+
+/**
+ Runs the parser for an element, and if it fails, says which element it was, where it is in the
+ file, and which attribute caused it (where that can be found).
+*/
+pub(crate) fn parse_element<E: Into<ParseError>>(
+    element: &str,
+    position: TextPosition,
+    attributes: &[OwnedAttribute],
+    mut parse: impl FnMut(&[OwnedAttribute]) -> Result<(), E>,
+) -> Result<(), ParseError> {
+    let error = match parse(attributes) {
+        Ok(()) => return Ok(()),
+        Err(error) => error.into(),
+    };
+
+    // The element parsers don't know which attribute they failed on, but each attribute is
+    // handled on its own, so try them one at a time to find it.
+    let attribute = attributes
+        .iter()
+        .find(|attr| parse(std::slice::from_ref(attr)).is_err())
+        .map(|attr| attr.name.local_name.clone());
+
+    Err(ParseError::InvalidElement {
+        element: element.to_string(),
+        attribute,
+        line: position.row + 1,
+        column: position.column + 1,
+        source: Box::new(error),
+    })
 }
 
 #[derive(thiserror::Error, Debug)]
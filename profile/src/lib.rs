@@ -1,4 +1,7 @@
+use crate::error::SaveError;
 use enum_map::Enum;
+use std::fs::{remove_file, rename, File};
+use std::path::{Path, PathBuf};
 use strum::EnumIter;
 
 pub mod components;
@@ -6,6 +9,7 @@ pub mod error;
 pub mod mic_profile;
 pub mod microphone;
 pub mod profile;
+pub mod unknown;
 
 #[derive(Debug, Enum, EnumIter, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum SampleButtons {
@@ -15,3 +19,43 @@ pub enum SampleButtons {
     BottomRight,
     Clear,
 }
+
+/**
+ Writes to a temporary file next to the path, and renames it into place once everything has been
+ written and synced, so a crash or power loss part way through a save never leaves a broken file
+ behind. The daemon uses this for its own files too.
+*/
+pub fn write_atomically<F>(path: &Path, write: F) -> Result<(), SaveError>
+where
+    F: FnOnce(&mut File) -> Result<(), SaveError>,
+{
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    let result = File::create(&temp)
+        .map_err(SaveError::from)
+        .and_then(|mut file| {
+            write(&mut file)?;
+            file.sync_all()?;
+            Ok(())
+        });
+
+    if let Err(error) = result {
+        let _ = remove_file(&temp);
+        return Err(error);
+    }
+    rename(&temp, path)?;
+
+    // The rename itself isn't durable until the directory has been synced. Not every platform can
+    // open a directory to do that, so it's only done where it's possible.
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        if let Ok(directory) = File::open(parent) {
+            let _ = directory.sync_all();
+        }
+    }
+    Ok(())
+}
@@ -1,21 +1,26 @@
-use crate::error::{ParseError, SaveError};
+use crate::error::{parse_element, ParseError, SaveError};
 use crate::microphone::compressor::Compressor;
 use crate::microphone::equalizer::Equalizer;
 use crate::microphone::equalizer_mini::EqualizerMini;
 use crate::microphone::gate::Gate;
 use crate::microphone::mic_setup::MicSetup;
 use crate::microphone::ui_setup::UiSetup;
+use crate::unknown::{get_attribute_map, UnknownElement, UnknownElementReader};
+use crate::write_atomically;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
 use std::io::{Read, Write};
 use std::os::raw::c_float;
 use std::path::Path;
+use xml::common::Position;
 use xml::reader::XmlEvent as XmlReaderEvent;
 use xml::writer::events::StartElementBuilder;
 use xml::writer::XmlEvent as XmlWriterEvent;
-use xml::{EmitterConfig, EventReader};
+use xml::{EmitterConfig, EventReader, EventWriter};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MicProfileSettings {
     equalizer: Equalizer,
     equalizer_mini: EqualizerMini,
@@ -24,11 +29,18 @@ pub struct MicProfileSettings {
     deess: u8,
     mic_setup: MicSetup,
     ui_setup: UiSetup,
+
+    // The attributes of each element as they were read, and any elements we don't handle, so
+    // that settings from newer versions of the official app survive being saved.
+    #[cfg_attr(feature = "serde", serde(default))]
+    preserved_attributes: HashMap<String, HashMap<String, String>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    unknown_elements: Vec<UnknownElement>,
 }
 
 impl MicProfileSettings {
     pub fn load<R: Read>(read: R) -> Result<Self, ParseError> {
-        let parser = EventReader::new(read);
+        let mut parser = EventReader::new(read);
 
         let mut equalizer = Equalizer::new();
         let mut equalizer_mini = EqualizerMini::new();
@@ -37,39 +49,69 @@ impl MicProfileSettings {
         let mut deess = 0;
         let mut mic_setup = MicSetup::new();
         let mut ui_setup = UiSetup::new();
+        let mut preserved_attributes = HashMap::new();
+        let mut unknown_elements = UnknownElementReader::default();
+
+        loop {
+            let event = parser.next();
+            let position = parser.position();
 
-        for e in parser {
-            match e {
+            match event {
                 Ok(XmlReaderEvent::StartElement {
                     name, attributes, ..
                 }) => {
+                    if unknown_elements.is_reading() {
+                        unknown_elements.start(&name.local_name, &attributes);
+                        continue;
+                    }
+
+                    if name.local_name != "MicProfileTree" {
+                        let attribute_map = get_attribute_map(&attributes);
+                        preserved_attributes.insert(name.local_name.clone(), attribute_map);
+                    }
+
                     if name.local_name == "dspTreeMicProfile" {
                         // Ok, this is an incredibly large tag, with many settings (30 or so), so
                         // we split it into 3 separate elements.
-                        equalizer.parse_equaliser(&attributes)?;
-                        equalizer_mini.parse_equaliser(&attributes)?;
-                        compressor.parse_compressor(&attributes)?;
-                        gate.parse_gate(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            equalizer.parse_equaliser(a)
+                        })?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            equalizer_mini.parse_equaliser(a)
+                        })?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            compressor.parse_compressor(a)
+                        })?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            gate.parse_gate(a)
+                        })?;
 
                         // Before we're done here, there's a single attribute that doesn't fit into
                         // any of the above categories, find it and handle it here..
-                        for attr in &attributes {
-                            if attr.name.local_name == "MIC_DEESS_AMOUNT" {
-                                deess = attr.value.parse::<c_float>()? as u8;
-                                break;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            for attr in a {
+                                if attr.name.local_name == "MIC_DEESS_AMOUNT" {
+                                    deess = attr.value.parse::<c_float>()? as u8;
+                                    break;
+                                }
                             }
-                        }
+                            Ok::<(), ParseError>(())
+                        })?;
 
                         continue;
                     }
 
                     if name.local_name == "setupTreeMicProfile" {
-                        mic_setup.parse_config(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            mic_setup.parse_config(a)
+                        })?;
                         continue;
                     }
 
                     if name.local_name == "micProfileUIMicProfile" {
-                        ui_setup.parse_ui(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            ui_setup.parse_ui(a)
+                        })?;
                         continue;
                     }
 
@@ -77,12 +119,16 @@ impl MicProfileSettings {
                         continue;
                     }
 
-                    println!("Unhandled Tag: {}", name.local_name);
+                    preserved_attributes.remove(&name.local_name);
+                    unknown_elements.start(&name.local_name, &attributes);
                 }
-                Err(e) => {
-                    println!("Error: {}", e);
-                    break;
+                Ok(XmlReaderEvent::EndElement { .. }) => unknown_elements.end(),
+                Ok(XmlReaderEvent::Characters(text)) | Ok(XmlReaderEvent::Whitespace(text)) => {
+                    unknown_elements.text(text)
                 }
+                Ok(XmlReaderEvent::CData(cdata)) => unknown_elements.cdata(cdata),
+                Ok(XmlReaderEvent::EndDocument) => break,
+                Err(e) => return Err(ParseError::invalid_xml(&e)),
                 _ => {}
             }
         }
@@ -95,16 +141,15 @@ impl MicProfileSettings {
             deess,
             mic_setup,
             ui_setup,
+            preserved_attributes,
+            unknown_elements: unknown_elements.finish(),
         })
     }
 
     pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
         dbg!("Saving File: {}", &path.as_ref());
 
-        let out_file = File::create(path)?;
-        self.write_to(out_file)?;
-
-        Ok(())
+        write_atomically(path.as_ref(), |file| Ok(self.write_to(file)?))
     }
 
     pub fn write_to<W: Write>(&self, mut sink: W) -> Result<(), xml::writer::Error> {
@@ -116,7 +161,7 @@ impl MicProfileSettings {
         writer.write(XmlWriterEvent::start_element("MicProfileTree"))?;
 
         // First, we need to write the EQ, Compressor and Gate..
-        let mut attributes: HashMap<String, String> = HashMap::default();
+        let mut attributes = self.get_preserved_attributes("dspTreeMicProfile");
 
         // The mini and main can both have configs in the same file.
         self.equalizer.write_equaliser(&mut attributes);
@@ -124,22 +169,33 @@ impl MicProfileSettings {
         self.compressor.write_compressor(&mut attributes);
         self.gate.write_gate(&mut attributes);
         attributes.insert("MIC_DEESS_AMOUNT".to_string(), format!("{}", self.deess));
+        write_element(&mut writer, "dspTreeMicProfile", &attributes)?;
 
-        let mut element: StartElementBuilder = XmlWriterEvent::start_element("dspTreeMicProfile");
-        for (key, value) in &attributes {
-            element = element.attr(key.as_str(), value.as_str());
-        }
-        writer.write(element)?;
-        writer.write(XmlWriterEvent::end_element())?;
+        let mut attributes = self.get_preserved_attributes("setupTreeMicProfile");
+        self.mic_setup.write_config(&mut attributes);
+        write_element(&mut writer, "setupTreeMicProfile", &attributes)?;
+
+        let mut attributes = self.get_preserved_attributes("micProfileUIMicProfile");
+        self.ui_setup.write_ui(&mut attributes);
+        write_element(&mut writer, "micProfileUIMicProfile", &attributes)?;
 
-        self.mic_setup.write_config(&mut writer)?;
-        self.ui_setup.write_ui(&mut writer)?;
+        for element in &self.unknown_elements {
+            element.write(&mut writer)?;
+        }
 
         writer.write(XmlWriterEvent::end_element())?;
 
         Ok(())
     }
 
+    // Known attributes are overwritten when writing, leaving just the ones we don't handle.
+    fn get_preserved_attributes(&self, element: &str) -> HashMap<String, String> {
+        self.preserved_attributes
+            .get(element)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     pub fn setup_mut(&mut self) -> &mut MicSetup {
         &mut self.mic_setup
     }
@@ -175,4 +231,97 @@ impl MicProfileSettings {
     pub fn deess(&self) -> u8 {
         self.deess
     }
+    pub fn set_deess(&mut self, deess: u8) {
+        self.deess = deess;
+    }
+
+    pub fn ui_setup(&self) -> &UiSetup {
+        &self.ui_setup
+    }
+}
+
+fn write_element<W: Write>(
+    writer: &mut EventWriter<&mut W>,
+    name: &str,
+    attributes: &HashMap<String, String>,
+) -> Result<(), xml::writer::Error> {
+    let mut element: StartElementBuilder = XmlWriterEvent::start_element(name);
+    for (key, value) in attributes {
+        element = element.attr(key.as_str(), value.as_str());
+    }
+    writer.write(element)?;
+    writer.write(XmlWriterEvent::end_element())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A profile with settings we don't handle: an attribute on dspTreeMicProfile, and an element
+    // with a nested element, text, CDATA and whitespace inside it.
+    const PROFILE: &str = include_str!("../test-data/unknown.goxlrMicProfile");
+
+    // The elements, attributes (sorted, as their order isn't kept) and text of a file. Whitespace
+    // is left out, as the writer lays out the known elements itself.
+    fn read_events(xml: &str) -> Vec<String> {
+        EventReader::new(xml.as_bytes())
+            .into_iter()
+            .filter_map(|event| match event.unwrap() {
+                XmlReaderEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    let mut attributes: Vec<String> = attributes
+                        .iter()
+                        .map(|attr| format!("{}={}", attr.name.local_name, attr.value))
+                        .collect();
+                    attributes.sort();
+                    Some(format!("<{} {}>", name.local_name, attributes.join(" ")))
+                }
+                XmlReaderEvent::EndElement { name } => Some(format!("</{}>", name.local_name)),
+                XmlReaderEvent::Characters(text) => Some(text),
+                XmlReaderEvent::CData(cdata) => Some(format!("<![CDATA[{}]]>", cdata)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn save(profile: &MicProfileSettings) -> String {
+        let mut output = vec![];
+        profile.write_to(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn round_trip(xml: &str) -> String {
+        save(&MicProfileSettings::load(xml.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn saving_keeps_everything() {
+        let saved = round_trip(PROFILE);
+        assert_eq!(read_events(&saved), read_events(PROFILE));
+
+        // Loading what was saved gives the same again.
+        assert_eq!(read_events(&round_trip(&saved)), read_events(PROFILE));
+    }
+
+    #[test]
+    fn unknown_elements_are_written_as_they_were() {
+        let start = PROFILE.find("  <futureSettings").unwrap();
+        let end = PROFILE.find("</MicProfileTree>").unwrap();
+        assert!(round_trip(PROFILE).contains(&PROFILE[start..end]));
+    }
+
+    #[test]
+    fn changes_are_saved_alongside_unknown_settings() {
+        let mut profile = MicProfileSettings::load(PROFILE.as_bytes()).unwrap();
+        assert_eq!(profile.deess(), 40);
+        profile.set_deess(10);
+
+        let expected: Vec<String> = read_events(PROFILE)
+            .into_iter()
+            .map(|event| event.replace("MIC_DEESS_AMOUNT=40", "MIC_DEESS_AMOUNT=10"))
+            .collect();
+        assert_eq!(read_events(&save(&profile)), expected);
+    }
 }
@@ -1,3 +1,5 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::os::raw::c_float;
 use xml::attribute::OwnedAttribute;
@@ -13,6 +15,8 @@ pub enum ParseError {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Compressor {
     threshold: i8,
     ratio: u8,
@@ -1,3 +1,5 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::os::raw::c_float;
 use std::str::FromStr;
@@ -16,6 +18,8 @@ pub enum ParseError {
 // The EQ has a crap load of values (20 total), we could consider splitting
 // them into Gain and Freq to keep stuff tidy?
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Equalizer {
     eq_31h_gain: i8,
     eq_63h_gain: i8,
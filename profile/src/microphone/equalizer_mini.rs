@@ -1,3 +1,5 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::os::raw::c_float;
 use std::str::FromStr;
@@ -15,6 +17,8 @@ pub enum ParseError {
 
 // Mini processes mostly the same way as the main, although has a smaller frequency set.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct EqualizerMini {
     eq_90h_gain: i8,
     eq_250h_gain: i8,
@@ -1,10 +1,8 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::io::Write;
 use std::str::FromStr;
 use xml::attribute::OwnedAttribute;
-use xml::writer::events::StartElementBuilder;
-use xml::writer::XmlEvent as XmlWriterEvent;
-use xml::EventWriter;
 
 #[derive(thiserror::Error, Debug)]
 #[allow(clippy::enum_variant_names)]
@@ -14,6 +12,8 @@ pub enum ParseError {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct MicSetup {
     mic_type: u8,
 
@@ -65,13 +65,7 @@ impl MicSetup {
         Ok(())
     }
 
-    pub fn write_config<W: Write>(
-        &self,
-        writer: &mut EventWriter<&mut W>,
-    ) -> Result<(), xml::writer::Error> {
-        let mut element: StartElementBuilder = XmlWriterEvent::start_element("setupTreeMicProfile");
-
-        let mut attributes: HashMap<String, String> = HashMap::default();
+    pub fn write_config(&self, attributes: &mut HashMap<String, String>) {
         attributes.insert("MIC_TYPE".to_string(), format!("{}", self.mic_type));
         attributes.insert(
             "DYNAMIC_MIC_GAIN".to_string(),
@@ -85,14 +79,6 @@ impl MicSetup {
             "TRS_MIC_GAIN".to_string(),
             format!("{}", (self.trs_mic_gain as u32 * 65536)),
         );
-
-        for (key, value) in &attributes {
-            element = element.attr(key.as_str(), value.as_str());
-        }
-
-        writer.write(element)?;
-        writer.write(XmlWriterEvent::end_element())?;
-        Ok(())
     }
 
     pub fn mic_type(&self) -> u8 {
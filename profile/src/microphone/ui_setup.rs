@@ -1,9 +1,7 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::io::Write;
 use xml::attribute::OwnedAttribute;
-use xml::writer::events::StartElementBuilder;
-use xml::writer::XmlEvent as XmlWriterEvent;
-use xml::EventWriter;
 
 #[derive(thiserror::Error, Debug)]
 #[allow(clippy::enum_variant_names)]
@@ -18,6 +16,8 @@ pub enum ParseError {
  */
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct UiSetup {
     eq_advanced: bool,
     comp_advanced: bool,
@@ -83,14 +83,7 @@ impl UiSetup {
         Ok(())
     }
 
-    pub fn write_ui<W: Write>(
-        &self,
-        writer: &mut EventWriter<&mut W>,
-    ) -> Result<(), xml::writer::Error> {
-        let mut element: StartElementBuilder =
-            XmlWriterEvent::start_element("micProfileUIMicProfile");
-
-        let mut attributes: HashMap<String, String> = HashMap::default();
+    pub fn write_ui(&self, attributes: &mut HashMap<String, String>) {
         attributes.insert(
             "eqAdvanced".to_string(),
             format!("{}", self.eq_advanced as u8),
@@ -107,13 +100,18 @@ impl UiSetup {
             "eqFineTuneEnabled".to_string(),
             format!("{}", self.eq_fine_tune as u8),
         );
+    }
 
-        for (key, value) in &attributes {
-            element = element.attr(key.as_str(), value.as_str());
-        }
-
-        writer.write(element)?;
-        writer.write(XmlWriterEvent::end_element())?;
-        Ok(())
+    pub fn eq_advanced(&self) -> bool {
+        self.eq_advanced
+    }
+    pub fn comp_advanced(&self) -> bool {
+        self.comp_advanced
+    }
+    pub fn gate_advanced(&self) -> bool {
+        self.gate_advanced
+    }
+    pub fn eq_fine_tune(&self) -> bool {
+        self.eq_fine_tune
     }
 }
@@ -1,12 +1,12 @@
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, Write};
 use std::path::Path;
-use std::process::exit;
 use std::str::FromStr;
 
 use enum_map::EnumMap;
 use strum::EnumProperty;
 use strum::IntoEnumIterator;
+use xml::common::Position;
 use xml::reader::XmlEvent as XmlReaderEvent;
 use xml::{EmitterConfig, EventReader};
 use zip::write::FileOptions;
@@ -29,9 +29,9 @@ use crate::components::root::RootElement;
 use crate::components::sample::SampleBase;
 use crate::components::scribble::Scribble;
 use crate::components::simple::{SimpleElement, SimpleElements};
-use crate::error::{ParseError, SaveError};
-use crate::SampleButtons;
+use crate::error::{parse_element, ParseError, SaveError};
 use crate::SampleButtons::{BottomLeft, BottomRight, Clear, TopLeft, TopRight};
+use crate::{write_atomically, SampleButtons};
 
 #[derive(Debug)]
 pub struct Profile {
@@ -66,7 +66,11 @@ impl Profile {
         dbg!("Saving File: {}", &path.as_ref());
 
         // Create a new ZipFile at the requested location
-        let mut archive = zip::ZipWriter::new(File::create(path.as_ref())?);
+        write_atomically(path.as_ref(), |file| self.write_to(file))
+    }
+
+    pub fn write_to<W: Write + Seek>(&self, sink: W) -> Result<(), SaveError> {
+        let mut archive = zip::ZipWriter::new(sink);
 
         // Store the profile..
         archive.start_file("profile.xml", FileOptions::default())?;
@@ -123,7 +127,7 @@ pub struct ProfileSettings {
 
 impl ProfileSettings {
     pub fn load<R: Read>(read: R) -> Result<Self, ParseError> {
-        let parser = EventReader::new(read);
+        let mut parser = EventReader::new(read);
 
         let mut root = RootElement::new();
         let mut browser = BrowserPreviewTree::new("browserPreviewTree".to_string());
@@ -162,19 +166,23 @@ impl ProfileSettings {
 
         let mut active_sample_button = Option::None;
 
-        for e in parser {
-            match e {
+        loop {
+            let event = parser.next();
+            let position = parser.position();
+
+            match event {
                 Ok(XmlReaderEvent::StartElement {
                     name, attributes, ..
                 }) => {
                     if name.local_name == "ValueTreeRoot" {
                         // This also handles <AppTree, due to a single shared value.
-                        root.parse_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            root.parse_root(a)
+                        })?;
 
                         // This code was made for XML version 2, v1 not currently supported.
                         if root.get_version() > 2 {
-                            println!("XML Version Not Supported: {}", root.get_version());
-                            exit(-1);
+                            return Err(ParseError::UnsupportedVersion(root.get_version()));
                         }
 
                         if root.get_version() < 2 {
@@ -187,22 +195,30 @@ impl ProfileSettings {
                     }
 
                     if name.local_name == "browserPreviewTree" {
-                        browser.parse_browser(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            browser.parse_browser(a)
+                        })?;
                         continue;
                     }
 
                     if name.local_name == "mixerTree" {
-                        mixer.parse_mixers(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            mixer.parse_mixers(a)
+                        })?;
                         continue;
                     }
 
                     if name.local_name == "selectedContext" {
-                        context.parse_context(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            context.parse_context(a)
+                        })?;
                         continue;
                     }
 
                     if name.local_name == "muteChat" {
-                        mute_chat.parse_mute_chat(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            mute_chat.parse_mute_chat(a)
+                        })?;
                         continue;
                     }
 
@@ -217,7 +233,9 @@ impl ProfileSettings {
                             .transpose()?
                         {
                             let mut mute_button = MuteButton::new(id);
-                            mute_button.parse_button(&attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                mute_button.parse_button(a)
+                            })?;
                             mute_buttons.insert(id as usize - 1, mute_button);
                             continue;
                         }
@@ -233,7 +251,9 @@ impl ProfileSettings {
                             .transpose()?
                         {
                             let mut fader = Fader::new(id);
-                            fader.parse_fader(&attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                fader.parse_fader(a)
+                            })?;
                             faders.insert(id as usize, fader);
                             continue;
                         }
@@ -246,7 +266,9 @@ impl ProfileSettings {
                         for preset in Preset::iter() {
                             if preset.get_str("contextTitle").unwrap() == name.local_name {
                                 let mut effect = Effects::new(preset);
-                                effect.parse_effect(&attributes)?;
+                                parse_element(&name.local_name, position, &attributes, |a| {
+                                    effect.parse_effect(a)
+                                })?;
                                 effects[preset] = Some(effect);
                                 found = true;
                                 break;
@@ -266,14 +288,18 @@ impl ProfileSettings {
                             .transpose()?
                         {
                             let mut scribble = Scribble::new(id);
-                            scribble.parse_scribble(&attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                scribble.parse_scribble(a)
+                            })?;
                             scribbles.insert(id as usize - 1, scribble);
                             continue;
                         }
                     }
 
                     if name.local_name == "megaphoneEffect" {
-                        megaphone_effect.parse_megaphone_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            megaphone_effect.parse_megaphone_root(a)
+                        })?;
                         continue;
                     }
 
@@ -288,13 +314,17 @@ impl ProfileSettings {
                             .map(|s| u8::from_str(&s.to_string()))
                             .transpose()?
                         {
-                            megaphone_effect.parse_megaphone_preset(id, &attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                megaphone_effect.parse_megaphone_preset(id, a)
+                            })?;
                             continue;
                         }
                     }
 
                     if name.local_name == "robotEffect" {
-                        robot_effect.parse_robot_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            robot_effect.parse_robot_root(a)
+                        })?;
                         continue;
                     }
 
@@ -306,13 +336,17 @@ impl ProfileSettings {
                             .map(|s| u8::from_str(&s.to_string()))
                             .transpose()?
                         {
-                            robot_effect.parse_robot_preset(id, &attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                robot_effect.parse_robot_preset(id, a)
+                            })?;
                             continue;
                         }
                     }
 
                     if name.local_name == "hardtuneEffect" {
-                        hardtune_effect.parse_hardtune_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            hardtune_effect.parse_hardtune_root(a)
+                        })?;
                         continue;
                     }
 
@@ -324,13 +358,17 @@ impl ProfileSettings {
                             .map(|s| u8::from_str(&s.to_string()))
                             .transpose()?
                         {
-                            hardtune_effect.parse_hardtune_preset(id, &attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                hardtune_effect.parse_hardtune_preset(id, a)
+                            })?;
                             continue;
                         }
                     }
 
                     if name.local_name == "reverbEncoder" {
-                        reverb_encoder.parse_reverb_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            reverb_encoder.parse_reverb_root(a)
+                        })?;
                         continue;
                     }
 
@@ -342,13 +380,17 @@ impl ProfileSettings {
                             .map(|s| u8::from_str(&s.to_string()))
                             .transpose()?
                         {
-                            reverb_encoder.parse_reverb_preset(id, &attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                reverb_encoder.parse_reverb_preset(id, a)
+                            })?;
                             continue;
                         }
                     }
 
                     if name.local_name == "echoEncoder" {
-                        echo_encoder.parse_echo_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            echo_encoder.parse_echo_root(a)
+                        })?;
                         continue;
                     }
 
@@ -360,13 +402,17 @@ impl ProfileSettings {
                             .map(|s| u8::from_str(&s.to_string()))
                             .transpose()?
                         {
-                            echo_encoder.parse_echo_preset(id, &attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                echo_encoder.parse_echo_preset(id, a)
+                            })?;
                             continue;
                         }
                     }
 
                     if name.local_name == "pitchEncoder" {
-                        pitch_encoder.parse_pitch_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            pitch_encoder.parse_pitch_root(a)
+                        })?;
                         continue;
                     }
 
@@ -378,13 +424,17 @@ impl ProfileSettings {
                             .map(|s| u8::from_str(&s.to_string()))
                             .transpose()?
                         {
-                            pitch_encoder.parse_pitch_preset(id, &attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                pitch_encoder.parse_pitch_preset(id, a)
+                            })?;
                             continue;
                         }
                     }
 
                     if name.local_name == "genderEncoder" {
-                        gender_encoder.parse_gender_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            gender_encoder.parse_gender_root(a)
+                        })?;
                         continue;
                     }
 
@@ -396,7 +446,9 @@ impl ProfileSettings {
                             .map(|s| u8::from_str(&s.to_string()))
                             .transpose()?
                         {
-                            gender_encoder.parse_gender_preset(id, &attributes)?;
+                            parse_element(&name.local_name, position, &attributes, |a| {
+                                gender_encoder.parse_gender_preset(id, a)
+                            })?;
                             continue;
                         }
                     }
@@ -404,7 +456,9 @@ impl ProfileSettings {
                     // These can probably be a little cleaner..
                     if name.local_name == "sampleTopLeft" {
                         let mut sampler = SampleBase::new("sampleTopLeft".to_string());
-                        sampler.parse_sample_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            sampler.parse_sample_root(a)
+                        })?;
                         sampler_map[TopLeft] = Option::Some(sampler);
                         active_sample_button = sampler_map[TopLeft].as_mut();
                         continue;
@@ -412,7 +466,9 @@ impl ProfileSettings {
 
                     if name.local_name == "sampleTopRight" {
                         let mut sampler = SampleBase::new("sampleTopRight".to_string());
-                        sampler.parse_sample_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            sampler.parse_sample_root(a)
+                        })?;
                         sampler_map[TopRight] = Option::Some(sampler);
                         active_sample_button = sampler_map[TopRight].as_mut();
                         continue;
@@ -420,7 +476,9 @@ impl ProfileSettings {
 
                     if name.local_name == "sampleBottomLeft" {
                         let mut sampler = SampleBase::new("sampleBottomLeft".to_string());
-                        sampler.parse_sample_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            sampler.parse_sample_root(a)
+                        })?;
                         sampler_map[BottomLeft] = Option::Some(sampler);
                         active_sample_button = sampler_map[BottomLeft].as_mut();
                         continue;
@@ -428,7 +486,9 @@ impl ProfileSettings {
 
                     if name.local_name == "sampleBottomRight" {
                         let mut sampler = SampleBase::new("sampleBottomRight".to_string());
-                        sampler.parse_sample_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            sampler.parse_sample_root(a)
+                        })?;
                         sampler_map[BottomRight] = Option::Some(sampler);
                         active_sample_button = sampler_map[BottomRight].as_mut();
                         continue;
@@ -436,7 +496,9 @@ impl ProfileSettings {
 
                     if name.local_name == "sampleClear" {
                         let mut sampler = SampleBase::new("sampleClear".to_string());
-                        sampler.parse_sample_root(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            sampler.parse_sample_root(a)
+                        })?;
                         sampler_map[Clear] = Option::Some(sampler);
                         active_sample_button = sampler_map[Clear].as_mut();
                         continue;
@@ -445,7 +507,9 @@ impl ProfileSettings {
                     if name.local_name.starts_with("sampleStack") {
                         if let Some(id) = name.local_name.chars().last() {
                             if let Some(button) = &mut active_sample_button {
-                                button.parse_sample_stack(id, &attributes)?;
+                                parse_element(&name.local_name, position, &attributes, |a| {
+                                    button.parse_sample_stack(id, a)
+                                })?;
                                 continue;
                             }
                         }
@@ -459,7 +523,9 @@ impl ProfileSettings {
                     {
                         // In this case, the tag name, and attribute prefixes are the same..
                         let mut simple_element = SimpleElement::new(name.local_name.clone());
-                        simple_element.parse_simple(&attributes)?;
+                        parse_element(&name.local_name, position, &attributes, |a| {
+                            simple_element.parse_simple(a)
+                        })?;
                         simple_elements[SimpleElements::from_str(&name.local_name)?] =
                             Some(simple_element);
 
@@ -486,10 +552,8 @@ impl ProfileSettings {
                         active_sample_button = Option::None;
                     }
                 }
-                Err(e) => {
-                    println!("Error: {}", e);
-                    break;
-                }
+                Ok(XmlReaderEvent::EndDocument) => break,
+                Err(e) => return Err(ParseError::invalid_xml(&e)),
                 _ => {}
             }
         }
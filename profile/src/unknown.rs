@@ -0,0 +1,122 @@
+/*
+Newer versions of the official app add settings which we don't know about yet. Rather than
+dropping them when a profile is saved, elements we don't handle are kept here (along with
+anything nested inside them, including text and whitespace) and written back out as they were.
+ */
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use xml::attribute::OwnedAttribute;
+use xml::writer::events::StartElementBuilder;
+use xml::writer::XmlEvent as XmlWriterEvent;
+use xml::EventWriter;
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UnknownElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<UnknownNode>,
+}
+
+// Anything inside an unknown element, kept in the order it was read.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+pub enum UnknownNode {
+    Element(UnknownElement),
+    Text(String),
+    CData { cdata: String },
+}
+
+impl UnknownElement {
+    pub fn new(name: &str, attributes: &[OwnedAttribute]) -> Self {
+        Self {
+            name: name.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|attr| (attr.name.local_name.clone(), attr.value.clone()))
+                .collect(),
+            children: vec![],
+        }
+    }
+
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut EventWriter<&mut W>,
+    ) -> Result<(), xml::writer::Error> {
+        let mut element: StartElementBuilder = XmlWriterEvent::start_element(self.name.as_str());
+        for (key, value) in &self.attributes {
+            element = element.attr(key.as_str(), value.as_str());
+        }
+        writer.write(element)?;
+
+        // Once text has been written the writer stops indenting, so the original whitespace is
+        // kept rather than mixed with the writer's own.
+        for child in &self.children {
+            match child {
+                UnknownNode::Element(element) => element.write(writer)?,
+                UnknownNode::Text(text) => writer.write(XmlWriterEvent::characters(text))?,
+                UnknownNode::CData { cdata } => writer.write(XmlWriterEvent::cdata(cdata))?,
+            }
+        }
+
+        writer.write(XmlWriterEvent::end_element())?;
+        Ok(())
+    }
+}
+
+/**
+ Keeps track of unknown elements while a file is being read, nesting them as they were. Any
+ element started while inside an unknown element is also treated as unknown.
+*/
+#[derive(Debug, Default)]
+pub struct UnknownElementReader {
+    stack: Vec<UnknownElement>,
+    elements: Vec<UnknownElement>,
+}
+
+impl UnknownElementReader {
+    pub fn is_reading(&self) -> bool {
+        !self.stack.is_empty()
+    }
+
+    pub fn start(&mut self, name: &str, attributes: &[OwnedAttribute]) {
+        self.stack.push(UnknownElement::new(name, attributes));
+    }
+
+    pub fn end(&mut self) {
+        if let Some(element) = self.stack.pop() {
+            match self.stack.last_mut() {
+                Some(parent) => parent.children.push(UnknownNode::Element(element)),
+                None => self.elements.push(element),
+            }
+        }
+    }
+
+    // Text (or whitespace) outside of an unknown element is ignored.
+    pub fn text(&mut self, text: String) {
+        if let Some(element) = self.stack.last_mut() {
+            element.children.push(UnknownNode::Text(text));
+        }
+    }
+
+    pub fn cdata(&mut self, cdata: String) {
+        if let Some(element) = self.stack.last_mut() {
+            element.children.push(UnknownNode::CData { cdata });
+        }
+    }
+
+    pub fn finish(self) -> Vec<UnknownElement> {
+        self.elements
+    }
+}
+
+// All of an element's attributes, so any which aren't handled can be written back.
+pub fn get_attribute_map(attributes: &[OwnedAttribute]) -> HashMap<String, String> {
+    attributes
+        .iter()
+        .map(|attr| (attr.name.local_name.clone(), attr.value.clone()))
+        .collect()
+}
@@ -0,0 +1,276 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A colour as red, green and blue. It can be parsed from RRGGBB or RGB hex (with or without a
+/// leading #), `rgb(r, g, b)` or one of the named colours below, and is displayed as RRGGBB.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RgbColour {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+// The basic CSS colours, plus orange.
+const NAMED_COLOURS: [(&str, RgbColour); 17] = [
+    ("black", RgbColour::new(0x00, 0x00, 0x00)),
+    ("silver", RgbColour::new(0xC0, 0xC0, 0xC0)),
+    ("gray", RgbColour::new(0x80, 0x80, 0x80)),
+    ("white", RgbColour::new(0xFF, 0xFF, 0xFF)),
+    ("maroon", RgbColour::new(0x80, 0x00, 0x00)),
+    ("red", RgbColour::new(0xFF, 0x00, 0x00)),
+    ("purple", RgbColour::new(0x80, 0x00, 0x80)),
+    ("fuchsia", RgbColour::new(0xFF, 0x00, 0xFF)),
+    ("green", RgbColour::new(0x00, 0x80, 0x00)),
+    ("lime", RgbColour::new(0x00, 0xFF, 0x00)),
+    ("olive", RgbColour::new(0x80, 0x80, 0x00)),
+    ("yellow", RgbColour::new(0xFF, 0xFF, 0x00)),
+    ("navy", RgbColour::new(0x00, 0x00, 0x80)),
+    ("blue", RgbColour::new(0x00, 0x00, 0xFF)),
+    ("teal", RgbColour::new(0x00, 0x80, 0x80)),
+    ("aqua", RgbColour::new(0x00, 0xFF, 0xFF)),
+    ("orange", RgbColour::new(0xFF, 0xA5, 0x00)),
+];
+
+impl RgbColour {
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    // Hue is in degrees, saturation and value are between 0 and 1.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let hue = hue.rem_euclid(360.0);
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+        let (red, green, blue) = match (hue / 60.0) as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        let offset = value - chroma;
+        let to_byte = |channel: f32| ((channel + offset) * 255.0).round() as u8;
+        Self::new(to_byte(red), to_byte(green), to_byte(blue))
+    }
+
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let red = self.red as f32 / 255.0;
+        let green = self.green as f32 / 255.0;
+        let blue = self.blue as f32 / 255.0;
+
+        let max = red.max(green).max(blue);
+        let delta = max - red.min(green).min(blue);
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == red {
+            60.0 * ((green - blue) / delta).rem_euclid(6.0)
+        } else if max == green {
+            60.0 * ((blue - red) / delta + 2.0)
+        } else {
+            60.0 * ((red - green) / delta + 4.0)
+        };
+
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        (hue, saturation, max)
+    }
+
+    fn from_hex(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digit = |index: usize| u8::from_str_radix(&hex[index..index + 1], 16).ok();
+        let pair = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+        match hex.len() {
+            // Each digit is doubled up, so F80 is FF8800
+            3 => Some(Self::new(
+                digit(0)? * 0x11,
+                digit(1)? * 0x11,
+                digit(2)? * 0x11,
+            )),
+            6 => Some(Self::new(pair(0)?, pair(2)?, pair(4)?)),
+            _ => None,
+        }
+    }
+
+    fn from_rgb_function(components: &str) -> Option<Self> {
+        let components: Vec<u8> = components
+            .split(',')
+            .map(|component| component.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        match components[..] {
+            [red, green, blue] => Some(Self::new(red, green, blue)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for RgbColour {
+    type Err = ColourError;
+
+    fn from_str(colour: &str) -> Result<Self, Self::Err> {
+        let colour = colour.trim();
+        if colour.is_empty() {
+            return Err(ColourError::Empty);
+        }
+
+        if let Some(hex) = colour.strip_prefix('#') {
+            return Self::from_hex(hex).ok_or_else(|| ColourError::InvalidHex(hex.to_string()));
+        }
+
+        let lower = colour.to_ascii_lowercase();
+        if let Some(components) = lower
+            .strip_prefix("rgb(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            return Self::from_rgb_function(components)
+                .ok_or_else(|| ColourError::InvalidRgb(colour.to_string()));
+        }
+
+        if let Some((_, named)) = NAMED_COLOURS.iter().find(|(name, _)| *name == lower) {
+            return Ok(*named);
+        }
+
+        // Otherwise it should be hex without the #, as the IPC has always taken.
+        if colour.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Self::from_hex(colour)
+                .ok_or_else(|| ColourError::InvalidHex(colour.to_string()));
+        }
+        Err(ColourError::UnknownName(colour.to_string()))
+    }
+}
+
+impl fmt::Display for RgbColour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02X}{:02X}{:02X}", self.red, self.green, self.blue)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ColourError {
+    Empty,
+    // Hex colours need 3 or 6 digits
+    InvalidHex(String),
+    // rgb() needs three components between 0 and 255
+    InvalidRgb(String),
+    UnknownName(String),
+}
+
+impl fmt::Display for ColourError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColourError::Empty => write!(f, "No colour was given"),
+            ColourError::InvalidHex(hex) => {
+                write!(f, "{} isn't a hex colour, expected RRGGBB or RGB", hex)
+            }
+            ColourError::InvalidRgb(rgb) => write!(
+                f,
+                "{} isn't a valid rgb() colour, expected three values from 0 to 255",
+                rgb
+            ),
+            ColourError::UnknownName(name) => write!(f, "{} isn't a known colour name", name),
+        }
+    }
+}
+
+impl std::error::Error for ColourError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(colour: &str) -> Result<RgbColour, ColourError> {
+        colour.parse()
+    }
+
+    #[test]
+    fn valid_colours() {
+        let cases = [
+            ("#FF8800", RgbColour::new(0xFF, 0x88, 0x00)),
+            ("#ff8800", RgbColour::new(0xFF, 0x88, 0x00)),
+            ("#F80", RgbColour::new(0xFF, 0x88, 0x00)),
+            ("#f80", RgbColour::new(0xFF, 0x88, 0x00)),
+            ("#aBcDeF", RgbColour::new(0xAB, 0xCD, 0xEF)),
+            ("00FF7f", RgbColour::new(0x00, 0xFF, 0x7F)),
+            ("0f7", RgbColour::new(0x00, 0xFF, 0x77)),
+            ("  #000000  ", RgbColour::new(0x00, 0x00, 0x00)),
+            ("rgb(255,136,0)", RgbColour::new(0xFF, 0x88, 0x00)),
+            ("rgb( 255 , 136 , 0 )", RgbColour::new(0xFF, 0x88, 0x00)),
+            ("RGB(0, 0, 0)", RgbColour::new(0x00, 0x00, 0x00)),
+            ("red", RgbColour::new(0xFF, 0x00, 0x00)),
+            ("Orange", RgbColour::new(0xFF, 0xA5, 0x00)),
+            ("AQUA", RgbColour::new(0x00, 0xFF, 0xFF)),
+        ];
+        for (colour, expected) in cases {
+            assert_eq!(parse(colour), Ok(expected), "{}", colour);
+        }
+    }
+
+    #[test]
+    fn invalid_colours() {
+        let cases = [
+            ("", ColourError::Empty),
+            ("   ", ColourError::Empty),
+            ("#", ColourError::InvalidHex("".to_string())),
+            ("#FF88", ColourError::InvalidHex("FF88".to_string())),
+            ("#FF880000", ColourError::InvalidHex("FF880000".to_string())),
+            ("#GG8800", ColourError::InvalidHex("GG8800".to_string())),
+            ("FF88", ColourError::InvalidHex("FF88".to_string())),
+            (
+                "rgb(256, 0, 0)",
+                ColourError::InvalidRgb("rgb(256, 0, 0)".to_string()),
+            ),
+            (
+                "rgb(-1, 0, 0)",
+                ColourError::InvalidRgb("rgb(-1, 0, 0)".to_string()),
+            ),
+            (
+                "rgb(0, 0)",
+                ColourError::InvalidRgb("rgb(0, 0)".to_string()),
+            ),
+            (
+                "rgb(0, 0, 0, 0)",
+                ColourError::InvalidRgb("rgb(0, 0, 0, 0)".to_string()),
+            ),
+            (
+                "rgb(0.5, 0, 0)",
+                ColourError::InvalidRgb("rgb(0.5, 0, 0)".to_string()),
+            ),
+            (
+                "rgb(0, 0, 0",
+                ColourError::UnknownName("rgb(0, 0, 0".to_string()),
+            ),
+            ("grey", ColourError::UnknownName("grey".to_string())),
+            ("reddish", ColourError::UnknownName("reddish".to_string())),
+        ];
+        for (colour, expected) in cases {
+            assert_eq!(parse(colour), Err(expected), "{}", colour);
+        }
+    }
+
+    #[test]
+    fn displayed_colours_parse_back() {
+        let mut colours: Vec<RgbColour> = NAMED_COLOURS.iter().map(|(_, colour)| *colour).collect();
+        colours.push(RgbColour::new(0x01, 0x23, 0x45));
+        colours.push(RgbColour::new(0xAB, 0xCD, 0xEF));
+        for colour in colours {
+            let displayed = colour.to_string();
+            assert_eq!(displayed.len(), 6);
+            assert_eq!(parse(&displayed), Ok(colour), "{}", displayed);
+            assert_eq!(
+                parse(&format!("#{}", displayed)),
+                Ok(colour),
+                "{}",
+                displayed
+            );
+        }
+    }
+}
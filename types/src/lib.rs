@@ -10,7 +10,10 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::fmt::Formatter;
 use strum::{Display, EnumCount, EnumIter};
 
-#[derive(Copy, Clone, Debug, Display, EnumIter, EnumCount, PartialEq, Eq)]
+mod colour;
+pub use colour::*;
+
+#[derive(Copy, Clone, Debug, Display, EnumIter, EnumCount, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "clap", derive(ArgEnum))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ChannelName {
@@ -37,7 +40,7 @@ pub enum FaderName {
     D,
 }
 
-#[derive(Copy, Clone, Debug, Display, EnumIter, EnumCount, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Display, EnumIter, EnumCount, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "clap", derive(ArgEnum))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EncoderName {
@@ -71,7 +74,7 @@ impl std::fmt::Debug for VersionNumber {
     }
 }
 
-#[derive(Debug, Display, Enum, EnumIter, EnumCount)]
+#[derive(Debug, Display, Enum, EnumIter, EnumCount, Hash)]
 #[cfg_attr(feature = "clap", derive(ArgEnum))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "enumset", derive(EnumSetType))]
@@ -264,6 +267,32 @@ pub enum FaderDisplayStyle {
     GradientMeter,
 }
 
+// The hardware takes the display as two independent flags, these map between the two.
+impl FaderDisplayStyle {
+    pub fn from_flags(gradient: bool, meter: bool) -> Self {
+        match (gradient, meter) {
+            (false, false) => FaderDisplayStyle::TwoColour,
+            (true, false) => FaderDisplayStyle::Gradient,
+            (false, true) => FaderDisplayStyle::Meter,
+            (true, true) => FaderDisplayStyle::GradientMeter,
+        }
+    }
+
+    pub fn is_gradient(&self) -> bool {
+        matches!(
+            self,
+            FaderDisplayStyle::Gradient | FaderDisplayStyle::GradientMeter
+        )
+    }
+
+    pub fn is_meter(&self) -> bool {
+        matches!(
+            self,
+            FaderDisplayStyle::Meter | FaderDisplayStyle::GradientMeter
+        )
+    }
+}
+
 #[derive(Debug, Copy, Clone, Display, EnumIter, EnumCount, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "clap", derive(ArgEnum))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -335,6 +364,29 @@ pub enum EncoderColourTargets {
     Gender,
 }
 
+/// Named EQ curves, which set both the full and the mini EQ.
+#[derive(Debug, Copy, Clone, Display, EnumIter, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(ArgEnum))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EqPreset {
+    Flat,
+    Warm,
+    Bright,
+    Broadcast,
+}
+
+/// The parts of a device profile which can be applied on their own.
+#[derive(Debug, Copy, Clone, Display, EnumIter, EnumCount, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(ArgEnum))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ProfileSection {
+    Faders,
+    Volumes,
+    Routing,
+    Lighting,
+    Sampler,
+}
+
 // MuteChat
 #[derive(Debug, Copy, Clone, Display, EnumIter, EnumCount, PartialEq, Eq)]
 #[cfg_attr(feature = "clap", derive(ArgEnum))]
@@ -347,6 +399,17 @@ pub enum MuteFunction {
     ToLineOut,
 }
 
+#[derive(Debug, Copy, Clone, Display, EnumIter, EnumCount, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(ArgEnum))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CoughButtonMode {
+    Hold,
+    Toggle,
+
+    // A tap toggles the mute, holding mutes until the button is released
+    Hybrid,
+}
+
 #[derive(Debug, Copy, Clone, Display, EnumIter, EnumCount, PartialEq, Eq)]
 #[cfg_attr(feature = "clap", derive(ArgEnum))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -382,13 +445,25 @@ pub enum EffectBankPresets {
     Preset6,
 }
 
-#[derive(Debug, Copy, Clone, Display, PartialEq)]
+#[derive(Debug, Copy, Clone, Display, EnumIter, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "clap", derive(ArgEnum))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SampleBank {
     A,
     B,
     C,
 }
 
+#[derive(Debug, Copy, Clone, Display, EnumIter, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "clap", derive(ArgEnum))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SampleButtons {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
 #[derive(Debug, Copy, Clone, Display, EnumIter, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "clap", derive(ArgEnum))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -0,0 +1,79 @@
+/*
+Captures every command sent to a device and the device's response, so a protocol problem can be
+reported along with exactly what was said. Unlike a trace, this includes reads, retries and the
+index of each request. The file has one JSON object per line, with the time in microseconds since
+the Unix epoch and the data in hex:
+
+    {"time_us":1690000000123456,"device":"1.5","direction":"out","command_id":"0080600a","name":"SetChannelVolume(Mic)","index":12,"data":"ff"}
+    {"time_us":1690000000127012,"device":"1.5","direction":"in","command_id":"0080600a","index":12,"data":""}
+
+A request which failed has an "error" in place of its response's data.
+ */
+
+use crate::commands::Command;
+use crate::trace::to_hex;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A capture file, which can be shared between devices.
+#[derive(Debug, Clone)]
+pub struct UsbCapture {
+    file: Arc<Mutex<File>>,
+}
+
+impl UsbCapture {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            file: Arc::new(Mutex::new(File::create(path)?)),
+        })
+    }
+
+    pub fn request(&self, device: &str, command: Command, index: u16, body: &[u8]) {
+        self.write(format!(
+            "\"device\":\"{}\",\"direction\":\"out\",\"command_id\":\"{:08x}\",\"name\":\"{}\",\"index\":{},\"data\":\"{}\"",
+            escape(device),
+            command.command_id(),
+            escape(&format!("{:?}", command)),
+            index,
+            to_hex(body)
+        ));
+    }
+
+    pub fn response(
+        &self,
+        device: &str,
+        command: Command,
+        index: u16,
+        result: &Result<Vec<u8>, rusb::Error>,
+    ) {
+        let outcome = match result {
+            Ok(data) => format!("\"data\":\"{}\"", to_hex(data)),
+            Err(error) => format!("\"error\":\"{}\"", escape(&error.to_string())),
+        };
+        self.write(format!(
+            "\"device\":\"{}\",\"direction\":\"in\",\"command_id\":\"{:08x}\",\"index\":{},{}",
+            escape(device),
+            command.command_id(),
+            index,
+            outcome
+        ));
+    }
+
+    fn write(&self, fields: String) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_micros())
+            .unwrap_or_default();
+
+        // Losing a line of the capture shouldn't stop the device from working.
+        let mut file = self.file.lock().unwrap();
+        let _ = writeln!(file, "{{\"time_us\":{},{}}}", time, fields);
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
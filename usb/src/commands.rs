@@ -7,6 +7,7 @@ pub enum Command {
     SystemInfo(SystemInfoCommand),
     SetChannelState(ChannelName),
     SetChannelVolume(ChannelName),
+    SetSubChannelVolume(ChannelName),
     SetEncoderValue(EncoderName),
     SetEncoderMode(EncoderName),
     SetFader(FaderName),
@@ -20,6 +21,10 @@ pub enum Command {
     SetScribble(FaderName),
     GetButtonStates,
     GetHardwareInfo(HardwareInfoCommand),
+
+    // Any command by its id, for protocol research. It's sent straight away (never batched), and
+    // may or may not change the device's state.
+    Raw(u32),
 }
 
 impl Command {
@@ -29,6 +34,7 @@ impl Command {
             Command::SystemInfo(sub) => sub.id(),
             Command::SetChannelState(channel) => (0x809 << 12) | *channel as u32,
             Command::SetChannelVolume(channel) => (0x806 << 12) | *channel as u32,
+            Command::SetSubChannelVolume(channel) => (0x806 << 12) | (0x10 + *channel as u32),
             Command::SetEncoderValue(encoder) => (0x80a << 12) | *encoder as u32,
             Command::SetEncoderMode(encoder) => (0x811 << 12) | *encoder as u32,
             Command::SetFader(fader) => (0x805 << 12) | *fader as u32,
@@ -42,8 +48,30 @@ impl Command {
             Command::GetMicrophoneLevel => (0x80c << 12),
             Command::SetMicrophoneParameters => (0x80b << 12),
             Command::SetEffectParameters => (0x801 << 12),
+            Command::Raw(id) => *id,
         }
     }
+
+    /// Whether the command only changes the device's state, and has no useful response.
+    pub fn is_write(&self) -> bool {
+        !matches!(
+            self,
+            Command::ResetCommandIndex
+                | Command::SystemInfo(_)
+                | Command::GetButtonStates
+                | Command::GetHardwareInfo(_)
+                | Command::GetMicrophoneLevel
+                | Command::Raw(_)
+        )
+    }
+
+    /// Whether the body is a list of key / value pairs, rather than a single value.
+    pub fn has_parameter_list(&self) -> bool {
+        matches!(
+            self,
+            Command::SetEffectParameters | Command::SetMicrophoneParameters
+        )
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -1,4 +1,6 @@
-#[derive(Copy, Clone, Debug)]
+use strum::EnumIter;
+
+#[derive(Copy, Clone, Debug, EnumIter)]
 pub enum DCPCategory {
     Peaks,
     Router,
@@ -23,4 +23,16 @@ pub enum CommandError {
 
     #[error("Malformed response from GoXLR")]
     MalformedResponse(#[from] std::io::Error),
+
+    #[error("Not supported by this device")]
+    NotSupported,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MockScriptError {
+    #[error("Line {0} of the mock script is invalid: {1}")]
+    InvalidLine(usize, String),
+
+    #[error("Unknown button, fader or encoder in the mock script: {0}")]
+    UnknownName(String),
 }
@@ -1,24 +1,14 @@
-use crate::buttonstate::{ButtonStates, Buttons, CurrentButtonStates};
-use crate::channelstate::ChannelState;
-use crate::commands::SystemInfoCommand;
-use crate::commands::SystemInfoCommand::SupportsDCPCategory;
-use crate::commands::{Command, HardwareInfoCommand};
-use crate::dcp::DCPCategory;
+use crate::capture::UsbCapture;
+use crate::commands::Command;
+use crate::device::{GoXlrDevice, InterruptWait, UsbDeviceInfo};
 use crate::error::{CommandError, ConnectError};
-use crate::routing::InputDevice;
-use byteorder::{ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
-use enumset::EnumSet;
-use goxlr_types::{
-    ChannelName, EffectKey, EncoderName, FaderName, FirmwareVersions, MicrophoneParamKey,
-    MicrophoneType, VersionNumber,
-};
-use log::{debug, info};
+use byteorder::{ByteOrder, LittleEndian};
+use log::{debug, info, warn};
 use rusb::Error::Pipe;
 use rusb::{
     Device, DeviceDescriptor, DeviceHandle, Direction, GlobalContext, Language, Recipient,
     RequestType, UsbContext,
 };
-use std::io::{Cursor, Write};
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -28,9 +18,12 @@ pub struct GoXLR<T: UsbContext> {
     device: Device<T>,
     device_descriptor: DeviceDescriptor,
     timeout: Duration,
+    retry_policy: RetryPolicy,
     language: Language,
     command_count: u16,
     device_is_claimed: bool,
+    batch: Option<Vec<(Command, Vec<u8>)>>,
+    capture: Option<UsbCapture>,
 }
 
 // Todo: Be nicer about this..
@@ -40,20 +33,105 @@ pub const VID_GOXLR: u16 = 0x1220;
 pub const PID_GOXLR_MINI: u16 = 0x8fe4;
 pub const PID_GOXLR_FULL: u16 = 0x8fe0;
 
+/// How long to wait for a USB transfer, and how to retry commands which hit a transient error.
+#[derive(Debug, Copy, Clone)]
+pub struct RetryPolicy {
+    pub timeout: Duration,
+    pub retries: u8,
+
+    /// The delay before the first retry, doubled on each further attempt.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            retries: 2,
+            backoff: Duration::from_millis(20),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn get_backoff(&self, attempt: u8) -> Duration {
+        self.backoff * 2u32.pow(attempt.saturating_sub(1).into())
+    }
+}
+
+// Adds a write to a batch, replacing any earlier write of the same setting. Effect and microphone
+// parameters are merged into a single request instead, with later values for a key winning.
+fn queue_request(batch: &mut Vec<(Command, Vec<u8>)>, command: Command, body: &[u8]) {
+    let mut body = body.to_vec();
+    if let Some(index) = batch.iter().position(|(queued, _)| *queued == command) {
+        let (_, previous) = batch.remove(index);
+        if command.has_parameter_list() {
+            body = merge_parameters(&previous, &body);
+        }
+    }
+    batch.push((command, body));
+}
+
+// Parameter lists are made up of 8 byte pairs, a 4 byte key followed by a 4 byte value.
+fn merge_parameters(previous: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut pairs: Vec<&[u8]> = previous.chunks(8).collect();
+    for pair in body.chunks(8) {
+        pairs.retain(|existing| existing[0..4] != pair[0..4]);
+        pairs.push(pair);
+    }
+    pairs.concat()
+}
+
+fn is_transient_error(error: rusb::Error) -> bool {
+    matches!(
+        error,
+        rusb::Error::Timeout | rusb::Error::Busy | rusb::Error::Interrupted | rusb::Error::Io
+    )
+}
+
+/// A GoXLR which has been found on the bus, but not opened yet.
+pub struct DetectedDevice {
+    device: Device<GlobalContext>,
+    descriptor: DeviceDescriptor,
+}
+
+impl DetectedDevice {
+    pub fn bus_number(&self) -> u8 {
+        self.device.bus_number()
+    }
+
+    pub fn address(&self) -> u8 {
+        self.device.address()
+    }
+
+    pub fn open(self) -> Result<GoXLR<GlobalContext>, ConnectError> {
+        GoXLR::from_device(self.device.open()?, self.descriptor)
+    }
+}
+
+/// Lists the GoXLRs connected to the system.
+pub fn find_devices() -> Result<Vec<DetectedDevice>, ConnectError> {
+    let mut found = vec![];
+    for device in rusb::devices()?.iter() {
+        if let Ok(descriptor) = device.device_descriptor() {
+            if descriptor.vendor_id() == VID_GOXLR
+                && (descriptor.product_id() == PID_GOXLR_FULL
+                    || descriptor.product_id() == PID_GOXLR_MINI)
+            {
+                found.push(DetectedDevice { device, descriptor });
+            }
+        }
+    }
+    Ok(found)
+}
+
 impl GoXLR<GlobalContext> {
     pub fn open() -> Result<Self, ConnectError> {
         let mut error = ConnectError::DeviceNotFound;
-        for device in rusb::devices()?.iter() {
-            if let Ok(descriptor) = device.device_descriptor() {
-                if descriptor.vendor_id() == VID_GOXLR
-                    && (descriptor.product_id() == PID_GOXLR_FULL
-                        || descriptor.product_id() == PID_GOXLR_MINI)
-                {
-                    match device.open() {
-                        Ok(handle) => return GoXLR::from_device(handle, descriptor),
-                        Err(e) => error = e.into(),
-                    }
-                }
+        for device in find_devices()? {
+            match device.open() {
+                Ok(goxlr) => return Ok(goxlr),
+                Err(e) => error = e,
             }
         }
 
@@ -67,7 +145,8 @@ impl<T: UsbContext> GoXLR<T> {
         device_descriptor: DeviceDescriptor,
     ) -> Result<Self, ConnectError> {
         let device = handle.device();
-        let timeout = Duration::from_secs(1);
+        let retry_policy = RetryPolicy::default();
+        let timeout = retry_policy.timeout;
 
         info!("Connected to possible GoXLR device at {:?}", device);
 
@@ -92,9 +171,12 @@ impl<T: UsbContext> GoXLR<T> {
             device,
             device_descriptor,
             timeout,
+            retry_policy,
             language,
             command_count: 0,
             device_is_claimed,
+            batch: None,
+            capture: None,
         };
 
         // Resets the state of the device (unconfirmed - Might just be the command id counter)
@@ -137,6 +219,16 @@ impl<T: UsbContext> GoXLR<T> {
         Ok(goxlr)
     }
 
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.timeout = retry_policy.timeout;
+        self.retry_policy = retry_policy;
+    }
+
+    /// Records every request sent to the device from now on, and its response.
+    pub fn set_capture(&mut self, capture: UsbCapture) {
+        self.capture = Some(capture);
+    }
+
     pub fn usb_device_descriptor(&self) -> &DeviceDescriptor {
         &self.device_descriptor
     }
@@ -161,10 +253,6 @@ impl<T: UsbContext> GoXLR<T> {
         self.device_is_claimed
     }
 
-    pub fn usb_device_has_kernel_driver_active(&self) -> Result<bool, rusb::Error> {
-        self.handle.kernel_driver_active(0)
-    }
-
     pub fn usb_bus_number(&self) -> u8 {
         self.device.bus_number()
     }
@@ -231,16 +319,65 @@ impl<T: UsbContext> GoXLR<T> {
         Ok(())
     }
 
-    pub fn request_data(&mut self, command: Command, body: &[u8]) -> Result<Vec<u8>, rusb::Error> {
+    fn send_batch(&mut self) -> Result<(), rusb::Error> {
+        let batch = match &mut self.batch {
+            Some(batch) if !batch.is_empty() => std::mem::take(batch),
+            _ => return Ok(()),
+        };
+
+        debug!("Sending {} batched requests", batch.len());
+        for (command, body) in batch {
+            self.send_with_retries(command, &body)?;
+        }
+        Ok(())
+    }
+
+    fn send_with_retries(&mut self, command: Command, body: &[u8]) -> Result<Vec<u8>, rusb::Error> {
+        let mut attempt = 0;
+        loop {
+            match self.send_request(command, body) {
+                Err(error) if is_transient_error(error) && attempt < self.retry_policy.retries => {
+                    attempt += 1;
+                    warn!(
+                        "Error sending {:?} ({}), retrying (Attempt {} of {})",
+                        command, error, attempt, self.retry_policy.retries
+                    );
+                    sleep(self.retry_policy.get_backoff(attempt));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn send_request(&mut self, command: Command, body: &[u8]) -> Result<Vec<u8>, rusb::Error> {
         if command == Command::ResetCommandIndex {
             self.command_count = 0;
         } else {
             if self.command_count == u16::MAX {
-                let _ = self.request_data(Command::ResetCommandIndex, &[])?;
+                let _ = self.send_with_retries(Command::ResetCommandIndex, &[])?;
             }
             self.command_count += 1;
         }
         let command_index = self.command_count;
+
+        let capture = self.capture.clone();
+        let device = format!("{}.{}", self.usb_bus_number(), self.usb_address());
+        if let Some(capture) = &capture {
+            capture.request(&device, command, command_index, body);
+        }
+        let result = self.exchange(command, command_index, body);
+        if let Some(capture) = &capture {
+            capture.response(&device, command, command_index, &result);
+        }
+        result
+    }
+
+    fn exchange(
+        &mut self,
+        command: Command,
+        command_index: u16,
+        body: &[u8],
+    ) -> Result<Vec<u8>, rusb::Error> {
         let mut full_request = vec![0; 16];
         LittleEndian::write_u32(&mut full_request[0..4], command.command_id());
         LittleEndian::write_u16(&mut full_request[4..6], body.len() as u16);
@@ -260,306 +397,114 @@ impl<T: UsbContext> GoXLR<T> {
         }
         sleep(sleep_time);
 
-        // Interrupt reading doesnt work, because we can't claim the interface.
-        //self.await_interrupt(Duration::from_secs(2));
-
         if SUPER_DEBUG {
             debug!("Reading Response..");
         }
-        let mut response = vec![];
-
         for i in 0..20 {
             let response_value = self.read_control(3, 0, 0, 1040);
             if response_value == Err(Pipe) {
-                if i < 20 {
-                    debug!("Response not arrived yet for {:?}, sleeping and retrying (Attempt {} of 20)", command, i + 1);
-                    sleep(sleep_time);
-                    continue;
-                } else {
-                    debug!("Failed to receive response (Attempt 20 of 20), possible Dead GoXLR?");
-                    return Err(response_value.err().unwrap());
-                }
+                debug!(
+                    "Response not arrived yet for {:?}, sleeping and retrying (Attempt {} of 20)",
+                    command,
+                    i + 1
+                );
+                sleep(sleep_time);
+                continue;
             }
-            if response_value.is_err() {
-                let err = response_value.err().unwrap();
+            if let Err(err) = response_value {
                 debug!("Error Occured during packet read: {}", err);
                 return Err(err);
             }
 
             let mut response_header = response_value.unwrap();
-            response = response_header.split_off(16);
+            let response = response_header.split_off(16);
             let response_length = LittleEndian::read_u16(&response_header[4..6]);
             let response_command_index = LittleEndian::read_u16(&response_header[6..8]);
 
             debug_assert!(response.len() == response_length as usize);
             debug_assert!(response_command_index == command_index);
 
-            break;
+            return Ok(response);
         }
 
-        Ok(response)
+        debug!("Failed to receive response (Attempt 20 of 20), possible Dead GoXLR?");
+        Err(Pipe)
     }
+}
 
-    pub fn supports_dcp_category(&mut self, category: DCPCategory) -> Result<bool, rusb::Error> {
-        let mut out = [0; 2];
-        LittleEndian::write_u16(&mut out, category.id());
-        let result = self.request_data(Command::SystemInfo(SupportsDCPCategory), &out)?;
-        Ok(LittleEndian::read_u16(&result) == 1)
+impl<T: UsbContext + 'static> GoXlrDevice for GoXLR<T> {
+    /// Holds back writes until `end_batch` is called, so settings which are changed more than
+    /// once are only sent to the device once. Reads will send any held writes first.
+    fn begin_batch(&mut self) {
+        if self.batch.is_none() {
+            self.batch = Some(Vec::new());
+        }
     }
 
-    pub fn get_system_info(&mut self) -> Result<(), rusb::Error> {
-        let _result =
-            self.request_data(Command::SystemInfo(SystemInfoCommand::FirmwareVersion), &[])?;
-        // TODO: parse that?
-        Ok(())
+    fn end_batch(&mut self) -> Result<(), CommandError> {
+        let result = self.send_batch();
+        self.batch = None;
+        Ok(result?)
     }
 
-    pub fn get_firmware_version(&mut self) -> Result<FirmwareVersions, CommandError> {
-        let result = self.request_data(
-            Command::GetHardwareInfo(HardwareInfoCommand::FirmwareVersion),
-            &[],
-        )?;
-        let mut cursor = Cursor::new(result);
-        let firmware_packed = cursor.read_u32::<LittleEndian>()?;
-        let firmware_build = cursor.read_u32::<LittleEndian>()?;
-        let firmware = VersionNumber(
-            firmware_packed >> 12,
-            (firmware_packed >> 8) & 0xF,
-            firmware_packed & 0xFF,
-            firmware_build,
-        );
-
-        let _unknown = cursor.read_u32::<LittleEndian>()?;
-        let fpga_count = cursor.read_u32::<LittleEndian>()?;
-
-        let dice_build = cursor.read_u32::<LittleEndian>()?;
-        let dice_packed = cursor.read_u32::<LittleEndian>()?;
-        let dice = VersionNumber(
-            (dice_packed >> 20) & 0xF,
-            (dice_packed >> 12) & 0xFF,
-            dice_packed & 0xFFF,
-            dice_build,
-        );
-
-        Ok(FirmwareVersions {
-            firmware,
-            fpga_count,
-            dice,
+    fn request_data(&mut self, command: Command, body: &[u8]) -> Result<Vec<u8>, CommandError> {
+        if let Some(batch) = &mut self.batch {
+            if command.is_write() {
+                queue_request(batch, command, body);
+                return Ok(Vec::new());
+            }
+            self.send_batch()?;
+        }
+        Ok(self.send_with_retries(command, body)?)
+    }
+
+    /// Opens a second handle to the device, which can wait for interrupts on another thread.
+    fn interrupt_reader(&self) -> Result<Box<dyn InterruptWait>, CommandError> {
+        Ok(Box::new(InterruptReader {
+            handle: self.device.open()?,
+        }))
+    }
+
+    fn get_usb_info(&self) -> Result<UsbDeviceInfo, CommandError> {
+        let version = self.device_descriptor.device_version();
+        let usb_version = self.device_descriptor.usb_version();
+        Ok(UsbDeviceInfo {
+            manufacturer_name: self.usb_device_manufacturer()?,
+            product_name: self.usb_device_product_name()?,
+            is_mini: self.device_descriptor.product_id() == PID_GOXLR_MINI,
+            is_claimed: self.usb_device_is_claimed(),
+            has_kernel_driver_attached: self.usb_device_has_kernel_driver_active()?,
+            bus_number: self.usb_bus_number(),
+            address: self.usb_address(),
+            version: (version.0, version.1, version.2),
+            vendor_id: self.device_descriptor.vendor_id(),
+            product_id: self.device_descriptor.product_id(),
+            usb_version: (usb_version.0, usb_version.1, usb_version.2),
+            speed: format!("{:?}", self.device.speed()),
         })
     }
 
-    pub fn get_serial_number(&mut self) -> Result<(String, String), CommandError> {
-        let result = self.request_data(
-            Command::GetHardwareInfo(HardwareInfoCommand::SerialNumber),
-            &[],
-        )?;
-
-        let serial_slice = &result[..24];
-        let serial_len = serial_slice
-            .iter()
-            .position(|&c| c == 0)
-            .unwrap_or(serial_slice.len()) as usize;
-        let serial_number = String::from_utf8_lossy(&serial_slice[..serial_len]).to_string();
-
-        let date_slice = &result[24..];
-        let date_len = date_slice
-            .iter()
-            .position(|&c| c == 0)
-            .unwrap_or(date_slice.len()) as usize;
-        let manufacture_date = String::from_utf8_lossy(&date_slice[..date_len]).to_string();
-        Ok((serial_number, manufacture_date))
-    }
-
-    pub fn set_fader(&mut self, fader: FaderName, channel: ChannelName) -> Result<(), rusb::Error> {
-        // Channel ID, unknown, unknown, unknown
-        self.request_data(Command::SetFader(fader), &[channel as u8, 0x00, 0x00, 0x00])?;
-        Ok(())
+    fn usb_device_has_kernel_driver_active(&self) -> Result<bool, CommandError> {
+        Ok(self.handle.kernel_driver_active(0)?)
     }
 
-    pub fn set_volume(&mut self, channel: ChannelName, volume: u8) -> Result<(), rusb::Error> {
-        self.request_data(Command::SetChannelVolume(channel), &[volume])?;
-        Ok(())
-    }
-
-    pub fn set_encoder_value(
-        &mut self,
-        encoder: EncoderName,
-        value: u8,
-    ) -> Result<(), rusb::Error> {
-        self.request_data(Command::SetEncoderValue(encoder), &[value])?;
-        Ok(())
-    }
-
-    pub fn set_encoder_mode(
-        &mut self,
-        encoder: EncoderName,
-        mode: u8,
-        resolution: u8,
-    ) -> Result<(), rusb::Error> {
-        self.request_data(Command::SetEncoderMode(encoder), &[mode, resolution])?;
-        Ok(())
-    }
-
-    pub fn set_channel_state(
-        &mut self,
-        channel: ChannelName,
-        state: ChannelState,
-    ) -> Result<(), rusb::Error> {
-        self.request_data(Command::SetChannelState(channel), &[state.id()])?;
-        Ok(())
-    }
-
-    pub fn set_button_states(&mut self, data: [ButtonStates; 24]) -> Result<(), rusb::Error> {
-        self.request_data(Command::SetButtonStates(), &data.map(|state| state as u8))?;
-        Ok(())
-    }
-
-    pub fn set_button_colours(&mut self, data: [u8; 328]) -> Result<(), rusb::Error> {
-        self.request_data(Command::SetColourMap(), &data)?;
-        Ok(())
-    }
-
-    pub fn set_button_colours_1_3_40(&mut self, data: [u8; 520]) -> Result<(), rusb::Error> {
-        self.request_data(Command::SetColourMap(), &data)?;
-        Ok(())
-    }
-
-    pub fn set_fader_display_mode(
-        &mut self,
-        fader: FaderName,
-        gradient: bool,
-        meter: bool,
-    ) -> Result<(), rusb::Error> {
-        // This one really doesn't need anything fancy..
-        let gradient_byte: u8 = if gradient { 0x01 } else { 0x00 };
-        let meter_byte: u8 = if meter { 0x01 } else { 0x00 };
-
-        // TODO: Seemingly broken?
-        self.request_data(
-            Command::SetFaderDisplayMode(fader),
-            &[gradient_byte, meter_byte],
-        )?;
-        Ok(())
-    }
-
-    pub fn set_fader_scribble(
-        &mut self,
-        fader: FaderName,
-        data: [u8; 1024],
-    ) -> Result<(), rusb::Error> {
-        // Dump it, see what happens..
-        self.request_data(Command::SetScribble(fader), &data)?;
-        Ok(())
-    }
-
-    pub fn set_routing(
-        &mut self,
-        input_device: InputDevice,
-        data: [u8; 22],
-    ) -> Result<(), rusb::Error> {
-        self.request_data(Command::SetRouting(input_device), &data)?;
-        Ok(())
-    }
-
-    pub fn set_microphone_gain(
-        &mut self,
-        microphone_type: MicrophoneType,
-        gain: u16,
-    ) -> Result<(), CommandError> {
-        let mut gain_value = [0; 4];
-        LittleEndian::write_u16(&mut gain_value[2..], gain);
-        self.set_mic_param(&[
-            (
-                MicrophoneParamKey::MicType,
-                match microphone_type.has_phantom_power() {
-                    true => [0x01, 0x00, 0x00, 0x00],
-                    false => [0x00, 0x00, 0x00, 0x00],
-                },
-            ),
-            (microphone_type.get_gain_param(), gain_value),
-        ])?;
-        Ok(())
-    }
-
-    pub fn get_microphone_level(&mut self) -> Result<u16, rusb::Error> {
-        let result = self.request_data(Command::GetMicrophoneLevel, &[])?;
-
-        Ok(LittleEndian::read_u16(&result))
-    }
-
-    pub fn set_effect_values(&mut self, effects: &[(EffectKey, i32)]) -> Result<(), CommandError> {
-        let mut data = Vec::with_capacity(effects.len() * 8);
-        let mut cursor = Cursor::new(&mut data);
-        for (key, value) in effects {
-            cursor.write_u32::<LittleEndian>(*key as u32)?;
-            cursor.write_i32::<LittleEndian>(*value)?;
-        }
-        self.request_data(Command::SetEffectParameters, &data)?;
-
-        Ok(())
-    }
-
-    pub fn set_mic_param(
-        &mut self,
-        params: &[(MicrophoneParamKey, [u8; 4])],
-    ) -> Result<(), CommandError> {
-        let mut data = Vec::with_capacity(params.len() * 8);
-        let mut cursor = Cursor::new(&mut data);
-        for (key, value) in params {
-            cursor.write_u32::<LittleEndian>(*key as u32)?;
-            cursor.write_all(value)?;
-        }
-        self.request_data(Command::SetMicrophoneParameters, &data)?;
-
-        Ok(())
+    fn is_connected(&self) -> bool {
+        self.handle.active_configuration().is_ok()
     }
+}
 
-    pub fn get_button_states(&mut self) -> Result<CurrentButtonStates, rusb::Error> {
-        let result = self.request_data(Command::GetButtonStates, &[])?;
-        let mut pressed = EnumSet::empty();
-        let mut mixers = [0; 4];
-        let mut encoders = [0; 4];
-        let button_states = LittleEndian::read_u32(&result[0..4]);
-
-        mixers[0] = result[8];
-        mixers[1] = result[9];
-        mixers[2] = result[10];
-        mixers[3] = result[11];
-
-        // These can technically be negative, cast straight to i8
-        encoders[0] = result[4] as i8; // Pitch
-        encoders[1] = result[5] as i8; // Gender
-        encoders[2] = result[6] as i8; // Reverb
-        encoders[3] = result[7] as i8; // Echo
-
-        for button in EnumSet::<Buttons>::all() {
-            if button_states & (1 << button as u8) != 0 {
-                pressed.insert(button);
-            }
-        }
-
-        Ok(CurrentButtonStates {
-            pressed,
-            volumes: mixers,
-            encoders,
-        })
-    }
+/// Waits for the interrupts sent by the GoXLR when a button, fader or encoder changes.
+pub struct InterruptReader<T: UsbContext> {
+    handle: DeviceHandle<T>,
+}
 
-    pub fn await_interrupt(&mut self, duration: Duration) -> bool {
+impl<T: UsbContext> InterruptWait for InterruptReader<T> {
+    fn wait(&self, timeout: Duration) -> Result<bool, CommandError> {
         let mut buffer = [0u8; 6];
-        let message = self.handle.read_interrupt(0x81, &mut buffer, duration);
-        if message.is_err() {
-            println!("Error Reading Interrupt..");
+        match self.handle.read_interrupt(0x81, &mut buffer, timeout) {
+            Ok(_) => Ok(true),
+            Err(rusb::Error::Timeout) => Ok(false),
+            Err(error) => Err(error.into()),
         }
-
-        matches!(
-            //self.handle.read_interrupt(0x81, &mut buffer, duration),
-            message,
-            Ok(_)
-        )
-    }
-
-    pub fn is_connected(&self) -> bool {
-        self.handle.active_configuration().is_ok()
     }
 }
@@ -1,10 +1,14 @@
 pub use rusb;
 pub mod buttonstate;
+pub mod capture;
 pub mod channelstate;
 pub mod colouring;
 pub mod commands;
 pub mod dcp;
+pub mod device;
 pub mod error;
 pub mod goxlr;
 pub mod microphone;
+pub mod mock;
 pub mod routing;
+pub mod trace;
@@ -0,0 +1,259 @@
+/*
+A stand in for a GoXLR, so the daemon can be run without one (when working on a UI, or in
+tests). Writes are accepted, and the volumes and encoder values are remembered so they can be
+read back like on a real device. Button presses and fader or encoder movements come from a
+script of timed events, one per line:
+
+    # Hold the Bleep button for a second, then pull fader A down
+    1000 press Bleep
+    2000 release Bleep
+    2500 fader A 0
+    3000 encoder Reverb 12
+
+Times are in milliseconds from when the device is created, and names are matched ignoring case.
+ */
+
+use crate::buttonstate::Buttons;
+use crate::commands::{Command, HardwareInfoCommand, SystemInfoCommand};
+use crate::device::{GoXlrDevice, InterruptWait, UsbDeviceInfo};
+use crate::error::{CommandError, MockScriptError};
+use crate::goxlr::{PID_GOXLR_FULL, PID_GOXLR_MINI, VID_GOXLR};
+use byteorder::{ByteOrder, LittleEndian};
+use enumset::EnumSet;
+use goxlr_types::{ChannelName, EncoderName, FaderName};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use strum::{EnumCount, IntoEnumIterator};
+
+pub const MOCK_SERIAL: &str = "MOCK00000000001";
+const MOCK_MANUFACTURED_DATE: &str = "2022-01-01";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MockInput {
+    Press(Buttons),
+    Release(Buttons),
+    Fader(FaderName, u8),
+    Encoder(EncoderName, i8),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MockEvent {
+    pub time: Duration,
+    pub input: MockInput,
+}
+
+#[derive(Debug)]
+pub struct MockGoXLR {
+    mini: bool,
+    started: Instant,
+    script: VecDeque<MockEvent>,
+    pressed: EnumSet<Buttons>,
+    faders: [ChannelName; 4],
+    volumes: [u8; ChannelName::COUNT],
+    encoders: [i8; 4],
+}
+
+impl MockGoXLR {
+    pub fn new(mini: bool, mut script: Vec<MockEvent>) -> Self {
+        script.sort_by_key(|event| event.time);
+        Self {
+            mini,
+            started: Instant::now(),
+            script: script.into(),
+            pressed: EnumSet::empty(),
+            faders: [
+                ChannelName::Mic,
+                ChannelName::Chat,
+                ChannelName::Music,
+                ChannelName::System,
+            ],
+            volumes: [0; ChannelName::COUNT],
+            encoders: [0; 4],
+        }
+    }
+
+    // Applies every event in the script which is now due.
+    fn run_script(&mut self) {
+        let elapsed = self.started.elapsed();
+        while self
+            .script
+            .front()
+            .is_some_and(|event| event.time <= elapsed)
+        {
+            match self.script.pop_front().unwrap().input {
+                MockInput::Press(button) => {
+                    self.pressed.insert(button);
+                }
+                MockInput::Release(button) => {
+                    self.pressed.remove(button);
+                }
+                MockInput::Fader(fader, volume) => {
+                    self.volumes[self.faders[fader as usize] as usize] = volume;
+                }
+                MockInput::Encoder(encoder, value) => self.encoders[encoder as usize] = value,
+            }
+        }
+    }
+
+    fn button_states_response(&mut self) -> Vec<u8> {
+        self.run_script();
+
+        let mut pressed = 0;
+        for button in self.pressed {
+            pressed |= 1 << button as u8;
+        }
+
+        let mut result = vec![0; 12];
+        LittleEndian::write_u32(&mut result[0..4], pressed);
+        for (index, value) in self.encoders.iter().enumerate() {
+            result[4 + index] = *value as u8;
+        }
+        for (index, channel) in self.faders.iter().enumerate() {
+            result[8 + index] = self.volumes[*channel as usize];
+        }
+        result
+    }
+
+    // Reports the first firmware with every feature, so nothing is hidden from the UI.
+    fn firmware_version_response(&self) -> Vec<u8> {
+        let (major, minor, patch, build) = match self.mini {
+            true => (1, 2, 0, 46),
+            false => (1, 4, 2, 107),
+        };
+
+        let mut result = vec![0; 24];
+        LittleEndian::write_u32(&mut result[0..4], (major << 12) | (minor << 8) | patch);
+        LittleEndian::write_u32(&mut result[4..8], build);
+        LittleEndian::write_u32(&mut result[12..16], 1);
+        LittleEndian::write_u32(&mut result[20..24], 1 << 20);
+        result
+    }
+
+    fn serial_number_response(&self) -> Vec<u8> {
+        let mut result = vec![0; 48];
+        result[..MOCK_SERIAL.len()].copy_from_slice(MOCK_SERIAL.as_bytes());
+        result[24..24 + MOCK_MANUFACTURED_DATE.len()]
+            .copy_from_slice(MOCK_MANUFACTURED_DATE.as_bytes());
+        result
+    }
+}
+
+impl GoXlrDevice for MockGoXLR {
+    fn begin_batch(&mut self) {}
+
+    fn end_batch(&mut self) -> Result<(), CommandError> {
+        Ok(())
+    }
+
+    fn request_data(&mut self, command: Command, body: &[u8]) -> Result<Vec<u8>, CommandError> {
+        let response = match command {
+            Command::GetButtonStates => self.button_states_response(),
+            Command::GetHardwareInfo(HardwareInfoCommand::FirmwareVersion) => {
+                self.firmware_version_response()
+            }
+            Command::GetHardwareInfo(HardwareInfoCommand::SerialNumber) => {
+                self.serial_number_response()
+            }
+            Command::SystemInfo(SystemInfoCommand::SupportsDCPCategory) => vec![1, 0],
+            Command::GetMicrophoneLevel => vec![0, 0],
+            Command::SetFader(fader) => {
+                if let Some(channel) = body
+                    .first()
+                    .and_then(|id| ChannelName::iter().nth(*id as usize))
+                {
+                    self.faders[fader as usize] = channel;
+                }
+                vec![]
+            }
+            Command::SetChannelVolume(channel) => {
+                if let Some(volume) = body.first() {
+                    self.volumes[channel as usize] = *volume;
+                }
+                vec![]
+            }
+            Command::SetEncoderValue(encoder) => {
+                if let Some(value) = body.first() {
+                    self.encoders[encoder as usize] = *value as i8;
+                }
+                vec![]
+            }
+            _ => vec![],
+        };
+        Ok(response)
+    }
+
+    fn interrupt_reader(&self) -> Result<Box<dyn InterruptWait>, CommandError> {
+        Err(CommandError::NotSupported)
+    }
+
+    fn get_usb_info(&self) -> Result<UsbDeviceInfo, CommandError> {
+        Ok(UsbDeviceInfo {
+            manufacturer_name: "GoXLR Utility".to_string(),
+            product_name: "Mock GoXLR".to_string(),
+            is_mini: self.mini,
+            is_claimed: true,
+            has_kernel_driver_attached: false,
+            bus_number: 0,
+            address: 0,
+            version: (0, 0, 0),
+            vendor_id: VID_GOXLR,
+            product_id: match self.mini {
+                true => PID_GOXLR_MINI,
+                false => PID_GOXLR_FULL,
+            },
+            usb_version: (2, 0, 0),
+            speed: "High".to_string(),
+        })
+    }
+
+    fn usb_device_has_kernel_driver_active(&self) -> Result<bool, CommandError> {
+        Ok(false)
+    }
+
+    fn is_connected(&self) -> bool {
+        true
+    }
+}
+
+pub fn parse_mock_script(script: &str) -> Result<Vec<MockEvent>, MockScriptError> {
+    let mut events = vec![];
+    for (index, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: &str| MockScriptError::InvalidLine(index + 1, message.to_string());
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let time = parts[0]
+            .parse()
+            .map_err(|_| error("Expected a time in milliseconds"))?;
+        let input = match parts[1..] {
+            ["press", button] => MockInput::Press(find_named(Buttons::iter(), button)?),
+            ["release", button] => MockInput::Release(find_named(Buttons::iter(), button)?),
+            ["fader", fader, volume] => MockInput::Fader(
+                find_named(FaderName::iter(), fader)?,
+                volume.parse().map_err(|_| error("Invalid fader volume"))?,
+            ),
+            ["encoder", encoder, value] => MockInput::Encoder(
+                find_named(EncoderName::iter(), encoder)?,
+                value.parse().map_err(|_| error("Invalid encoder value"))?,
+            ),
+            _ => return Err(error("Unknown event")),
+        };
+        events.push(MockEvent {
+            time: Duration::from_millis(time),
+            input,
+        });
+    }
+    Ok(events)
+}
+
+fn find_named<T: std::fmt::Debug>(
+    mut values: impl Iterator<Item = T>,
+    name: &str,
+) -> Result<T, MockScriptError> {
+    values
+        .find(|value| format!("{:?}", value).eq_ignore_ascii_case(name))
+        .ok_or_else(|| MockScriptError::UnknownName(name.to_string()))
+}
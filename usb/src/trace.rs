@@ -0,0 +1,164 @@
+/*
+Records the writes sent to a device, so the hardware writes caused by a change to the daemon can
+be compared with those from before it (for example, checking a refactor of how profiles are
+applied sends exactly the same commands). Reads are left out, as when the device is polled
+depends on timing rather than on what the daemon did.
+
+A trace is saved as text, one write per line, with the command id, its name and the body in hex:
+
+    0080600a SetChannelVolume(Mic) ff
+ */
+
+use crate::commands::Command;
+use crate::device::{GoXlrDevice, InterruptWait, UsbDeviceInfo};
+use crate::error::CommandError;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub command_id: u32,
+    pub name: String,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsbTrace {
+    pub entries: Vec<TraceEntry>,
+}
+
+/// Where two traces first differ, with the entry from each (None if that trace ended first).
+#[derive(Debug, Clone)]
+pub struct TraceDifference {
+    pub index: usize,
+    pub expected: Option<TraceEntry>,
+    pub actual: Option<TraceEntry>,
+}
+
+impl UsbTrace {
+    pub fn parse(trace: &str) -> Result<Self, String> {
+        let mut entries = vec![];
+        for (index, line) in trace.lines().enumerate() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.is_empty() {
+                continue;
+            }
+            let invalid = || format!("Line {} of the trace is invalid", index + 1);
+
+            let (id, name, body) = match parts[..] {
+                [id, name] => (id, name, ""),
+                [id, name, body] => (id, name, body),
+                _ => return Err(invalid()),
+            };
+            entries.push(TraceEntry {
+                command_id: u32::from_str_radix(id, 16).map_err(|_| invalid())?,
+                name: name.to_string(),
+                body: parse_hex(body).ok_or_else(invalid)?,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn first_difference(&self, actual: &UsbTrace) -> Option<TraceDifference> {
+        let length = self.entries.len().max(actual.entries.len());
+        (0..length)
+            .map(|index| TraceDifference {
+                index,
+                expected: self.entries.get(index).cloned(),
+                actual: actual.entries.get(index).cloned(),
+            })
+            .find(|difference| {
+                let key = |entry: &Option<TraceEntry>| {
+                    entry.as_ref().map(|e| (e.command_id, e.body.clone()))
+                };
+                key(&difference.expected) != key(&difference.actual)
+            })
+    }
+}
+
+impl std::fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:08x} {}", self.command_id, self.name)?;
+        if !self.body.is_empty() {
+            write!(f, " {}", to_hex(&self.body))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for UsbTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+pub type SharedTrace = Arc<Mutex<UsbTrace>>;
+
+/// Passes everything through to another device, adding its writes to a trace.
+pub struct TraceRecorder<D: GoXlrDevice> {
+    device: D,
+    trace: SharedTrace,
+}
+
+impl<D: GoXlrDevice> TraceRecorder<D> {
+    pub fn new(device: D, trace: SharedTrace) -> Self {
+        Self { device, trace }
+    }
+}
+
+impl<D: GoXlrDevice> GoXlrDevice for TraceRecorder<D> {
+    fn begin_batch(&mut self) {
+        self.device.begin_batch()
+    }
+
+    fn end_batch(&mut self) -> Result<(), CommandError> {
+        self.device.end_batch()
+    }
+
+    fn request_data(&mut self, command: Command, body: &[u8]) -> Result<Vec<u8>, CommandError> {
+        if command.is_write() {
+            self.trace.lock().unwrap().entries.push(TraceEntry {
+                command_id: command.command_id(),
+                name: format!("{:?}", command),
+                body: body.to_vec(),
+            });
+        }
+        self.device.request_data(command, body)
+    }
+
+    fn interrupt_reader(&self) -> Result<Box<dyn InterruptWait>, CommandError> {
+        self.device.interrupt_reader()
+    }
+
+    fn get_usb_info(&self) -> Result<UsbDeviceInfo, CommandError> {
+        self.device.get_usb_info()
+    }
+
+    fn usb_device_has_kernel_driver_active(&self) -> Result<bool, CommandError> {
+        self.device.usb_device_has_kernel_driver_active()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.device.is_connected()
+    }
+}
+
+pub fn to_hex(data: &[u8]) -> String {
+    data.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}